@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::figure::FiguredBassSymbol;
+use crate::key::{Key, Mode};
+use crate::meter::{self, Duration, Meter};
+use crate::musicxml::FiguredBassPart;
+use crate::parse::{parse_event, ParseError};
+use crate::pitch::{Letter, SpelledPitch};
+use crate::voicing::{Voice, Voicing};
+
+// ABC notation: reading a figured bass from a tune, and writing a
+// realization as a tune of four voices.
+//
+// The bass is the notes of one voice, the lowest of a chord in brackets.
+// Its figures are annotations before a note, usually below it: "_6" or
+// "_6/5" (also "_6;5" or "_6\n5", stacked); "_4-3" is a suspension over
+// the one note, as in a progression, and figures before a note tied over
+// go over the held bass. Chord symbols ("Am") are not figures and are
+// skipped. A fermata ("!fermata!" or "H") over a bass note ends a phrase.
+// Only the first tune of a file is read.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbcError {
+    Syntax { line: usize, message: String }, // counting lines from 1
+    NoSuchVoice(String),
+    NoNotes,
+    Figure(ParseError),
+}
+
+impl fmt::Display for AbcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AbcError::Syntax { line, message } => write!(f, "ABC line {}: {}", line, message),
+            AbcError::NoSuchVoice(id) => write!(f, "no voice {:?} in the tune", id),
+            AbcError::NoNotes => write!(f, "no notes in the bass voice"),
+            AbcError::Figure(e) => write!(f, "invalid figure {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AbcError {}
+
+// A note of the bass voice as written, before the key signature and the
+// accidentals earlier in its bar are applied
+#[derive(Debug, Clone)]
+struct Note {
+    letter: Letter,
+    accidental: Option<i8>,
+    octave: i8,
+    figures: Vec<String>,
+    tied: bool, // to the next note
+    fermata: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    Note(Note),
+    Rest,
+    Bar,
+    Key(Key),
+}
+
+// The figured bass in voice `voice` (by id) of the first tune in `text`,
+// or in the first voice with figures (the last voice if none has any).
+// The figures are read in `key` if given, else in the tune's K: field.
+pub fn read_abc(text: &str, voice: Option<&str>, key: Option<&Key>) -> Result<FiguredBassPart, AbcError> {
+    let mut voices: Vec<(String, Vec<Token>)> = Vec::new();
+    let mut current = 0;
+    let mut tune_key = None;
+    let mut in_body = false;
+
+    for (n, line) in text.lines().enumerate() {
+        let line_number = n + 1;
+        // A blank line ends the tune
+        if line.trim().is_empty() && in_body {
+            break;
+        }
+        let line = strip_comment(line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some((field, value)) = field(line) {
+            match field {
+                'K' => {
+                    let parsed = abc_key(value).map_err(|message| AbcError::Syntax { line: line_number, message })?;
+                    if in_body && let Some((_, tokens)) = voices.get_mut(current) {
+                        tokens.push(Token::Key(parsed));
+                    } else if !in_body {
+                        tune_key = Some(parsed);
+                        in_body = true;
+                    }
+                }
+                'V' => current = voice_index(&mut voices, value),
+                _ => {}
+            }
+            continue;
+        }
+        if !in_body {
+            continue;
+        }
+        if voices.is_empty() {
+            current = voice_index(&mut voices, "1");
+        }
+        let mut scanner = Scanner { chars: line.chars().collect(), at: 0, line: line_number };
+        scanner.tokens(&mut voices, &mut current)?;
+    }
+
+    let part = match voice {
+        Some(id) => voices.iter().find(|(v, _)| v == id).ok_or(AbcError::NoSuchVoice(id.to_string()))?,
+        None => voices
+            .iter()
+            .find(|(_, tokens)| tokens.iter().any(|t| matches!(t, Token::Note(note) if !note.figures.is_empty())))
+            .or(voices.last())
+            .ok_or(AbcError::NoNotes)?,
+    };
+    let tune_key = tune_key.unwrap_or_else(Key::c_major);
+    let key = key.cloned().unwrap_or_else(|| tune_key.clone());
+
+    // Each bass note and the figures over it (several, in turn, over a
+    // note held by ties)
+    let mut signature = tune_key.signature();
+    let mut accidentals: HashMap<(Letter, i8), i8> = HashMap::new();
+    let mut events: Vec<(SpelledPitch, Vec<String>, bool)> = Vec::new();
+    let mut tied_from: Option<SpelledPitch> = None;
+    for token in &part.1 {
+        match token {
+            Token::Bar => accidentals.clear(),
+            Token::Key(key) => {
+                signature = key.signature();
+                accidentals.clear();
+            }
+            Token::Rest => tied_from = None,
+            Token::Note(note) => {
+                let place = (note.letter, note.octave);
+                let accidental = match note.accidental {
+                    Some(accidental) => accidental,
+                    // A note tied over the bar line keeps its accidental
+                    None => match tied_from {
+                        Some(from) if (from.letter, from.octave) == place => from.accidental,
+                        _ => *accidentals.get(&place).unwrap_or(&signature[note.letter as usize]),
+                    },
+                };
+                accidentals.insert(place, accidental);
+                let pitch = SpelledPitch::new(note.letter, accidental, note.octave);
+                match tied_from {
+                    Some(from) if from.pitch() == pitch.pitch() => {
+                        if let Some((_, figures, fermata)) = events.last_mut() {
+                            figures.extend(note.figures.iter().cloned());
+                            *fermata |= note.fermata;
+                        }
+                    }
+                    _ => events.push((pitch, note.figures.clone(), note.fermata)),
+                }
+                tied_from = note.tied.then_some(pitch);
+            }
+        }
+    }
+    if events.is_empty() {
+        return Err(AbcError::NoNotes);
+    }
+
+    let mut symbols: Vec<FiguredBassSymbol> = Vec::new();
+    for (bass, figures, fermata) in events {
+        let figures = if figures.is_empty() { vec![String::new()] } else { figures };
+        for (i, figure) in figures.iter().enumerate() {
+            let states = parse_event(&format!("{}:{}", bass, figure), &key).map_err(AbcError::Figure)?;
+            symbols.extend(states.into_iter().map(|mut state| {
+                state.held |= i > 0;
+                state
+            }));
+        }
+        if let Some(last) = symbols.last_mut() {
+            last.fermata = fermata;
+        }
+    }
+    meter::place(&mut symbols);
+    Ok(FiguredBassPart { id: part.0.clone(), key, symbols })
+}
+
+// `realization` of `symbols` as an ABC tune: soprano and alto on one
+// staff, tenor and bass on another, each chord as long as its symbol and
+// the figures under the bass, in the first symbol's meter, with fermatas
+// over the chords that end phrases. A bass held under several figures is
+// tied over, and so is any voice keeping its note.
+pub fn write_abc(symbols: &[FiguredBassSymbol], realization: &[Voicing], title: &str) -> String {
+    const LINE: usize = 4; // bars a line
+    let key = symbols.first().map_or_else(Key::c_major, |symbol| symbol.key.clone());
+    let meter = symbols.first().map_or(Meter::COMMON, |symbol| symbol.meter);
+
+    let mut abc = format!("X:1\nT:{}\nM:{}\nL:1/4\n%%score (S A) (T B)\nK:{}\n", title, meter, abc_key_name(&key));
+    let voices = [
+        (Voice::Soprano, "S", "treble", "Soprano"),
+        (Voice::Alto, "A", "treble", "Alto"),
+        (Voice::Tenor, "T", "bass", "Tenor"),
+        (Voice::Bass, "B", "bass", "Bass"),
+    ];
+    let signature = key.signature();
+    for (voice, id, clef, name) in voices {
+        abc.push_str(&format!("V:{} clef={} name=\"{}\"\n", id, clef, name));
+        let mut accidentals: HashMap<(Letter, i8), i8> = HashMap::new();
+        let mut line = String::new();
+        let mut bars = 0;
+        for (i, (voicing, symbol)) in realization.iter().zip(symbols).enumerate() {
+            if i > 0 && symbol.offset == Duration::ZERO {
+                accidentals.clear();
+                bars += 1;
+                line.push_str(if bars % LINE == 0 { " |\n" } else { " | " });
+            } else if i > 0 {
+                line.push(' ');
+            }
+            let pitch = voicing.voice(voice);
+            let spelled = symbol.spell(pitch);
+            if voice == Voice::Bass {
+                let figure = symbol.written_figure().replace('♮', "n");
+                if !figure.is_empty() {
+                    line.push_str(&format!("\"_{}\"", figure));
+                }
+            }
+            if symbol.fermata {
+                line.push_str("!fermata!");
+            }
+            let place = (spelled.letter, spelled.octave);
+            let current = *accidentals.get(&place).unwrap_or(&signature[spelled.letter as usize]);
+            line.push_str(&abc_note(spelled, spelled.accidental != current));
+            if symbol.duration != Duration::BEAT {
+                line.push_str(&symbol.duration.to_string());
+            }
+            accidentals.insert(place, spelled.accidental);
+            let next = realization.get(i + 1).zip(symbols.get(i + 1));
+            if next.is_some_and(|(next, next_symbol)| next_symbol.held && next.voice(voice) == pitch) {
+                line.push('-');
+            }
+        }
+        abc.push_str(&line);
+        abc.push_str(" |]\n");
+    }
+    abc
+}
+
+// "C,", "^f", "_B,,", "c'": the letter in capitals from C4 down, commas
+// and apostrophes for each octave further
+fn abc_note(pitch: SpelledPitch, accidental: bool) -> String {
+    let mut note = String::new();
+    if accidental {
+        note.push_str(match pitch.accidental {
+            0 => "=",
+            a if a > 0 => &"^^"[..a.min(2) as usize],
+            a => &"__"[..(-a).min(2) as usize],
+        });
+    }
+    let letter = pitch.letter.to_string();
+    match pitch.octave {
+        octave if octave >= 5 => {
+            note.push_str(&letter.to_lowercase());
+            note.push_str(&"'".repeat((octave - 5) as usize));
+        }
+        octave => {
+            note.push_str(&letter);
+            note.push_str(&",".repeat((4 - octave) as usize));
+        }
+    }
+    note
+}
+
+// "Gm", "Bb", "Ddor"
+fn abc_key_name(key: &Key) -> String {
+    let mode = match key.mode {
+        Mode::Major => "",
+        Mode::Minor | Mode::HarmonicMinor | Mode::MelodicMinor => "m",
+        Mode::Dorian => "dor",
+        Mode::Phrygian => "phr",
+        Mode::Lydian => "lyd",
+        Mode::Mixolydian => "mix",
+        Mode::Locrian => "loc",
+    };
+    format!("{}{}", key.tonic_name(), mode)
+}
+
+// A K: field: "G", "Gm", "F#min", "Bb dorian", "D mix clef=bass"; "none"
+// (or nothing) is C major. Explicit accidentals after the key are
+// ignored.
+fn abc_key(value: &str) -> Result<Key, String> {
+    let value = value.trim();
+    let compact: String =
+        value.split_whitespace().take_while(|word| !word.contains('=') && !word.starts_with(['^', '_'])).collect();
+    if compact.is_empty() || compact.eq_ignore_ascii_case("none") {
+        return Ok(Key::c_major());
+    }
+    let tonic_length = compact.char_indices().skip(1).find(|&(_, c)| c != '#' && c != 'b').map_or(compact.len(), |(i, _)| i);
+    let (tonic, rest) = compact.split_at(tonic_length);
+    let mode = rest.to_ascii_lowercase();
+    let mode = match mode.get(..3).unwrap_or(&mode) {
+        "" | "maj" | "ion" => "major",
+        "m" | "min" | "aeo" => "minor",
+        "mix" => "mixolydian",
+        "dor" => "dorian",
+        "phr" => "phrygian",
+        "lyd" => "lydian",
+        "loc" => "locrian",
+        _ => return Err(format!("unknown key {:?}", value)),
+    };
+    format!("{} {}", tonic, mode).parse()
+}
+
+// The voice with id `id` (the first word of a V: field), added if new
+fn voice_index(voices: &mut Vec<(String, Vec<Token>)>, id: &str) -> usize {
+    let id = id.split_whitespace().next().unwrap_or("");
+    voices.iter().position(|(v, _)| v == id).unwrap_or_else(|| {
+        voices.push((id.to_string(), Vec::new()));
+        voices.len() - 1
+    })
+}
+
+// A header or body field on a line of its own: "K:Gm", "V:2 clef=bass"
+fn field(line: &str) -> Option<(char, &str)> {
+    let mut chars = line.chars();
+    let name = chars.next().filter(char::is_ascii_alphabetic)?;
+    let value = chars.as_str().strip_prefix(':')?;
+    Some((name, value.trim()))
+}
+
+// The line up to a "%" comment ("\%" is a percent sign)
+fn strip_comment(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    let end = (0..bytes.len()).find(|&i| bytes[i] == b'%' && (i == 0 || bytes[i - 1] != b'\\')).unwrap_or(bytes.len());
+    &line[..end]
+}
+
+// The music on one line, a character at a time
+struct Scanner {
+    chars: Vec<char>,
+    at: usize,
+    line: usize,
+}
+
+impl Scanner {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
+    }
+
+    fn error(&self, message: impl Into<String>) -> AbcError {
+        AbcError::Syntax { line: self.line, message: message.into() }
+    }
+
+    // Everything up to the next `end`, which is passed over
+    fn until(&mut self, end: char) -> Result<String, AbcError> {
+        let start = self.at;
+        while let Some(c) = self.peek() {
+            self.at += 1;
+            if c == end {
+                return Ok(self.chars[start..self.at - 1].iter().collect());
+            }
+        }
+        Err(self.error(format!("missing {:?}", end)))
+    }
+
+    // A note's length ("2", "/", "3/2"), ignored
+    fn length(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '/') {
+            self.at += 1;
+        }
+    }
+
+    // A pitch: accidentals, a letter and octave marks
+    fn note(&mut self) -> Result<Note, AbcError> {
+        let mut accidental = None;
+        while let Some(c) = self.peek().filter(|c| matches!(c, '^' | '_' | '=')) {
+            let step = match c {
+                '^' => 1,
+                '_' => -1,
+                _ => 0,
+            };
+            accidental = Some(accidental.unwrap_or(0) + step);
+            self.at += 1;
+        }
+        let c = self.peek().ok_or_else(|| self.error("accidental with no note"))?;
+        let letter = Letter::from_char(c).filter(|_| c.is_ascii_alphabetic()).ok_or_else(|| self.error(format!("expected a note, found {:?}", c)))?;
+        self.at += 1;
+        let mut octave = if c.is_ascii_lowercase() { 5 } else { 4 };
+        while let Some(mark) = self.peek().filter(|&c| c == ',' || c == '\'') {
+            octave += if mark == ',' { -1 } else { 1 };
+            self.at += 1;
+        }
+        Ok(Note { letter, accidental, octave, figures: Vec::new(), tied: false, fermata: false })
+    }
+
+    // The tokens of the line, into the voice `current` (which an inline
+    // [V:] field changes)
+    fn tokens(&mut self, voices: &mut Vec<(String, Vec<Token>)>, current: &mut usize) -> Result<(), AbcError> {
+        let mut figures: Vec<String> = Vec::new();
+        let mut fermata = false; // over the next note
+        let mut overlay = false; // "&" to the end of the bar: another voice
+        while let Some(c) = self.peek() {
+            let tokens = &mut voices[*current].1;
+            match c {
+                '"' => {
+                    self.at += 1;
+                    let text = self.until('"')?;
+                    let (position, body) = match text.chars().next() {
+                        Some(p @ ('^' | '_' | '<' | '>' | '@')) => (Some(p), &text[p.len_utf8()..]),
+                        _ => (None, text.as_str()),
+                    };
+                    let body = body.replace("\\n", "/").replace(';', "/");
+                    // Chord symbols are not figures, but annotations must be
+                    let is_figure = body.chars().all(|c| c.is_ascii_digit() || "#bn\\+/- ".contains(c));
+                    if is_figure && !body.trim().is_empty() {
+                        figures.push(body.split_whitespace().collect());
+                    } else if position.is_some() && !body.trim().is_empty() {
+                        return Err(AbcError::Figure(ParseError::new(&text, "not a figure")));
+                    }
+                }
+                '!' | '+' => {
+                    self.at += 1;
+                    fermata |= self.until(c)? == "fermata" && !overlay;
+                }
+                'H' => {
+                    self.at += 1;
+                    fermata |= !overlay;
+                }
+                '{' => {
+                    self.at += 1;
+                    self.until('}')?;
+                }
+                '[' if self.chars.get(self.at + 2) == Some(&':') => {
+                    self.at += 1;
+                    let inline = self.until(']')?;
+                    if let Some((name, value)) = field(&inline) {
+                        match name {
+                            'V' => *current = voice_index(voices, value),
+                            'K' => {
+                                let key = abc_key(value).map_err(|message| self.error(message))?;
+                                tokens.push(Token::Key(key));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                '[' if self.chars.get(self.at + 1).is_some_and(|c| *c == '|' || c.is_ascii_digit()) => self.at += 1,
+                '[' => {
+                    // A chord: its lowest note is the bass
+                    self.at += 1;
+                    let mut lowest: Option<Note> = None;
+                    while self.peek().is_some_and(|c| c != ']') {
+                        if self.peek().is_some_and(|c| c.is_whitespace() || c == '-') {
+                            self.at += 1;
+                            continue;
+                        }
+                        let note = self.note()?;
+                        self.length();
+                        let height = |n: &Note| (n.octave, n.letter);
+                        if lowest.as_ref().is_none_or(|low| height(&note) < height(low)) {
+                            lowest = Some(note);
+                        }
+                    }
+                    self.at += 1;
+                    self.length();
+                    if let Some(mut note) = lowest.filter(|_| !overlay) {
+                        note.figures = std::mem::take(&mut figures);
+                        note.fermata = std::mem::take(&mut fermata);
+                        tokens.push(Token::Note(note));
+                    }
+                }
+                '|' | ':' | ']' => {
+                    self.at += 1;
+                    // A bar line and its repeat number, if any
+                    while self.peek().is_some_and(|c| matches!(c, '|' | ':' | ']') || c.is_ascii_digit()) {
+                        self.at += 1;
+                    }
+                    tokens.push(Token::Bar);
+                    overlay = false;
+                }
+                '&' => {
+                    self.at += 1;
+                    overlay = true;
+                }
+                '-' => {
+                    self.at += 1;
+                    if let Some(Token::Note(note)) = tokens.last_mut()
+                        && !overlay
+                    {
+                        note.tied = true;
+                    }
+                }
+                'z' | 'x' | 'Z' | 'X' => {
+                    self.at += 1;
+                    self.length();
+                    if !overlay {
+                        figures.clear();
+                        fermata = false;
+                        tokens.push(Token::Rest);
+                    }
+                }
+                '^' | '_' | '=' | 'A'..='G' | 'a'..='g' => {
+                    let mut note = self.note()?;
+                    self.length();
+                    if !overlay {
+                        note.figures = std::mem::take(&mut figures);
+                        note.fermata = std::mem::take(&mut fermata);
+                        tokens.push(Token::Note(note));
+                    }
+                }
+                // Slurs, tuplets, broken rhythm, decorations, spacers
+                _ => self.at += 1,
+            }
+        }
+        Ok(())
+    }
+}
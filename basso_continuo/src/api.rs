@@ -0,0 +1,322 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::abc::read_abc;
+use crate::chord::parse_chord_progression;
+use crate::diagnostics::Violation;
+use crate::figure::FiguredBassSymbol;
+use crate::key::Key;
+use crate::musicxml::read_figured_bass;
+use crate::parse::{parse_melody, parse_realization};
+use crate::profile::Profile;
+use crate::progression::ProgressionFile;
+use crate::range::RangeProfile;
+use crate::realize::{Pin, Realizer, Strategy};
+use crate::roman::{self, detect_key, parse_roman_progression};
+use crate::romantext::read_romantext;
+use crate::voicing::{Completeness, Voice, Voicing};
+
+// The engine behind one JSON request and response, for hosts with no
+// files or terminal: a browser build (each *_json function can be
+// exported as it is), a server, another language. Nothing here reads a
+// file or panics on bad input; every problem is an error response.
+//
+//   {"progression": "G2 Bb2:6 C3:6/5 D3:4-3 G2", "key": "G minor", "beam": 8}
+//
+//   {"key": "G minor", "chords": [{"bass": "G2", "figure": "5/3", "held": false,
+//     "pedal": false, "fermata": false, "numeral": "i", "voicing": {"soprano": "D5", ...}}, ...],
+//    "violations": [], "score": 81.2, "grade": null}
+//
+//   {"error": "..."}
+//
+// The progression is written as "notation" says, figures if not given.
+// Musical values are written as on the command line ("G minor",
+// "bach-chorale", "3:soprano=E5").
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub message: String,
+}
+
+impl ApiError {
+    fn new(message: impl fmt::Display) -> Self {
+        Self { message: message.to_string() }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Notation {
+    #[default]
+    Figures, // a progression file, or bass notes with figures on one line
+    Roman,
+    Chords,
+    Melody, // harmonized rather than realized
+    Abc,
+    MusicXml,
+    RomanText,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Request {
+    pub progression: String,
+    pub notation: Notation,
+    pub key: Option<String>, // else the key the progression gives, else C major
+    pub detect_key: bool,    // name the numerals in the key the progression sounds in
+    pub realization: Option<String>, // for analyze: "G4,E4,C4,C3 A4,F4,C4,A2 ..."
+    pub profile: Option<String>,
+    pub ranges: Option<String>,
+    pub completeness: Option<String>,
+    pub greedy: bool,
+    pub beam: Option<usize>,
+    pub seed: Option<u64>,
+    pub temperature: Option<f32>,
+    pub forbid_violations: bool,
+    pub pins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub key: String, // the key the numerals are in
+    pub chords: Vec<ChordReport>,
+    pub violations: Vec<ViolationReport>,
+    pub score: Option<f32>, // the rules' total for the realization
+    pub grade: Option<GradeReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChordReport {
+    pub bass: String,
+    pub figure: String,
+    pub held: bool,
+    #[serde(default)]
+    pub pedal: bool, // held as a pedal under a new harmony
+    #[serde(default)]
+    pub fermata: bool, // the chord ends a phrase
+    pub numeral: Option<String>,
+    pub voicing: Option<SpelledVoicing>,
+}
+
+// The notes of a voicing spelled in its chord's key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpelledVoicing {
+    pub soprano: String,
+    pub alto: String,
+    pub tenor: String,
+    pub bass: String,
+}
+
+impl SpelledVoicing {
+    pub fn new(voicing: &Voicing, symbol: &FiguredBassSymbol) -> Self {
+        let name = |voice: Voice| symbol.spell(voicing.voice(voice)).to_string();
+        Self { soprano: name(Voice::Soprano), alto: name(Voice::Alto), tenor: name(Voice::Tenor), bass: name(Voice::Bass) }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViolationReport {
+    pub kind: String,
+    pub severity: String,
+    pub voices: Vec<String>,
+    pub chords: Vec<usize>, // counting from 1
+    pub message: String,
+}
+
+impl From<&Violation> for ViolationReport {
+    fn from(violation: &Violation) -> Self {
+        Self {
+            kind: violation.kind.to_string(),
+            severity: violation.severity.to_string(),
+            voices: violation.voices.iter().map(Voice::to_string).collect(),
+            chords: violation.chords.iter().map(|c| c + 1).collect(),
+            message: violation.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradeReport {
+    pub mark: u32, // out of 100
+    pub score: f32,
+    pub best: Option<f32>, // the best realization's score, if there is one
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+// Realize the progression (or harmonize the melody) and report on it
+pub fn realize(request: &Request) -> Result<Response, ApiError> {
+    let realizer = request.realizer()?;
+    let (key, symbols, harmonized) = request.read(&realizer)?;
+    let voicings = match harmonized {
+        Some(voicings) => voicings,
+        None => realizer.realize(&symbols).map_err(ApiError::new)?,
+    };
+    let mut response = request.report(&key, &symbols, Some(&voicings));
+    response.score = Some(realizer.total_score(&symbols, &voicings));
+    Ok(response)
+}
+
+// Name the chords of the progression, and check and grade the
+// realization given with it, if one is
+pub fn analyze(request: &Request) -> Result<Response, ApiError> {
+    let realizer = request.realizer()?;
+    let (key, symbols, harmonized) = request.read(&realizer)?;
+    let voicings = match &request.realization {
+        Some(text) => Some(parse_realization(text).map_err(ApiError::new)?),
+        None => harmonized,
+    };
+    let Some(voicings) = voicings else {
+        return Ok(request.report(&key, &symbols, None));
+    };
+    let grade = realizer.grade(&symbols, &voicings).map_err(ApiError::new)?;
+    let mut response = request.report(&key, &symbols, Some(&voicings));
+    response.score = Some(grade.score);
+    response.grade = Some(GradeReport { mark: grade.mark, score: grade.score, best: grade.best });
+    Ok(response)
+}
+
+// The same from and to JSON text, an error being {"error": "..."}
+pub fn realize_json(request: &str) -> String {
+    respond(request, realize)
+}
+
+pub fn analyze_json(request: &str) -> String {
+    respond(request, analyze)
+}
+
+fn respond(request: &str, answer: fn(&Request) -> Result<Response, ApiError>) -> String {
+    let response = serde_json::from_str(request).map_err(ApiError::new).and_then(|request| answer(&request));
+    match response {
+        Ok(response) => to_json(&response),
+        Err(e) => to_json(&ErrorResponse { error: e.message }),
+    }
+}
+
+// Serializing these cannot fail, but an error is an answer too
+pub(crate) fn to_json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).unwrap_or_else(|e| format!("{{\"error\":{:?}}}", e.to_string()))
+}
+
+impl Request {
+    fn realizer(&self) -> Result<Realizer, ApiError> {
+        let mut realizer = Realizer::new().forbid_violations(self.forbid_violations);
+        if let Some(profile) = &self.profile {
+            realizer = realizer.profile(profile.parse::<Profile>().map_err(ApiError::new)?);
+        }
+        if let Some(ranges) = &self.ranges {
+            realizer = realizer.ranges(ranges.parse::<RangeProfile>().map_err(ApiError::new)?);
+        }
+        if let Some(completeness) = &self.completeness {
+            realizer = realizer.completeness(completeness.parse::<Completeness>().map_err(ApiError::new)?);
+        }
+        let strategies = [self.greedy, self.beam.is_some(), self.seed.is_some()];
+        if strategies.iter().filter(|&&given| given).count() > 1 {
+            return Err(ApiError::new("give only one of greedy, beam and seed"));
+        }
+        if self.greedy {
+            realizer = realizer.strategy(Strategy::Greedy);
+        } else if let Some(width) = self.beam {
+            if width == 0 {
+                return Err(ApiError::new("beam should be 1 or more"));
+            }
+            realizer = realizer.strategy(Strategy::Beam(width));
+        } else if let Some(seed) = self.seed {
+            realizer = realizer.strategy(Strategy::Sample(seed));
+        }
+        if let Some(temperature) = self.temperature {
+            if temperature.is_nan() || temperature < 0.0 {
+                return Err(ApiError::new("temperature should be 0 or more"));
+            }
+            realizer = realizer.temperature(temperature);
+        }
+        for pin in &self.pins {
+            let pin: Pin = pin.parse().map_err(ApiError::new)?;
+            realizer = realizer.pin(pin.chord, pin.voice, pin.pitch);
+        }
+        Ok(realizer)
+    }
+
+    // The progression in its key, with the voicings of a harmonized
+    // melody
+    #[allow(clippy::type_complexity)]
+    fn read(&self, realizer: &Realizer) -> Result<(Key, Vec<FiguredBassSymbol>, Option<Vec<Voicing>>), ApiError> {
+        let given: Option<Key> = self.key.as_deref().map(str::parse).transpose().map_err(ApiError::new)?;
+        let key = given.clone().unwrap_or_else(Key::c_major);
+        let text = self.progression.as_str();
+        let (key, symbols, harmonized) = match self.notation {
+            Notation::Figures => {
+                let mut file = ProgressionFile::parse(text, &key).map_err(|e| ApiError::new(e.show(text)))?;
+                if given.is_some() {
+                    file.key = key;
+                }
+                (file.key.clone(), file.symbols(), None)
+            }
+            Notation::Roman => {
+                let symbols = parse_roman_progression(text, &key).map_err(ApiError::new)?;
+                (key, symbols, None)
+            }
+            Notation::Chords => {
+                let symbols = parse_chord_progression(text, &key).map_err(ApiError::new)?;
+                (key, symbols, None)
+            }
+            Notation::Melody => {
+                let melody = parse_melody(text).map_err(ApiError::new)?;
+                let harmonization = realizer.harmonize(&melody, &key).map_err(ApiError::new)?;
+                (key, harmonization.symbols, Some(harmonization.voicings))
+            }
+            Notation::Abc => {
+                let bass = read_abc(text, None, given.as_ref()).map_err(ApiError::new)?;
+                (bass.key, bass.symbols, None)
+            }
+            Notation::MusicXml => {
+                let bass = read_figured_bass(text, None, given.as_ref()).map_err(ApiError::new)?;
+                (bass.key, bass.symbols, None)
+            }
+            Notation::RomanText => {
+                let symbols = read_romantext(text, given.as_ref()).map_err(ApiError::new)?;
+                (symbols[0].key.clone(), symbols, None)
+            }
+        };
+        if symbols.is_empty() {
+            return Err(ApiError::new("the progression has no chords"));
+        }
+        Ok((key, symbols, harmonized))
+    }
+
+    fn report(&self, key: &Key, symbols: &[FiguredBassSymbol], voicings: Option<&[Voicing]>) -> Response {
+        let analysis_key = if self.detect_key { detect_key(symbols) } else { key.clone() };
+        let numerals = roman::analyze(symbols, &analysis_key);
+        let chords = symbols
+            .iter()
+            .zip(numerals)
+            .enumerate()
+            .map(|(i, (symbol, numeral))| ChordReport {
+                bass: symbol.spell(symbol.bass).to_string(),
+                figure: symbol.figure.to_string(),
+                held: symbol.held,
+                pedal: symbol.pedal,
+                fermata: symbol.fermata,
+                numeral: numeral.map(|n| n.to_string()),
+                voicing: voicings.and_then(|v| v.get(i)).map(|voicing| SpelledVoicing::new(voicing, symbol)),
+            })
+            .collect();
+        let violations = match voicings {
+            Some(voicings) => crate::diagnostics::analyze(symbols, voicings).iter().map(ViolationReport::from).collect(),
+            None => Vec::new(),
+        };
+        Response { key: analysis_key.to_string(), chords, violations, score: None, grade: None }
+    }
+}
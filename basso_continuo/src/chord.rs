@@ -0,0 +1,295 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::figure::{Figure, FiguredBassSymbol};
+use crate::key::Key;
+use crate::meter;
+use crate::parse::ParseError;
+use crate::pitch::{Letter, Pitch, PitchClass};
+
+// Lead-sheet chord symbols:
+//
+//   root         a letter with any sharps or flats: "C", "F#", "Bb"
+//   quality      "m" (or "min", "-") minor, "dim" (or "o", "°") diminished,
+//                "aug" (or "+") augmented, "ø" half diminished, "sus4"
+//                (or "sus") and "sus2" in place of the third, "5" for a
+//                bare fifth
+//   extensions   "7", "maj7" (or "M7", "Δ"), "6", "69", "9", "11", "13",
+//                "add9", and altered tones "b5", "#5", "b9", "#9", "#11",
+//                "b13"; parentheses and commas are ignored
+//   bass         "/" and a note name for an inversion or other bass:
+//                "C/E", "F#m7b5/A"
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChordSymbol {
+    pub root: Letter,
+    pub accidental: i8, // sharps (positive) or flats (negative) on the root
+    // Each chord tone as its number above the root (1, 3, 5, 7, 9, ...) and
+    // the semitones it lies above the root, from the root up
+    pub tones: Vec<(u8, u8)>,
+    pub bass: Option<(Letter, i8)>,
+    suffix: String, // as written, for Display
+}
+
+impl ChordSymbol {
+    pub fn root_pitch_class(&self) -> PitchClass {
+        PitchClass::new(self.root.pitch_class()).transpose(self.accidental as i16)
+    }
+
+    pub fn pitch_classes(&self) -> Vec<PitchClass> {
+        let root = self.root_pitch_class();
+        self.tones.iter().map(|&(_, semitones)| root.transpose(semitones as i16)).collect()
+    }
+
+    pub fn bass_pitch_class(&self) -> PitchClass {
+        match self.bass {
+            Some((letter, accidental)) => PitchClass::new(letter.pitch_class()).transpose(accidental as i16),
+            None => self.root_pitch_class(),
+        }
+    }
+
+    // The figured bass symbol for this chord, with the bass in the octave
+    // nearest `near`. Four voices hold at most four pitch classes, so
+    // larger chords lose their perfect fifth and then their lower
+    // extensions (the 9th of a 13th chord) until they fit.
+    pub fn symbol(&self, key: &Key, near: Pitch) -> Result<FiguredBassSymbol, ParseError> {
+        let invalid = |message: String| ParseError::new(&self.to_string(), message);
+        let root = self.root_pitch_class();
+        let bass_pc = self.bass_pitch_class();
+        let bass = bass_pc.nearest(near);
+        let bass_letter = self.bass.map_or(self.root, |(letter, _)| letter);
+
+        let mut upper: Vec<(u8, u8)> =
+            self.tones.iter().copied().filter(|&(_, semitones)| root.transpose(semitones as i16) != bass_pc).collect();
+        if upper.len() > 3 {
+            upper.retain(|&tone| tone != (5, 7));
+        }
+        while upper.len() > 3 {
+            let highest = upper.iter().map(|&(number, _)| number).max().unwrap_or(0);
+            match upper.iter().position(|&(number, _)| number >= 9 && number < highest) {
+                Some(i) => upper.remove(i),
+                None => return Err(invalid("too many tones for four voices".to_string())),
+            };
+        }
+
+        // Numbers count letters up from the bass, so they hold however the
+        // key reads the bass; a 9th over the root stays a 9 rather than a 2.
+        // Figures have no double sharps or flats, so a tone that would need
+        // one (the bb7 of Cdim7 in C major) takes the number next to it.
+        let mut above: Vec<(u8, PitchClass)> = upper
+            .iter()
+            .map(|&(number, semitones)| {
+                let letter = self.root.up(number as usize - 1);
+                let steps = (letter as u8 + 7 - bass_letter as u8) % 7;
+                let figure = match steps {
+                    0 => 8,
+                    1 if number >= 9 => 9,
+                    _ => steps + 1,
+                };
+                let pc = root.transpose(semitones as i16);
+                let figure = [figure, figure - 1, figure + 1]
+                    .into_iter()
+                    .find(|&n| n > 1 && Figure::for_tones(bass, &[(n, pc)], key).is_some())
+                    .unwrap_or(figure);
+                (figure, pc)
+            })
+            .collect();
+        above.sort();
+
+        let figure = Figure::for_tones(bass, &above, key)
+            .ok_or_else(|| invalid(format!("cannot figure this chord in {}", key)))?;
+        Ok(FiguredBassSymbol::new(bass, figure, key))
+    }
+}
+
+fn note_text(letter: Letter, accidental: i8) -> String {
+    let sign = if accidental > 0 { "#" } else { "b" };
+    format!("{}{}", letter, sign.repeat(accidental.unsigned_abs() as usize))
+}
+
+impl fmt::Display for ChordSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", note_text(self.root, self.accidental), self.suffix)?;
+        if let Some((letter, accidental)) = self.bass {
+            write!(f, "/{}", note_text(letter, accidental))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ChordSymbol {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_chord_symbol(s)
+    }
+}
+
+// A letter and the sharps or flats after it, and the rest of the text
+fn parse_note(text: &str) -> Option<(Letter, i8, &str)> {
+    let mut chars = text.chars();
+    let letter = chars.next().filter(char::is_ascii_uppercase).and_then(Letter::from_char)?;
+    let mut rest = chars.as_str();
+    let mut accidental = 0;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            'b' | '♭' => accidental -= 1,
+            '#' | '♯' => accidental += 1,
+            _ => break,
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    Some((letter, accidental, rest))
+}
+
+fn parse_chord_symbol(input: &str) -> Result<ChordSymbol, ParseError> {
+    let invalid = |message: &str| ParseError::new(input, message);
+    let text = input.trim();
+
+    let (root, accidental, rest) =
+        parse_note(text).ok_or_else(|| invalid("expected a chord root such as C, F# or Bb"))?;
+    let (suffix, bass) = match rest.rsplit_once('/') {
+        Some((suffix, bass)) => match parse_note(bass) {
+            Some((letter, accidental, "")) => (suffix, Some((letter, accidental))),
+            _ => return Err(invalid(&format!("invalid bass note {:?}", bass))),
+        },
+        None => (rest, None),
+    };
+
+    let mut third = Some(4);
+    let mut fifth = Some(7);
+    let mut seventh = None;
+    let mut major_seventh = false;
+    let mut diminished = false;
+    let mut extensions: Vec<(u8, u8)> = Vec::new();
+
+    let mut rest = suffix;
+    while !rest.is_empty() {
+        let digits = |s: &str| s.chars().take_while(char::is_ascii_digit).collect::<String>();
+        let mut take = |token: &str| match rest.strip_prefix(token) {
+            Some(after) => {
+                rest = after;
+                true
+            }
+            None => false,
+        };
+
+        if take("(") || take(")") || take(",") || take(" ") {
+            continue;
+        }
+        if take("Δ") || take("∆") {
+            major_seventh = true;
+            seventh = Some(11);
+        } else if take("maj") || take("Maj") || take("M") {
+            major_seventh = true;
+        } else if take("min") || take("mi") || take("m") || take("-") {
+            third = Some(3);
+        } else if take("dim") || take("o") || take("°") {
+            third = Some(3);
+            fifth = Some(6);
+            diminished = true;
+        } else if take("ø") || take("Ø") {
+            third = Some(3);
+            fifth = Some(6);
+            seventh = Some(10);
+        } else if take("aug") || take("+") {
+            fifth = Some(8);
+        } else if take("sus2") {
+            third = None;
+            extensions.push((2, 2));
+        } else if take("sus4") || take("sus") {
+            third = None;
+            extensions.push((4, 5));
+        } else if take("add") {
+            let number = digits(rest);
+            rest = &rest[number.len()..];
+            let tone = match number.as_str() {
+                "2" | "9" => (9, 2),
+                "4" | "11" => (11, 5),
+                "6" | "13" => (13, 9),
+                _ => return Err(invalid(&format!("cannot add {:?}", number))),
+            };
+            extensions.push(tone);
+        } else if let Some(sign @ ('b' | '#' | '♭' | '♯')) = rest.chars().next() {
+            rest = &rest[sign.len_utf8()..];
+            let number = digits(rest);
+            rest = &rest[number.len()..];
+            let shift = if matches!(sign, 'b' | '♭') { -1 } else { 1 };
+            let (number, natural) = match number.as_str() {
+                "5" => {
+                    fifth = Some((7 + shift) as u8);
+                    continue;
+                }
+                "9" => (9, 2),
+                "11" => (11, 5),
+                "13" => (13, 9),
+                _ => return Err(invalid(&format!("cannot alter {:?}", number))),
+            };
+            extensions.push((number, (natural + shift) as u8));
+        } else {
+            let number = digits(rest);
+            if number.is_empty() {
+                return Err(invalid(&format!("unexpected {:?} in chord symbol", rest)));
+            }
+            rest = &rest[number.len()..];
+            let seventh_above = if major_seventh {
+                11
+            } else if diminished {
+                9
+            } else {
+                10
+            };
+            match number.as_str() {
+                "5" => third = None,
+                "6" => extensions.push((6, 9)),
+                "69" => extensions.extend([(6, 9), (9, 2)]),
+                "7" => seventh = seventh.or(Some(seventh_above)),
+                "9" => {
+                    seventh = seventh.or(Some(seventh_above));
+                    extensions.push((9, 2));
+                }
+                "11" => {
+                    seventh = seventh.or(Some(seventh_above));
+                    extensions.extend([(9, 2), (11, 5)]);
+                }
+                "13" => {
+                    seventh = seventh.or(Some(seventh_above));
+                    extensions.extend([(9, 2), (13, 9)]);
+                }
+                _ => return Err(invalid(&format!("unknown extension {:?}", number))),
+            }
+        }
+    }
+
+    let mut tones = vec![(1, 0)];
+    tones.extend(third.map(|semitones| (3, semitones)));
+    tones.extend(fifth.map(|semitones| (5, semitones)));
+    tones.extend(seventh.map(|semitones| (7, semitones)));
+    for (number, semitones) in extensions {
+        // An altered 9th or 13th replaces the natural one
+        tones.retain(|&(n, _)| n != number);
+        tones.push((number, semitones));
+    }
+    tones.sort_by_key(|&(number, _)| number);
+
+    Ok(ChordSymbol { root, accidental, tones, bass, suffix: suffix.to_string() })
+}
+
+// Chord symbols separated by whitespace, with "|" bar lines ignored and ";"
+// after one for a fermata. Each bass is taken in the octave nearest a
+// point halfway between the bass before and D3, as for Roman numerals.
+pub fn parse_chord_progression(text: &str, key: &Key) -> Result<Vec<FiguredBassSymbol>, ParseError> {
+    const CENTER: u8 = 50;
+    let mut prev = CENTER;
+    let mut symbols = Vec::new();
+    for chord in text.split_whitespace().filter(|&token| token != "|") {
+        let (chord, fermata) = chord.strip_suffix(';').map_or((chord, false), |chord| (chord, true));
+        let mut symbol = chord.parse::<ChordSymbol>()?.symbol(key, Pitch::new((prev + CENTER) / 2))?;
+        symbol.fermata = fermata;
+        prev = symbol.bass.midi_number;
+        symbols.push(symbol);
+    }
+    meter::place(&mut symbols);
+    Ok(symbols)
+}
@@ -0,0 +1,306 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use toml::{Table, Value};
+
+use crate::key::Key;
+use crate::profile::Profile;
+use crate::range::RangeProfile;
+use crate::realize::{Realizer, Strategy};
+use crate::rules::HiddenPerfects;
+use crate::scoring::ScoringWeights;
+use crate::voicing::Completeness;
+
+// Defaults for the command line, so they need not be given on every run:
+// a TOML file, ~/.config/basso_continuo/config.toml unless another is
+// named. Flags on the command line override it.
+//
+//   [realize]
+//   key = "G minor"
+//   profile = "bach-chorale"   # or the weights below, not both
+//   ranges = "satb"
+//   completeness = "sevenths"
+//   beam = 8                   # or greedy = true, or seed = 42
+//   temperature = 2.0
+//   forbid-violations = true
+//
+//   [weights]                  # over ScoringWeights::default()
+//   motion = 1.0
+//   spacing-limit = 9
+//
+//   [rules]                    # false turns a rule off; hidden-perfects,
+//   contrary-motion = false    # which only some profiles have, can be
+//   hidden-perfects = true     # turned on
+//
+//   [output]
+//   explain = true
+//   detect-key = true
+//   export = "kern"            # what export writes without --to
+//   title = "Exercises"
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub message: String,
+}
+
+impl ConfigError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub key: Option<Key>,
+    pub profile: Option<Profile>,
+    pub weights: Option<ScoringWeights>,
+    pub rules: Vec<(String, bool)>, // rules turned on or off, by name
+    pub ranges: Option<RangeProfile>,
+    pub completeness: Option<Completeness>,
+    pub strategy: Option<Strategy>,
+    pub temperature: Option<f32>,
+    pub forbid_violations: bool,
+    pub explain: bool,
+    pub detect_key: bool,
+    pub export: Option<String>, // abc, kern, midi, musicxml, romantext or progression
+    pub title: Option<String>,
+}
+
+// The rules [rules] can name: the standard ones and hidden-perfects
+const RULES: [&str; 14] = [
+    "doubling",
+    "spacing",
+    "range-comfort",
+    "parallel-perfects",
+    "hidden-perfects",
+    "voice-motion",
+    "contrary-motion",
+    "chordal-seventh",
+    "melodic-intervals",
+    "suspension",
+    "cadential-six-four",
+    "strong-beat-six-four",
+    "harmonic-rhythm",
+    "cadence",
+];
+
+// The formats export can write
+const EXPORT_FORMATS: [&str; 6] = ["abc", "kern", "midi", "musicxml", "romantext", "progression"];
+
+impl Config {
+    // $XDG_CONFIG_HOME/basso_continuo/config.toml, or under ~/.config
+    pub fn default_path() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("basso_continuo").join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|e| ConfigError::new(format!("cannot read {}: {}", path.display(), e)))?;
+        Self::parse(&text).map_err(|e| ConfigError::new(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let table: Table = text.parse().map_err(|e: toml::de::Error| ConfigError::new(e.to_string().trim_end()))?;
+        let mut config = Config::default();
+        for (section, entries) in &table {
+            let entries = entries
+                .as_table()
+                .ok_or_else(|| ConfigError::new(format!("{} should be a section, [{}]", section, section)))?;
+            match section.as_str() {
+                "realize" => config.read_realize(entries)?,
+                "weights" => config.weights = Some(weights(entries)?),
+                "rules" => {
+                    for (name, on) in entries {
+                        if !RULES.contains(&name.as_str()) {
+                            let message = format!("[rules] has no rule {:?}, expected one of {}", name, RULES.join(", "));
+                            return Err(ConfigError::new(message));
+                        }
+                        config.rules.push((name.clone(), boolean("rules", name, on)?));
+                    }
+                }
+                "output" => config.read_output(entries)?,
+                _ => {
+                    let message = format!("unknown section [{}], expected realize, weights, rules or output", section);
+                    return Err(ConfigError::new(message));
+                }
+            }
+        }
+        if config.profile.is_some() && config.weights.is_some() {
+            return Err(ConfigError::new("a profile has weights of its own: give a profile or [weights], not both"));
+        }
+        Ok(config)
+    }
+
+    // A realizer set up as the file says
+    pub fn realizer(&self) -> Realizer {
+        let mut realizer = Realizer::new();
+        if let Some(profile) = self.profile {
+            realizer = realizer.profile(profile);
+        } else if let Some(weights) = &self.weights {
+            realizer = realizer.weights(weights.clone());
+        }
+        for (name, on) in &self.rules {
+            if !on {
+                realizer.rules.remove(name);
+            } else if name == "hidden-perfects" && !realizer.rules.contains(name) {
+                // Weighted as the Bach chorale profile has it
+                realizer.rules.add(HiddenPerfects { weight: 20.0, forbid: false });
+            }
+        }
+        if let Some(ranges) = &self.ranges {
+            realizer = realizer.ranges(ranges.clone());
+        }
+        if let Some(completeness) = self.completeness {
+            realizer = realizer.completeness(completeness);
+        }
+        if let Some(strategy) = self.strategy {
+            realizer = realizer.strategy(strategy);
+        }
+        if let Some(temperature) = self.temperature {
+            realizer = realizer.temperature(temperature);
+        }
+        realizer.forbid_violations(self.forbid_violations)
+    }
+
+    fn read_realize(&mut self, entries: &Table) -> Result<(), ConfigError> {
+        const SECTION: &str = "realize";
+        for (name, value) in entries {
+            match name.as_str() {
+                "key" => self.key = Some(parsed(SECTION, name, value)?),
+                "profile" => self.profile = Some(parsed(SECTION, name, value)?),
+                "ranges" => self.ranges = Some(parsed(SECTION, name, value)?),
+                "completeness" => self.completeness = Some(parsed(SECTION, name, value)?),
+                "greedy" | "beam" | "seed" => {
+                    if self.strategy.is_some() {
+                        return Err(ConfigError::new("[realize] takes only one of greedy, beam and seed"));
+                    }
+                    let strategy = match name.as_str() {
+                        "greedy" if boolean(SECTION, name, value)? => Strategy::Greedy,
+                        "greedy" => continue,
+                        "beam" => Strategy::Beam(integer(SECTION, name, value, 1)? as usize),
+                        _ => Strategy::Sample(integer(SECTION, name, value, 0)?),
+                    };
+                    self.strategy = Some(strategy);
+                }
+                "temperature" => self.temperature = Some(number(SECTION, name, value)?),
+                "forbid-violations" => self.forbid_violations = boolean(SECTION, name, value)?,
+                _ => return Err(unknown(SECTION, name, "key, profile, ranges, completeness, greedy, beam, seed, temperature or forbid-violations")),
+            }
+        }
+        Ok(())
+    }
+
+    fn read_output(&mut self, entries: &Table) -> Result<(), ConfigError> {
+        const SECTION: &str = "output";
+        for (name, value) in entries {
+            match name.as_str() {
+                "explain" => self.explain = boolean(SECTION, name, value)?,
+                "detect-key" => self.detect_key = boolean(SECTION, name, value)?,
+                "export" => {
+                    let format = string(SECTION, name, value)?;
+                    if !EXPORT_FORMATS.contains(&format) {
+                        let message = format!("[output] export {:?} is not a format, expected {}", format, EXPORT_FORMATS.join(", "));
+                        return Err(ConfigError::new(message));
+                    }
+                    self.export = Some(format.to_string());
+                }
+                "title" => self.title = Some(string(SECTION, name, value)?.to_string()),
+                _ => return Err(unknown(SECTION, name, "explain, detect-key, export or title")),
+            }
+        }
+        Ok(())
+    }
+}
+
+// The default weights with those the file gives
+fn weights(entries: &Table) -> Result<ScoringWeights, ConfigError> {
+    const SECTION: &str = "weights";
+    let mut weights = ScoringWeights::default();
+    for (name, value) in entries {
+        if name == "spacing-limit" {
+            weights = weights.spacing_limit(integer(SECTION, name, value, 0)?.min(127) as u8);
+            continue;
+        }
+        let weight = number(SECTION, name, value)?;
+        weights = match name.as_str() {
+            "doubling" => weights.doubling(weight),
+            "leading-tone-doubling" => weights.leading_tone_doubling(weight),
+            "spacing" => weights.spacing(weight),
+            "range-comfort" => weights.range_comfort(weight),
+            "parallels" => weights.parallels(weight),
+            "motion" => weights.motion(weight),
+            "contrary-motion" => weights.contrary_motion(weight),
+            "seventh-resolution" => weights.seventh_resolution(weight),
+            "seventh-preparation" => weights.seventh_preparation(weight),
+            "cadential-six-four" => weights.cadential_six_four(weight),
+            "suspension" => weights.suspension(weight),
+            "augmented-leap" => weights.augmented_leap(weight),
+            "wide-leap" => weights.wide_leap(weight),
+            "leap-recovery" => weights.leap_recovery(weight),
+            "strong-beat-six-four" => weights.strong_beat_six_four(weight),
+            "harmonic-rhythm" => weights.harmonic_rhythm(weight),
+            "weak-beat-doubling" => weights.weak_beat_doubling(weight),
+            "cadence" => weights.cadence(weight),
+            _ => {
+                return Err(unknown(
+                    SECTION,
+                    name,
+                    "doubling, leading-tone-doubling, spacing, spacing-limit, range-comfort, parallels, motion, \
+                     contrary-motion, seventh-resolution, seventh-preparation, cadential-six-four, suspension, \
+                     augmented-leap, wide-leap, leap-recovery, strong-beat-six-four, harmonic-rhythm, \
+                     weak-beat-doubling or cadence",
+                ));
+            }
+        };
+    }
+    Ok(weights)
+}
+
+fn unknown(section: &str, name: &str, expected: &str) -> ConfigError {
+    ConfigError::new(format!("[{}] has no setting {:?}, expected {}", section, name, expected))
+}
+
+fn string<'a>(section: &str, name: &str, value: &'a Value) -> Result<&'a str, ConfigError> {
+    value.as_str().ok_or_else(|| ConfigError::new(format!("[{}] {} should be a string", section, name)))
+}
+
+fn boolean(section: &str, name: &str, value: &Value) -> Result<bool, ConfigError> {
+    value.as_bool().ok_or_else(|| ConfigError::new(format!("[{}] {} should be true or false", section, name)))
+}
+
+// A whole number, `min` or more
+fn integer(section: &str, name: &str, value: &Value, min: i64) -> Result<u64, ConfigError> {
+    match value.as_integer() {
+        Some(n) if n >= min => Ok(n as u64),
+        _ => Err(ConfigError::new(format!("[{}] {} should be a whole number, {} or more", section, name, min))),
+    }
+}
+
+// A number, not negative, written with or without a decimal point
+fn number(section: &str, name: &str, value: &Value) -> Result<f32, ConfigError> {
+    let number = value.as_float().or_else(|| value.as_integer().map(|n| n as f64));
+    match number {
+        Some(n) if n >= 0.0 => Ok(n as f32),
+        _ => Err(ConfigError::new(format!("[{}] {} should be a number, 0 or more", section, name))),
+    }
+}
+
+// A value written as on the command line, "G minor" or "ttbb"
+fn parsed<T: FromStr<Err = String>>(section: &str, name: &str, value: &Value) -> Result<T, ConfigError> {
+    let text = string(section, name, value)?;
+    text.parse().map_err(|e: String| ConfigError::new(format!("[{}] {}: {}", section, name, e)))
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+
+use crate::figure::FiguredBassSymbol;
+use crate::pitch::PitchClass;
+use crate::scoring::{has_hidden_perfects, is_augmented_or_diminished, parallel_perfects};
+use crate::voicing::{missing_chord_tones, Completeness, Voice, Voicing};
+
+// Voice-leading problems in a realization, one value per problem found, for
+// reporting rather than scoring: the rules weigh these against each other,
+// this says where they are.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    Warning, // discouraged, but sometimes the best there is
+    Error,   // against the rules of common-practice part writing
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ViolationKind {
+    WrongNote,          // a note that is not in the chord, or a bass other than the one given
+    MissingChordTone,   // a chord tone no voice sings, other than a fifth the root stands in for
+    ParallelFifths,     // two voices a fifth (or compound fifth) apart move the same way to another
+    ParallelOctaves,    // likewise for unisons and octaves
+    HiddenPerfects,     // the outer voices move the same way into a fifth or octave, the soprano leaping
+    VoiceCrossing,      // a voice below the one under it
+    VoiceOverlap,       // a voice moves past the note the voice next to it just left
+    Spacing,            // more than an octave between neighbouring upper voices
+    DoubledLeadingTone, // the leading tone in more than one voice
+    DoubledSeventh,     // the chordal seventh in more than one voice
+    UnresolvedSeventh,  // a chordal seventh that does not step down into the next chord
+    AugmentedLeap,      // an augmented or diminished melodic interval
+}
+
+impl ViolationKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            ViolationKind::WrongNote
+            | ViolationKind::MissingChordTone
+            | ViolationKind::DoubledSeventh
+            | ViolationKind::ParallelFifths
+            | ViolationKind::ParallelOctaves
+            | ViolationKind::VoiceCrossing
+            | ViolationKind::UnresolvedSeventh => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ViolationKind::WrongNote => "wrong note",
+            ViolationKind::MissingChordTone => "missing chord tone",
+            ViolationKind::ParallelFifths => "parallel fifths",
+            ViolationKind::ParallelOctaves => "parallel octaves",
+            ViolationKind::HiddenPerfects => "hidden fifth or octave",
+            ViolationKind::VoiceCrossing => "voice crossing",
+            ViolationKind::VoiceOverlap => "voice overlap",
+            ViolationKind::Spacing => "spacing over an octave",
+            ViolationKind::DoubledLeadingTone => "doubled leading tone",
+            ViolationKind::DoubledSeventh => "doubled seventh",
+            ViolationKind::UnresolvedSeventh => "unresolved seventh",
+            ViolationKind::AugmentedLeap => "augmented or diminished leap",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub voices: Vec<Voice>,
+    pub chords: Vec<usize>, // indices into the progression: one chord, or two for a move between them
+    pub severity: Severity,
+}
+
+impl Violation {
+    fn new(kind: ViolationKind, voices: Vec<Voice>, chords: Vec<usize>) -> Self {
+        Self { kind, voices, chords, severity: kind.severity() }
+    }
+}
+
+// "parallel fifths in soprano and bass, chords 2-3", counting from 1
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        for (i, voice) in self.voices.iter().enumerate() {
+            let sep = match i {
+                0 => " in ",
+                _ if i + 1 == self.voices.len() => " and ",
+                _ => ", ",
+            };
+            write!(f, "{}{}", sep, voice)?;
+        }
+        let chords: Vec<String> = self.chords.iter().map(|c| (c + 1).to_string()).collect();
+        let label = if chords.len() == 1 { "chord" } else { "chords" };
+        write!(f, ", {} {}", label, chords.join("-"))
+    }
+}
+
+const VOICES: [Voice; 4] = [Voice::Soprano, Voice::Alto, Voice::Tenor, Voice::Bass];
+
+// Every problem in `voicings` as a realization of `symbols`, chord by chord
+// (each chord's own problems, then those moving into it), errors and
+// warnings alike. The lines start afresh after a fermata, so nothing is
+// made of the move across it.
+pub fn analyze(symbols: &[FiguredBassSymbol], voicings: &[Voicing]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (i, (voicing, symbol)) in voicings.iter().zip(symbols).enumerate() {
+        chord_violations(i, voicing, symbol, &mut violations);
+        if i > 0 && !symbols[i - 1].fermata {
+            motion_violations(i, &voicings[i - 1], voicing, &symbols[i - 1], symbol, &mut violations);
+        }
+    }
+    violations
+}
+
+fn chord_violations(i: usize, voicing: &Voicing, symbol: &FiguredBassSymbol, violations: &mut Vec<Violation>) {
+    // Only a realization written outside the realizer can have these
+    for voice in VOICES {
+        let pc = voicing.voice(voice).pitch_class();
+        let wrong = match voice {
+            Voice::Bass => pc != symbol.bass.pitch_class(),
+            _ => symbol.chord_tones.iter().all(|tone| tone.pitch_class() != pc),
+        };
+        if wrong {
+            violations.push(Violation::new(ViolationKind::WrongNote, vec![voice], vec![i]));
+        }
+    }
+    // Any fifth may go, as under the realizer's most lenient policy
+    if !missing_chord_tones(voicing, symbol, Completeness::OmitFifth).is_empty() {
+        violations.push(Violation::new(ViolationKind::MissingChordTone, Vec::new(), vec![i]));
+    }
+
+    for pair in VOICES.windows(2) {
+        let (upper, lower) = (voicing.voice(pair[0]), voicing.voice(pair[1]));
+        if upper < lower {
+            violations.push(Violation::new(ViolationKind::VoiceCrossing, pair.to_vec(), vec![i]));
+        } else if pair[1] != Voice::Bass && upper.semitones() - lower.semitones() > 12 {
+            violations.push(Violation::new(ViolationKind::Spacing, pair.to_vec(), vec![i]));
+        }
+    }
+
+    let leading_tone = PitchClass::new(symbol.key.leading_tone());
+    let on_leading_tone: Vec<Voice> =
+        VOICES.into_iter().filter(|&voice| voicing.voice(voice).pitch_class() == leading_tone).collect();
+    if on_leading_tone.len() > 1 {
+        violations.push(Violation::new(ViolationKind::DoubledLeadingTone, on_leading_tone, vec![i]));
+    }
+    if let Some(seventh) = symbol.seventh() {
+        let on_seventh: Vec<Voice> =
+            VOICES.into_iter().filter(|&voice| voicing.voice(voice).pitch_class() == seventh).collect();
+        if on_seventh.len() > 1 {
+            violations.push(Violation::new(ViolationKind::DoubledSeventh, on_seventh, vec![i]));
+        }
+    }
+}
+
+fn motion_violations(
+    i: usize,
+    prev: &Voicing,
+    voicing: &Voicing,
+    before: &FiguredBassSymbol,
+    symbol: &FiguredBassSymbol,
+    violations: &mut Vec<Violation>,
+) {
+    let chords = vec![i - 1, i];
+
+    for (upper, lower, interval) in parallel_perfects(prev, voicing) {
+        let kind = match interval {
+            7 => ViolationKind::ParallelFifths,
+            _ => ViolationKind::ParallelOctaves,
+        };
+        violations.push(Violation::new(kind, vec![upper, lower], chords.clone()));
+    }
+
+    // Reported only when they are not parallels already
+    let parallel_outer = violations.iter().any(|v| v.chords == chords && v.voices == [Voice::Soprano, Voice::Bass]);
+    if has_hidden_perfects(prev, voicing) && !parallel_outer {
+        violations.push(Violation::new(ViolationKind::HiddenPerfects, vec![Voice::Soprano, Voice::Bass], chords.clone()));
+    }
+
+    for pair in VOICES.windows(2) {
+        let (upper, lower) = (pair[0], pair[1]);
+        if voicing.voice(upper) < prev.voice(lower) || voicing.voice(lower) > prev.voice(upper) {
+            violations.push(Violation::new(ViolationKind::VoiceOverlap, pair.to_vec(), chords.clone()));
+        }
+    }
+
+    // The bass is given, so only the upper voices are held to these
+    for voice in [Voice::Soprano, Voice::Alto, Voice::Tenor] {
+        let (from, to) = (prev.voice(voice), voicing.voice(voice));
+        let step = to.semitones() - from.semitones();
+        if let Some(seventh) = before.seventh()
+            && from.pitch_class() == seventh
+            && !(step == -1 || step == -2 || (step == 0 && symbol.seventh() == Some(seventh)))
+        {
+            violations.push(Violation::new(ViolationKind::UnresolvedSeventh, vec![voice], chords.clone()));
+        }
+        if is_augmented_or_diminished(from, to, &symbol.key) {
+            violations.push(Violation::new(ViolationKind::AugmentedLeap, vec![voice], chords.clone()));
+        }
+    }
+}
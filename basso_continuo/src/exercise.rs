@@ -0,0 +1,290 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::figure::{Figure, FiguredBassSymbol, Interval};
+use crate::key::Key;
+use crate::parse::{parse_progression, ParseError};
+use crate::pitch::{Pitch, PitchClass};
+use crate::range::RangeProfile;
+use crate::roman::RomanNumeral;
+use crate::sample::Rng;
+
+// Figured bass exercises to be realized, made up at random. Each is a
+// phrase in a major or minor key running from the tonic through
+// predominant and dominant chords to a V-I cadence; each difficulty adds
+// to the one before it.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Difficulty {
+    #[default]
+    Diatonic,    // root position triads
+    Inversions,  // first inversions, diminished triads and the cadential 6/4
+    Sevenths,    // V7 in every position, ii65 and the leading-tone seventh
+    Suspensions, // a 4-3 at the cadence and 9-8s along the way
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Difficulty::Diatonic => "diatonic",
+            Difficulty::Inversions => "inversions",
+            Difficulty::Sevenths => "sevenths",
+            Difficulty::Suspensions => "suspensions",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "diatonic" => Ok(Difficulty::Diatonic),
+            "inversions" => Ok(Difficulty::Inversions),
+            "sevenths" => Ok(Difficulty::Sevenths),
+            "suspensions" => Ok(Difficulty::Suspensions),
+            _ => Err(format!(
+                "unknown difficulty {:?}, expected diatonic, inversions, sevenths or suspensions",
+                s
+            )),
+        }
+    }
+}
+
+// An exercise as handed out, and as the realizer reads it
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Exercise {
+    pub key: Key,
+    pub bass_line: String, // "C3 A2:6 D3:6/5 G2:4-3 C3"
+    pub symbols: Vec<FiguredBassSymbol>,
+}
+
+impl fmt::Display for Exercise {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.bass_line)
+    }
+}
+
+// Numerals for each degree as triads and as seventh chords
+const MAJOR: [&str; 7] = ["I", "ii", "iii", "IV", "V", "vi", "vii°"];
+const MINOR: [&str; 7] = ["i", "ii°", "III", "iv", "V", "VI", "vii°"];
+const MAJOR_SEVENTHS: [&str; 7] = ["I", "ii", "iii", "IV", "V", "vi", "viiø"];
+const MINOR_SEVENTHS: [&str; 7] = ["i", "iiø", "III", "iv", "V", "VI", "vii°"];
+const TRIAD_INVERSIONS: [&str; 3] = ["", "6", "64"];
+const SEVENTH_INVERSIONS: [&str; 4] = ["7", "65", "43", "42"];
+
+// The degrees a phrase may move to from `degree`, and how often:
+// tonic to anything, predominants on to the dominant, the dominant home
+// (or deceptively to vi)
+fn successors(degree: usize) -> &'static [(usize, f64)] {
+    match degree {
+        0 => &[(3, 3.0), (1, 2.0), (5, 2.0), (4, 2.0), (6, 1.0), (2, 1.0)],
+        1 => &[(4, 3.0), (6, 1.0)],
+        2 => &[(5, 2.0), (3, 2.0)],
+        3 => &[(4, 3.0), (1, 1.0), (0, 1.0), (6, 1.0)],
+        4 => &[(0, 3.0), (5, 1.0)],
+        5 => &[(3, 3.0), (1, 3.0)],
+        _ => &[(0, 1.0)],
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Suspension {
+    FourThree,
+    NineEight,
+}
+
+// One chord of the exercise before it is written out
+struct Chord {
+    degree: usize,
+    inversion: usize,
+    seventh: bool,
+    suspension: Option<Suspension>,
+}
+
+impl Chord {
+    fn numeral(&self, key: &Key) -> Result<RomanNumeral, ParseError> {
+        let (triads, sevenths) = if key.mode.is_minor() { (MINOR, MINOR_SEVENTHS) } else { (MAJOR, MAJOR_SEVENTHS) };
+        let text = match self.seventh {
+            true => format!("{}{}", sevenths[self.degree], SEVENTH_INVERSIONS[self.inversion]),
+            false => format!("{}{}", triads[self.degree], TRIAD_INVERSIONS[self.inversion]),
+        };
+        text.parse()
+    }
+}
+
+impl Exercise {
+    // A phrase of `length` chords (three at least) in `key`, the same one
+    // again for the same seed. Only major and minor keys have the chords
+    // it is made of.
+    pub fn generate(key: &Key, length: usize, difficulty: Difficulty, seed: u64) -> Result<Exercise, ParseError> {
+        let mut rng = Rng::new(seed);
+        let length = length.max(3);
+        let diminished = |degree: usize| degree == 6 || (degree == 1 && key.mode.is_minor());
+        let allowed = |degree: usize| difficulty >= Difficulty::Inversions || !diminished(degree);
+
+        // Chord 0 is the tonic and the last two V-I, so the walk between
+        // has to end on a chord that can go to V: can_end[i][d] is whether
+        // degree d on chord i still can
+        let mut can_end = vec![[false; 7]; length - 1];
+        can_end[length - 3] = std::array::from_fn(|d| allowed(d) && successors(d).iter().any(|&(next, _)| next == 4));
+        for i in (1..length - 3).rev() {
+            let after = can_end[i + 1];
+            can_end[i] = std::array::from_fn(|d| allowed(d) && successors(d).iter().any(|&(next, _)| after[next]));
+        }
+        let mut degrees = vec![0];
+        for i in 1..length - 2 {
+            let options: Vec<(usize, f64)> =
+                successors(degrees[i - 1]).iter().copied().filter(|&(d, _)| can_end[i][d]).collect();
+            let weights: Vec<f64> = options.iter().map(|&(_, weight)| weight).collect();
+            degrees.push(options[rng.weighted(&weights)].0);
+        }
+        degrees.extend([4, 0]);
+
+        let mut chords: Vec<Chord> =
+            degrees.iter().map(|&degree| Chord { degree, inversion: 0, seventh: false, suspension: None }).collect();
+        let middle = 1..length - 2;
+
+        if difficulty >= Difficulty::Inversions {
+            for chord in &mut chords[middle.clone()] {
+                if diminished(chord.degree) || rng.chance(1.0 / 3.0) {
+                    chord.inversion = 1;
+                }
+            }
+            // V6 leads to a tonic in root position
+            for i in middle.clone() {
+                if chords[i].degree == 4 && chords[i + 1].inversion != 0 {
+                    chords[i].inversion = 0;
+                }
+            }
+            // I64 between a predominant and the cadential V
+            let six_four = length - 3;
+            if six_four >= 2 && degrees[six_four] == 0 && matches!(degrees[six_four - 1], 1 | 3) && rng.chance(0.5) {
+                chords[six_four].inversion = 2;
+            }
+        }
+
+        if difficulty >= Difficulty::Sevenths {
+            for i in 0..length {
+                match chords[i].degree {
+                    4 if rng.chance(0.5) => {
+                        chords[i].seventh = true;
+                        // The position follows from the resolution: V7 or
+                        // V65 to a tonic in root position, V43 or V42 to
+                        // one in first inversion, V7 to vi
+                        if middle.contains(&i) {
+                            chords[i].inversion = match (chords[i + 1].degree, chords[i + 1].inversion) {
+                                (0, 0) => rng.weighted(&[1.0; 2]),
+                                (0, _) => 2 + rng.weighted(&[1.0; 2]),
+                                _ => 0,
+                            };
+                        }
+                    }
+                    1 if rng.chance(0.5) => {
+                        chords[i].seventh = true;
+                        chords[i].inversion = 1;
+                    }
+                    6 if rng.chance(1.0 / 3.0) => chords[i].seventh = true,
+                    _ => {}
+                }
+            }
+        }
+
+        let numerals: Vec<RomanNumeral> = chords.iter().map(|chord| chord.numeral(key)).collect::<Result<_, _>>()?;
+
+        // Each bass in the octave nearest a point halfway between the bass
+        // before and D3, as for Roman numerals, but kept in the bass range
+        const CENTER: u8 = 50;
+        let range = RangeProfile::default().bass;
+        let mut prev = CENTER;
+        let mut symbols = Vec::with_capacity(length);
+        for numeral in &numerals {
+            let mut symbol = numeral.symbol(key, Pitch::new((prev + CENTER) / 2))?;
+            if symbol.bass.midi_number < range.min {
+                symbol = numeral.symbol(key, Pitch::new(symbol.bass.midi_number + 12))?;
+            } else if symbol.bass.midi_number > range.max {
+                symbol = numeral.symbol(key, Pitch::new(symbol.bass.midi_number - 12))?;
+            }
+            prev = symbol.bass.midi_number;
+            symbols.push(symbol);
+        }
+
+        // A suspension needs its note in the chord before, and not while
+        // that chord is a 6/4 resolving already
+        if difficulty >= Difficulty::Suspensions {
+            for i in 1..length - 1 {
+                let chord = &chords[i];
+                if chord.seventh || chord.inversion != 0 || chords[i - 1].inversion == 2 {
+                    continue;
+                }
+                let (suspension, odds) = match i == length - 2 {
+                    true => (Suspension::FourThree, 0.6),
+                    false => (Suspension::NineEight, 0.25),
+                };
+                let prepared = numerals[i - 1].pitch_classes(key).contains(&suspended(&symbols[i], suspension, key));
+                if prepared && rng.chance(odds) {
+                    chords[i].suspension = Some(suspension);
+                }
+            }
+        }
+
+        let events: Vec<String> = chords
+            .iter()
+            .zip(&symbols)
+            .map(|(chord, symbol)| {
+                let bass = symbol.spell(symbol.bass).to_string();
+                let figures = match chord.suspension {
+                    Some(suspension) => {
+                        // A plain triad after the dash is written "3", as in "4-3"
+                        let (first, second) = suspension_figures(&symbol.figure, suspension);
+                        let second = match second.abbreviated() {
+                            text if text.is_empty() => "3".to_string(),
+                            text => text,
+                        };
+                        format!("{}-{}", first.abbreviated(), second)
+                    }
+                    None => symbol.figure.abbreviated(),
+                };
+                match figures.is_empty() {
+                    true => bass,
+                    false => format!("{}:{}", bass, figures),
+                }
+            })
+            .collect();
+        let bass_line = events.join(" ");
+        let symbols = parse_progression(&bass_line, key)?;
+        Ok(Exercise { key: key.clone(), bass_line, symbols })
+    }
+}
+
+// The note a suspension over `symbol` holds over from the chord before
+fn suspended(symbol: &FiguredBassSymbol, suspension: Suspension, key: &Key) -> PitchClass {
+    let number = match suspension {
+        Suspension::FourThree => 4,
+        Suspension::NineEight => 9,
+    };
+    symbol.figure.interval_pitch(symbol.bass, &Interval::diatonic(number), key).pitch_class()
+}
+
+// The figure a root position triad takes while suspended, and then
+// resolved: 5/4 then 5/3, or 9/5/3 then 8/5/3
+fn suspension_figures(figure: &Figure, suspension: Suspension) -> (Figure, Figure) {
+    let mut held = figure.clone();
+    let mut resolved = figure.clone();
+    match suspension {
+        Suspension::FourThree => {
+            held.intervals.retain(|iv| iv.number != 3);
+            held.intervals.push(Interval::diatonic(4));
+        }
+        Suspension::NineEight => {
+            held.intervals.push(Interval::diatonic(9));
+            resolved.intervals.push(Interval::diatonic(8));
+        }
+    }
+    held.intervals.sort_by_key(|iv| iv.number);
+    resolved.intervals.sort_by_key(|iv| iv.number);
+    (held, resolved)
+}
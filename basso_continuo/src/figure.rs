@@ -0,0 +1,364 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::key::Key;
+use crate::meter::{Duration, Meter};
+use crate::parse::{self, ParseError};
+use crate::pitch::{Letter, Pitch, PitchClass, SpelledPitch};
+use crate::voicing::Completeness;
+
+// The intervals above the bass, with any implied ones filled in
+// (so "6" is stored as 6/3 and a blank figure as 5/3)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Figure {
+    pub intervals: Vec<Interval>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Interval {
+    pub number: u8, // 3, 5, 6, 7, etc.
+    pub accidental: Accidental,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Accidental {
+    Natural,
+    Sharp,
+    Flat,
+    None, // Use the key signature
+}
+
+impl Interval {
+    pub fn diatonic(number: u8) -> Self {
+        Self { number, accidental: Accidental::None }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let accidental = match self.accidental {
+            Accidental::Natural => "♮",
+            Accidental::Sharp => "#",
+            Accidental::Flat => "b",
+            Accidental::None => "",
+        };
+        write!(f, "{}{}", accidental, self.number)
+    }
+}
+
+// Top to bottom, the way figures are stacked over the bass: "7/5/3"
+impl fmt::Display for Figure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, interval) in self.intervals.iter().rev().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            write!(f, "{}", interval)?;
+        }
+        Ok(())
+    }
+}
+
+// Conventional figured bass notation, see `parse::parse_figure`
+impl FromStr for Figure {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse::parse_figure(s)
+    }
+}
+
+impl Figure {
+    // Blank figure: root position triad (5/3)
+    pub fn root_position() -> Self {
+        Figure { intervals: vec![Interval::diatonic(3), Interval::diatonic(5)] }
+    }
+
+    // The shortest figure that reads back as this one, as it would be
+    // written: "6" for 6/3, "" for 5/3, "#3" for 5/#3, "4/2" for 6/4/2
+    pub fn abbreviated(&self) -> String {
+        let mut sorted = self.intervals.clone();
+        sorted.sort_by_key(|iv| iv.number);
+        // Leave out each combination of intervals with no accidental, the
+        // most left out first, until the rest still reads as the whole
+        let optional: Vec<usize> =
+            (0..sorted.len()).filter(|&i| sorted[i].accidental == Accidental::None).collect();
+        let mut masks: Vec<u32> = (0..1u32 << optional.len()).collect();
+        masks.sort_by_key(|mask| std::cmp::Reverse(mask.count_ones()));
+        for mask in masks {
+            let kept: Vec<String> = (0..sorted.len())
+                .rev()
+                .filter(|i| optional.iter().position(|o| o == i).is_none_or(|bit| mask & (1 << bit) == 0))
+                .map(|i| sorted[i].to_string())
+                .collect();
+            let text = kept.join("/");
+            if parse::parse_figure(&text).is_ok_and(|figure| figure.intervals == sorted) {
+                return text;
+            }
+        }
+        self.to_string()
+    }
+
+    // Convert figured bass to actual chord tones
+    pub fn realize(&self, bass: Pitch, key: &Key) -> Vec<Pitch> {
+        let mut tones = vec![bass]; // Bass is always included
+
+        for interval in &self.intervals {
+            let semitones = self.interval_to_semitones(bass, interval, key);
+            let pitch = Pitch::new(bass.midi_number + semitones as u8);
+            tones.push(pitch);
+        }
+
+        tones.sort();
+        tones.dedup_by_key(|p| p.pitch_class());
+        tones
+    }
+
+    // Pitch of one of the figure's intervals above `bass`, in the octave
+    // above it
+    pub fn interval_pitch(&self, bass: Pitch, interval: &Interval, key: &Key) -> Pitch {
+        Pitch::new(bass.midi_number + self.interval_to_semitones(bass, interval, key) as u8)
+    }
+
+    // The figure that puts each (number, pitch class) pair above `bass` in
+    // `key`, with whatever accidentals that takes. None when a pitch class
+    // is more than a semitone from the note its number gives.
+    pub fn for_tones(bass: Pitch, tones: &[(u8, PitchClass)], key: &Key) -> Option<Self> {
+        let bass_degree = key.pitch_class_to_scale_degree(bass.pitch_class().0);
+        let blank = Figure { intervals: Vec::new() };
+        let mut intervals = Vec::new();
+        for &(number, target) in tones {
+            let diatonic = blank.interval_pitch(bass, &Interval::diatonic(number), key).pitch_class();
+            let natural = PitchClass::new(key.natural_pitch_class(bass_degree + number as usize - 1));
+            let accidental = match diatonic.interval_to(target) {
+                0 => Accidental::None,
+                _ if target == natural => Accidental::Natural,
+                1 => Accidental::Sharp,
+                11 => Accidental::Flat,
+                _ => return None,
+            };
+            intervals.push(Interval { number, accidental });
+        }
+        Some(Figure { intervals })
+    }
+
+    // How many scale steps the root lies above the bass: the chord tone
+    // the others stack up from in thirds (0 for root position, 5 for a
+    // 6 chord, 3 for 6/4, ...). None when the figure is not a stack of
+    // thirds, e.g. a suspension
+    pub fn root_step(&self) -> Option<u8> {
+        let steps: Vec<u8> = std::iter::once(0).chain(self.intervals.iter().map(|i| (i.number - 1) % 7)).collect();
+        steps.iter().copied().find(|&root| steps.iter().all(|&s| (s + 7 - root) % 7 % 2 == 0))
+    }
+
+    fn interval_to_semitones(&self, bass: Pitch, interval: &Interval, key: &Key) -> i8 {
+        let bass_pc = bass.pitch_class();
+        let bass_degree = key.pitch_class_to_scale_degree(bass_pc.0);
+
+        // Calculate target scale degree
+        let target_degree = (bass_degree + interval.number as usize - 1) % 7;
+        let target_pc = PitchClass::new(key.scale[target_degree]);
+
+        // Accidentals are relative to the key signature: a sharp or flat
+        // moves the diatonic note a semitone, a natural cancels the
+        // signature on that letter
+        let target_pc = match interval.accidental {
+            Accidental::None => target_pc,
+            Accidental::Sharp => target_pc.transpose(1),
+            Accidental::Flat => target_pc.transpose(-1),
+            Accidental::Natural => PitchClass::new(key.natural_pitch_class(target_degree)),
+        };
+
+        bass_pc.interval_to(target_pc) as i8
+    }
+}
+
+// The part a chord tone plays in its chord
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChordMember {
+    Root,
+    Third,
+    Fifth,
+    Seventh,
+    Other, // a suspension, added ninth or other non-tertian tone
+}
+
+impl ChordMember {
+    // Member `steps` scale steps above the root
+    fn above_root(steps: u8) -> Self {
+        match steps % 7 {
+            0 => ChordMember::Root,
+            2 => ChordMember::Third,
+            4 => ChordMember::Fifth,
+            6 => ChordMember::Seventh,
+            _ => ChordMember::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FiguredBassSymbol {
+    pub bass: Pitch,
+    pub figure: Figure,
+    pub chord_tones: Vec<Pitch>,
+    // Each pitch class in the chord and the member it is; a figure that is
+    // not a stack of thirds is read from the bass
+    pub members: Vec<(PitchClass, ChordMember)>,
+    pub key: Key,
+    // The bass is held over from the symbol before: a later harmonic state
+    // of the same bass note, as in a suspension's resolution
+    pub held: bool,
+    // The held bass is a pedal, sounding on under a new harmony rather
+    // than resolving a suspension
+    pub pedal: bool,
+    // The letter each pitch class of the chord is written with, counting
+    // the figures up from the bass: a 6 over F# is a D, never a C##
+    pub letters: Vec<(PitchClass, Letter)>,
+    // Overrides the realizer's completeness policy for this chord
+    pub completeness: Option<Completeness>,
+    // How long the chord lasts, and where in a bar of `meter` it starts:
+    // a beat on the downbeat of 4/4 unless the input says otherwise
+    pub duration: Duration,
+    pub meter: Meter,
+    pub offset: Duration,
+    // The chord ends a phrase, held under a fermata: the lines after it
+    // start afresh
+    pub fermata: bool,
+}
+
+// Each pitch class of `figure` over `bass` and the member it is, the root
+// `root` scale steps above the bass
+fn members(bass: Pitch, figure: &Figure, key: &Key, root: u8) -> Vec<(PitchClass, ChordMember)> {
+    let mut members = vec![(bass.pitch_class(), ChordMember::above_root(7 - root))];
+    for interval in &figure.intervals {
+        let pc = figure.interval_pitch(bass, interval, key).pitch_class();
+        if members.iter().all(|&(p, _)| p != pc) {
+            members.push((pc, ChordMember::above_root(interval.number - 1 + 7 - root)));
+        }
+    }
+    members
+}
+
+impl FiguredBassSymbol {
+    pub fn new(bass: Pitch, figure: Figure, key: &Key) -> Self {
+        let chord_tones = figure.realize(bass, key);
+
+        let bass_letter = key.letter(key.pitch_class_to_scale_degree(bass.pitch_class().0));
+        let mut letters = vec![(bass.pitch_class(), bass_letter)];
+        for interval in &figure.intervals {
+            let pc = figure.interval_pitch(bass, interval, key).pitch_class();
+            if letters.iter().all(|&(p, _)| p != pc) {
+                letters.push((pc, bass_letter.up(interval.number as usize - 1)));
+            }
+        }
+
+        let members = members(bass, &figure, key, figure.root_step().unwrap_or(0));
+
+        Self {
+            bass,
+            figure,
+            chord_tones,
+            members,
+            key: key.clone(),
+            held: false,
+            pedal: false,
+            letters,
+            completeness: None,
+            duration: Duration::BEAT,
+            meter: Meter::COMMON,
+            offset: Duration::ZERO,
+            fermata: false,
+        }
+    }
+
+    // The figure as written over the bass, abbreviated, except over a held
+    // bass: a blank there would read as no change of harmony
+    pub fn written_figure(&self) -> String {
+        match self.figure.abbreviated() {
+            text if text.is_empty() && self.held => self.figure.to_string(),
+            text => text,
+        }
+    }
+
+    // `pitch` spelled as a note of this chord, or as the key reads it if it
+    // is not one
+    pub fn spell(&self, pitch: Pitch) -> SpelledPitch {
+        match self.letters.iter().find(|&&(pc, _)| pc == pitch.pitch_class()) {
+            Some(&(_, letter)) => SpelledPitch::with_letter(pitch, letter),
+            None => pitch.spelled(&self.key),
+        }
+    }
+
+    pub fn member(&self, pitch: Pitch) -> Option<ChordMember> {
+        self.members.iter().find(|&&(pc, _)| pc == pitch.pitch_class()).map(|&(_, m)| m)
+    }
+
+    // Pitch class of `member`, if the chord has one
+    pub fn pitch_class_of(&self, member: ChordMember) -> Option<PitchClass> {
+        self.members.iter().find(|&&(_, m)| m == member).map(|&(pc, _)| pc)
+    }
+
+    // Pitch class of the root; the bass when the figure is not a stack of
+    // thirds
+    pub fn root(&self) -> PitchClass {
+        self.pitch_class_of(ChordMember::Root).unwrap_or(self.bass.pitch_class())
+    }
+
+    // Pitch class of the interval `steps` scale steps above the bass (0 for
+    // the bass itself), if the figure has one
+    fn pitch_class_at_step(&self, steps: u8) -> Option<PitchClass> {
+        if steps == 0 {
+            return Some(self.bass.pitch_class());
+        }
+        self.figure
+            .intervals
+            .iter()
+            .find(|iv| (iv.number - 1) % 7 == steps)
+            .map(|iv| self.figure.interval_pitch(self.bass, iv, &self.key).pitch_class())
+    }
+
+    // Notes suspended in this chord that resolve in `next` over the held
+    // bass, as (suspension, resolution) pitch classes: each interval `next`
+    // drops for the one a step below it, as in 4-3, 7-6 or 9-8
+    pub fn suspensions(&self, next: &FiguredBassSymbol) -> Vec<(PitchClass, PitchClass)> {
+        if !next.held || next.pedal {
+            return Vec::new();
+        }
+        self.figure
+            .intervals
+            .iter()
+            .map(|iv| (iv.number - 1) % 7)
+            .filter(|&steps| steps != 0 && next.pitch_class_at_step(steps).is_none())
+            .filter_map(|steps| Some((self.pitch_class_at_step(steps)?, next.pitch_class_at_step((steps + 6) % 7)?)))
+            .collect()
+    }
+
+    // Make this a chord over a pedal, the bass held on from the chord
+    // before. A figure that is a stack of thirds with the bass is read as
+    // ever; otherwise, if there are three notes over the bass, the harmony
+    // is theirs alone, the bass a note outside it, so 7/4/2 over the tonic
+    // is vii°7. Fewer, as in the 4 of a 4-3, are read from the bass.
+    pub fn over_pedal(&mut self) {
+        self.held = true;
+        self.pedal = true;
+        let mut steps: Vec<u8> = self.figure.intervals.iter().map(|i| (i.number - 1) % 7).collect();
+        steps.sort();
+        steps.dedup();
+        if self.figure.root_step().is_none()
+            && steps.len() >= 3
+            && let Some(root) = steps.iter().copied().find(|&root| steps.iter().all(|&s| (s + 7 - root) % 7 % 2 == 0))
+        {
+            self.members = members(self.bass, &self.figure, &self.key, root);
+        }
+    }
+
+    // Pitch class of the chordal seventh, if this is a seventh chord
+    pub fn seventh(&self) -> Option<PitchClass> {
+        self.pitch_class_of(ChordMember::Seventh)
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt;
+
+use crate::diagnostics::{self, Severity, Violation};
+use crate::figure::FiguredBassSymbol;
+use crate::realize::Realizer;
+use crate::voicing::Voicing;
+
+// Checking a realization written elsewhere, a student's exercise say,
+// against the figured bass it realizes: the rules become a marker rather
+// than a search.
+
+// Marks out of 100 lost for each problem found
+const ERROR_DEDUCTION: u32 = 10;
+const WARNING_DEDUCTION: u32 = 3;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grade {
+    // Every problem, wrong notes and doubling among them, chord by chord
+    pub violations: Vec<Violation>,
+    // The rules' total for the realization, and for the best one the
+    // realizer finds for the same bass (None if it finds none)
+    pub score: f32,
+    pub best: Option<f32>,
+    pub mark: u32, // out of 100
+}
+
+impl Grade {
+    pub fn errors(&self) -> usize {
+        self.violations.iter().filter(|v| v.severity == Severity::Error).count()
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.violations.iter().filter(|v| v.severity == Severity::Warning).count()
+    }
+}
+
+// "72/100: 2 errors, 3 warnings, score 85.5 (best 151.2)"
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/100: {} errors, {} warnings, score {:.1}", self.mark, self.errors(), self.warnings(), self.score)?;
+        if let Some(best) = self.best {
+            write!(f, " (best {:.1})", best)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GradeError {
+    // The realization has a different number of chords from the bass
+    Length { chords: usize, voicings: usize },
+}
+
+impl fmt::Display for GradeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GradeError::Length { chords, voicings } => {
+                write!(f, "the figured bass has {} chords but the realization has {}", chords, voicings)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GradeError {}
+
+impl Realizer {
+    // Grade `voicings` as a realization of `symbols`, with this realizer's
+    // rules setting the score and its best realization the standard
+    pub fn grade(&self, symbols: &[FiguredBassSymbol], voicings: &[Voicing]) -> Result<Grade, GradeError> {
+        if symbols.len() != voicings.len() {
+            return Err(GradeError::Length { chords: symbols.len(), voicings: voicings.len() });
+        }
+        let violations = diagnostics::analyze(symbols, voicings);
+        let deductions: u32 = violations
+            .iter()
+            .map(|v| match v.severity {
+                Severity::Error => ERROR_DEDUCTION,
+                Severity::Warning => WARNING_DEDUCTION,
+            })
+            .sum();
+        let best = self.realize(symbols).ok().map(|best| self.total_score(symbols, &best));
+        Ok(Grade {
+            violations,
+            score: self.total_score(symbols, voicings),
+            best,
+            mark: 100u32.saturating_sub(deductions),
+        })
+    }
+}
@@ -0,0 +1,252 @@
+use crate::figure::FiguredBassSymbol;
+use crate::key::Key;
+use crate::meter::{self, Duration};
+use crate::pitch::{Pitch, PitchClass};
+use crate::realize::{PinProblem, RealizationError, Realizer};
+use crate::roman::{Quality, RomanNumeral, SeventhKind};
+use crate::voicing::{generate_voicings, Voicing};
+
+// Harmonizing a given soprano: the chords are chosen as well as the inner
+// voices and the bass. Each melody note may take any diatonic triad that
+// contains it, in root position or first inversion, or the dominant
+// seventh; the search then runs over (chord, voicing) pairs with the same
+// rules the realizer uses, plus a preference for common-practice chord
+// successions.
+
+// Preferences between chords, on top of the voice-leading rules
+const PROGRESSION: f32 = 20.0; // a move the common practice favours, such as ii to V
+const RETROGRESSION: f32 = 40.0; // penalty for moving against it, such as V to IV
+const REPETITION: f32 = 10.0; // penalty for the same chord twice running
+const INVERSION: f32 = 5.0; // penalty for a chord out of root position
+const CADENCE: f32 = 100.0; // starting and ending on the tonic, and V before the last chord
+
+// A harmonized melody: the chords chosen, their voicings and the total score
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Harmonization {
+    pub numerals: Vec<RomanNumeral>,
+    pub symbols: Vec<FiguredBassSymbol>,
+    pub voicings: Vec<Voicing>,
+    pub score: f32,
+}
+
+// One chord a melody note may take, with the bass in one octave
+struct Choice {
+    numeral: RomanNumeral,
+    symbol: FiguredBassSymbol,
+    voicings: Vec<Voicing>,
+}
+
+impl Realizer {
+    // The best harmonization of `melody` in `key`, with the soprano singing
+    // the melody as given (always a full search, whatever the strategy).
+    // Pins apply as to a realization, chord i being melody note i.
+    pub fn harmonize(&self, melody: &[Pitch], key: &Key) -> Result<Harmonization, RealizationError> {
+        if let Some(&pin) = self.pins.iter().find(|pin| pin.chord >= melody.len()) {
+            return Err(RealizationError::Unsatisfiable { pin, problem: PinProblem::NoSuchChord });
+        }
+        let choices: Vec<Vec<Choice>> = melody
+            .iter()
+            .enumerate()
+            .map(|(i, &soprano)| {
+                let choices = self.choices(i, soprano, key);
+                if choices.is_empty() {
+                    return Err(match self.pins.iter().find(|pin| pin.chord == i) {
+                        Some(&pin) => RealizationError::Unsatisfiable { pin, problem: PinProblem::NoVoicing },
+                        None => RealizationError::NoHarmony { note: i, soprano },
+                    });
+                }
+                Ok(choices)
+            })
+            .collect::<Result<_, _>>()?;
+
+        // The states of note i are every voicing of every choice, as
+        // (choice, voicing) indices
+        let states: Vec<Vec<(usize, usize)>> = choices
+            .iter()
+            .map(|step| {
+                step.iter().enumerate().flat_map(|(c, choice)| (0..choice.voicings.len()).map(move |v| (c, v))).collect()
+            })
+            .collect();
+        let last = melody.len().saturating_sub(1);
+
+        // Viterbi as in realize_optimal: best[i][s] is the highest score of a
+        // harmonization of notes 0..=i ending in state s, back[i][s] the
+        // state of note i - 1 it came from
+        let mut best: Vec<Vec<f32>> = Vec::with_capacity(melody.len());
+        let mut back: Vec<Vec<usize>> = Vec::with_capacity(melody.len());
+        for (i, step) in states.iter().enumerate() {
+            // The rules see the chord before and this one; windows[p][c]
+            // holds that pair of symbols for choices p and c
+            let windows: Vec<Vec<Vec<FiguredBassSymbol>>> = match i {
+                0 => vec![choices[0].iter().map(|choice| vec![choice.symbol.clone()]).collect()],
+                _ => choices[i - 1]
+                    .iter()
+                    .map(|before| {
+                        choices[i].iter().map(|choice| vec![before.symbol.clone(), choice.symbol.clone()]).collect()
+                    })
+                    .collect(),
+            };
+
+            let (scores, from): (Vec<f32>, Vec<usize>) = step
+                .iter()
+                .map(|&(c, v)| {
+                    let choice = &choices[i][c];
+                    let voicing = &choice.voicings[v];
+                    let local = position_score(&choice.numeral, i, last);
+                    if i == 0 {
+                        return (local + self.evaluate(&windows[0][c], 0, voicing, None, None).0, 0);
+                    }
+                    states[i - 1]
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &(p, pv))| {
+                            let before = &choices[i - 1][p];
+                            let earlier = (i >= 2).then(|| {
+                                let (ep, ev) = states[i - 2][back[i - 1][j]];
+                                &choices[i - 2][ep].voicings[ev]
+                            });
+                            let rules = self.evaluate(&windows[p][c], 1, voicing, Some(&before.voicings[pv]), earlier).0;
+                            let harmony = progression_score(&before.numeral, &choice.numeral);
+                            (best[i - 1][j] + local + rules + harmony, j)
+                        })
+                        .fold((f32::MIN, 0), |a, b| if b.0 > a.0 { b } else { a })
+                })
+                .unzip();
+            best.push(scores);
+            back.push(from);
+        }
+
+        let Some(scores) = best.last() else {
+            return Ok(Harmonization { numerals: Vec::new(), symbols: Vec::new(), voicings: Vec::new(), score: 0.0 });
+        };
+        let mut s = (0..scores.len()).fold(0, |a, b| if scores[b] > scores[a] { b } else { a });
+        let score = scores[s];
+
+        let mut path = Vec::with_capacity(melody.len());
+        for i in (0..melody.len()).rev() {
+            path.push(states[i][s]);
+            s = back[i][s];
+        }
+        path.reverse();
+
+        let mut harmonization = Harmonization { numerals: Vec::new(), symbols: Vec::new(), voicings: Vec::new(), score };
+        for (i, (c, v)) in path.into_iter().enumerate() {
+            let choice = &choices[i][c];
+            harmonization.numerals.push(choice.numeral.clone());
+            harmonization.symbols.push(choice.symbol.clone());
+            harmonization.voicings.push(choice.voicings[v].clone());
+        }
+        meter::place(&mut harmonization.symbols);
+        harmonization.voicings = self.allowed(&harmonization.symbols, harmonization.voicings)?;
+        Ok(harmonization)
+    }
+
+    // Every chord that can go under `soprano` as note i, in every octave of
+    // the bass range, with the voicings that keep the soprano on it
+    fn choices(&self, i: usize, soprano: Pitch, key: &Key) -> Vec<Choice> {
+        let bass = self.ranges.bass;
+        let mut choices = Vec::new();
+        for numeral in candidate_numerals(key) {
+            if !numeral.pitch_classes(key).contains(&soprano.pitch_class()) {
+                continue;
+            }
+            let mut basses: Vec<Pitch> = (bass.min..=bass.max)
+                .step_by(12)
+                .chain([bass.max])
+                .map(|near| numeral.bass(key).nearest(Pitch::new(near)))
+                .filter(|&pitch| bass.contains(pitch))
+                .collect();
+            basses.sort();
+            basses.dedup();
+            for near in basses {
+                let Ok(mut symbol) = numeral.symbol(key, near) else {
+                    continue;
+                };
+                // Each melody note a beat of 4/4, for the rules that go by
+                // the meter
+                symbol.offset = Duration::new(i as u32, 1).within(symbol.meter.bar());
+                let voicings: Vec<Voicing> = generate_voicings(&symbol, &self.ranges, self.completeness)
+                    .into_iter()
+                    .filter(|voicing| voicing.soprano == soprano && self.pinned(i, voicing))
+                    .collect();
+                if !voicings.is_empty() {
+                    choices.push(Choice { numeral: numeral.clone(), symbol, voicings });
+                }
+            }
+        }
+        choices
+    }
+}
+
+// The diatonic triads in root position and first inversion (the
+// diminished triad only in first inversion), and V7 and V65. In minor the
+// dominant is major and vii° is built on the leading tone.
+fn candidate_numerals(key: &Key) -> Vec<RomanNumeral> {
+    let mut numerals = Vec::new();
+    for degree in 0..7 {
+        let chord: Vec<PitchClass> = key.diatonic_chord(degree, 3).into_iter().map(PitchClass::new).collect();
+        let quality = match (key.mode.is_minor(), degree) {
+            (true, 4) => Quality::Major,
+            (true, 6) => Quality::Diminished,
+            _ => match (chord[0].interval_to(chord[1]), chord[0].interval_to(chord[2])) {
+                (4, 7) => Quality::Major,
+                (3, 7) => Quality::Minor,
+                (3, 6) => Quality::Diminished,
+                _ => Quality::Augmented,
+            },
+        };
+        let numeral = |inversion, seventh| RomanNumeral { degree, alteration: 0, quality, seventh, inversion, applied: None };
+        if quality != Quality::Diminished {
+            numerals.push(numeral(0, None));
+        }
+        numerals.push(numeral(1, None));
+        if degree == 4 && quality == Quality::Major {
+            numerals.push(numeral(0, Some(SeventhKind::Minor)));
+            numerals.push(numeral(1, Some(SeventhKind::Minor)));
+        }
+    }
+    numerals
+}
+
+// How well `next` follows `chord`, by root movement between the degrees
+fn progression_score(chord: &RomanNumeral, next: &RomanNumeral) -> f32 {
+    if chord.degree == next.degree {
+        return if chord == next { -REPETITION } else { 0.0 };
+    }
+    let favoured: &[usize] = match chord.degree {
+        0 => &[1, 2, 3, 4, 5, 6],
+        1 => &[4, 6],
+        2 => &[3, 5],
+        3 => &[0, 1, 4, 6],
+        4 => &[0, 5],
+        5 => &[1, 3, 4],
+        _ => &[0],
+    };
+    let retrogressive: &[usize] = match chord.degree {
+        4 => &[1, 3],
+        6 => &[1, 3, 5],
+        1 => &[3],
+        _ => &[],
+    };
+    if favoured.contains(&next.degree) {
+        PROGRESSION
+    } else if retrogressive.contains(&next.degree) {
+        -RETROGRESSION
+    } else {
+        0.0
+    }
+}
+
+// What a chord is worth where it stands: the first and last chords on the
+// tonic in root position, the one before the last on the dominant
+fn position_score(numeral: &RomanNumeral, i: usize, last: usize) -> f32 {
+    let mut score = if numeral.inversion > 0 { -INVERSION } else { 0.0 };
+    if (i == 0 || i == last) && numeral.degree == 0 && numeral.inversion == 0 {
+        score += CADENCE;
+    }
+    if last > 0 && i == last - 1 && numeral.degree == 4 && numeral.inversion == 0 {
+        score += CADENCE;
+    }
+    score
+}
@@ -0,0 +1,142 @@
+use crate::figure::FiguredBassSymbol;
+use crate::key::{Key, Mode};
+use crate::meter::{Duration, Meter};
+use crate::pitch::{Letter, SpelledPitch};
+use crate::voicing::{Voice, Voicing};
+
+// Humdrum **kern: a realization as a spine for each voice, lowest first as
+// Humdrum lays them out, with the figures in a **fb spine beside the bass
+// (where Verovio looks for them), for the Humdrum toolkit and corpus
+// tools. Each chord is as long as its symbol, in bars of the first
+// symbol's meter, and one that ends a phrase has a fermata (";") in every
+// voice.
+
+// The spines, left to right
+const VOICES: [(Voice, &str, &str); 4] = [
+    (Voice::Bass, "Bass", "*clefF4"),
+    (Voice::Tenor, "Tenor", "*clefGv2"),
+    (Voice::Alto, "Alto", "*clefG2"),
+    (Voice::Soprano, "Soprano", "*clefG2"),
+];
+
+pub fn write_kern(symbols: &[FiguredBassSymbol], realization: &[Voicing], title: &str) -> String {
+    let key = symbols.first().map_or_else(Key::c_major, |symbol| symbol.key.clone());
+    let mut lines = vec![format!("!!!OTL: {}", title)];
+
+    // The **fb spine comes second, after the bass, and has no
+    // interpretations of its own
+    let record = |bass: String, fb: String, upper: [String; 3]| {
+        let [tenor, alto, soprano] = upper;
+        [bass, fb, tenor, alto, soprano].join("\t")
+    };
+    let every = |token: &str| record(token.to_string(), "*".to_string(), std::array::from_fn(|_| token.to_string()));
+    lines.push(record("**kern".to_string(), "**fb".to_string(), std::array::from_fn(|_| "**kern".to_string())));
+    lines.push(record(
+        format!("*I\"{}", VOICES[0].1),
+        "*".to_string(),
+        std::array::from_fn(|i| format!("*I\"{}", VOICES[i + 1].1)),
+    ));
+    lines.push(record(VOICES[0].2.to_string(), "*".to_string(), std::array::from_fn(|i| VOICES[i + 1].2.to_string())));
+    lines.push(every(&key_signature(&key)));
+    lines.push(every(&key_designation(&key)));
+    let meter = symbols.first().map_or(Meter::COMMON, |symbol| symbol.meter);
+    lines.push(every(&format!("*M{}", meter)));
+
+    let mut bars = 1;
+    for (i, (voicing, symbol)) in realization.iter().zip(symbols).enumerate() {
+        if i > 0 && symbol.offset == Duration::ZERO {
+            bars += 1;
+            let bar = format!("={}", bars);
+            lines.push(record(bar.clone(), bar.clone(), std::array::from_fn(|_| bar.clone())));
+        }
+        // A voice keeping its note into a held chord is tied over
+        let tie = |voice: Voice| {
+            let pitch = voicing.voice(voice);
+            let from = i > 0 && symbol.held && realization[i - 1].voice(voice) == pitch;
+            let to = symbols.get(i + 1).is_some_and(|next| next.held) && realization.get(i + 1).is_some_and(|next| next.voice(voice) == pitch);
+            let mut note = recip(symbol.duration) + &kern_pitch(symbol.spell(pitch));
+            if symbol.fermata {
+                note.push(';');
+            }
+            match (from, to) {
+                (false, false) => note,
+                (false, true) => format!("[{}", note),
+                (true, true) => format!("{}_", note),
+                (true, false) => format!("{}]", note),
+            }
+        };
+        let figure = match fb_figure(symbol) {
+            text if text.is_empty() => ".".to_string(),
+            text => text,
+        };
+        lines.push(record(tie(VOICES[0].0), figure, std::array::from_fn(|i| tie(VOICES[i + 1].0))));
+    }
+    lines.push(record("==".to_string(), "==".to_string(), std::array::from_fn(|_| "==".to_string())));
+    lines.push(record("*-".to_string(), "*-".to_string(), std::array::from_fn(|_| "*-".to_string())));
+    lines.join("\n") + "\n"
+}
+
+// A length as kern writes it, the part of a whole note it is turned over:
+// "4" a quarter, "2." a dotted half, "3%2" anything else (two thirds of a
+// whole, here)
+fn recip(duration: Duration) -> String {
+    let (beats, per) = (duration.numerator, duration.denominator * 4); // wholes: beats / per
+    // In lowest terms: the duration's are, so only a factor of 4 is shared
+    let common = [4, 2, 1].into_iter().find(|n| beats % n == 0).unwrap_or(1);
+    let (beats, per) = (beats / common, per / common);
+    if per % beats == 0 {
+        format!("{}", per / beats)
+    } else if (per * 3) % (beats * 2) == 0 {
+        format!("{}.", per * 3 / (beats * 2))
+    } else {
+        format!("{}%{}", per, beats)
+    }
+}
+
+// C4 is "c", C5 "cc", B3 "B", C2 "CC"; every accidental is written, "#"
+// for sharps and "-" for flats
+fn kern_pitch(pitch: SpelledPitch) -> String {
+    let letter = pitch.letter.to_string();
+    let name = match pitch.octave {
+        octave if octave >= 4 => letter.to_lowercase().repeat((octave - 3) as usize),
+        octave => letter.repeat((4 - octave) as usize),
+    };
+    let accidental = if pitch.accidental > 0 { "#" } else { "-" };
+    name + &accidental.repeat(pitch.accidental.unsigned_abs() as usize)
+}
+
+// **fb figures: the numerals top to bottom, separated by spaces, with
+// "-" for a flat
+fn fb_figure(symbol: &FiguredBassSymbol) -> String {
+    symbol.written_figure().replace('/', " ").replace('b', "-").replace('♮', "n")
+}
+
+// "*k[f#c#]", sharps and flats in the order they are added
+fn key_signature(key: &Key) -> String {
+    let signature = key.signature();
+    let sharps = [Letter::F, Letter::C, Letter::G, Letter::D, Letter::A, Letter::E, Letter::B];
+    let mut text = String::new();
+    for letter in sharps.iter().filter(|&&l| signature[l as usize] > 0) {
+        text.push_str(&format!("{}#", letter.to_string().to_lowercase()));
+    }
+    for letter in sharps.iter().rev().filter(|&&l| signature[l as usize] < 0) {
+        text.push_str(&format!("{}-", letter.to_string().to_lowercase()));
+    }
+    format!("*k[{}]", text)
+}
+
+// "*G:" for G major, "*b-:" for Bb minor, "*d:dor" for D dorian: the
+// tonic in lower case when the third is minor
+fn key_designation(key: &Key) -> String {
+    let tonic = key.tonic_name().replace('b', "-");
+    let tonic = if key.mode.is_minor() { tonic.to_lowercase() } else { tonic };
+    let mode = match key.mode {
+        Mode::Dorian => "dor",
+        Mode::Phrygian => "phr",
+        Mode::Lydian => "lyd",
+        Mode::Mixolydian => "mix",
+        Mode::Locrian => "loc",
+        _ => "",
+    };
+    format!("*{}:{}", tonic, mode)
+}
@@ -0,0 +1,275 @@
+use std::fmt;
+use std::ops::Index;
+use std::str::FromStr;
+
+use crate::pitch::Letter;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Key {
+    pub tonic: u8, // pitch class 0-11
+    pub tonic_letter: Letter, // C# minor, not Db minor
+    pub mode: Mode,
+    pub scale: Scale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mode {
+    Major,
+    Minor, // Natural minor
+    HarmonicMinor,
+    MelodicMinor, // ascending form
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+}
+
+impl Mode {
+    // Semitones from the tonic to each degree
+    pub fn steps(&self) -> [u8; 7] {
+        match self {
+            Mode::Major => [0, 2, 4, 5, 7, 9, 11],
+            Mode::Minor => [0, 2, 3, 5, 7, 8, 10],
+            Mode::HarmonicMinor => [0, 2, 3, 5, 7, 8, 11],
+            Mode::MelodicMinor => [0, 2, 3, 5, 7, 9, 11],
+            Mode::Dorian => [0, 2, 3, 5, 7, 9, 10],
+            Mode::Phrygian => [0, 1, 3, 5, 7, 8, 10],
+            Mode::Lydian => [0, 2, 4, 6, 7, 9, 11],
+            Mode::Mixolydian => [0, 2, 4, 5, 7, 9, 10],
+            Mode::Locrian => [0, 1, 3, 5, 6, 8, 10],
+        }
+    }
+
+    // Whether the third is minor
+    pub fn is_minor(&self) -> bool {
+        self.steps()[2] == 3
+    }
+}
+
+// The pitch classes of a key's seven degrees, tonic first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Scale {
+    pub pitch_classes: [u8; 7],
+}
+
+impl Scale {
+    pub fn new(tonic: u8, mode: Mode) -> Self {
+        Self { pitch_classes: mode.steps().map(|step| (tonic + step) % 12) }
+    }
+
+    // Degree (0 = tonic) of a pitch class in the scale
+    pub fn degree_of(&self, pc: u8) -> Option<usize> {
+        self.pitch_classes.iter().position(|&x| x == pc % 12)
+    }
+
+    pub fn contains(&self, pc: u8) -> bool {
+        self.degree_of(pc).is_some()
+    }
+
+    // The chord built in thirds on `degree` from the scale's own notes:
+    // 3 tones for a triad, 4 for a seventh chord, root first
+    pub fn chord(&self, degree: usize, size: usize) -> Vec<u8> {
+        (0..size).map(|i| self[degree + 2 * i]).collect()
+    }
+}
+
+// Degrees wrap around, so scale[7] is the tonic again
+impl Index<usize> for Scale {
+    type Output = u8;
+
+    fn index(&self, degree: usize) -> &u8 {
+        &self.pitch_classes[degree % 7]
+    }
+}
+
+impl Key {
+    // The key on `tonic` spelled with the fewest accidentals in its
+    // signature: Db major but C# minor. A tie goes to sharps when the
+    // third is minor (D# minor), else to tonic_name's spelling (F# major).
+    pub fn new(tonic: u8, mode: Mode) -> Self {
+        let tonic = tonic % 12;
+        let accidentals = |key: &Key| key.signature().iter().map(|a| a.unsigned_abs() as u32).sum::<u32>();
+        let usual = Key::with_letter(tonic, tonic_letter(tonic), mode);
+        let other = Letter::ALL
+            .into_iter()
+            .filter(|&letter| letter != usual.tonic_letter && matches!((tonic + 12 - letter.pitch_class()) % 12, 1 | 11))
+            .map(|letter| Key::with_letter(tonic, letter, mode))
+            .find(|key| {
+                let sharp = (tonic + 12 - key.tonic_letter.pitch_class()) % 12 == 1;
+                match accidentals(key).cmp(&accidentals(&usual)) {
+                    std::cmp::Ordering::Less => true,
+                    std::cmp::Ordering::Equal => sharp && mode.is_minor(),
+                    std::cmp::Ordering::Greater => false,
+                }
+            });
+        other.unwrap_or(usual)
+    }
+
+    // The key on `tonic` written with `letter`, as the user spelled it:
+    // "C# minor" rather than Db
+    pub fn with_letter(tonic: u8, letter: Letter, mode: Mode) -> Self {
+        let tonic = tonic % 12;
+        Self { tonic, tonic_letter: letter, mode, scale: Scale::new(tonic, mode) }
+    }
+
+    pub fn c_major() -> Self {
+        Self::new(0, Mode::Major)
+    }
+
+    // Letter of a scale degree, counting from the tonic's
+    pub fn letter(&self, degree: usize) -> Letter {
+        self.tonic_letter.up(degree)
+    }
+
+    // The tonic as the key spells it: "C#", "Bb"
+    pub fn tonic_name(&self) -> String {
+        let alter = (self.tonic as i16 - self.tonic_letter.pitch_class() as i16 + 6).rem_euclid(12) - 6;
+        let accidental = if alter < 0 { "b" } else { "#" };
+        format!("{}{}", self.tonic_letter, accidental.repeat(alter.unsigned_abs() as usize))
+    }
+
+    // Pitch class of a scale degree's letter without its key signature
+    // accidental: the 7th degree of D major (C#) gives C
+    pub fn natural_pitch_class(&self, degree: usize) -> u8 {
+        self.letter(degree).pitch_class()
+    }
+
+    // Pitch class a semitone below the tonic, raised in minor (and in
+    // the modes with a whole step below the tonic)
+    pub fn leading_tone(&self) -> u8 {
+        (self.tonic + 11) % 12
+    }
+
+    pub fn contains(&self, pc: u8) -> bool {
+        self.scale.contains(pc)
+    }
+
+    // The accidental the key signature gives each letter, C first, in
+    // sharps as SpelledPitch counts them; harmonic and melodic minor keys
+    // have the natural minor's signature
+    pub fn signature(&self) -> [i8; 7] {
+        let key = match self.mode {
+            Mode::HarmonicMinor | Mode::MelodicMinor => Key::with_letter(self.tonic, self.tonic_letter, Mode::Minor),
+            _ => self.clone(),
+        };
+        let mut signature = [0; 7];
+        for degree in 0..7 {
+            let letter = key.letter(degree);
+            signature[letter as usize] = ((key.scale[degree] as i16 - letter.pitch_class() as i16 + 6).rem_euclid(12) - 6) as i8;
+        }
+        signature
+    }
+
+    // Diatonic triad (or seventh chord, for size 4) on a scale degree,
+    // as pitch classes from the root up
+    pub fn diatonic_chord(&self, degree: usize, size: usize) -> Vec<u8> {
+        self.scale.chord(degree, size)
+    }
+
+    // Scale degree (0 = tonic) a bass note is read as when counting figures.
+    // A note outside the key counts as an altered neighbour the way it is
+    // usually spelled in continuo parts: a raised degree (F#, C#, G# in C
+    // major; the raised 6th and 7th in minor) except for the lowered 3rd
+    // and 7th in major and the lowered 2nd in minor.
+    pub fn pitch_class_to_scale_degree(&self, pc: u8) -> usize {
+        if let Some(degree) = self.scale.degree_of(pc) {
+            return degree;
+        }
+        let below = self.scale.degree_of(pc + 11);
+        let above = self.scale.degree_of(pc + 1);
+        let lowered = if self.mode.is_minor() {
+            above == Some(1)
+        } else {
+            matches!(above, Some(2) | Some(6))
+        };
+        match (below, above) {
+            (_, Some(degree)) if lowered => degree,
+            (Some(degree), _) | (None, Some(degree)) => degree,
+            (None, None) => 0,
+        }
+    }
+}
+
+// Conventional spelling of a major tonic: flats for Db, Eb, Ab and Bb
+pub fn tonic_name(pc: u8) -> &'static str {
+    ["C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B"][(pc % 12) as usize]
+}
+
+fn tonic_letter(pc: u8) -> Letter {
+    tonic_name(pc).chars().next().and_then(Letter::from_char).unwrap_or(Letter::C)
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Mode::Major => "major",
+            Mode::Minor => "minor",
+            Mode::HarmonicMinor => "harmonic minor",
+            Mode::MelodicMinor => "melodic minor",
+            Mode::Dorian => "dorian",
+            Mode::Phrygian => "phrygian",
+            Mode::Lydian => "lydian",
+            Mode::Mixolydian => "mixolydian",
+            Mode::Locrian => "locrian",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// "major", "harmonic minor", "Dorian"; also "ionian" and "aeolian"
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<String> = s.split_whitespace().map(str::to_ascii_lowercase).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match words[..] {
+            [] | ["major"] | ["maj"] | ["ionian"] => Ok(Mode::Major),
+            ["minor"] | ["min"] | ["natural", "minor"] | ["aeolian"] => Ok(Mode::Minor),
+            ["harmonic", "minor"] => Ok(Mode::HarmonicMinor),
+            ["melodic", "minor"] => Ok(Mode::MelodicMinor),
+            ["dorian"] => Ok(Mode::Dorian),
+            ["phrygian"] => Ok(Mode::Phrygian),
+            ["lydian"] => Ok(Mode::Lydian),
+            ["mixolydian"] => Ok(Mode::Mixolydian),
+            ["locrian"] => Ok(Mode::Locrian),
+            _ => Err(format!("unknown mode {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.tonic_name(), self.mode)
+    }
+}
+
+// "C major", "f# minor", "A harmonic minor", "D dorian", "Bb" (major when
+// the mode is left out)
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid key {:?}, expected e.g. \"D minor\" or \"Bb major\"", s);
+        let s = s.trim();
+        let (tonic, mode) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let mode: Mode = mode.parse().map_err(|_| invalid())?;
+
+        let mut chars = tonic.chars();
+        let letter = chars.next().and_then(Letter::from_char).ok_or_else(invalid)?;
+        let mut alter: i16 = 0;
+        for c in chars {
+            match c {
+                '#' => alter += 1,
+                'b' => alter -= 1,
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(Key::with_letter((letter.pitch_class() as i16 + alter).rem_euclid(12) as u8, letter, mode))
+    }
+}
@@ -0,0 +1,80 @@
+pub mod abc;
+#[cfg(feature = "serde")]
+pub mod api;
+pub mod chord;
+pub mod config;
+pub mod diagnostics;
+pub mod exercise;
+pub mod figure;
+pub mod grade;
+pub mod harmonize;
+pub mod kern;
+pub mod key;
+pub mod meter;
+pub mod midi;
+pub mod musicxml;
+#[cfg(feature = "serde")]
+pub mod ndjson;
+pub mod osc;
+pub mod parse;
+pub mod pitch;
+pub mod profile;
+pub mod progression;
+pub mod range;
+pub mod realize;
+pub mod roman;
+pub mod romantext;
+pub mod rules;
+mod sample;
+pub mod scoring;
+#[cfg(feature = "soundfont")]
+pub mod soundfont;
+#[cfg(feature = "serde")]
+pub mod server;
+pub mod stream;
+pub mod synth;
+pub mod temperament;
+pub mod voicing;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
+
+pub use abc::{read_abc, write_abc, AbcError};
+#[cfg(feature = "serde")]
+pub use api::{analyze_json, realize_json, ApiError};
+pub use chord::{parse_chord_progression, ChordSymbol};
+pub use config::{Config, ConfigError};
+pub use diagnostics::{Severity, Violation, ViolationKind};
+pub use exercise::{Difficulty, Exercise};
+pub use figure::{Accidental, ChordMember, Figure, FiguredBassSymbol, Interval};
+pub use grade::{Grade, GradeError};
+pub use harmonize::Harmonization;
+pub use kern::write_kern;
+pub use key::{Key, Mode};
+pub use meter::{Duration, Meter};
+pub use midi::{read_bass_line, write_midi, MidiError, MidiMessage, MidiParser};
+pub use musicxml::{read_figured_bass, write_musicxml, FiguredBassPart, MusicXmlError};
+#[cfg(feature = "serde")]
+pub use ndjson::NdjsonSession;
+pub use osc::{encode_bundle, OscArg, OscMessage, OscSender};
+pub use parse::{
+    parse_event, parse_figure, parse_melody, parse_pitch, parse_progression, parse_realization, parse_spelled_pitch,
+    parse_symbol, parse_voicing, ParseError,
+};
+pub use pitch::{Letter, Pitch, PitchClass, SpelledPitch};
+pub use profile::Profile;
+pub use progression::{Barline, Event, FileError, Item, ProgressionFile, Span};
+pub use range::{RangeProfile, VoiceRange};
+pub use realize::{
+    realize_figured_bass, realize_figured_bass_n, realize_with, total_score, Hint, Pin, PinProblem, Realization,
+    RealizationError, Realizer, Strategy,
+};
+pub use romantext::{read_romantext, write_romantext, RomanTextError};
+pub use roman::{analyze, detect_key, parse_roman_progression, Quality, RomanNumeral, SeventhKind};
+pub use rules::{Rule, RuleContext, RuleOutcome, RuleSet, ScoreBreakdown};
+pub use scoring::ScoringWeights;
+#[cfg(feature = "soundfont")]
+pub use soundfont::{Preset, SoundFont, SoundFontError};
+pub use stream::StreamingRealizer;
+pub use synth::{encode_wav, Synth, Timbre};
+pub use temperament::Temperament;
+pub use voicing::{Completeness, Constraint, Voice, Voicing};
@@ -1,610 +1,926 @@
-use std::fmt;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use basso_continuo::diagnostics;
+#[cfg(feature = "serde")]
+use basso_continuo::NdjsonSession;
+#[cfg(feature = "soundfont")]
+use basso_continuo::SoundFont;
+use basso_continuo::{
+    analyze, detect_key, midi, parse_chord_progression, parse_melody, parse_realization, parse_roman_progression,
+    read_abc, read_bass_line, read_figured_bass, read_romantext, write_abc, write_kern, write_midi, write_musicxml,
+    write_romantext, Completeness,
+    Config, Difficulty, Exercise, FiguredBassSymbol, Key, MidiMessage, MidiParser, OscSender, Pin, Pitch, Profile,
+    ProgressionFile, RangeProfile, Realizer, Severity, Strategy, StreamingRealizer, Synth, Temperament, Timbre, Voicing,
+};
+
+#[derive(Parser)]
+#[command(name = "basso_continuo", version, about = "Realize figured bass in four voices")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Read defaults from FILE (default: ~/.config/basso_continuo/config.toml, if there is one)"
+    )]
+    config: Option<PathBuf>,
+    #[arg(long, global = true, conflicts_with = "config", help = "Ignore the config file")]
+    no_config: bool,
+}
 
-// ============================================================================
-// CORE DATA STRUCTURES
-// ============================================================================
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Realize a progression in four voices and report on the result")]
+    Realize {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        realizer: RealizerArgs,
+        #[arg(long, help = "Label the chords with Roman numerals in the key the progression sounds in")]
+        detect_key: bool,
+        #[arg(long, help = "Show how each rule scored each chord")]
+        explain: bool,
+    },
+    #[command(about = "Label a progression's chords with Roman numerals, and check a realization of it")]
+    Analyze {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(long, help = "Label the chords in the key the progression sounds in rather than in its own")]
+        detect_key: bool,
+        #[arg(
+            long,
+            value_name = "REALIZATION",
+            help = "A realization to check for voice-leading faults: chords of four notes, soprano first, e.g. \"G4,E4,C4,C3 A4,F4,C4,A2\""
+        )]
+        realization: Option<String>,
+    },
+    #[command(about = "Grade a realization of a progression written elsewhere")]
+    Grade {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(help = "The realization: chords of four notes, soprano first, e.g. \"G4,E4,C4,C3 A4,F4,C4,A2 B4,F4,D4,G2\"")]
+        realization: String,
+        #[command(flatten)]
+        realizer: RealizerArgs,
+        #[arg(long, help = "Show how each rule scored each chord")]
+        explain: bool,
+    },
+    #[command(about = "Realize a progression and write it out in another format")]
+    Export {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        realizer: RealizerArgs,
+        #[arg(long, value_enum, help = "The format to write (default: the config file's, if it gives one)")]
+        to: Option<ExportFormat>,
+        #[arg(short, long, value_name = "FILE", help = "Where to write it (default: standard output)")]
+        output: Option<PathBuf>,
+        #[arg(long, help = "The title to give it (default: the input's, else \"Realization\")")]
+        title: Option<String>,
+        #[arg(long, help = "With --to romantext, analyze in the key the progression sounds in")]
+        detect_key: bool,
+        #[arg(long, value_name = "BPM", default_value_t = 72.0, value_parser = tempo, help = "With --to midi, quarter-note beats a minute")]
+        tempo: f32,
+    },
+    #[command(about = "Realize a progression and render it as a WAV file, to hear it without any MIDI set up")]
+    Render {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        realizer: RealizerArgs,
+        #[arg(short, long, value_name = "FILE", help = "The WAV file to write")]
+        output: PathBuf,
+        #[command(flatten)]
+        sound: SoundArgs,
+    },
+    #[command(
+        about = "Realize a progression and play it aloud, showing each chord as it sounds",
+        long_about = "Realize a progression and play it aloud, showing each chord as it sounds. The audio goes through an \
+            external player that reads a WAV file on standard input: aplay, pw-play, sox's play or ffplay, the first \
+            found on the PATH, unless --player names another"
+    )]
+    Play {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        realizer: RealizerArgs,
+        #[command(flatten)]
+        sound: SoundArgs,
+        #[arg(
+            long,
+            value_name = "COMMAND",
+            help = "The program to play the audio with, reading a WAV file on standard input (default: the first of aplay, pw-play, sox's play and ffplay found)"
+        )]
+        player: Option<String>,
+        #[arg(long, help = "Label the chords with Roman numerals in the key the progression sounds in")]
+        detect_key: bool,
+    },
+    #[command(about = "Play along live: realize bass notes from a raw MIDI device as they are played")]
+    Live {
+        #[arg(value_name = "IN", help = "The raw MIDI device to read bass notes from, e.g. /dev/snd/midiC1D0")]
+        input: PathBuf,
+        #[arg(value_name = "OUT", required_unless_present = "osc", help = "The device to send the upper voices of each chord to")]
+        output: Option<PathBuf>,
+        #[arg(long, value_name = "HOST:PORT", help = "Also send each chord as OSC over UDP, e.g. to SuperCollider at 127.0.0.1:57120")]
+        osc: Option<String>,
+        #[arg(long, value_name = "ADDRESS", default_value = "/basso_continuo", help = "What the OSC addresses start with")]
+        osc_prefix: String,
+        #[arg(long, help = "The key the notes are harmonized in (default: the config file's, else C major)")]
+        key: Option<Key>,
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 0,
+            help = "Wait for N more bass notes before choosing a chord, for smoother voice leading at the cost of playing it late"
+        )]
+        lookahead: usize,
+        #[command(flatten)]
+        realizer: RealizerArgs,
+    },
+    #[cfg(feature = "serde")]
+    #[command(about = "Realize bass events read as JSON lines from standard input, a JSON line out for each chord")]
+    Stream {
+        #[arg(long, help = "The key the events are read in until one names another (default: the config file's, else C major)")]
+        key: Option<Key>,
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 0,
+            help = "Wait for N more events before deciding a chord, for smoother voice leading"
+        )]
+        lookahead: usize,
+        #[command(flatten)]
+        realizer: RealizerArgs,
+    },
+    #[cfg(feature = "serde")]
+    #[command(about = "Serve the JSON API over HTTP: POST /realize and POST /analyze")]
+    Serve {
+        #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1:8080", help = "The address to listen on")]
+        listen: String,
+    },
+    #[command(about = "Make up a figured bass to realize, and realize it as the answer")]
+    Exercise {
+        #[arg(help = "diatonic, inversions, sevenths or suspensions, each adding to the one before")]
+        difficulty: Difficulty,
+        #[arg(long, help = "The key of the exercise (default: the config file's, else C major)")]
+        key: Option<Key>,
+        #[arg(long, value_name = "N", default_value_t = 8, value_parser = exercise_length, help = "Chords in the exercise")]
+        length: usize,
+        #[arg(
+            long,
+            value_name = "SEED",
+            help = "The same exercise again for the same SEED (default: a new one each time, its seed shown)"
+        )]
+        exercise_seed: Option<u64>,
+        #[command(flatten)]
+        realizer: RealizerArgs,
+    },
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct Pitch {
-    midi_number: u8, // C4 = 60
+// Where the progression comes from and how it is written
+#[derive(Args)]
+struct InputArgs {
+    #[arg(
+        value_name = "PROGRESSION",
+        help = "The progression, the path of a file holding it, or - for standard input: bass notes with figures, e.g. \"C3 A3:6 G3:7 C3\", unless --from says otherwise"
+    )]
+    progression: String,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = InputFormat::Auto,
+        help = "How the progression is written (default: by the file's extension, else figures)"
+    )]
+    from: InputFormat,
+    #[arg(
+        long,
+        help = "The key the progression is read in, e.g. \"D minor\" or \"E phrygian\" (default: the key the input gives, else the config file's, else C major)"
+    )]
+    key: Option<Key>,
+    #[arg(long, value_name = "N", value_parser = track, help = "The MIDI track to take the bass from, counting from 1 (default: the lowest)")]
+    track: Option<usize>,
+    #[arg(long, value_name = "ID", help = "The MusicXML part to take the bass from (default: the part with figures)")]
+    part: Option<String>,
+    #[arg(long, value_name = "ID", help = "The ABC voice to take the bass from (default: the voice with figures)")]
+    voice: Option<String>,
 }
 
-impl Pitch {
-    fn new(midi_number: u8) -> Self {
-        Self { midi_number }
-    }
-    
-    fn semitones(&self) -> i16 {
-        self.midi_number as i16
-    }
-    
-    fn name(&self) -> String {
-        let names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
-        let octave = (self.midi_number / 12) as i16 - 1;
-        let note = names[(self.midi_number % 12) as usize];
-        format!("{}{}", note, octave)
-    }
-    
-    fn from_bass_and_interval(bass: Pitch, interval: i8, key: &Key) -> Self {
-        let bass_pc = bass.midi_number % 12;
-        let scale_degree = key.pitch_class_to_scale_degree(bass_pc);
-        let target_degree = ((scale_degree as i8 + interval - 1) % 7) as usize;
-        let target_pc = key.scale[target_degree];
-        
-        // Find the closest target_pc above or at bass
-        let mut midi = bass.midi_number;
-        while midi % 12 != target_pc {
-            midi += 1;
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum InputFormat {
+    #[value(help = "By the file's extension: .mid, .musicxml, .xml, .abc or .rntxt; anything else is figures")]
+    Auto,
+    #[value(help = "Bass notes with figures, \"C3 A3:6 G3:7 C3\", or a progression file")]
+    Figures,
+    #[value(help = "Roman numerals in the key, \"I IV6 V7 I\"")]
+    Roman,
+    #[value(help = "Lead-sheet chord symbols, \"Cmaj7 Am7 Dm7 G7\"")]
+    Chords,
+    #[value(help = "A soprano melody, \"E5 D5 C5\", harmonized rather than realized")]
+    Melody,
+    #[value(help = "A MIDI file's lowest line, each note a 5/3 chord")]
+    Midi,
+    #[value(name = "musicxml", help = "A MusicXML score's bass part and its figures")]
+    MusicXml,
+    #[value(help = "An ABC tune's bass voice, figures as annotations (\"_6/5\"C,)")]
+    Abc,
+    #[value(name = "romantext", help = "A RomanText analysis, \"m1 g: i b3 V7\"")]
+    RomanText,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ExportFormat {
+    #[value(help = "An ABC tune of four voices, the figures under the bass")]
+    Abc,
+    #[value(help = "Humdrum **kern, a spine for each voice and the figures in a **fb spine")]
+    Kern,
+    #[value(help = "A Standard MIDI File, a track for each voice")]
+    Midi,
+    #[value(name = "musicxml", help = "A MusicXML score, a part for each voice and the figures over the bass")]
+    MusicXml,
+    #[value(name = "romantext", help = "The Roman numeral analysis as RomanText")]
+    RomanText,
+    #[value(help = "The figured bass itself as a progression file")]
+    Progression,
+}
+
+// How the realizer goes about it
+#[derive(Args)]
+struct RealizerArgs {
+    #[arg(long, help = "Rule set: strict, bach-chorale or continuo (default: the standard rules)")]
+    profile: Option<Profile>,
+    #[arg(
+        long,
+        help = "satb (default), ssaa, ttbb, quartet, or four ranges top voice first, e.g. C4-G5,G3-C5,C3-G4,E2-C4"
+    )]
+    ranges: Option<RangeProfile>,
+    #[arg(
+        long,
+        value_name = "POLICY",
+        help = "Chord tones that may be left out: complete, sevenths (the fifth of seventh chords; default) or omit-fifth"
+    )]
+    completeness: Option<Completeness>,
+    #[arg(long, conflicts_with_all = ["beam", "seed"], help = "Pick the best voicing chord by chord")]
+    greedy: bool,
+    #[arg(long, value_name = "WIDTH", value_parser = beam_width, conflicts_with = "seed", help = "Keep only WIDTH partial realizations per chord")]
+    beam: Option<usize>,
+    #[arg(long, help = "Pick a good realization at random, the same one again for the same SEED")]
+    seed: Option<u64>,
+    #[arg(
+        long,
+        value_name = "T",
+        value_parser = temperature,
+        help = "How far from the best --seed may stray: 0 always picks the best (default 3)"
+    )]
+    temperature: Option<f32>,
+    #[arg(long, help = "Fail rather than break a hard rule when there is no way round it")]
+    forbid_violations: bool,
+    #[arg(
+        long = "pin",
+        value_name = "CHORD:VOICE=PITCH",
+        help = "Make a voice sing a note in one chord, counting chords from 1, e.g. 3:soprano=E5; may be repeated"
+    )]
+    pins: Vec<Pin>,
+}
+
+impl RealizerArgs {
+    // The config file's realizer, with what the flags say instead
+    fn realizer(&self, config: &Config) -> Realizer {
+        let mut config = config.clone();
+        if self.profile.is_some() {
+            config.profile = self.profile;
+            config.weights = None;
+        }
+        config.ranges = self.ranges.clone().or(config.ranges);
+        config.completeness = self.completeness.or(config.completeness);
+        if self.greedy {
+            config.strategy = Some(Strategy::Greedy);
+        } else if let Some(width) = self.beam {
+            config.strategy = Some(Strategy::Beam(width));
+        } else if let Some(seed) = self.seed {
+            config.strategy = Some(Strategy::Sample(seed));
         }
-        
-        Pitch::new(midi)
+        config.temperature = self.temperature.or(config.temperature);
+        config.forbid_violations |= self.forbid_violations;
+        let mut realizer = config.realizer();
+        for pin in &self.pins {
+            realizer = realizer.pin(pin.chord, pin.voice, pin.pitch);
+        }
+        realizer
     }
 }
 
-#[derive(Debug, Clone)]
-struct Voicing {
-    soprano: Pitch,
-    alto: Pitch,
-    tenor: Pitch,
-    bass: Pitch,
+// How a realization sounds
+#[derive(Args)]
+struct SoundArgs {
+    #[arg(long, value_name = "BPM", default_value_t = 72.0, value_parser = tempo, help = "Quarter-note beats a minute")]
+    tempo: f32,
+    #[arg(long, default_value_t = Timbre::Organ, help = "organ, or sine for pure tones")]
+    timbre: Timbre,
+    #[arg(
+        long,
+        default_value_t = Temperament::Equal,
+        help = "equal, meantone (quarter-comma, on C), werckmeister (III) or just (over each chord's key)"
+    )]
+    temperament: Temperament,
+    #[arg(
+        long,
+        value_name = "HZ",
+        default_value_t = 440.0,
+        value_parser = reference_pitch,
+        help = "The pitch of A4, e.g. 415 for baroque pitch"
+    )]
+    reference: f32,
+    #[cfg(feature = "soundfont")]
+    #[arg(long, value_name = "FILE", help = "Play the chords on an instrument from this SoundFont (.sf2) instead; its filters, modulators and envelopes other than the release are ignored")]
+    soundfont: Option<PathBuf>,
+    #[cfg(feature = "soundfont")]
+    #[arg(
+        long,
+        value_name = "NAME",
+        requires = "soundfont",
+        help = "The SoundFont's preset to play, by name or as BANK:PROGRAM (default: the first)"
+    )]
+    preset: Option<String>,
 }
 
-impl fmt::Display for Voicing {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "S:{} A:{} T:{} B:{}", 
-               self.soprano.name(), self.alto.name(), 
-               self.tenor.name(), self.bass.name())
+impl SoundArgs {
+    fn synth(&self) -> Synth {
+        let synth = Synth::new()
+            .tempo(self.tempo)
+            .timbre(self.timbre)
+            .temperament(self.temperament)
+            .reference(self.reference);
+        #[cfg(feature = "soundfont")]
+        if let Some(path) = &self.soundfont {
+            let bytes = fs::read(path).unwrap_or_else(|e| fail(&format!("cannot read {}: {}", path.display(), e)));
+            let soundfont = SoundFont::read(&bytes).unwrap_or_else(|e| fail(&format!("{}: {}", path.display(), e)));
+            let preset = match &self.preset {
+                Some(query) => soundfont.preset(query).unwrap_or_else(|e| fail(&e.to_string())),
+                None => &soundfont.presets[0],
+            };
+            return synth.preset(preset.clone());
+        }
+        synth
     }
 }
 
-// ============================================================================
-// KEY AND SCALE
-// ============================================================================
-
-#[derive(Debug, Clone)]
-struct Key {
-    tonic: u8, // pitch class 0-11
-    mode: Mode,
-    scale: Vec<u8>, // pitch classes of the scale degrees
+// Values clap checks beyond their type
+fn track(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n - 1),
+        _ => Err(format!("invalid track {:?}, expected a number from 1", value)),
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Mode {
-    Major,
-    Minor, // Natural minor
+fn beam_width(value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(width) if width > 0 => Ok(width),
+        _ => Err(format!("invalid beam width {:?}", value)),
+    }
 }
 
-impl Key {
-    fn new(tonic: u8, mode: Mode) -> Self {
-        let scale = match mode {
-            Mode::Major => vec![
-                tonic,
-                (tonic + 2) % 12,
-                (tonic + 4) % 12,
-                (tonic + 5) % 12,
-                (tonic + 7) % 12,
-                (tonic + 9) % 12,
-                (tonic + 11) % 12,
-            ],
-            Mode::Minor => vec![
-                tonic,
-                (tonic + 2) % 12,
-                (tonic + 3) % 12,
-                (tonic + 5) % 12,
-                (tonic + 7) % 12,
-                (tonic + 8) % 12,
-                (tonic + 10) % 12,
-            ],
-        };
-        
-        Self { tonic, mode, scale }
+fn temperature(value: &str) -> Result<f32, String> {
+    match value.parse() {
+        Ok(temperature) if temperature >= 0.0 => Ok(temperature),
+        _ => Err(format!("invalid temperature {:?}", value)),
     }
-    
-    fn c_major() -> Self {
-        Self::new(0, Mode::Major)
+}
+
+fn tempo(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(tempo) if tempo.is_finite() && tempo > 0.0 => Ok(tempo),
+        _ => Err(format!("invalid tempo {:?}, expected beats a minute", value)),
     }
-    
-    fn pitch_class_to_scale_degree(&self, pc: u8) -> usize {
-        self.scale.iter().position(|&x| x == pc).unwrap_or(0)
+}
+
+fn reference_pitch(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(hz) if (100.0..=1000.0).contains(&hz) => Ok(hz),
+        _ => Err(format!("invalid reference pitch {:?}, expected A4 in hertz such as 415 or 440", value)),
     }
 }
 
-// ============================================================================
-// FIGURED BASS STRUCTURES
-// ============================================================================
+fn exercise_length(value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(n) if n >= 3 => Ok(n),
+        _ => Err(format!("invalid length {:?}, expected 3 or more chords", value)),
+    }
+}
 
-#[derive(Debug, Clone)]
-struct Figure {
-    intervals: Vec<Interval>,
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(2);
 }
 
-#[derive(Debug, Clone)]
-struct Interval {
-    number: u8, // 3, 5, 6, 7, etc.
-    accidental: Accidental,
+// A progression as read, in its key; a harmonized melody comes with its
+// voicings
+struct Progression {
+    key: Key,
+    title: Option<String>,
+    symbols: Vec<FiguredBassSymbol>,
+    harmonized: Option<Vec<Voicing>>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Accidental {
-    Natural,
-    Sharp,
-    Flat,
-    None, // Use the key signature
+impl InputArgs {
+    fn format(&self) -> InputFormat {
+        if self.from != InputFormat::Auto {
+            return self.from;
+        }
+        let extension = Path::new(&self.progression).extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("mid" | "midi") => InputFormat::Midi,
+            Some("musicxml" | "xml") => InputFormat::MusicXml,
+            Some("abc") => InputFormat::Abc,
+            Some("rntxt") => InputFormat::RomanText,
+            _ => InputFormat::Figures,
+        }
+    }
+
+    // The bytes of the progression: standard input for "-", the file if
+    // there is one, else the argument itself
+    fn bytes(&self, format: InputFormat) -> Vec<u8> {
+        let path = self.progression.as_str();
+        if path == "-" {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes).unwrap_or_else(|e| fail(&format!("cannot read standard input: {}", e)));
+            return bytes;
+        }
+        if Path::new(path).is_file() {
+            return fs::read(path).unwrap_or_else(|e| fail(&format!("cannot read {}: {}", path, e)));
+        }
+        if matches!(format, InputFormat::Midi | InputFormat::MusicXml | InputFormat::Abc | InputFormat::RomanText) {
+            fail(&format!("no such file: {}", path));
+        }
+        path.as_bytes().to_vec()
+    }
+
+    // The progression, in --key, else the key the input gives, else the
+    // config file's, else C major
+    fn read(&self, realizer: &Realizer, config: &Config) -> Progression {
+        let format = self.format();
+        let bytes = self.bytes(format);
+        let text = || String::from_utf8(bytes.clone()).unwrap_or_else(|_| fail(&format!("{} is not UTF-8 text", self.progression)));
+        let key = self.key.clone().or_else(|| config.key.clone()).unwrap_or_else(Key::c_major);
+        let progression = |key: Key, symbols| Progression { key, title: None, symbols, harmonized: None };
+        match format {
+            InputFormat::Auto | InputFormat::Figures => {
+                let text = text();
+                let mut file = ProgressionFile::parse(&text, &key).unwrap_or_else(|e| fail(&e.show(&text)));
+                if self.key.is_some() {
+                    file.key = key;
+                }
+                Progression { symbols: file.symbols(), key: file.key, title: file.title, harmonized: None }
+            }
+            InputFormat::Roman => {
+                let symbols = parse_roman_progression(&text(), &key).unwrap_or_else(|e| fail(&e.to_string()));
+                progression(key, symbols)
+            }
+            InputFormat::Chords => {
+                let symbols = parse_chord_progression(&text(), &key).unwrap_or_else(|e| fail(&e.to_string()));
+                progression(key, symbols)
+            }
+            InputFormat::Melody => {
+                let melody = parse_melody(&text()).unwrap_or_else(|e| fail(&e.to_string()));
+                let harmonization = realizer.harmonize(&melody, &key).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                });
+                Progression { key, title: None, symbols: harmonization.symbols, harmonized: Some(harmonization.voicings) }
+            }
+            InputFormat::Midi => {
+                let bass = read_bass_line(&bytes, self.track).unwrap_or_else(|e| fail(&e.to_string()));
+                let symbols = midi::unfigured(&bass, &key);
+                progression(key, symbols)
+            }
+            InputFormat::MusicXml => {
+                let bass = read_figured_bass(&text(), self.part.as_deref(), self.key.as_ref())
+                    .unwrap_or_else(|e| fail(&e.to_string()));
+                progression(bass.key, bass.symbols)
+            }
+            InputFormat::Abc => {
+                let bass = read_abc(&text(), self.voice.as_deref(), self.key.as_ref()).unwrap_or_else(|e| fail(&e.to_string()));
+                progression(bass.key, bass.symbols)
+            }
+            InputFormat::RomanText => {
+                let symbols = read_romantext(&text(), self.key.as_ref().or(config.key.as_ref())).unwrap_or_else(|e| fail(&e.to_string()));
+                progression(symbols[0].key.clone(), symbols)
+            }
+        }
+    }
 }
 
-impl Figure {
-    // Parse figured bass notation
-    fn parse(notation: &str) -> Self {
-        let notation = notation.trim();
-        
-        // Empty or whitespace = root position triad (5/3)
-        if notation.is_empty() {
-            return Figure {
-                intervals: vec![
-                    Interval { number: 3, accidental: Accidental::None },
-                    Interval { number: 5, accidental: Accidental::None },
-                ],
+fn main() {
+    let cli = Cli::parse();
+    let config = match (&cli.config, cli.no_config) {
+        (_, true) => Config::default(),
+        (Some(path), false) => Config::load(path).unwrap_or_else(|e| fail(&e.to_string())),
+        (None, false) => match Config::default_path().filter(|path| path.is_file()) {
+            Some(path) => Config::load(&path).unwrap_or_else(|e| fail(&e.to_string())),
+            None => Config::default(),
+        },
+    };
+    let default_key = || config.key.clone().unwrap_or_else(Key::c_major);
+
+    match cli.command {
+        Command::Realize { input, realizer, detect_key: detect, explain } => {
+            let realizer = realizer.realizer(&config);
+            let progression = input.read(&realizer, &config);
+            let (detect, explain) = (detect || config.detect_key, explain || config.explain);
+            print_progression(&progression);
+            let voicings = match progression.harmonized.clone() {
+                Some(voicings) => {
+                    println!();
+                    voicings
+                }
+                None => {
+                    println!("\nRealizing figured bass...\n");
+                    realize(&realizer, &progression.symbols)
+                }
             };
+            print_voicings(&progression, &voicings, detect);
+            if explain {
+                print_scores(&realizer, &progression.symbols, &voicings);
+            }
+            print_analysis(&progression.symbols, &voicings);
         }
-        
-        // Common shorthand patterns
-        match notation {
-            "6" => return Figure {
-                intervals: vec![
-                    Interval { number: 3, accidental: Accidental::None },
-                    Interval { number: 6, accidental: Accidental::None },
-                ],
-            },
-            "6/4" | "64" => return Figure {
-                intervals: vec![
-                    Interval { number: 4, accidental: Accidental::None },
-                    Interval { number: 6, accidental: Accidental::None },
-                ],
-            },
-            "7" => return Figure {
-                intervals: vec![
-                    Interval { number: 3, accidental: Accidental::None },
-                    Interval { number: 5, accidental: Accidental::None },
-                    Interval { number: 7, accidental: Accidental::None },
-                ],
-            },
-            "6/5" | "65" => return Figure {
-                intervals: vec![
-                    Interval { number: 3, accidental: Accidental::None },
-                    Interval { number: 5, accidental: Accidental::None },
-                    Interval { number: 6, accidental: Accidental::None },
-                ],
-            },
-            "4/3" | "43" => return Figure {
-                intervals: vec![
-                    Interval { number: 3, accidental: Accidental::None },
-                    Interval { number: 4, accidental: Accidental::None },
-                    Interval { number: 6, accidental: Accidental::None },
-                ],
-            },
-            "4/2" | "42" | "2" => return Figure {
-                intervals: vec![
-                    Interval { number: 2, accidental: Accidental::None },
-                    Interval { number: 4, accidental: Accidental::None },
-                    Interval { number: 6, accidental: Accidental::None },
-                ],
-            },
-            "#" => return Figure {
-                intervals: vec![
-                    Interval { number: 3, accidental: Accidental::Sharp },
-                    Interval { number: 5, accidental: Accidental::None },
-                ],
-            },
-            _ => {}
+        Command::Analyze { input, detect_key: detect, realization } => {
+            let progression = input.read(&config.realizer(), &config);
+            let detect = detect || config.detect_key;
+            print_progression(&progression);
+            let analysis_key = if detect { detect_key(&progression.symbols) } else { progression.key.clone() };
+            let numerals = analyze(&progression.symbols, &analysis_key);
+            println!("\nNumerals in {}:", analysis_key);
+            for (i, (symbol, numeral)) in progression.symbols.iter().zip(&numerals).enumerate() {
+                let numeral = numeral.as_ref().map_or("?".to_string(), |n| n.to_string());
+                println!("  Chord {}: {} {}  {}", i + 1, symbol.spell(symbol.bass), symbol.figure, numeral);
+            }
+            let voicings = match realization {
+                Some(text) => Some(parse_realization(&text).unwrap_or_else(|e| fail(&e.to_string()))),
+                None => progression.harmonized.clone(),
+            };
+            if let Some(voicings) = voicings {
+                if voicings.len() != progression.symbols.len() {
+                    fail(&format!("the realization has {} chords, the progression {}", voicings.len(), progression.symbols.len()));
+                }
+                print_analysis(&progression.symbols, &voicings);
+            }
         }
-        
-        // Parse more complex notation (simplified parser)
-        let mut intervals = Vec::new();
-        let parts: Vec<&str> = notation.split('/').collect();
-        
-        for part in parts {
-            let part = part.trim();
-            if part.is_empty() {
-                continue;
+        Command::Grade { input, realization, realizer, explain } => {
+            if input.format() == InputFormat::Melody {
+                fail("a melody is harmonized, not graded");
+            }
+            let realizer = realizer.realizer(&config);
+            let progression = input.read(&realizer, &config);
+            let explain = explain || config.explain;
+            print_progression(&progression);
+            let voicings = parse_realization(&realization).unwrap_or_else(|e| fail(&e.to_string()));
+            let grade = realizer.grade(&progression.symbols, &voicings).unwrap_or_else(|e| fail(&e.to_string()));
+            println!("\nGrading realization...\n");
+            print_voicings(&progression, &voicings, false);
+            if explain {
+                print_scores(&realizer, &progression.symbols, &voicings);
             }
-            
-            let (accidental, num_str) = if part.starts_with('#') {
-                (Accidental::Sharp, &part[1..])
-            } else if part.starts_with('b') {
-                (Accidental::Flat, &part[1..])
-            } else if part.starts_with('♮') {
-                (Accidental::Natural, &part[3..]) // ♮ is 3 bytes in UTF-8
-            } else {
-                (Accidental::None, part)
+            print_analysis(&progression.symbols, &voicings);
+            println!("Grade: {}", grade);
+        }
+        Command::Export { input, realizer, to, output, title, detect_key: detect, tempo } => {
+            let to = match (to, &config.export) {
+                (Some(to), _) => to,
+                (None, Some(format)) => ExportFormat::from_str(format, true).unwrap_or_else(|e| fail(&e)),
+                (None, None) => fail("export needs --to, or an export format in the config file"),
+            };
+            let realizer = realizer.realizer(&config);
+            let progression = input.read(&realizer, &config);
+            let detect = detect || config.detect_key;
+            let title = title
+                .or_else(|| progression.title.clone())
+                .or_else(|| config.title.clone())
+                .unwrap_or_else(|| "Realization".to_string());
+            let voicings = || progression.harmonized.clone().unwrap_or_else(|| realize(&realizer, &progression.symbols));
+            let bytes = match to {
+                ExportFormat::Abc => write_abc(&progression.symbols, &voicings(), &title).into_bytes(),
+                ExportFormat::Kern => write_kern(&progression.symbols, &voicings(), &title).into_bytes(),
+                ExportFormat::Midi => write_midi(&progression.symbols, &voicings(), &title, tempo),
+                ExportFormat::MusicXml => write_musicxml(&progression.symbols, &voicings(), &title).into_bytes(),
+                ExportFormat::RomanText => {
+                    let analysis_key = if detect { detect_key(&progression.symbols) } else { progression.key.clone() };
+                    write_romantext(&analyze(&progression.symbols, &analysis_key), &analysis_key, &title).into_bytes()
+                }
+                ExportFormat::Progression => {
+                    ProgressionFile::from_symbols(&progression.symbols, &progression.key, Some(&title)).to_string().into_bytes()
+                }
             };
-            
-            if let Ok(number) = num_str.parse::<u8>() {
-                intervals.push(Interval { number, accidental });
+            match output {
+                Some(path) => fs::write(&path, bytes).unwrap_or_else(|e| fail(&format!("cannot write {}: {}", path.display(), e))),
+                None => io::stdout().write_all(&bytes).unwrap_or_else(|e| fail(&format!("cannot write the export: {}", e))),
             }
         }
-        
-        Figure { intervals }
-    }
-    
-    // Convert figured bass to actual chord tones
-    fn realize(&self, bass: Pitch, key: &Key) -> Vec<Pitch> {
-        let mut tones = vec![bass]; // Bass is always included
-        
-        for interval in &self.intervals {
-            let semitones = self.interval_to_semitones(bass, interval, key);
-            let pitch = Pitch::new(bass.midi_number + semitones as u8);
-            tones.push(pitch);
+        Command::Render { input, realizer, output, sound } => {
+            let synth = sound.synth();
+            let realizer = realizer.realizer(&config);
+            let progression = input.read(&realizer, &config);
+            let voicings = progression.harmonized.clone().unwrap_or_else(|| realize(&realizer, &progression.symbols));
+            let wav = synth.render_wav(&progression.symbols, &voicings);
+            fs::write(&output, wav).unwrap_or_else(|e| fail(&format!("cannot write {}: {}", output.display(), e)));
+            println!("Wrote {} chords to {}", voicings.len(), output.display());
         }
-        
-        tones.sort();
-        tones.dedup_by_key(|p| p.midi_number % 12);
-        tones
-    }
-    
-    fn interval_to_semitones(&self, bass: Pitch, interval: &Interval, key: &Key) -> i8 {
-        let bass_pc = bass.midi_number % 12;
-        let bass_degree = key.pitch_class_to_scale_degree(bass_pc);
-        
-        // Calculate target scale degree
-        let target_degree = (bass_degree + interval.number as usize - 1) % 7;
-        let target_pc = key.scale[target_degree];
-        
-        // Calculate semitone distance
-        let mut semitones = if target_pc >= bass_pc {
-            target_pc - bass_pc
-        } else {
-            12 + target_pc - bass_pc
-        };
-        
-        // Apply accidentals
-        match interval.accidental {
-            Accidental::Sharp => semitones += 1,
-            Accidental::Flat => semitones = semitones.saturating_sub(1),
-            Accidental::Natural => {
-                // Force natural (cancel key signature) - simplified
+        Command::Play { input, realizer, sound, player, detect_key: detect } => {
+            let synth = sound.synth();
+            let player = player.or_else(audio_player).unwrap_or_else(|| {
+                let tried: Vec<&str> = PLAYERS.iter().filter_map(|command| command.split(' ').next()).collect();
+                fail(&format!(
+                    "found no audio player on the PATH (tried {}); install one or name another with --player",
+                    tried.join(", ")
+                ))
+            });
+            let realizer = realizer.realizer(&config);
+            let progression = input.read(&realizer, &config);
+            let detect = detect || config.detect_key;
+            print_progression(&progression);
+            let voicings = progression.harmonized.clone().unwrap_or_else(|| realize(&realizer, &progression.symbols));
+            println!();
+            play_aloud(&progression, &voicings, &synth, &player, detect);
+        }
+        Command::Live { input, output, osc, osc_prefix, key, lookahead, realizer } => {
+            let key = key.unwrap_or_else(default_key);
+            let osc = osc.map(|addr| {
+                OscSender::connect(addr.as_str(), &osc_prefix).unwrap_or_else(|e| fail(&format!("cannot send OSC to {}: {}", addr, e)))
+            });
+            let stream = StreamingRealizer::new(realizer.realizer(&config), lookahead);
+            play_live(stream, &key, &input, output.as_deref(), osc.as_ref());
+        }
+        #[cfg(feature = "serde")]
+        Command::Stream { key, lookahead, realizer } => {
+            let key = key.unwrap_or_else(default_key);
+            stream_json(NdjsonSession::new(realizer.realizer(&config), key, lookahead));
+        }
+        #[cfg(feature = "serde")]
+        Command::Serve { listen } => {
+            println!("Serving on http://{}", listen);
+            if let Err(e) = basso_continuo::server::serve(&listen) {
+                fail(&format!("cannot listen on {}: {}", listen, e));
             }
-            Accidental::None => {}
         }
-        
-        semitones as i8
+        Command::Exercise { difficulty, key, length, exercise_seed, realizer } => {
+            let realizer = realizer.realizer(&config);
+            let key = key.unwrap_or_else(default_key);
+            let seed = exercise_seed.unwrap_or_else(|| {
+                SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos() as u64)
+            });
+            let exercise = Exercise::generate(&key, length, difficulty, seed).unwrap_or_else(|e| fail(&e.to_string()));
+            println!("Exercise ({}, seed {}): {}\n", difficulty, seed, exercise);
+            let progression = Progression { key, title: None, symbols: exercise.symbols, harmonized: None };
+            print_progression(&progression);
+            println!("\nRealizing figured bass...\n");
+            let voicings = realize(&realizer, &progression.symbols);
+            print_voicings(&progression, &voicings, false);
+            print_analysis(&progression.symbols, &voicings);
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-struct FiguredBassSymbol {
-    bass: Pitch,
-    figure: Figure,
-    chord_tones: Vec<Pitch>,
+fn realize(realizer: &Realizer, symbols: &[FiguredBassSymbol]) -> Vec<Voicing> {
+    realizer.realize(symbols).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    })
 }
 
-impl FiguredBassSymbol {
-    fn new(bass: Pitch, figure_notation: &str, key: &Key) -> Self {
-        let figure = Figure::parse(figure_notation);
-        let chord_tones = figure.realize(bass, key);
-        
-        Self {
-            bass,
-            figure,
-            chord_tones,
+fn print_progression(progression: &Progression) {
+    println!("Figured Bass Progression in {}:", progression.key);
+    for (i, symbol) in progression.symbols.iter().enumerate() {
+        let held = match (symbol.pedal, symbol.held) {
+            (true, _) => " (pedal)",
+            (false, true) => " (held)",
+            (false, false) => "",
+        };
+        let fermata = if symbol.fermata { " (fermata)" } else { "" };
+        println!("  {}: {} {}{}{}", i + 1, symbol.spell(symbol.bass), symbol.figure, held, fermata);
+        print!("     Chord tones: ");
+        for &tone in &symbol.chord_tones {
+            print!("{} ", symbol.spell(tone));
         }
+        println!();
     }
 }
 
-// Voice ranges in MIDI numbers
-const SOPRANO_MIN: u8 = 60; // C4
-const SOPRANO_MAX: u8 = 79; // G5
-const ALTO_MIN: u8 = 55;    // G3
-const ALTO_MAX: u8 = 72;    // C5
-const TENOR_MIN: u8 = 48;   // C3
-const TENOR_MAX: u8 = 67;   // G4
-const BASS_MIN: u8 = 40;    // E2
-const BASS_MAX: u8 = 60;    // C4
-
-// ============================================================================
-// VOICING GENERATION
-// ============================================================================
-
-fn generate_voicings(symbol: &FiguredBassSymbol) -> Vec<Voicing> {
-    let mut voicings = Vec::new();
-    let bass = symbol.bass;
-    
-    // Get chord tones in various octaves for upper voices
-    let soprano_notes = get_notes_in_range(&symbol.chord_tones, SOPRANO_MIN, SOPRANO_MAX);
-    let alto_notes = get_notes_in_range(&symbol.chord_tones, ALTO_MIN, ALTO_MAX);
-    let tenor_notes = get_notes_in_range(&symbol.chord_tones, TENOR_MIN, TENOR_MAX);
-    
-    // Generate all combinations
-    for &soprano in &soprano_notes {
-        for &alto in &alto_notes {
-            for &tenor in &tenor_notes {
-                let voicing = Voicing { soprano, alto, tenor, bass };
-                
-                // Basic validity checks
-                if is_valid_voicing(&voicing, &symbol.chord_tones) {
-                    voicings.push(voicing);
-                }
-            }
-        }
+fn print_voicings(progression: &Progression, voicings: &[Voicing], detect: bool) {
+    let analysis_key = if detect { detect_key(&progression.symbols) } else { progression.key.clone() };
+    let numerals = analyze(&progression.symbols, &analysis_key);
+    println!("Voicings (numerals in {}):", analysis_key);
+    for (i, ((voicing, symbol), numeral)) in voicings.iter().zip(&progression.symbols).zip(&numerals).enumerate() {
+        let numeral = numeral.as_ref().map_or("?".to_string(), |n| n.to_string());
+        println!("  Chord {}: {}  {}", i + 1, voicing.spelled(symbol), numeral);
     }
-    
-    voicings
 }
 
-fn get_notes_in_range(chord_tones: &[Pitch], min: u8, max: u8) -> Vec<Pitch> {
-    let mut notes = Vec::new();
-    
-    for &tone in chord_tones {
-        let pitch_class = tone.midi_number % 12;
-        
-        // Generate this pitch class in all octaves within range
-        let mut midi = pitch_class;
-        while midi < min {
-            midi += 12;
-        }
-        while midi <= max {
-            notes.push(Pitch::new(midi));
-            midi += 12;
-        }
+fn print_scores(realizer: &Realizer, symbols: &[FiguredBassSymbol], voicings: &[Voicing]) {
+    println!("\nScores:");
+    for (i, breakdown) in realizer.breakdown(symbols, voicings).iter().enumerate() {
+        println!("  Chord {}: {}", i + 1, breakdown);
     }
-    
-    notes.sort();
-    notes.dedup();
-    notes
 }
 
-fn is_valid_voicing(voicing: &Voicing, chord_tones: &[Pitch]) -> bool {
-    // Check voices don't cross
-    if voicing.soprano.midi_number < voicing.alto.midi_number {
-        return false;
-    }
-    if voicing.alto.midi_number < voicing.tenor.midi_number {
-        return false;
-    }
-    if voicing.tenor.midi_number < voicing.bass.midi_number {
-        return false;
-    }
-    
-    // Check spacing between upper voices (not more than an octave)
-    if voicing.soprano.midi_number - voicing.alto.midi_number > 12 {
-        return false;
-    }
-    if voicing.alto.midi_number - voicing.tenor.midi_number > 12 {
-        return false;
-    }
-    
-    // Check all chord tones are represented
-    let voicing_pcs: Vec<u8> = vec![
-        voicing.soprano.midi_number % 12,
-        voicing.alto.midi_number % 12,
-        voicing.tenor.midi_number % 12,
-        voicing.bass.midi_number % 12,
-    ];
-    
-    for tone in chord_tones {
-        let pc = tone.midi_number % 12;
-        if !voicing_pcs.contains(&pc) {
-            return false;
-        }
+fn print_analysis(symbols: &[FiguredBassSymbol], voicings: &[Voicing]) {
+    println!("\n--- Analysis ---");
+
+    for violation in diagnostics::analyze(symbols, voicings) {
+        let label = match violation.severity {
+            Severity::Warning => "Warning",
+            Severity::Error => "Error",
+        };
+        println!("{}: {}", label, violation);
     }
-    
-    true
-}
 
-// ============================================================================
-// SCORING FUNCTIONS
-// ============================================================================
-
-fn score_voicing(voicing: &Voicing, prev: Option<&Voicing>, root: Pitch) -> f32 {
-    let mut score = 0.0;
-    
-    // Static scores
-    score += doubling_score(voicing, root);
-    score += spacing_score(voicing);
-    score += range_comfort_score(voicing);
-    
-    // Dynamic scores (if there's a previous chord)
-    if let Some(prev_voicing) = prev {
-        score += parallel_motion_penalty(prev_voicing, voicing);
-        score += voice_motion_score(prev_voicing, voicing);
-        score += contrary_motion_bonus(prev_voicing, voicing);
+    // Calculate total voice motion
+    let mut total_motion = 0;
+    for i in 1..voicings.len() {
+        total_motion += (voicings[i].soprano.semitones() - voicings[i-1].soprano.semitones()).abs();
+        total_motion += (voicings[i].alto.semitones() - voicings[i-1].alto.semitones()).abs();
+        total_motion += (voicings[i].tenor.semitones() - voicings[i-1].tenor.semitones()).abs();
     }
-    
-    score
+    println!("Total voice motion: {} semitones", total_motion);
 }
 
-fn doubling_score(voicing: &Voicing, root: Pitch) -> f32 {
-    let root_pc = root.midi_number % 12;
-    let mut score = 0.0;
-    
-    let voices = [
-        voicing.soprano.midi_number % 12,
-        voicing.alto.midi_number % 12,
-        voicing.tenor.midi_number % 12,
-        voicing.bass.midi_number % 12,
-    ];
-    
-    for &voice_pc in &voices {
-        if voice_pc == root_pc {
-            score += 10.0; // Prefer doubling the root
+// Answer each line of standard input as it comes, so another program can
+// keep a session open through a pipe
+#[cfg(feature = "serde")]
+fn stream_json(mut session: NdjsonSession) {
+    let mut stdout = io::stdout().lock();
+    let mut send = |lines: Vec<String>| {
+        for line in lines {
+            if writeln!(stdout, "{}", line).and_then(|_| stdout.flush()).is_err() {
+                process::exit(0); // the reader has gone
+            }
         }
+    };
+    for line in io::stdin().lines() {
+        let line = line.unwrap_or_else(|e| {
+            eprintln!("cannot read standard input: {}", e);
+            process::exit(1);
+        });
+        send(session.respond(&line));
     }
-    
-    score
+    send(session.finish());
 }
 
-fn spacing_score(voicing: &Voicing) -> f32 {
-    let mut score = 0.0;
-    
-    // Penalize large gaps in upper voices
-    let sop_alto_gap = voicing.soprano.midi_number - voicing.alto.midi_number;
-    let alto_tenor_gap = voicing.alto.midi_number - voicing.tenor.midi_number;
-    
-    if sop_alto_gap > 7 {
-        score -= (sop_alto_gap - 7) as f32 * 2.0;
-    }
-    if alto_tenor_gap > 7 {
-        score -= (alto_tenor_gap - 7) as f32 * 2.0;
-    }
-    
-    score
-}
+// Players that take a WAV file on standard input, in the order tried
+const PLAYERS: [&str; 4] = ["aplay -q -", "pw-play -", "play -q -t wav -", "ffplay -nodisp -autoexit -loglevel quiet -"];
 
-fn range_comfort_score(voicing: &Voicing) -> f32 {
-    let mut score = 0.0;
-    
-    // Prefer notes in the middle of each range
-    let soprano_mid = (SOPRANO_MIN + SOPRANO_MAX) / 2;
-    let alto_mid = (ALTO_MIN + ALTO_MAX) / 2;
-    let tenor_mid = (TENOR_MIN + TENOR_MAX) / 2;
-    
-    score -= ((voicing.soprano.midi_number as i16 - soprano_mid as i16).abs() as f32) * 0.1;
-    score -= ((voicing.alto.midi_number as i16 - alto_mid as i16).abs() as f32) * 0.1;
-    score -= ((voicing.tenor.midi_number as i16 - tenor_mid as i16).abs() as f32) * 0.1;
-    
-    score
+// The first of PLAYERS on the PATH
+fn audio_player() -> Option<String> {
+    let paths: Vec<PathBuf> = env::var_os("PATH").map(|path| env::split_paths(&path).collect()).unwrap_or_default();
+    let installed = |command: &str| paths.iter().any(|dir| dir.join(command.split(' ').next().unwrap_or("")).is_file());
+    PLAYERS.into_iter().find(|command| installed(command)).map(str::to_string)
 }
 
-fn parallel_motion_penalty(v1: &Voicing, v2: &Voicing) -> f32 {
-    let voices1 = [v1.soprano, v1.alto, v1.tenor, v1.bass];
-    let voices2 = [v2.soprano, v2.alto, v2.tenor, v2.bass];
-    
-    for i in 0..4 {
-        for j in (i+1)..4 {
-            let interval1 = (voices1[i].semitones() - voices1[j].semitones()).abs();
-            let interval2 = (voices2[i].semitones() - voices2[j].semitones()).abs();
-            
-            // Check for parallel perfect 5ths (7 semitones) or octaves (12 semitones)
-            if (interval1 == 7 || interval1 == 12) && interval1 == interval2 {
-                let motion1 = voices2[i].semitones() - voices1[i].semitones();
-                let motion2 = voices2[j].semitones() - voices1[j].semitones();
-                
-                // Parallel motion (same direction)?
-                if motion1 != 0 && motion2 != 0 && motion1.signum() == motion2.signum() {
-                    return -1000.0; // Huge penalty!
-                }
+// Play the realization through `player`, printing each chord as it
+// sounds; on a terminal the chord sounding is highlighted
+fn play_aloud(progression: &Progression, voicings: &[Voicing], synth: &Synth, player: &str, detect: bool) {
+    let mut words = player.split_whitespace();
+    let program = words.next().unwrap_or_else(|| fail("--player is empty"));
+    let mut child = process::Command::new(program)
+        .args(words)
+        .stdin(process::Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|e| fail(&format!("cannot run {}: {}", program, e)));
+
+    let wav = synth.render_wav(&progression.symbols, voicings);
+    let mut stdin = child.stdin.take().expect("the player's input is piped");
+    // A player that stops early has stopped listening; it says why itself
+    let writer = thread::spawn(move || {
+        let _ = stdin.write_all(&wav);
+    });
+
+    let analysis_key = if detect { detect_key(&progression.symbols) } else { progression.key.clone() };
+    let numerals = analyze(&progression.symbols, &analysis_key);
+    let line = |i: usize| {
+        let numeral = numerals[i].as_ref().map_or("?".to_string(), |n| n.to_string());
+        format!("  Chord {}: {}  {}", i + 1, voicings[i].spelled(&progression.symbols[i]), numeral)
+    };
+    let terminal = io::stdout().is_terminal();
+    let times: Vec<Duration> = synth.times(&progression.symbols).into_iter().map(Duration::from_secs_f32).collect();
+    println!("Playing at {} beats a minute (numerals in {}):", synth.tempo, analysis_key);
+    let start = Instant::now();
+    for (i, &time) in times.iter().enumerate().take(voicings.len()) {
+        thread::sleep((start + time).saturating_duration_since(Instant::now()));
+        if terminal {
+            // Unhighlight the chord before, then highlight this one
+            if i > 0 {
+                println!("\x1b[1A\r{}\x1b[K", line(i - 1));
             }
+            println!("\x1b[7m{}\x1b[0m", line(i));
+        } else {
+            println!("{}", line(i));
         }
     }
-    
-    0.0
-}
-
-fn voice_motion_score(v1: &Voicing, v2: &Voicing) -> f32 {
-    let total_motion = 
-        (v2.soprano.semitones() - v1.soprano.semitones()).abs() +
-        (v2.alto.semitones() - v1.alto.semitones()).abs() +
-        (v2.tenor.semitones() - v1.tenor.semitones()).abs();
-    
-    // Prefer less motion (common tone retention, stepwise motion)
-    -0.5 * (total_motion as f32)
-}
-
-fn contrary_motion_bonus(v1: &Voicing, v2: &Voicing) -> f32 {
-    let mut score = 0.0;
-    
-    let sop_motion = v2.soprano.semitones() - v1.soprano.semitones();
-    let bass_motion = v2.bass.semitones() - v1.bass.semitones();
-    
-    // Bonus for contrary motion between outer voices
-    if sop_motion != 0 && bass_motion != 0 && sop_motion.signum() != bass_motion.signum() {
-        score += 5.0;
+    thread::sleep((start + times[voicings.len()]).saturating_duration_since(Instant::now()));
+    if terminal && !voicings.is_empty() {
+        println!("\x1b[1A\r{}\x1b[K", line(voicings.len() - 1));
+    }
+    let _ = writer.join();
+    match child.wait() {
+        Ok(status) if status.success() => {}
+        Ok(status) => fail(&format!("{} stopped: {}", program, status)),
+        Err(e) => fail(&format!("cannot wait for {}: {}", program, e)),
     }
-    
-    score
 }
 
-// ============================================================================
-// MAIN REALIZATION ALGORITHM
-// ============================================================================
-
-fn realize_figured_bass(symbols: &[FiguredBassSymbol]) -> Vec<Voicing> {
-    let mut result = Vec::new();
-    
-    for (i, symbol) in symbols.iter().enumerate() {
-        let candidates = generate_voicings(symbol);
-        
-        if candidates.is_empty() {
-            panic!("No valid voicings found for chord {}", i);
+// Realize bass notes from `input` as they are played, sending each chord's
+// upper voices to `output` as it is decided and releasing the chord before,
+// and the whole chord to `osc`. A note played above one still held is an
+// upper voice, not the bass.
+fn play_live(mut stream: StreamingRealizer, key: &Key, input: &Path, output: Option<&Path>, osc: Option<&OscSender>) {
+    let mut input = File::open(input).unwrap_or_else(|e| fail(&format!("cannot open {}: {}", input.display(), e)));
+    let mut output = output.map(|path| {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .unwrap_or_else(|e| fail(&format!("cannot open {}: {}", path.display(), e)))
+    });
+
+    let mut parser = MidiParser::new();
+    let mut held: Vec<u8> = Vec::new();
+    let mut sounding: Vec<u8> = Vec::new();
+    let mut play = |voicing: &Voicing, count: usize| {
+        println!("  Chord {}: {}", count, voicing);
+        let mut bytes = Vec::new();
+        for key in sounding.drain(..) {
+            bytes.extend(midi::encode(MidiMessage::NoteOff { channel: 0, key }));
+        }
+        for pitch in voicing.upper() {
+            bytes.extend(midi::encode(MidiMessage::NoteOn { channel: 0, key: pitch.midi_number, velocity: 80 }));
+            sounding.push(pitch.midi_number);
+        }
+        if let Some(output) = &mut output
+            && let Err(e) = output.write_all(&bytes).and_then(|_| output.flush())
+        {
+            eprintln!("cannot send to the output: {}", e);
+            process::exit(1);
         }
-        
-        let prev = if i > 0 { Some(&result[i - 1]) } else { None };
-        
-        // Find best voicing
-        let mut best_voicing = None;
-        let mut best_score = f32::MIN;
-        
-        for candidate in &candidates {
-            let score = score_voicing(candidate, prev, symbol.bass);
-            if score > best_score {
-                best_score = score;
-                best_voicing = Some(candidate.clone());
+        // A dropped packet is a missed chord, not a reason to stop playing
+        if let Some(osc) = osc
+            && let Err(e) = osc.send_chord(count - 1, voicing)
+        {
+            eprintln!("cannot send OSC: {}", e);
+        }
+    };
+
+    println!("Playing along in {}...", key);
+    let mut byte = [0u8; 1];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("cannot read the input: {}", e);
+                process::exit(1);
             }
         }
-        
-        result.push(best_voicing.unwrap());
-    }
-    
-    result
-}
-
-// ============================================================================
-// EXAMPLE USAGE
-// ============================================================================
-
-fn main() {
-    let key = Key::c_major();
-    
-    // Example: I - IV6 - V - I progression in C major
-    // Using figured bass notation!
-    let progression = vec![
-        FiguredBassSymbol::new(Pitch::new(48), "", &key),      // C3, root position (I)
-        FiguredBassSymbol::new(Pitch::new(57), "6", &key),     // A3, first inversion (IV6)
-        FiguredBassSymbol::new(Pitch::new(55), "7", &key),     // G3, dominant 7th (V7)
-        FiguredBassSymbol::new(Pitch::new(48), "", &key),      // C3, root position (I)
-    ];
-    
-    println!("Figured Bass Progression:");
-    for (i, symbol) in progression.iter().enumerate() {
-        let figure_str = match i {
-            0 => "(root position)",
-            1 => "6",
-            2 => "7",
-            3 => "(root position)",
-            _ => "",
-        };
-        println!("  {}: {} {}", i + 1, symbol.bass.name(), figure_str);
-        print!("     Chord tones: ");
-        for tone in &symbol.chord_tones {
-            print!("{} ", tone.name());
+        match parser.feed(byte[0]) {
+            Some(MidiMessage::NoteOn { key: note, .. }) => {
+                let below = held.iter().any(|&k| k < note);
+                held.push(note);
+                if below {
+                    continue;
+                }
+                let symbol = midi::unfigured(&[Pitch::new(note)], key).remove(0);
+                match stream.push(symbol) {
+                    Ok(Some(voicing)) => play(&voicing, stream.voicings().len()),
+                    Ok(None) => {}
+                    Err(e) => eprintln!("skipping {}: {}", Pitch::new(note).name(), e),
+                }
+            }
+            Some(MidiMessage::NoteOff { key: note, .. }) => held.retain(|&k| k != note),
+            None => {}
         }
-        println!();
     }
-    
-    println!("\nRealizing figured bass...\n");
-    
-    let voicings = realize_figured_bass(&progression);
-    
-    println!("Voicings:");
-    for (i, voicing) in voicings.iter().enumerate() {
-        println!("  Chord {}: {}", i + 1, voicing);
+
+    // The input has ended: play what is still to be decided, then let go
+    let decided = stream.voicings().len();
+    let rest = stream.finish().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    for (i, voicing) in rest.iter().enumerate() {
+        play(voicing, decided + i + 1);
     }
-    
-    println!("\n--- Analysis ---");
-    
-    // Check for parallel fifths/octaves
-    for i in 1..voicings.len() {
-        let penalty = parallel_motion_penalty(&voicings[i-1], &voicings[i]);
-        if penalty < 0.0 {
-            println!("Warning: Parallel motion detected between chords {} and {}", i, i+1);
-        }
+    let release: Vec<u8> = sounding.iter().flat_map(|&key| midi::encode(MidiMessage::NoteOff { channel: 0, key })).collect();
+    if let Some(output) = &mut output {
+        let _ = output.write_all(&release);
     }
-    
-    // Calculate total voice motion
-    let mut total_motion = 0;
-    for i in 1..voicings.len() {
-        total_motion += (voicings[i].soprano.semitones() - voicings[i-1].soprano.semitones()).abs();
-        total_motion += (voicings[i].alto.semitones() - voicings[i-1].alto.semitones()).abs();
-        total_motion += (voicings[i].tenor.semitones() - voicings[i-1].tenor.semitones()).abs();
+    if let (Some(osc), Some(last)) = (osc, stream.voicings().last()) {
+        let _ = osc.release(last);
     }
-    println!("Total voice motion: {} semitones", total_motion);
-}
\ No newline at end of file
+}
@@ -0,0 +1,198 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+
+use crate::figure::FiguredBassSymbol;
+
+// Rhythm: how long each chord lasts and where it falls in the bar. Lengths
+// are counted in quarter-note beats, whatever the meter's unit, so 6/8
+// has a bar of 3.
+
+// A length in quarter-note beats, in lowest terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Duration {
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+impl Duration {
+    pub const ZERO: Duration = Duration { numerator: 0, denominator: 1 };
+    pub const BEAT: Duration = Duration { numerator: 1, denominator: 1 };
+
+    pub fn new(numerator: u32, denominator: u32) -> Self {
+        Self::reduced(numerator as u64, denominator as u64)
+    }
+
+    fn reduced(numerator: u64, denominator: u64) -> Self {
+        let divisor = gcd(numerator, denominator).max(1);
+        Self { numerator: (numerator / divisor) as u32, denominator: (denominator / divisor) as u32 }
+    }
+
+    pub fn beats(&self) -> f32 {
+        self.numerator as f32 / self.denominator as f32
+    }
+
+    // An `n`th part of it, as each figure over a bass note has
+    pub fn split(&self, n: usize) -> Self {
+        Duration::reduced(self.numerator as u64, self.denominator as u64 * n.max(1) as u64)
+    }
+
+    // Where a note this far into a bar of `bar` falls in the bar it ends
+    // up in
+    pub fn within(&self, bar: Duration) -> Self {
+        if bar == Duration::ZERO {
+            return *self;
+        }
+        // Numerators over a common denominator
+        let (a, b) = (self.numerator as u64 * bar.denominator as u64, bar.numerator as u64 * self.denominator as u64);
+        Duration::reduced(a % b, self.denominator as u64 * bar.denominator as u64)
+    }
+}
+
+impl Add for Duration {
+    type Output = Duration;
+
+    fn add(self, other: Duration) -> Duration {
+        let (a, b) = (self.numerator as u64 * other.denominator as u64, other.numerator as u64 * self.denominator as u64);
+        Duration::reduced(a + b, self.denominator as u64 * other.denominator as u64)
+    }
+}
+
+// The difference, or nothing if `other` is the longer
+impl Sub for Duration {
+    type Output = Duration;
+
+    fn sub(self, other: Duration) -> Duration {
+        let (a, b) = (self.numerator as u64 * other.denominator as u64, other.numerator as u64 * self.denominator as u64);
+        Duration::reduced(a.saturating_sub(b), self.denominator as u64 * other.denominator as u64)
+    }
+}
+
+impl Ord for Duration {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.numerator as u64 * other.denominator as u64).cmp(&(other.numerator as u64 * self.denominator as u64))
+    }
+}
+
+impl PartialOrd for Duration {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// "2", "3/2"
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.denominator {
+            1 => write!(f, "{}", self.numerator),
+            denominator => write!(f, "{}/{}", self.numerator, denominator),
+        }
+    }
+}
+
+impl FromStr for Duration {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid length {:?}, expected beats such as 2 or 3/2", s);
+        let (numerator, denominator) = s.split_once('/').unwrap_or((s, "1"));
+        let numerator: u32 = numerator.parse().map_err(|_| invalid())?;
+        let denominator: u32 = denominator.parse().map_err(|_| invalid())?;
+        // Short enough to add up without overflowing
+        if numerator == 0 || denominator == 0 || numerator > 1024 || denominator > 64 {
+            return Err(invalid());
+        }
+        Ok(Duration::new(numerator, denominator))
+    }
+}
+
+// A time signature: `beats` notes of `unit` a bar, 4 a quarter note
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Meter {
+    pub beats: u32,
+    pub unit: u32,
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Meter::COMMON
+    }
+}
+
+impl Meter {
+    pub const COMMON: Meter = Meter { beats: 4, unit: 4 };
+
+    pub fn new(beats: u32, unit: u32) -> Self {
+        Self { beats, unit }
+    }
+
+    // The length of a bar
+    pub fn bar(&self) -> Duration {
+        Duration::new(self.beats * 4, self.unit)
+    }
+
+    // Beats in a bar: a compound meter's (6/8, 9/8, 12/8, 6/4) are dotted,
+    // so 6/8 has 2 and 3/4 has 3
+    pub fn count(&self) -> u32 {
+        if self.beats > 3 && self.beats.is_multiple_of(3) { self.beats / 3 } else { self.beats }
+    }
+
+    // How strongly a chord `offset` into the bar is accented: 2 on the
+    // downbeat, 1 on the middle beat of a bar of four or more (the third of
+    // 4/4, the third dotted quarter of 12/8), 0 on the other beats and off
+    // the beat
+    pub fn accent(&self, offset: Duration) -> u8 {
+        let offset = offset.within(self.bar());
+        if offset == Duration::ZERO {
+            return 2;
+        }
+        let count = self.count();
+        if count >= 4 && count.is_multiple_of(2) && offset == self.bar().split(2) { 1 } else { 0 }
+    }
+}
+
+// "3/4", "6/8"
+impl fmt::Display for Meter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.beats, self.unit)
+    }
+}
+
+// "3/4", or "C" for 4/4 and "C|" for 2/2
+impl FromStr for Meter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid meter {:?}, expected a time signature such as 3/4, 6/8 or C", s);
+        match s.trim() {
+            "C" | "c" => return Ok(Meter::COMMON),
+            "C|" | "c|" => return Ok(Meter::new(2, 2)),
+            _ => {}
+        }
+        let (beats, unit) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let beats: u32 = beats.trim().parse().map_err(|_| invalid())?;
+        let unit: u32 = unit.trim().parse().map_err(|_| invalid())?;
+        if beats == 0 || beats > 64 || !matches!(unit, 1 | 2 | 4 | 8 | 16 | 32) {
+            return Err(invalid());
+        }
+        Ok(Meter::new(beats, unit))
+    }
+}
+
+// Give `symbols` their places in the bar, one after another from a
+// downbeat, each in its own meter
+pub fn place(symbols: &mut [FiguredBassSymbol]) {
+    let mut offset = Duration::ZERO;
+    for symbol in symbols {
+        offset = offset.within(symbol.meter.bar());
+        symbol.offset = offset;
+        offset = offset + symbol.duration;
+    }
+}
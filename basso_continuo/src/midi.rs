@@ -0,0 +1,420 @@
+use std::fmt;
+
+use crate::figure::{Figure, FiguredBassSymbol};
+use crate::key::{Key, Mode};
+use crate::meter::{self, Duration, Meter};
+use crate::pitch::Pitch;
+use crate::voicing::{Voice, Voicing};
+
+// Reading a bass line from a Standard MIDI File: the notes of one track,
+// reduced to the lowest line through them. Timing, velocity and every
+// other message are dropped; only the order of the bass notes is kept.
+// Writing one goes the other way, a realization with its rhythm.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MidiError {
+    NotMidi,                                     // no "MThd" header
+    Truncated,                                   // a chunk or event runs past the end of the file
+    Malformed(String),                           // something no MIDI file should contain
+    NoSuchTrack { track: usize, tracks: usize }, // counting from 0
+    NoNotes,                                     // the track (or every track) has no notes
+}
+
+impl fmt::Display for MidiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MidiError::NotMidi => write!(f, "not a MIDI file"),
+            MidiError::Truncated => write!(f, "the MIDI file ends too soon"),
+            MidiError::Malformed(message) => write!(f, "malformed MIDI file: {}", message),
+            MidiError::NoSuchTrack { track, tracks } => {
+                write!(f, "no track {} in a MIDI file of {} tracks", track + 1, tracks)
+            }
+            MidiError::NoNotes => write!(f, "no notes to take a bass line from"),
+        }
+    }
+}
+
+impl std::error::Error for MidiError {}
+
+// A note as played: when it starts and stops, in ticks from the start of
+// its track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Note {
+    start: u64,
+    end: u64,
+    key: u8,
+}
+
+// The lowest line through `track` of the MIDI file in `bytes`, counting
+// tracks from 0, or through the track lowest on average if none is given.
+// At each moment notes start, the lowest of them is the next bass note,
+// unless a lower note is still held from before (it is then an upper
+// voice moving over the bass). Drum notes (channel 10) are ignored.
+pub fn read_bass_line(bytes: &[u8], track: Option<usize>) -> Result<Vec<Pitch>, MidiError> {
+    let tracks = read_tracks(bytes)?;
+    let notes = match track {
+        Some(track) => tracks.get(track).ok_or(MidiError::NoSuchTrack { track, tracks: tracks.len() })?,
+        None => {
+            let average = |notes: &Vec<Note>| notes.iter().map(|n| n.key as f32).sum::<f32>() / notes.len() as f32;
+            tracks
+                .iter()
+                .filter(|notes| !notes.is_empty())
+                .min_by(|a, b| average(a).total_cmp(&average(b)))
+                .ok_or(MidiError::NoNotes)?
+        }
+    };
+    if notes.is_empty() {
+        return Err(MidiError::NoNotes);
+    }
+
+    let mut notes = notes.clone();
+    notes.sort_by_key(|note| (note.start, note.key));
+    let mut bass = Vec::new();
+    for (i, note) in notes.iter().enumerate() {
+        let first_at_its_start = i == 0 || notes[i - 1].start != note.start;
+        let held_below = notes[..i].iter().any(|n| n.start < note.start && n.end > note.start && n.key < note.key);
+        if first_at_its_start && !held_below {
+            bass.push(Pitch::new(note.key));
+        }
+    }
+    Ok(bass)
+}
+
+// A bass line with no figures: each note takes a root position triad, as
+// a note with a blank figure does
+pub fn unfigured(bass: &[Pitch], key: &Key) -> Vec<FiguredBassSymbol> {
+    let mut symbols: Vec<_> = bass.iter().map(|&pitch| FiguredBassSymbol::new(pitch, Figure::root_position(), key)).collect();
+    meter::place(&mut symbols);
+    symbols
+}
+
+// The bytes of a file, read front to back
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], MidiError> {
+        let bytes = self.bytes.get(self.at..self.at + n).ok_or(MidiError::Truncated)?;
+        self.at += n;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, MidiError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, MidiError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, MidiError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    // A variable-length quantity: seven bits a byte, high bit set on all
+    // but the last, four bytes at most
+    fn varint(&mut self) -> Result<u32, MidiError> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(MidiError::Malformed("variable-length number over four bytes".to_string()))
+    }
+
+    fn done(&self) -> bool {
+        self.at >= self.bytes.len()
+    }
+}
+
+// The notes of every track, in file order
+fn read_tracks(bytes: &[u8]) -> Result<Vec<Vec<Note>>, MidiError> {
+    let mut file = Reader { bytes, at: 0 };
+    if file.take(4).map_err(|_| MidiError::NotMidi)? != b"MThd" {
+        return Err(MidiError::NotMidi);
+    }
+    let header_length = file.u32()? as usize;
+    let mut header = Reader { bytes: file.take(header_length)?, at: 0 };
+    let _format = header.u16()?;
+    let count = header.u16()? as usize;
+
+    let mut tracks = Vec::with_capacity(count);
+    while tracks.len() < count && !file.done() {
+        let kind = file.take(4)?;
+        let length = file.u32()? as usize;
+        let chunk = file.take(length)?;
+        // Chunks of other kinds are for other programs
+        if kind == b"MTrk" {
+            tracks.push(read_track(chunk)?);
+        }
+    }
+    Ok(tracks)
+}
+
+fn read_track(bytes: &[u8]) -> Result<Vec<Note>, MidiError> {
+    let mut track = Reader { bytes, at: 0 };
+    let mut time = 0u64;
+    let mut status = None;
+    let mut sounding: Vec<(u8, u8, u64)> = Vec::new(); // channel, key, start
+    let mut notes = Vec::new();
+
+    while !track.done() {
+        time += track.varint()? as u64;
+        let mut first = track.byte()?;
+        if first & 0x80 != 0 {
+            match first {
+                0xff => {
+                    let kind = track.byte()?;
+                    let length = track.varint()? as usize;
+                    track.take(length)?;
+                    if kind == 0x2f {
+                        break; // end of track
+                    }
+                    continue;
+                }
+                0xf0 | 0xf7 => {
+                    let length = track.varint()? as usize;
+                    track.take(length)?;
+                    continue;
+                }
+                0xf1..=0xfe => return Err(MidiError::Malformed(format!("system message {:#04x} in a track", first))),
+                _ => {
+                    status = Some(first);
+                    first = track.byte()?;
+                }
+            }
+        }
+        // A data byte first repeats the status before it (running status)
+        let status = status.ok_or_else(|| MidiError::Malformed("data before any status".to_string()))?;
+        let channel = status & 0x0f;
+        let second = match status & 0xf0 {
+            0xc0 | 0xd0 => 0,
+            _ => track.byte()?,
+        };
+        let (key, velocity) = (first, second);
+        let on = status & 0xf0 == 0x90 && velocity > 0;
+        let off = status & 0xf0 == 0x80 || (status & 0xf0 == 0x90 && velocity == 0);
+        if channel == 9 {
+            continue;
+        }
+        // A note struck again while sounding ends the one before
+        if (on || off)
+            && let Some(i) = sounding.iter().position(|&(c, k, _)| c == channel && k == key)
+        {
+            let (_, _, start) = sounding.remove(i);
+            notes.push(Note { start, end: time, key });
+        }
+        if on {
+            sounding.push((channel, key, time));
+        }
+    }
+    // Notes never released last to the end of the track
+    notes.extend(sounding.into_iter().map(|(_, key, start)| Note { start, end: time, key }));
+    Ok(notes)
+}
+
+// A note starting or stopping, from a live MIDI stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MidiMessage {
+    NoteOn { channel: u8, key: u8, velocity: u8 },
+    NoteOff { channel: u8, key: u8 },
+}
+
+// Reads the messages of a live MIDI byte stream, such as a raw MIDI
+// device, a byte at a time. Everything but notes is skipped; real-time
+// bytes (clock, active sensing) may come between a message's bytes.
+#[derive(Debug, Clone, Default)]
+pub struct MidiParser {
+    status: Option<u8>,
+    data: Vec<u8>,
+    in_sysex: bool,
+}
+
+impl MidiParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The message `byte` completes, if any
+    pub fn feed(&mut self, byte: u8) -> Option<MidiMessage> {
+        match byte {
+            0xf8..=0xff => return None,
+            0xf0 => {
+                self.in_sysex = true;
+                self.status = None;
+                return None;
+            }
+            0xf7 => {
+                self.in_sysex = false;
+                return None;
+            }
+            0xf1..=0xf6 => {
+                self.status = None;
+                return None;
+            }
+            0x80..=0xef => {
+                self.in_sysex = false;
+                self.status = Some(byte);
+                self.data.clear();
+                return None;
+            }
+            _ => {}
+        }
+        if self.in_sysex {
+            return None;
+        }
+        let status = self.status?;
+        self.data.push(byte);
+        let length = match status & 0xf0 {
+            0xc0 | 0xd0 => 1,
+            _ => 2,
+        };
+        if self.data.len() < length {
+            return None;
+        }
+        // Running status: the next data bytes start another message
+        let data = std::mem::take(&mut self.data);
+        let channel = status & 0x0f;
+        match (status & 0xf0, &data[..]) {
+            (0x90, &[key, 0]) | (0x80, &[key, _]) => Some(MidiMessage::NoteOff { channel, key }),
+            (0x90, &[key, velocity]) => Some(MidiMessage::NoteOn { channel, key, velocity }),
+            _ => None,
+        }
+    }
+}
+
+// The bytes of a message, to send
+pub fn encode(message: MidiMessage) -> [u8; 3] {
+    match message {
+        MidiMessage::NoteOn { channel, key, velocity } => [0x90 | (channel & 0x0f), key & 0x7f, velocity & 0x7f],
+        MidiMessage::NoteOff { channel, key } => [0x80 | (channel & 0x0f), key & 0x7f, 0],
+    }
+}
+
+const TICKS: u64 = 480; // a quarter note
+
+// `realization` of `symbols` as a Standard MIDI File (format 1): a first
+// track with the title, tempo (quarter-note beats a minute) and the time
+// and key signatures wherever they change, then a track for each voice,
+// soprano first, each on its own channel. Every chord lasts as long as
+// its symbol from where it falls in the bar, so an upbeat starts after a
+// rest and the bar lines fall where they should. A voice keeping its
+// note into a held chord holds it on.
+pub fn write_midi(symbols: &[FiguredBassSymbol], realization: &[Voicing], title: &str, tempo: f32) -> Vec<u8> {
+    // Where each chord starts, and the last one ends
+    let mut starts = Vec::with_capacity(symbols.len() + 1);
+    let mut time = symbols.first().map_or(Duration::ZERO, |symbol| symbol.offset);
+    starts.push(ticks(time));
+    for symbol in symbols {
+        time = time + symbol.duration;
+        starts.push(ticks(time));
+    }
+
+    let mut conductor = vec![(0, meta(0x03, title.as_bytes()))];
+    let microseconds = (60_000_000.0 / tempo).round() as u32;
+    conductor.push((0, meta(0x51, &microseconds.to_be_bytes()[1..])));
+    for (i, symbol) in symbols.iter().enumerate() {
+        let before = i.checked_sub(1).map(|i| &symbols[i]);
+        // The first time signature goes at the very start, before any upbeat
+        let at = if i == 0 { 0 } else { starts[i] };
+        if before.is_none_or(|before| before.meter != symbol.meter) {
+            conductor.push((at, meta(0x58, &time_signature(symbol.meter))));
+        }
+        if before.is_none_or(|before| before.key != symbol.key) {
+            conductor.push((at, meta(0x59, &key_signature(&symbol.key))));
+        }
+    }
+
+    let voices = [(Voice::Soprano, "Soprano"), (Voice::Alto, "Alto"), (Voice::Tenor, "Tenor"), (Voice::Bass, "Bass")];
+    let mut tracks = vec![conductor];
+    for (channel, (voice, name)) in voices.into_iter().enumerate() {
+        let channel = channel as u8;
+        let mut events = vec![(0, meta(0x03, name.as_bytes()))];
+        let mut sounding: Option<u8> = None;
+        for (i, (voicing, symbol)) in realization.iter().zip(symbols).enumerate() {
+            let key = voicing.voice(voice).midi_number;
+            if symbol.held && sounding == Some(key) {
+                continue;
+            }
+            if let Some(key) = sounding.take() {
+                events.push((starts[i], encode(MidiMessage::NoteOff { channel, key }).to_vec()));
+            }
+            events.push((starts[i], encode(MidiMessage::NoteOn { channel, key, velocity: 80 }).to_vec()));
+            sounding = Some(key);
+        }
+        if let Some(key) = sounding {
+            let end = starts[realization.len().min(symbols.len())];
+            events.push((end, encode(MidiMessage::NoteOff { channel, key }).to_vec()));
+        }
+        tracks.push(events);
+    }
+
+    let mut file = b"MThd".to_vec();
+    file.extend(6u32.to_be_bytes());
+    file.extend(1u16.to_be_bytes()); // format 1: tracks played together
+    file.extend((tracks.len() as u16).to_be_bytes());
+    file.extend((TICKS as u16).to_be_bytes());
+    for events in tracks {
+        let mut track = Vec::new();
+        let mut now = 0;
+        for (time, bytes) in events {
+            push_varint(&mut track, (time - now) as u32);
+            track.extend(bytes);
+            now = time;
+        }
+        push_varint(&mut track, 0);
+        track.extend(meta(0x2f, &[])); // end of track
+        file.extend(b"MTrk");
+        file.extend((track.len() as u32).to_be_bytes());
+        file.extend(track);
+    }
+    file
+}
+
+// A length in ticks, to the nearest
+fn ticks(duration: Duration) -> u64 {
+    (duration.numerator as u64 * TICKS * 2 + duration.denominator as u64) / (duration.denominator as u64 * 2)
+}
+
+fn meta(kind: u8, data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0xff, kind];
+    push_varint(&mut bytes, data.len() as u32);
+    bytes.extend(data);
+    bytes
+}
+
+// Seven bits a byte, most significant first, the high bit set on all but
+// the last
+fn push_varint(bytes: &mut Vec<u8>, value: u32) {
+    let mut groups = vec![(value & 0x7f) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        groups.push((rest & 0x7f) as u8 | 0x80);
+        rest >>= 7;
+    }
+    bytes.extend(groups.into_iter().rev());
+}
+
+// Beats, the unit as a power of two, MIDI clocks (24 a quarter) a beat
+// as the meter counts them (dotted in 6/8) and 32nds a quarter
+fn time_signature(meter: Meter) -> [u8; 4] {
+    let unit_clocks = 96 / meter.unit;
+    let clocks = if meter.count() == meter.beats { unit_clocks } else { unit_clocks * 3 };
+    [meter.beats as u8, meter.unit.trailing_zeros() as u8, clocks as u8, 8]
+}
+
+// Sharps (or, below zero, flats) in the signature, and whether the key
+// is minor; the church modes go by their signature alone, as major keys
+fn key_signature(key: &Key) -> [u8; 2] {
+    let sharps: i8 = key.signature().iter().map(|accidental| accidental.signum()).sum();
+    let minor = matches!(key.mode, Mode::Minor | Mode::HarmonicMinor | Mode::MelodicMinor);
+    [sharps as u8, minor as u8]
+}
@@ -0,0 +1,624 @@
+use std::fmt;
+
+use crate::figure::FiguredBassSymbol;
+use crate::key::{Key, Mode};
+use crate::meter::{self, Duration};
+use crate::parse::{parse_figure, ParseError};
+use crate::pitch::{Letter, Pitch};
+use crate::voicing::{Voice, Voicing};
+
+// Reading a figured bass from a MusicXML score (partwise, uncompressed):
+// the notes of one part with the <figured-bass> elements written over
+// them. A <figured-bass> comes before the note it stands over; several
+// before one note are figures in turn over the held bass, as in a 4-3.
+// A note with no figures is a 5/3, as everywhere else, and one with a
+// <fermata> in its <notations> ends a phrase.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MusicXmlError {
+    Xml(String),                   // not well-formed XML
+    NotPartwise(String),           // the root element, if not <score-partwise>
+    NoSuchPart(String),            // the part id asked for
+    NoNotes,
+    InvalidNote(String),           // a pitch that makes no sense, as written
+    Figure(ParseError),
+}
+
+impl fmt::Display for MusicXmlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MusicXmlError::Xml(message) => write!(f, "invalid XML: {}", message),
+            MusicXmlError::NotPartwise(root) => {
+                write!(f, "expected a partwise MusicXML score, found <{}> (compressed .mxl files need unzipping first)", root)
+            }
+            MusicXmlError::NoSuchPart(id) => write!(f, "no part {:?} in the score", id),
+            MusicXmlError::NoNotes => write!(f, "no notes in the bass part"),
+            MusicXmlError::InvalidNote(text) => write!(f, "invalid note {}", text),
+            MusicXmlError::Figure(e) => write!(f, "invalid figure {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MusicXmlError {}
+
+// A figured bass read from a score, in the key of its first key signature
+// (C major if it has none)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FiguredBassPart {
+    pub id: String,
+    pub key: Key,
+    pub symbols: Vec<FiguredBassSymbol>,
+}
+
+// The figured bass of part `part` (by id) of the MusicXML score `text`, or
+// of the first part with figures (the last part if none has any). The
+// figures are read in `key` if given, else in the score's own key.
+pub fn read_figured_bass(text: &str, part: Option<&str>, key: Option<&Key>) -> Result<FiguredBassPart, MusicXmlError> {
+    let root = xml::parse(text).map_err(MusicXmlError::Xml)?;
+    if root.name != "score-partwise" {
+        return Err(MusicXmlError::NotPartwise(root.name));
+    }
+    let parts: Vec<&xml::Element> = root.children_named("part").collect();
+    let part = match part {
+        Some(id) => parts.iter().find(|p| p.attribute("id") == Some(id)).ok_or(MusicXmlError::NoSuchPart(id.to_string()))?,
+        None => parts
+            .iter()
+            .find(|p| p.descendants_named("figured-bass").next().is_some())
+            .or(parts.last())
+            .ok_or(MusicXmlError::NoNotes)?,
+    };
+
+    let key = match key {
+        Some(key) => key.clone(),
+        None => part.descendants_named("key").next().map_or_else(Key::c_major, key_signature),
+    };
+
+    // Each bass note and the figures over it, in turn
+    let mut events: Vec<(Pitch, Vec<String>, bool)> = Vec::new();
+    let mut figures: Vec<String> = Vec::new();
+    let mut voice = None;
+    for measure in part.children_named("measure") {
+        for element in &measure.children {
+            let xml::Node::Element(element) = element else {
+                continue;
+            };
+            match element.name.as_str() {
+                "figured-bass" => figures.push(figure_text(element)),
+                "note" => {
+                    let Some(pitch) = element.child("pitch") else {
+                        figures.clear(); // figures over a rest go unplayed
+                        continue;
+                    };
+                    let this_voice = element.child("voice").map(xml::Element::text);
+                    if voice.is_none() {
+                        voice = Some(this_voice.clone());
+                    }
+                    if voice.as_ref() != Some(&this_voice) {
+                        continue;
+                    }
+                    let pitch = note_pitch(pitch)?;
+                    let tied = element.children_named("tie").any(|tie| tie.attribute("type") == Some("stop"));
+                    let fermata = element.children_named("notations").any(|n| n.child("fermata").is_some());
+                    if element.child("chord").is_some() {
+                        // The lowest note of a chord is the bass
+                        if let Some((bass, _, over)) = events.last_mut() {
+                            *bass = (*bass).min(pitch);
+                            *over |= fermata;
+                        }
+                    } else if tied && let Some((_, held, over)) = events.last_mut() {
+                        held.append(&mut figures);
+                        *over |= fermata;
+                    } else {
+                        events.push((pitch, std::mem::take(&mut figures), fermata));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if events.is_empty() {
+        return Err(MusicXmlError::NoNotes);
+    }
+
+    let mut symbols = Vec::new();
+    for (bass, figures, fermata) in events {
+        let figures = if figures.is_empty() { vec![String::new()] } else { figures };
+        for (i, text) in figures.iter().enumerate() {
+            let mut symbol = FiguredBassSymbol::new(bass, parse_figure(text).map_err(MusicXmlError::Figure)?, &key);
+            symbol.held = i > 0;
+            symbol.fermata = fermata && i == figures.len() - 1;
+            symbols.push(symbol);
+        }
+    }
+    meter::place(&mut symbols);
+    Ok(FiguredBassPart { id: part.attribute("id").unwrap_or_default().to_string(), key, symbols })
+}
+
+// <key><fifths>-2</fifths><mode>minor</mode></key> is G minor
+fn key_signature(element: &xml::Element) -> Key {
+    let fifths: i32 = element.child("fifths").and_then(|f| f.text().parse().ok()).unwrap_or(0);
+    let minor = element.child("mode").is_some_and(|m| m.text() == "minor");
+    let major_tonic = (fifths * 7).rem_euclid(12) as u8;
+    match minor {
+        true => Key::new((major_tonic + 9) % 12, Mode::Minor),
+        false => Key::new(major_tonic, Mode::Major),
+    }
+}
+
+// <pitch><step>F</step><alter>1</alter><octave>2</octave></pitch>
+fn note_pitch(element: &xml::Element) -> Result<Pitch, MusicXmlError> {
+    let field = |name: &str| element.child(name).map(xml::Element::text).unwrap_or_default();
+    let invalid = || MusicXmlError::InvalidNote(format!("{}{}{}", field("step"), field("alter"), field("octave")));
+    let letter = field("step").chars().next().and_then(Letter::from_char).ok_or_else(invalid)?;
+    // Microtones round to the nearest semitone
+    let alter = match field("alter").as_str() {
+        "" => 0,
+        alter => alter.parse::<f32>().map_err(|_| invalid())?.round() as i32,
+    };
+    let octave: i32 = field("octave").parse().map_err(|_| invalid())?;
+    let midi = (octave + 1) * 12 + letter.pitch_class() as i32 + alter;
+    u8::try_from(midi).ok().filter(|&m| m <= 127).map(Pitch::new).ok_or_else(invalid)
+}
+
+// Part of a chord as one written note: a chord that crosses a bar line,
+// or lasts longer than any one note value, is several tied together
+struct Piece {
+    symbol: usize,
+    duration: Duration,
+    first: bool, // of its chord
+    last: bool,
+}
+
+// `realization` of `symbols` as a partwise MusicXML score: a part for each
+// voice, soprano first, with the figures over the bass part's notes (where
+// read_figured_bass finds them again). The chords keep their lengths, in
+// measures of each symbol's meter, an upbeat making a short first measure;
+// a chord crossing a bar line is tied over it, as is a voice keeping its
+// note into a held chord. The chords that end phrases have fermatas.
+pub fn write_musicxml(symbols: &[FiguredBassSymbol], realization: &[Voicing], title: &str) -> String {
+    let symbols = &symbols[..realization.len().min(symbols.len())];
+    let measures = measures(symbols);
+    // Divisions of a quarter note that every piece is a whole number of
+    let divisions = measures.iter().flatten().fold(1, |divisions, piece| lcm(divisions, piece.duration.denominator));
+    let upbeat = symbols.first().is_some_and(|symbol| symbol.offset != Duration::ZERO);
+
+    let voices = [
+        (Voice::Soprano, "Soprano", "<sign>G</sign><line>2</line>"),
+        (Voice::Alto, "Alto", "<sign>G</sign><line>2</line>"),
+        (Voice::Tenor, "Tenor", "<sign>G</sign><line>2</line><clef-octave-change>-1</clef-octave-change>"),
+        (Voice::Bass, "Bass", "<sign>F</sign><line>4</line>"),
+    ];
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 4.0 Partwise//EN\" \"http://www.musicxml.org/dtds/partwise.dtd\">\n");
+    xml.push_str("<score-partwise version=\"4.0\">\n");
+    xml.push_str(&format!("  <work><work-title>{}</work-title></work>\n", escape(title)));
+    xml.push_str("  <part-list>\n");
+    for (i, (_, name, _)) in voices.iter().enumerate() {
+        xml.push_str(&format!("    <score-part id=\"P{}\"><part-name>{}</part-name></score-part>\n", i + 1, name));
+    }
+    xml.push_str("  </part-list>\n");
+
+    for (i, &(voice, _, clef)) in voices.iter().enumerate() {
+        xml.push_str(&format!("  <part id=\"P{}\">\n", i + 1));
+        for (m, pieces) in measures.iter().enumerate() {
+            let Some(start) = pieces.first().map(|piece| &symbols[piece.symbol]) else {
+                continue;
+            };
+            match (m, upbeat) {
+                (0, true) => xml.push_str("    <measure number=\"0\" implicit=\"yes\">\n"),
+                _ => xml.push_str(&format!("    <measure number=\"{}\">\n", if upbeat { m } else { m + 1 })),
+            }
+
+            // Whatever is new since the measure before
+            let before = m.checked_sub(1).and_then(|m| measures[m].last()).map(|piece| &symbols[piece.symbol]);
+            let mut attributes = String::new();
+            if m == 0 {
+                attributes.push_str(&format!("<divisions>{}</divisions>", divisions));
+            }
+            if before.is_none_or(|before| before.key != start.key) {
+                attributes.push_str(&key_element(&start.key));
+            }
+            if before.is_none_or(|before| before.meter != start.meter) {
+                let meter = start.meter;
+                attributes.push_str(&format!("<time><beats>{}</beats><beat-type>{}</beat-type></time>", meter.beats, meter.unit));
+            }
+            if m == 0 {
+                attributes.push_str(&format!("<clef>{}</clef>", clef));
+            }
+            if !attributes.is_empty() {
+                xml.push_str(&format!("      <attributes>{}</attributes>\n", attributes));
+            }
+
+            for piece in pieces {
+                let (k, symbol) = (piece.symbol, &symbols[piece.symbol]);
+                let pitch = realization[k].voice(voice);
+                if voice == Voice::Bass && piece.first {
+                    xml.push_str(&figured_bass_element(&symbol.written_figure()));
+                }
+                let held_from = k > 0 && symbol.held && realization[k - 1].voice(voice) == pitch;
+                let held_into = symbols.get(k + 1).is_some_and(|next| next.held) && realization.get(k + 1).is_some_and(|next| next.voice(voice) == pitch);
+                let stop = !piece.first || held_from;
+                let start = !piece.last || held_into;
+
+                let spelled = symbol.spell(pitch);
+                let mut note = String::from("      <note><pitch>");
+                note.push_str(&format!("<step>{}</step>", spelled.letter));
+                if spelled.accidental != 0 {
+                    note.push_str(&format!("<alter>{}</alter>", spelled.accidental));
+                }
+                note.push_str(&format!("<octave>{}</octave></pitch>", spelled.octave));
+                let length = piece.duration.numerator * (divisions / piece.duration.denominator);
+                note.push_str(&format!("<duration>{}</duration>", length));
+                let mut notations = String::new();
+                if stop {
+                    note.push_str("<tie type=\"stop\"/>");
+                    notations.push_str("<tied type=\"stop\"/>");
+                }
+                if start {
+                    note.push_str("<tie type=\"start\"/>");
+                    notations.push_str("<tied type=\"start\"/>");
+                }
+                note.push_str("<voice>1</voice>");
+                // A triplet is written as the note two thirds of it would be
+                let triplet = note_type(piece.duration).is_none();
+                let written = if triplet { Duration::new(piece.duration.numerator * 3, piece.duration.denominator * 2) } else { piece.duration };
+                if let Some((name, dotted)) = note_type(written) {
+                    note.push_str(&format!("<type>{}</type>", name));
+                    if dotted {
+                        note.push_str("<dot/>");
+                    }
+                    if triplet {
+                        note.push_str("<time-modification><actual-notes>3</actual-notes><normal-notes>2</normal-notes></time-modification>");
+                    }
+                }
+                if symbol.fermata && piece.last {
+                    notations.push_str("<fermata type=\"upright\"/>");
+                }
+                if !notations.is_empty() {
+                    note.push_str(&format!("<notations>{}</notations>", notations));
+                }
+                note.push_str("</note>\n");
+                xml.push_str(&note);
+            }
+            if m == measures.len() - 1 {
+                xml.push_str("      <barline location=\"right\"><bar-style>light-heavy</bar-style></barline>\n");
+            }
+            xml.push_str("    </measure>\n");
+        }
+        xml.push_str("  </part>\n");
+    }
+    xml.push_str("</score-partwise>\n");
+    xml
+}
+
+// The chords of `symbols` cut at the bar lines into measures, and into
+// lengths that can be written as one note, plain or dotted. A length that
+// is no sum of those (a triplet) is left whole, to be written as a
+// triplet if it is one and without a note type if not.
+fn measures(symbols: &[FiguredBassSymbol]) -> Vec<Vec<Piece>> {
+    let mut measures: Vec<Vec<Piece>> = vec![Vec::new()];
+    let Some(first) = symbols.first() else {
+        return measures;
+    };
+    let mut room = first.meter.bar() - first.offset; // left in the measure
+    for (i, symbol) in symbols.iter().enumerate() {
+        let mut left = symbol.duration;
+        while left > Duration::ZERO {
+            if room == Duration::ZERO {
+                measures.push(Vec::new());
+                room = symbol.meter.bar();
+            }
+            let here = left.min(room);
+            for duration in note_values(here) {
+                let first = left == symbol.duration;
+                left = left - duration;
+                room = room - duration;
+                measures.last_mut().expect("there is always a measure").push(Piece {
+                    symbol: i,
+                    duration,
+                    first,
+                    last: left == Duration::ZERO,
+                });
+            }
+        }
+    }
+    measures
+}
+
+// `duration` as written notes, longest first
+fn note_values(duration: Duration) -> Vec<Duration> {
+    if !duration.denominator.is_power_of_two() {
+        return vec![duration];
+    }
+    let mut values = Vec::new();
+    let mut left = duration;
+    while left > Duration::ZERO {
+        let value = (0..=6)
+            .flat_map(|shift| [Duration::new(6, 1 << shift), Duration::new(4, 1 << shift)])
+            .find(|&value| value <= left)
+            .unwrap_or(left);
+        values.push(value);
+        left = left - value;
+    }
+    values
+}
+
+// "quarter", or "half" and a dot for a dotted half
+fn note_type(duration: Duration) -> Option<(&'static str, bool)> {
+    const TYPES: [&str; 7] = ["whole", "half", "quarter", "eighth", "16th", "32nd", "64th"];
+    TYPES.iter().enumerate().find_map(|(shift, &name)| {
+        if duration == Duration::new(4, 1 << shift) {
+            Some((name, false))
+        } else if duration == Duration::new(6, 1 << shift) {
+            Some((name, true))
+        } else {
+            None
+        }
+    })
+}
+
+// <key><fifths>-2</fifths><mode>minor</mode></key> for G minor, as
+// key_signature reads it
+fn key_element(key: &Key) -> String {
+    let fifths: i32 = key.signature().iter().map(|&accidental| accidental.signum() as i32).sum();
+    let mode = match key.mode {
+        Mode::Minor | Mode::HarmonicMinor | Mode::MelodicMinor => "minor".to_string(),
+        mode => mode.to_string(),
+    };
+    format!("<key><fifths>{}</fifths><mode>{}</mode></key>", fifths, mode)
+}
+
+// The figures "#6/4" as <figure> elements, top first; nothing for a blank
+// figure
+fn figured_bass_element(written: &str) -> String {
+    if written.is_empty() {
+        return String::new();
+    }
+    let mut xml = String::from("      <figured-bass>");
+    for figure in written.split('/') {
+        let number = figure.trim_start_matches(['#', 'b', '♮']);
+        let prefix = match &figure[..figure.len() - number.len()] {
+            "#" => "<prefix>sharp</prefix>",
+            "b" => "<prefix>flat</prefix>",
+            "♮" => "<prefix>natural</prefix>",
+            _ => "",
+        };
+        xml.push_str(&format!("<figure>{}<figure-number>{}</figure-number></figure>", prefix, number));
+    }
+    xml.push_str("</figured-bass>\n");
+    xml
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn lcm(a: u32, b: u32) -> u32 {
+    let gcd = |mut a: u32, mut b: u32| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    a / gcd(a, b) * b
+}
+
+// A <figured-bass> as the crate writes figures, top to bottom: prefix
+// and suffix accidentals as "#", "b" and "n", a slashed figure as "\"
+fn figure_text(element: &xml::Element) -> String {
+    let accidental = |text: String| match text.as_str() {
+        "sharp" | "double-sharp" | "sharp-sharp" => "#",
+        "flat" | "double-flat" | "flat-flat" => "b",
+        "natural" => "n",
+        "slash" | "back-slash" | "vertical" | "cross" => "\\",
+        "plus" => "+",
+        _ => "",
+    };
+    element
+        .children_named("figure")
+        .filter_map(|figure| {
+            let prefix = figure.child("prefix").map_or("", |p| accidental(p.text()));
+            let number = figure.child("figure-number").map(xml::Element::text).unwrap_or_default();
+            let suffix = figure.child("suffix").map_or("", |s| accidental(s.text()));
+            let text = format!("{}{}{}", prefix, number, suffix);
+            // A figure of only an extension line continues the one before
+            (!text.is_empty()).then_some(text)
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Just enough XML for MusicXML: elements, attributes, text, comments,
+// CDATA, processing instructions and the DOCTYPE, with the predefined
+// and numeric entities
+mod xml {
+    #[derive(Debug, Clone)]
+    pub enum Node {
+        Element(Element),
+        Text(String),
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct Element {
+        pub name: String,
+        pub attributes: Vec<(String, String)>,
+        pub children: Vec<Node>,
+    }
+
+    impl Element {
+        pub fn attribute(&self, name: &str) -> Option<&str> {
+            self.attributes.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+        }
+
+        pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+            self.children.iter().filter_map(move |node| match node {
+                Node::Element(element) if element.name == name => Some(element),
+                _ => None,
+            })
+        }
+
+        pub fn child(&self, name: &str) -> Option<&Element> {
+            self.children.iter().find_map(|node| match node {
+                Node::Element(element) if element.name == name => Some(element),
+                _ => None,
+            })
+        }
+
+        // Every element under this one named `name`, depth first
+        pub fn descendants_named<'a>(&'a self, name: &'a str) -> Box<dyn Iterator<Item = &'a Element> + 'a> {
+            Box::new(self.children.iter().flat_map(move |node| match node {
+                Node::Element(element) => {
+                    let this = (element.name == name).then_some(element);
+                    Box::new(this.into_iter().chain(element.descendants_named(name)))
+                        as Box<dyn Iterator<Item = &'a Element> + 'a>
+                }
+                Node::Text(_) => Box::new(std::iter::empty()),
+            }))
+        }
+
+        // The text inside, trimmed
+        pub fn text(&self) -> String {
+            let text: String = self
+                .children
+                .iter()
+                .filter_map(|node| match node {
+                    Node::Text(text) => Some(text.as_str()),
+                    Node::Element(_) => None,
+                })
+                .collect();
+            text.trim().to_string()
+        }
+    }
+
+    // The root element of a document
+    pub fn parse(text: &str) -> Result<Element, String> {
+        let mut parser = Parser { rest: text.trim_start_matches('\u{feff}') };
+        loop {
+            parser.rest = parser.rest.trim_start();
+            if parser.rest.starts_with("<?") {
+                parser.skip_past("?>")?;
+            } else if parser.rest.starts_with("<!--") {
+                parser.skip_past("-->")?;
+            } else if parser.rest.starts_with("<!DOCTYPE") {
+                // An internal subset in brackets may hold '>' of its own
+                let end = parser.rest.find('>').ok_or("unterminated DOCTYPE")?;
+                if parser.rest[..end].contains('[') {
+                    parser.skip_past("]>")?;
+                } else {
+                    parser.rest = &parser.rest[end + 1..];
+                }
+            } else {
+                break;
+            }
+        }
+        parser.element()
+    }
+
+    struct Parser<'a> {
+        rest: &'a str,
+    }
+
+    impl<'a> Parser<'a> {
+        fn skip_past(&mut self, end: &str) -> Result<&'a str, String> {
+            let at = self.rest.find(end).ok_or_else(|| format!("missing {:?}", end))?;
+            let skipped = &self.rest[..at];
+            self.rest = &self.rest[at + end.len()..];
+            Ok(skipped)
+        }
+
+        fn name(&mut self) -> Result<String, String> {
+            let end = self.rest.find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '=')).unwrap_or(self.rest.len());
+            if end == 0 {
+                return Err(format!("expected a name at {:?}", preview(self.rest)));
+            }
+            let name = self.rest[..end].to_string();
+            self.rest = &self.rest[end..];
+            Ok(name)
+        }
+
+        fn element(&mut self) -> Result<Element, String> {
+            self.rest = self.rest.strip_prefix('<').ok_or_else(|| format!("expected an element at {:?}", preview(self.rest)))?;
+            let name = self.name()?;
+            let mut attributes = Vec::new();
+            loop {
+                self.rest = self.rest.trim_start();
+                if let Some(rest) = self.rest.strip_prefix("/>") {
+                    self.rest = rest;
+                    return Ok(Element { name, attributes, children: Vec::new() });
+                }
+                if let Some(rest) = self.rest.strip_prefix('>') {
+                    self.rest = rest;
+                    break;
+                }
+                let attribute = self.name()?;
+                self.rest = self.rest.trim_start().strip_prefix('=').ok_or_else(|| format!("expected = after {}", attribute))?;
+                self.rest = self.rest.trim_start();
+                let quote = self.rest.chars().next().filter(|&c| c == '"' || c == '\'').ok_or("expected a quoted value")?;
+                self.rest = &self.rest[1..];
+                let value = self.skip_past(&quote.to_string())?;
+                attributes.push((attribute, unescape(value)?));
+            }
+
+            let mut children = Vec::new();
+            loop {
+                if let Some(rest) = self.rest.strip_prefix("</") {
+                    self.rest = rest;
+                    let closing = self.name()?;
+                    if closing != name {
+                        return Err(format!("<{}> closed by </{}>", name, closing));
+                    }
+                    self.rest = self.rest.trim_start().strip_prefix('>').ok_or("expected > after a closing tag")?;
+                    return Ok(Element { name, attributes, children });
+                } else if self.rest.starts_with("<!--") {
+                    self.skip_past("-->")?;
+                } else if let Some(rest) = self.rest.strip_prefix("<![CDATA[") {
+                    self.rest = rest;
+                    children.push(Node::Text(self.skip_past("]]>")?.to_string()));
+                } else if self.rest.starts_with("<?") {
+                    self.skip_past("?>")?;
+                } else if self.rest.starts_with('<') {
+                    children.push(Node::Element(self.element()?));
+                } else if self.rest.is_empty() {
+                    return Err(format!("<{}> is never closed", name));
+                } else {
+                    let end = self.rest.find('<').unwrap_or(self.rest.len());
+                    children.push(Node::Text(unescape(&self.rest[..end])?));
+                    self.rest = &self.rest[end..];
+                }
+            }
+        }
+    }
+
+    fn preview(text: &str) -> String {
+        text.chars().take(20).collect()
+    }
+
+    fn unescape(text: &str) -> Result<String, String> {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(at) = rest.find('&') {
+            out.push_str(&rest[..at]);
+            let end = rest[at..].find(';').ok_or("unterminated entity")? + at;
+            let entity = &rest[at + 1..end];
+            let c = match entity {
+                "lt" => '<',
+                "gt" => '>',
+                "amp" => '&',
+                "quot" => '"',
+                "apos" => '\'',
+                _ => {
+                    let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => entity.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                    };
+                    code.and_then(char::from_u32).ok_or_else(|| format!("unknown entity &{};", entity))?
+                }
+            };
+            out.push(c);
+            rest = &rest[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
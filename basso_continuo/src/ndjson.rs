@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::{to_json, SpelledVoicing, ViolationReport};
+use crate::diagnostics::{self, Violation};
+use crate::figure::{Figure, FiguredBassSymbol};
+use crate::key::Key;
+use crate::parse::{parse_figure, parse_spelled_pitch};
+use crate::realize::Realizer;
+use crate::roman;
+use crate::stream::StreamingRealizer;
+
+// Newline-delimited JSON, for driving the realizer from another program
+// through a pipe: a bass event on each line in, and a line out for each
+// chord as it is decided, with its voice-leading faults, or for an event
+// that could not be taken.
+//
+//   {"bass": "C3"}
+//   {"bass": "A3", "figure": "6"}
+//   {"bass": "D3", "figure": "4", "key": "G major"}
+//   {"bass": "D3", "figure": "3", "held": true}
+//   {"bass": "G2", "fermata": true}
+//   {"bass": "G2", "figure": "6/4", "pedal": true}
+//   {"flush": true}
+//
+//   {"chord":1,"bass":"C3","figure":"5/3","numeral":"I","voicing":{"soprano":"G4",...},"violations":[]}
+//   {"line":6,"error":"..."}
+//
+// "key" changes the key from that event on, "fermata" ends a phrase on
+// the event's chord, and "pedal" holds the bass on under a new harmony, as
+// "held" does for a suspension's resolution. With lookahead a chord is
+// only decided once more events have come in; a flush decides the chords
+// still waiting, as the end of the input does.
+
+// One line of input
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Request {
+    bass: Option<String>,
+    figure: Option<String>, // as in a progression, "6/5"; a 5/3 if not given
+    #[serde(default)]
+    held: bool,
+    #[serde(default)]
+    pedal: bool,
+    #[serde(default)]
+    fermata: bool, // the chord ends a phrase
+    key: Option<String>,
+    #[serde(default)]
+    flush: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ChordReply {
+    chord: usize, // counting from 1
+    bass: String,
+    figure: String,
+    numeral: Option<String>,
+    voicing: SpelledVoicing,
+    violations: Vec<ViolationReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorReply {
+    line: usize,
+    error: String,
+}
+
+pub struct NdjsonSession {
+    stream: StreamingRealizer,
+    key: Key,
+    line: usize, // lines read, blank ones included
+}
+
+impl NdjsonSession {
+    pub fn new(realizer: Realizer, key: Key, lookahead: usize) -> Self {
+        Self { stream: StreamingRealizer::new(realizer, lookahead), key, line: 0 }
+    }
+
+    // The lines to answer one line of input with: one for each chord it
+    // decides, or one for its error
+    pub fn respond(&mut self, line: &str) -> Vec<String> {
+        self.line += 1;
+        if line.trim().is_empty() {
+            return Vec::new();
+        }
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return vec![self.error(e.to_string())],
+        };
+        if request.flush {
+            return self.finish();
+        }
+        let symbol = match self.symbol(request) {
+            Ok(symbol) => symbol,
+            Err(message) => return vec![self.error(message)],
+        };
+        match self.stream.push(symbol) {
+            Ok(Some(_)) => vec![self.chord(self.stream.voicings().len() - 1)],
+            Ok(None) => Vec::new(),
+            Err(e) => vec![self.error(e.to_string())],
+        }
+    }
+
+    // The input has ended, or was flushed: lines for the chords still
+    // waiting
+    pub fn finish(&mut self) -> Vec<String> {
+        let decided = self.stream.voicings().len();
+        match self.stream.finish() {
+            Ok(rest) => (decided..decided + rest.len()).map(|i| self.chord(i)).collect(),
+            Err(e) => vec![self.error(e.to_string())],
+        }
+    }
+
+    fn symbol(&mut self, request: Request) -> Result<FiguredBassSymbol, String> {
+        if let Some(key) = request.key {
+            self.key = key.parse()?;
+        }
+        let bass = request.bass.ok_or("expected \"bass\" or \"flush\"")?;
+        let bass = parse_spelled_pitch(&bass).map_err(|e| e.to_string())?;
+        let figure = match request.figure {
+            Some(figure) => parse_figure(&figure).map_err(|e| e.to_string())?,
+            None => Figure::root_position(),
+        };
+        if (request.held || request.pedal) && self.stream.symbols().is_empty() {
+            return Err("the first chord cannot be held".to_string());
+        }
+        let mut symbol = FiguredBassSymbol::new(bass.pitch(), figure, &self.key);
+        symbol.held = request.held;
+        if request.pedal {
+            symbol.over_pedal();
+        }
+        symbol.fermata = request.fermata;
+        Ok(symbol)
+    }
+
+    // The reply for the decided chord at `index`, with the faults in it
+    // and in the move into it
+    fn chord(&self, index: usize) -> String {
+        let symbol = &self.stream.symbols()[index];
+        let voicing = &self.stream.voicings()[index];
+        // A suspension is named by the chord it resolves to, if that has
+        // come in
+        let following = &self.stream.symbols()[index..(index + 2).min(self.stream.symbols().len())];
+        let from = index.saturating_sub(1);
+        let violations = diagnostics::analyze(&self.stream.symbols()[from..=index], &self.stream.voicings()[from..=index])
+            .into_iter()
+            .map(|violation| Violation { chords: violation.chords.iter().map(|c| c + from).collect(), ..violation })
+            .filter(|violation| violation.chords.last() == Some(&index))
+            .map(|violation| ViolationReport::from(&violation))
+            .collect();
+        let reply = ChordReply {
+            chord: index + 1,
+            bass: symbol.spell(symbol.bass).to_string(),
+            figure: symbol.figure.to_string(),
+            numeral: roman::analyze(following, &symbol.key)[0].as_ref().map(|n| n.to_string()),
+            voicing: SpelledVoicing::new(voicing, symbol),
+            violations,
+        };
+        to_json(&reply)
+    }
+
+    fn error(&self, error: String) -> String {
+        to_json(&ErrorReply { line: self.line, error })
+    }
+}
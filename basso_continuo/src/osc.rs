@@ -0,0 +1,126 @@
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+
+use crate::voicing::{Voice, Voicing};
+
+// Open Sound Control over UDP, for driving SuperCollider, Max or Pd
+// patches with the chords as they are realized. Each chord goes out as
+// one bundle, to be acted on at once:
+//
+//   /basso_continuo/soprano  ,ii  72 80     MIDI note and velocity
+//   /basso_continuo/alto     ,ii  67 80
+//   /basso_continuo/tenor    ,ii  64 80
+//   /basso_continuo/bass     ,ii  48 80
+//   /basso_continuo/chord    ,iiiii  3 72 67 64 48
+//                                   (chord counting from 1, then the
+//                                    notes soprano first)
+//
+// and at the end each voice gets velocity 0, to let go. The prefix is
+// the sender's to choose.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    Str(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String, // "/basso_continuo/soprano"
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    pub fn new(address: impl Into<String>, args: Vec<OscArg>) -> Self {
+        Self { address: address.into(), args }
+    }
+
+    // The OSC 1.0 packet: the address and type tags as padded strings,
+    // then the arguments, big-endian
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        push_string(&mut bytes, &self.address);
+        let tags: String = std::iter::once(',')
+            .chain(self.args.iter().map(|arg| match arg {
+                OscArg::Int(_) => 'i',
+                OscArg::Float(_) => 'f',
+                OscArg::Str(_) => 's',
+            }))
+            .collect();
+        push_string(&mut bytes, &tags);
+        for arg in &self.args {
+            match arg {
+                OscArg::Int(n) => bytes.extend(n.to_be_bytes()),
+                OscArg::Float(x) => bytes.extend(x.to_be_bytes()),
+                OscArg::Str(s) => push_string(&mut bytes, s),
+            }
+        }
+        bytes
+    }
+}
+
+// `messages` as one bundle, to take effect as soon as it arrives
+pub fn encode_bundle(messages: &[OscMessage]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    push_string(&mut bytes, "#bundle");
+    bytes.extend(1u64.to_be_bytes()); // the time tag meaning "immediately"
+    for message in messages {
+        let packet = message.encode();
+        bytes.extend((packet.len() as i32).to_be_bytes());
+        bytes.extend(packet);
+    }
+    bytes
+}
+
+// A string with its terminating zero, padded to a multiple of four bytes
+fn push_string(bytes: &mut Vec<u8>, s: &str) {
+    bytes.extend(s.as_bytes());
+    let padding = 4 - s.len() % 4;
+    bytes.extend(std::iter::repeat_n(0, padding));
+}
+
+const VOICES: [Voice; 4] = [Voice::Soprano, Voice::Alto, Voice::Tenor, Voice::Bass];
+
+// Sends chords to one host and port
+#[derive(Debug)]
+pub struct OscSender {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+const VELOCITY: i32 = 80;
+
+impl OscSender {
+    // Send to `addr` ("127.0.0.1:57120", SuperCollider's port), addresses
+    // starting with `prefix` ("/basso_continuo")
+    pub fn connect(addr: impl ToSocketAddrs, prefix: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.connect(addr)?;
+        Ok(Self { socket, prefix: prefix.trim_end_matches('/').to_string() })
+    }
+
+    // Chord `index` (counting from 0), each voice and then the whole
+    pub fn send_chord(&self, index: usize, voicing: &Voicing) -> io::Result<()> {
+        let mut messages = self.notes(voicing, VELOCITY);
+        let mut args = vec![OscArg::Int(index as i32 + 1)];
+        args.extend(VOICES.map(|voice| OscArg::Int(voicing.voice(voice).midi_number as i32)));
+        messages.push(OscMessage::new(format!("{}/chord", self.prefix), args));
+        self.socket.send(&encode_bundle(&messages)).map(|_| ())
+    }
+
+    // Let go of the notes of `voicing`, the last chord sent
+    pub fn release(&self, voicing: &Voicing) -> io::Result<()> {
+        self.socket.send(&encode_bundle(&self.notes(voicing, 0))).map(|_| ())
+    }
+
+    fn notes(&self, voicing: &Voicing, velocity: i32) -> Vec<OscMessage> {
+        VOICES
+            .iter()
+            .map(|&voice| {
+                let note = OscArg::Int(voicing.voice(voice).midi_number as i32);
+                OscMessage::new(format!("{}/{}", self.prefix, voice), vec![note, OscArg::Int(velocity)])
+            })
+            .collect()
+    }
+}
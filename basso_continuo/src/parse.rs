@@ -0,0 +1,233 @@
+use std::fmt;
+
+use crate::figure::{Accidental, Figure, FiguredBassSymbol, Interval};
+use crate::key::Key;
+use crate::meter;
+use crate::pitch::{Letter, Pitch, SpelledPitch};
+use crate::voicing::Voicing;
+
+// Text input for figured bass:
+//
+//   figure       "6", "6/4" (or "64"), "7", "6/5", "4/3", "4/2" (or "2"),
+//                with accidentals before or after a numeral ("#", "b7",
+//                "6#", "n3", "6\\" for a slashed 6); blank means 5/3
+//   symbol       a bass note with its figure after a colon: "A3:6", "C3"
+//   event        a symbol whose bass is held under several figures joined
+//                by "-", as for suspensions: "C3:4-3", "E3:7-6", "G3:6/4-5/3"
+//   progression  events separated by whitespace: "C3 A3:6 G3:7 C3"
+//   melody       pitches separated by whitespace: "E5 D5 C5"
+//   voicing      four pitches, soprano first, joined by commas: "G4,E4,C4,C3"
+//   realization  voicings separated by whitespace, "|" bar lines ignored:
+//                "G4,E4,C4,C3 A4,F4,C4,A2 | B4,F4,D4,G2 C5,E4,C4,C3"
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub input: String,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(input: &str, message: impl Into<String>) -> Self {
+        Self { input: input.to_string(), message: message.into() }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {}", self.input, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Accidental signs; a slash through a numeral (typed as a trailing "\\")
+// raises it like a sharp
+fn accidental(c: char) -> Option<Accidental> {
+    match c {
+        '#' | '♯' | '+' | '\\' => Some(Accidental::Sharp),
+        'b' | '♭' => Some(Accidental::Flat),
+        'n' | '♮' => Some(Accidental::Natural),
+        _ => None,
+    }
+}
+
+// One slash-separated part of a figure: numerals, each with an accidental
+// before or after it. An accidental with no numeral alters the third.
+fn parse_part(part: &str, stacked: bool, notation: &str) -> Result<Vec<Interval>, ParseError> {
+    let mut intervals: Vec<Interval> = Vec::new();
+    let mut pending: Option<Accidental> = None;
+    let mut chars = part.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if let Some(acc) = accidental(c) {
+            if pending.is_some() {
+                return Err(ParseError::new(notation, format!("two accidentals in a row in {:?}", part)));
+            }
+            pending = Some(acc);
+        } else if c.is_ascii_digit() {
+            let mut digits = c.to_string();
+            // Stacked digits without slashes ("64", "65") are one interval each
+            while !stacked && let Some(d) = chars.next_if(char::is_ascii_digit) {
+                digits.push(d);
+            }
+            let number = digits.parse().map_err(|_| ParseError::new(notation, "interval out of range"))?;
+            let accidental = pending.take().unwrap_or(Accidental::None);
+            intervals.push(Interval { number, accidental });
+        } else {
+            return Err(ParseError::new(notation, format!("unexpected {:?} in figure", c)));
+        }
+    }
+
+    // A trailing accidental belongs to the numeral before it ("6#", "6\\")
+    if let Some(acc) = pending {
+        match intervals.last_mut() {
+            Some(last) if last.accidental == Accidental::None => last.accidental = acc,
+            Some(_) => return Err(ParseError::new(notation, format!("two accidentals on one numeral in {:?}", part))),
+            None => intervals.push(Interval { number: 3, accidental: acc }),
+        }
+    }
+    Ok(intervals)
+}
+
+// Intervals a figure leaves out by convention, given the ones it states
+// (highest first)
+fn implied(stated: &[u8]) -> &'static [u8] {
+    match stated {
+        [] => &[5, 3],
+        [3] | [4] | [7, 3] | [9, 3] => &[5],
+        [5] | [6] | [6, 5] | [7, 5] | [9, 5] => &[3],
+        [7] | [8] | [9] => &[5, 3],
+        [4, 3] | [4, 2] => &[6],
+        [2] => &[6, 4],
+        _ => &[],
+    }
+}
+
+// Parse a figure and fill in the intervals it implies, so "6" gives 6/3,
+// "4/3" gives 6/4/3, "#" gives 5/#3 and so on.
+pub fn parse_figure(notation: &str) -> Result<Figure, ParseError> {
+    let notation = notation.trim();
+    let stacked = !notation.contains(['/', ',']);
+    let mut intervals: Vec<Interval> = Vec::new();
+
+    if !notation.is_empty() {
+        for part in notation.split(['/', ',']).map(str::trim) {
+            if part.is_empty() {
+                return Err(ParseError::new(notation, "empty figure between separators"));
+            }
+            for interval in parse_part(part, stacked, notation)? {
+                if interval.number < 2 {
+                    return Err(ParseError::new(
+                        notation,
+                        format!("{} is not an interval above the bass", interval.number),
+                    ));
+                }
+                if intervals.iter().any(|iv| iv.number == interval.number) {
+                    return Err(ParseError::new(notation, format!("interval {} given twice", interval.number)));
+                }
+                intervals.push(interval);
+            }
+        }
+    }
+
+    let mut stated: Vec<u8> = intervals.iter().map(|iv| iv.number).collect();
+    stated.sort_unstable_by(|a, b| b.cmp(a));
+    for &number in implied(&stated) {
+        intervals.push(Interval::diatonic(number));
+    }
+    intervals.sort_by_key(|iv| iv.number);
+
+    Ok(Figure { intervals })
+}
+
+// Scientific pitch notation keeping the spelling: "C3", "F#4", "Bb2",
+// "F##3" (C4 = MIDI 60)
+pub fn parse_spelled_pitch(text: &str) -> Result<SpelledPitch, ParseError> {
+    let invalid = || ParseError::new(text, "expected a note such as C3, F#4 or Bb2");
+    let mut chars = text.chars();
+    let letter = chars.next().and_then(Letter::from_char).ok_or_else(invalid)?;
+
+    let rest = chars.as_str();
+    let octave_at = rest.find(|c: char| c != '#' && c != 'b').ok_or_else(invalid)?;
+    let (accidentals, octave) = rest.split_at(octave_at);
+    let alter: i16 = accidentals.chars().map(|c| if c == '#' { 1 } else { -1 }).sum();
+    let octave: i16 = octave.parse().map_err(|_| invalid())?;
+
+    let midi = (octave + 1) * 12 + letter.pitch_class() as i16 + alter;
+    if !(0..=127).contains(&midi) {
+        return Err(ParseError::new(text, "note is outside the MIDI range"));
+    }
+    Ok(SpelledPitch::new(letter, alter as i8, octave as i8))
+}
+
+// The same, forgetting the spelling
+pub fn parse_pitch(text: &str) -> Result<Pitch, ParseError> {
+    parse_spelled_pitch(text).map(|spelled| spelled.pitch())
+}
+
+// "A3:6" -> A3 with a 6/3 chord; a note on its own is a root position triad
+pub fn parse_symbol(text: &str, key: &Key) -> Result<FiguredBassSymbol, ParseError> {
+    let (note, figure) = text.split_once(':').unwrap_or((text, ""));
+    Ok(FiguredBassSymbol::new(parse_pitch(note)?, parse_figure(figure)?, key))
+}
+
+// "C3:4-3" -> C3 with a 5/4 chord, then a 5/3 chord over the held bass;
+// "C3;" ends a phrase, with a fermata over the last chord
+pub fn parse_event(text: &str, key: &Key) -> Result<Vec<FiguredBassSymbol>, ParseError> {
+    let (event, fermata) = match text.strip_suffix(';') {
+        Some(event) => (event, true),
+        None => (text, false),
+    };
+    let (note, figures) = event.split_once(':').unwrap_or((event, ""));
+    let bass = parse_pitch(note)?;
+
+    let mut states = Vec::new();
+    for figure in figures.split('-') {
+        if figure.trim().is_empty() && figures.contains('-') {
+            return Err(ParseError::new(text, "empty figure before or after \"-\""));
+        }
+        let mut symbol = FiguredBassSymbol::new(bass, parse_figure(figure)?, key);
+        symbol.held = !states.is_empty();
+        states.push(symbol);
+    }
+    if let Some(last) = states.last_mut() {
+        last.fermata = fermata;
+    }
+    Ok(states)
+}
+
+pub fn parse_melody(text: &str) -> Result<Vec<Pitch>, ParseError> {
+    text.split_whitespace().map(parse_pitch).collect()
+}
+
+pub fn parse_voicing(text: &str) -> Result<Voicing, ParseError> {
+    let pitches: Vec<Pitch> = text.split(',').map(|note| parse_pitch(note.trim())).collect::<Result<_, _>>()?;
+    match pitches[..] {
+        [soprano, alto, tenor, bass] => Ok(Voicing { soprano, alto, tenor, bass }),
+        _ => Err(ParseError::new(text, format!("expected four pitches, found {}", pitches.len()))),
+    }
+}
+
+pub fn parse_realization(text: &str) -> Result<Vec<Voicing>, ParseError> {
+    text.split_whitespace().filter(|&token| token != "|").map(parse_voicing).collect()
+}
+
+// Events separated by whitespace; "~" in place of a bass note holds the
+// bass before on as a pedal, "~:6/4"
+pub fn parse_progression(text: &str, key: &Key) -> Result<Vec<FiguredBassSymbol>, ParseError> {
+    let mut symbols: Vec<FiguredBassSymbol> = Vec::new();
+    for event in text.split_whitespace() {
+        let Some(rest) = event.strip_prefix('~') else {
+            symbols.extend(parse_event(event, key)?);
+            continue;
+        };
+        let Some(held) = symbols.last().map(|symbol| symbol.bass) else {
+            return Err(ParseError::new(event, "a pedal \"~\" needs a bass note before it to hold"));
+        };
+        let mut states = parse_event(&format!("{}{}", held.name(), rest), key)?;
+        states[0].over_pedal();
+        symbols.extend(states);
+    }
+    meter::place(&mut symbols);
+    Ok(symbols)
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::key::Key;
+use crate::parse::{self, ParseError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pitch {
+    pub midi_number: u8, // C4 = 60
+}
+
+// Scientific pitch notation, see `parse::parse_pitch`
+impl FromStr for Pitch {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse::parse_pitch(s)
+    }
+}
+
+impl Pitch {
+    pub fn new(midi_number: u8) -> Self {
+        Self { midi_number }
+    }
+
+    pub fn semitones(&self) -> i16 {
+        self.midi_number as i16
+    }
+
+    pub fn pitch_class(&self) -> PitchClass {
+        PitchClass::new(self.midi_number)
+    }
+
+    pub fn name(&self) -> String {
+        let octave = (self.midi_number / 12) as i16 - 1;
+        format!("{}{}", self.pitch_class(), octave)
+    }
+
+    // In hertz, in equal temperament with A4 at `reference` (440.0 for
+    // modern pitch, 415.0 for baroque)
+    pub fn frequency(&self, reference: f32) -> f32 {
+        reference * 2f32.powf((self.midi_number as f32 - 69.0) / 12.0)
+    }
+
+    // Spelled the way `key` reads it: scale notes with the key's letters,
+    // others as the altered degree pitch_class_to_scale_degree picks
+    pub fn spelled(&self, key: &Key) -> SpelledPitch {
+        let degree = key.pitch_class_to_scale_degree(self.pitch_class().0);
+        SpelledPitch::with_letter(*self, key.letter(degree))
+    }
+
+    pub fn from_bass_and_interval(bass: Pitch, interval: i8, key: &Key) -> Self {
+        let scale_degree = key.pitch_class_to_scale_degree(bass.pitch_class().0);
+        let target_degree = ((scale_degree as i8 + interval - 1) % 7) as usize;
+        let target_pc = PitchClass::new(key.scale[target_degree]);
+
+        // Closest target_pc above or at bass
+        Pitch::new(bass.midi_number + bass.pitch_class().interval_to(target_pc))
+    }
+}
+
+// A pitch with the octave dropped: 0 is C, 11 is B
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PitchClass(pub u8);
+
+impl PitchClass {
+    // Any number of semitones above C, wrapped into one octave
+    pub fn new(semitones: u8) -> Self {
+        Self(semitones % 12)
+    }
+
+    // Semitones up from this pitch class to `other`, 0-11
+    pub fn interval_to(self, other: PitchClass) -> u8 {
+        (other.0 + 12 - self.0) % 12
+    }
+
+    // Interval class: the shorter way round between the two, 0-6
+    pub fn interval_class(self, other: PitchClass) -> u8 {
+        let up = self.interval_to(other);
+        up.min(12 - up)
+    }
+
+    pub fn transpose(self, semitones: i16) -> Self {
+        Self((self.0 as i16 + semitones).rem_euclid(12) as u8)
+    }
+
+    // The pitch of this class closest to `near`, the lower one at a tritone
+    pub fn nearest(self, near: Pitch) -> Pitch {
+        let offset = (self.0 as i16 - near.pitch_class().0 as i16 + 6).rem_euclid(12) - 6;
+        Pitch::new((near.semitones() + offset).clamp(0, 127) as u8)
+    }
+}
+
+impl From<Pitch> for PitchClass {
+    fn from(pitch: Pitch) -> Self {
+        pitch.pitch_class()
+    }
+}
+
+// Sharp names, as Pitch::name uses
+impl fmt::Display for PitchClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+        write!(f, "{}", names[self.0 as usize])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Letter {
+    C,
+    D,
+    E,
+    F,
+    G,
+    A,
+    B,
+}
+
+impl Letter {
+    pub const ALL: [Letter; 7] = [Letter::C, Letter::D, Letter::E, Letter::F, Letter::G, Letter::A, Letter::B];
+
+    pub fn from_char(c: char) -> Option<Self> {
+        "CDEFGAB".find(c.to_ascii_uppercase()).map(|i| Letter::ALL[i])
+    }
+
+    // Pitch class of the natural note
+    pub fn pitch_class(self) -> u8 {
+        [0, 2, 4, 5, 7, 9, 11][self as usize]
+    }
+
+    // The letter `steps` scale steps higher, wrapping from B to C
+    pub fn up(self, steps: usize) -> Self {
+        Letter::ALL[(self as usize + steps) % 7]
+    }
+}
+
+impl fmt::Display for Letter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// A pitch with its spelling, for output: the same MIDI note can be F#4 or
+// Gb4 depending on the key and chord. `accidental` counts sharps (positive)
+// or flats (negative).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpelledPitch {
+    pub letter: Letter,
+    pub accidental: i8,
+    pub octave: i8,
+}
+
+impl SpelledPitch {
+    pub fn new(letter: Letter, accidental: i8, octave: i8) -> Self {
+        Self { letter, accidental, octave }
+    }
+
+    // `pitch` written with `letter`: MIDI 60 with B is B#3, with D is Dbb4
+    pub fn with_letter(pitch: Pitch, letter: Letter) -> Self {
+        let natural = letter.pitch_class() as i16;
+        let accidental = ((pitch.semitones() - natural + 6).rem_euclid(12) - 6) as i8;
+        let octave = ((pitch.semitones() - natural - accidental as i16) / 12 - 1) as i8;
+        Self { letter, accidental, octave }
+    }
+
+    pub fn pitch(&self) -> Pitch {
+        let midi = (self.octave as i16 + 1) * 12 + self.letter.pitch_class() as i16 + self.accidental as i16;
+        Pitch::new(midi.clamp(0, 127) as u8)
+    }
+}
+
+// Scientific pitch notation: "C4", "F#3", "Bb2", "F##3"
+impl fmt::Display for SpelledPitch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.accidental > 0 { "#" } else { "b" };
+        write!(f, "{}{}{}", self.letter, sign.repeat(self.accidental.unsigned_abs() as usize), self.octave)
+    }
+}
+
+impl FromStr for SpelledPitch {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse::parse_spelled_pitch(s)
+    }
+}
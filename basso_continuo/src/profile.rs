@@ -0,0 +1,71 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::rules::{HiddenPerfects, RuleSet};
+use crate::scoring::ScoringWeights;
+
+// Named rule sets for common styles of realization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Profile {
+    // Textbook part writing: parallel and hidden perfects both forbidden,
+    // smooth voice leading strongly preferred
+    Strict,
+    // Chorale style: the standard rules, with hidden perfects discouraged
+    BachChorale,
+    // Practical keyboard continuo: only blatant parallels are avoided,
+    // wide spacing, leaps and unprepared sevenths are fine
+    LenientContinuo,
+}
+
+impl Profile {
+    pub fn rules(&self) -> RuleSet {
+        match self {
+            Profile::Strict => {
+                let weights = ScoringWeights::default()
+                    .spacing(4.0)
+                    .range_comfort(0.2)
+                    .motion(1.0)
+                    .seventh_preparation(20.0);
+                RuleSet::standard(&weights).with(HiddenPerfects { weight: 500.0, forbid: true })
+            }
+            Profile::BachChorale => RuleSet::standard(&ScoringWeights::default())
+                .with(HiddenPerfects { weight: 20.0, forbid: false }),
+            Profile::LenientContinuo => {
+                let weights = ScoringWeights::default()
+                    .doubling(5.0)
+                    .spacing(0.5)
+                    .spacing_limit(12)
+                    .range_comfort(0.05)
+                    .seventh_preparation(0.0);
+                let mut rules = RuleSet::standard(&weights);
+                rules.remove("contrary-motion");
+                rules
+            }
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Profile::Strict => "strict",
+            Profile::BachChorale => "bach-chorale",
+            Profile::LenientContinuo => "continuo",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "strict" => Ok(Profile::Strict),
+            "bach" | "bach-chorale" | "chorale" => Ok(Profile::BachChorale),
+            "continuo" | "lenient" | "lenient-continuo" => Ok(Profile::LenientContinuo),
+            _ => Err(format!("unknown profile {:?}, expected strict, bach-chorale or continuo", s)),
+        }
+    }
+}
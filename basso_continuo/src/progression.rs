@@ -0,0 +1,380 @@
+use std::fmt;
+
+use crate::figure::{Figure, FiguredBassSymbol};
+use crate::key::Key;
+use crate::meter::{Duration, Meter};
+use crate::parse::{parse_figure, parse_spelled_pitch};
+use crate::pitch::SpelledPitch;
+
+// Progression files, the CLI's own input: a figured bass with its key,
+// meter, bar lines, note lengths, fermatas and pedals.
+//
+//   % Comments run from "%" to the end of the line ("#" is a sharp)
+//   title: Chorale
+//   key: G minor
+//   meter: 3/4
+//
+//   G2 | Bb2:6 C3:6/5 D3:4-3 | G2*3; ||
+//   G2 ~:6/4 ~ | D3*3; ||
+//
+// Headers ("key:", "meter:", "title:") come before the music; the meter
+// is 4/4 if not given. Each event is a bass note with, optionally, its
+// length in quarter-note beats after "*" (1 if not given: "*2", "*3/2"),
+// its figures after ":" as in a progression ("6/5", "4-3" over a held
+// bass, sharing its length), and ";" for a fermata, ending a phrase. "~"
+// in place of the note holds the bass before on as a pedal under new
+// harmonies, for as long as it is written, across bar lines if need be.
+// "|" is a bar line and "||" a double bar; music before the first bar
+// line that is short of a bar is an upbeat. A progression on one line, as
+// the CLI takes it, is a file with no headers.
+
+// Where something is in the file: line and column count from 1, in
+// characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl FileError {
+    fn new(span: Span, message: impl Into<String>) -> Self {
+        Self { span, message: message.into() }
+    }
+
+    // The error under the line of `source` it is on, marked:
+    //
+    //   line 2, column 4: expected a note such as C3, F#4 or Bb2
+    //     G2 H3:6 | D3
+    //        ^^^^
+    pub fn show(&self, source: &str) -> String {
+        let line = source.lines().nth(self.span.line.saturating_sub(1)).unwrap_or("");
+        let marker = " ".repeat(self.span.column.saturating_sub(1)) + &"^".repeat(self.span.length.max(1));
+        format!("{}\n  {}\n  {}", self, line, marker)
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.span.line, self.span.column, self.message)
+    }
+}
+
+impl std::error::Error for FileError {}
+
+// A bass note and the figures over it in turn
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Event {
+    pub bass: SpelledPitch,
+    pub figures: Vec<Figure>, // more than one over a held bass, as in a 4-3
+    pub duration: Duration,
+    pub fermata: bool,
+    pub pedal: bool, // the bass of the event before held on, written "~"
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Barline {
+    Single,
+    Double,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Item {
+    Event(Event),
+    Barline(Barline),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgressionFile {
+    pub title: Option<String>,
+    pub key: Key,
+    pub meter: Meter,
+    pub items: Vec<Item>,
+}
+
+impl ProgressionFile {
+    // The file in `text`, in `key` unless it names its own
+    pub fn parse(text: &str, key: &Key) -> Result<Self, FileError> {
+        let mut file = ProgressionFile { title: None, key: key.clone(), meter: Meter::COMMON, items: Vec::new() };
+        let mut in_music = false;
+        for (n, line) in text.lines().enumerate() {
+            let line_number = n + 1;
+            let line = line.split('%').next().unwrap_or("");
+            let words = words(line, line_number);
+            let Some(&(first, first_span)) = words.first() else {
+                continue;
+            };
+            // "key: G minor" is a header, "C3:6" an event
+            let header = first.split_once(':').map(|(name, _)| name).filter(|name| name.len() > 1 && name.chars().all(|c| c.is_ascii_alphabetic()));
+            if let Some(name) = header {
+                let name_span = within(first_span, 0, name.chars().count() + 1);
+                if in_music {
+                    return Err(FileError::new(name_span, "headers go before the music"));
+                }
+                let after = &line[line.find(':').unwrap_or(0) + 1..];
+                let value = after.trim();
+                let column = line.chars().count() - after.chars().count() + after.chars().take_while(|c| c.is_whitespace()).count() + 1;
+                let value_span = Span { line: line_number, column, length: value.chars().count() };
+                match name.to_ascii_lowercase().as_str() {
+                    "key" => file.key = value.parse().map_err(|e: String| FileError::new(value_span, e))?,
+                    "meter" => file.meter = value.parse().map_err(|e: String| FileError::new(value_span, e))?,
+                    "title" => file.title = Some(value.to_string()),
+                    _ => {
+                        let message = format!("unknown header {:?}, expected key, meter or title", name);
+                        return Err(FileError::new(name_span, message));
+                    }
+                }
+                continue;
+            }
+            in_music = true;
+            for (word, span) in words {
+                let item = match word {
+                    "|" => Item::Barline(Barline::Single),
+                    "||" => Item::Barline(Barline::Double),
+                    _ => Item::Event(event(word, span, file.events().last().map(|e| e.bass))?),
+                };
+                file.items.push(item);
+            }
+        }
+        Ok(file)
+    }
+
+    // `symbols` as a file in the first one's meter, a bar line before
+    // each downbeat and a double bar at the end; a held symbol is another
+    // figure over the bass before, lengthening it
+    pub fn from_symbols(symbols: &[FiguredBassSymbol], key: &Key, title: Option<&str>) -> Self {
+        let span = Span { line: 0, column: 0, length: 0 };
+        let mut items = Vec::new();
+        for symbol in symbols {
+            if symbol.held
+                && !symbol.pedal
+                && let Some(Item::Event(event)) = items.last_mut()
+            {
+                event.figures.push(symbol.figure.clone());
+                event.duration = event.duration + symbol.duration;
+                event.fermata = symbol.fermata;
+                continue;
+            }
+            if !items.is_empty() && symbol.offset == Duration::ZERO {
+                items.push(Item::Barline(Barline::Single));
+            }
+            let bass = symbol.spell(symbol.bass);
+            let figures = vec![symbol.figure.clone()];
+            let (duration, fermata, pedal) = (symbol.duration, symbol.fermata, symbol.pedal);
+            items.push(Item::Event(Event { bass, figures, duration, fermata, pedal, span }));
+        }
+        if !items.is_empty() {
+            items.push(Item::Barline(Barline::Double));
+        }
+        let meter = symbols.first().map_or(Meter::COMMON, |symbol| symbol.meter);
+        ProgressionFile { title: title.map(str::to_string), key: key.clone(), meter, items }
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &Event> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Event(event) => Some(event),
+            Item::Barline(_) => None,
+        })
+    }
+
+    // The progression to realize: each figure of each event in turn
+    pub fn symbols(&self) -> Vec<FiguredBassSymbol> {
+        let bar = self.meter.bar();
+        let mut symbols = Vec::new();
+        let mut offset = Duration::ZERO;
+        let mut before_first_bar = None; // the chords and their length
+        for item in &self.items {
+            match item {
+                Item::Barline(_) => {
+                    before_first_bar.get_or_insert((symbols.len(), offset));
+                    offset = Duration::ZERO;
+                }
+                Item::Event(event) => {
+                    let share = event.duration.split(event.figures.len());
+                    for (i, figure) in event.figures.iter().enumerate() {
+                        let mut symbol = FiguredBassSymbol::new(event.bass.pitch(), figure.clone(), &self.key);
+                        if event.pedal && i == 0 {
+                            symbol.over_pedal();
+                        }
+                        symbol.held = i > 0 || event.pedal;
+                        symbol.duration = share;
+                        symbol.meter = self.meter;
+                        symbol.offset = offset.within(bar);
+                        // The fermata is over the event's last chord
+                        symbol.fermata = event.fermata && i == event.figures.len() - 1;
+                        offset = offset + share;
+                        symbols.push(symbol);
+                    }
+                }
+            }
+        }
+        // Music short of a bar before the first bar line is an upbeat,
+        // ending where the bar does
+        if let Some((count, length)) = before_first_bar
+            && length < bar
+        {
+            for symbol in &mut symbols[..count] {
+                symbol.offset = symbol.offset + (bar - length);
+            }
+        }
+        symbols
+    }
+
+    // Where the chord at `index` in `symbols()` is written
+    pub fn span_of(&self, index: usize) -> Option<Span> {
+        self.events().flat_map(|event| event.figures.iter().map(|_| event.span)).nth(index)
+    }
+}
+
+// The file written out again, headers first and a line for every four
+// bars, so it parses back to the same progression
+impl fmt::Display for ProgressionFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(title) = &self.title {
+            writeln!(f, "title: {}", title)?;
+        }
+        writeln!(f, "key: {}", self.key)?;
+        if self.meter != Meter::COMMON {
+            writeln!(f, "meter: {}", self.meter)?;
+        }
+        writeln!(f)?;
+        let mut line = Vec::new();
+        let mut bars = 0;
+        for item in &self.items {
+            match item {
+                Item::Event(event) => line.push(event.to_string()),
+                Item::Barline(barline) => {
+                    line.push(if *barline == Barline::Double { "||" } else { "|" }.to_string());
+                    bars += 1;
+                    if bars % 4 == 0 || *barline == Barline::Double {
+                        writeln!(f, "{}", line.join(" "))?;
+                        line.clear();
+                    }
+                }
+            }
+        }
+        if !line.is_empty() {
+            writeln!(f, "{}", line.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+// "D3*2:4-3;", "~:6/4"
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.pedal {
+            write!(f, "~")?;
+        } else {
+            write!(f, "{}", self.bass)?;
+        }
+        if self.duration != Duration::BEAT {
+            write!(f, "*{}", self.duration)?;
+        }
+        // Blank after a dash would not parse, so a 5/3 there is "3", as in
+        // "4-3"
+        let figures: Vec<String> = self
+            .figures
+            .iter()
+            .enumerate()
+            .map(|(i, figure)| match figure.abbreviated() {
+                text if text.is_empty() && i > 0 => "3".to_string(),
+                text => text,
+            })
+            .collect();
+        if figures.len() > 1 || !figures[0].is_empty() {
+            write!(f, ":{}", figures.join("-"))?;
+        }
+        if self.fermata {
+            write!(f, ";")?;
+        }
+        Ok(())
+    }
+}
+
+// The words of a line and where each is
+fn words(line: &str, line_number: usize) -> Vec<(&str, Span)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut column = 0;
+    for (i, c) in line.char_indices() {
+        column += 1;
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some((i, column)),
+            (true, Some((from, from_column))) => {
+                words.push((&line[from..i], Span { line: line_number, column: from_column, length: column - from_column }));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some((from, from_column)) = start {
+        words.push((&line[from..], Span { line: line_number, column: from_column, length: column + 1 - from_column }));
+    }
+    words
+}
+
+// The part of `span` from `offset` characters in, `length` long
+fn within(span: Span, offset: usize, length: usize) -> Span {
+    Span { column: span.column + offset, length, ..span }
+}
+
+// "D3*2:4-3;", or "~*2:6/4" holding `held`, the bass before, as a pedal
+fn event(word: &str, span: Span, held: Option<SpelledPitch>) -> Result<Event, FileError> {
+    let chars = |text: &str| text.chars().count();
+    let (word, fermata) = match word.strip_suffix(';') {
+        Some(rest) => (rest, true),
+        None => (word, false),
+    };
+    let (head, figures) = match word.split_once(':') {
+        Some((head, figures)) => (head, Some(figures)),
+        None => (word, None),
+    };
+    let (note, duration) = match head.split_once('*') {
+        Some((note, duration)) => (note, Some(duration)),
+        None => (head, None),
+    };
+
+    let pedal = note == "~";
+    let bass = match held {
+        Some(held) if pedal => held,
+        None if pedal => return Err(FileError::new(within(span, 0, 1), "a pedal \"~\" needs a bass note before it to hold")),
+        _ => parse_spelled_pitch(note).map_err(|e| FileError::new(within(span, 0, chars(note)), e.message))?,
+    };
+    let duration = match duration {
+        Some(text) => {
+            let at = within(span, chars(note) + 1, chars(text));
+            text.parse().map_err(|e: String| FileError::new(at, e))?
+        }
+        None => Duration::BEAT,
+    };
+
+    let mut parsed = Vec::new();
+    match figures {
+        None => parsed.push(Figure::root_position()),
+        Some(figures) => {
+            let mut offset = chars(head) + 1;
+            for part in figures.split('-') {
+                let at = within(span, offset, chars(part));
+                if part.trim().is_empty() && figures.contains('-') {
+                    return Err(FileError::new(at, "empty figure before or after \"-\""));
+                }
+                parsed.push(parse_figure(part).map_err(|e| FileError::new(at, e.message))?);
+                offset += chars(part) + 1;
+            }
+        }
+    }
+    Ok(Event { bass, figures: parsed, duration, fermata, pedal, span })
+}
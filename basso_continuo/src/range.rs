@@ -0,0 +1,148 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::parse::parse_pitch;
+use crate::pitch::Pitch;
+use crate::voicing::Voice;
+
+// Lowest and highest MIDI note a voice may sing, inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VoiceRange {
+    pub min: u8,
+    pub max: u8,
+}
+
+impl VoiceRange {
+    pub const fn new(min: u8, max: u8) -> Self {
+        Self { min, max }
+    }
+
+    pub fn contains(&self, pitch: Pitch) -> bool {
+        (self.min..=self.max).contains(&pitch.midi_number)
+    }
+
+    // Middle of the range, where a voice is most comfortable
+    pub fn mid(&self) -> u8 {
+        ((self.min as u16 + self.max as u16) / 2) as u8
+    }
+}
+
+impl fmt::Display for VoiceRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", Pitch::new(self.min).name(), Pitch::new(self.max).name())
+    }
+}
+
+// The four voices' ranges, top to bottom. Whatever the ensemble, the parts
+// are still called soprano, alto, tenor and bass.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangeProfile {
+    pub soprano: VoiceRange,
+    pub alto: VoiceRange,
+    pub tenor: VoiceRange,
+    pub bass: VoiceRange,
+}
+
+impl Default for RangeProfile {
+    fn default() -> Self {
+        Self::satb()
+    }
+}
+
+impl RangeProfile {
+    pub fn range(&self, voice: Voice) -> VoiceRange {
+        match voice {
+            Voice::Soprano => self.soprano,
+            Voice::Alto => self.alto,
+            Voice::Tenor => self.tenor,
+            Voice::Bass => self.bass,
+        }
+    }
+
+    // Mixed choir
+    pub fn satb() -> Self {
+        Self {
+            soprano: VoiceRange::new(60, 79), // C4-G5
+            alto: VoiceRange::new(55, 72),    // G3-C5
+            tenor: VoiceRange::new(48, 67),   // C3-G4
+            bass: VoiceRange::new(40, 60),    // E2-C4
+        }
+    }
+
+    // Upper voices: soprano I/II, alto I/II
+    pub fn ssaa() -> Self {
+        Self {
+            soprano: VoiceRange::new(60, 79), // C4-G5
+            alto: VoiceRange::new(57, 76),    // A3-E5
+            tenor: VoiceRange::new(55, 72),   // G3-C5
+            bass: VoiceRange::new(53, 69),    // F3-A4
+        }
+    }
+
+    // Lower voices: tenor I/II, bass I/II
+    pub fn ttbb() -> Self {
+        Self {
+            soprano: VoiceRange::new(50, 69), // D3-A4
+            alto: VoiceRange::new(48, 67),    // C3-G4
+            tenor: VoiceRange::new(43, 62),   // G2-D4
+            bass: VoiceRange::new(36, 57),    // C2-A3
+        }
+    }
+
+    // Two violins, viola, cello
+    pub fn string_quartet() -> Self {
+        Self {
+            soprano: VoiceRange::new(55, 88), // G3-E6
+            alto: VoiceRange::new(55, 81),    // G3-A5
+            tenor: VoiceRange::new(48, 76),   // C3-E5
+            bass: VoiceRange::new(36, 67),    // C2-G4
+        }
+    }
+}
+
+impl fmt::Display for RangeProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.soprano, self.alto, self.tenor, self.bass)
+    }
+}
+
+// A preset name ("satb", "ssaa", "ttbb", "quartet") or four ranges, top
+// voice first: "C4-G5,G3-C5,C3-G4,E2-C4"
+impl FromStr for RangeProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "satb" => return Ok(Self::satb()),
+            "ssaa" => return Ok(Self::ssaa()),
+            "ttbb" => return Ok(Self::ttbb()),
+            "quartet" | "string-quartet" => return Ok(Self::string_quartet()),
+            _ => {}
+        }
+
+        let invalid = || {
+            format!(
+                "invalid ranges {:?}, expected satb, ssaa, ttbb, quartet or four ranges like C4-G5,G3-C5,C3-G4,E2-C4",
+                s
+            )
+        };
+        let ranges = s
+            .split(',')
+            .map(|range| {
+                let (low, high) = range.trim().split_once('-').ok_or_else(invalid)?;
+                let low = parse_pitch(low.trim()).map_err(|e| e.to_string())?;
+                let high = parse_pitch(high.trim()).map_err(|e| e.to_string())?;
+                if low > high {
+                    return Err(format!("range {:?} goes downwards", range.trim()));
+                }
+                Ok(VoiceRange::new(low.midi_number, high.midi_number))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        match ranges[..] {
+            [soprano, alto, tenor, bass] => Ok(Self { soprano, alto, tenor, bass }),
+            _ => Err(invalid()),
+        }
+    }
+}
@@ -0,0 +1,845 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::figure::FiguredBassSymbol;
+use crate::parse::ParseError;
+use crate::pitch::Pitch;
+use crate::profile::Profile;
+use crate::range::RangeProfile;
+use crate::rules::{RuleContext, RuleSet, ScoreBreakdown};
+use crate::sample::realize_sampled;
+use crate::scoring::ScoringWeights;
+use crate::voicing::{generate_voicings, rejections, Completeness, Constraint, Voice, Voicing};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RealizationError {
+    // Every voicing of a chord breaks some constraint; `eliminated_by`
+    // counts the candidates each constraint ruled out
+    NoVoicings {
+        chord: usize, // index into the progression
+        bass: Pitch,
+        eliminated_by: Vec<(Constraint, usize)>,
+    },
+    // No chord the harmonizer knows has a voicing with this melody note
+    // in the soprano
+    NoHarmony {
+        note: usize, // index into the melody
+        soprano: Pitch,
+    },
+    // A pinned note no voicing can sing
+    Unsatisfiable { pin: Pin, problem: PinProblem },
+    // Every realization is within `min_difference` voicings of one of
+    // those to avoid
+    NoDistinctRealization { min_difference: usize },
+    // With violations forbidden, every realization breaks one of `rules`
+    // by chord `chord` at the latest
+    Forbidden {
+        chord: usize, // index into the progression
+        rules: Vec<String>,
+    },
+}
+
+impl fmt::Display for RealizationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RealizationError::NoVoicings { chord, bass, eliminated_by } => {
+                write!(f, "no valid voicing for chord {} (bass {})", chord + 1, bass.name())?;
+                for (i, (constraint, count)) in eliminated_by.iter().enumerate() {
+                    let sep = if i == 0 { ": " } else { ", " };
+                    write!(f, "{}{} ruled out by {}", sep, count, constraint)?;
+                }
+                Ok(())
+            }
+            RealizationError::NoHarmony { note, soprano } => {
+                write!(f, "no chord to harmonize melody note {} ({})", note + 1, soprano.name())
+            }
+            RealizationError::Unsatisfiable { pin, problem } => {
+                write!(f, "cannot pin {}: {}", pin, problem)
+            }
+            RealizationError::NoDistinctRealization { min_difference } => {
+                write!(f, "no other realization differs in at least {} voicings", min_difference)
+            }
+            RealizationError::Forbidden { chord, rules } => {
+                write!(f, "no realization reaches chord {} without breaking {}", chord + 1, rules.join(" or "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for RealizationError {}
+
+// A note a voice must sing in one chord: "the soprano is E5 on chord 3"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pin {
+    pub chord: usize, // index into the progression
+    pub voice: Voice,
+    pub pitch: Pitch,
+}
+
+impl fmt::Display for Pin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} on chord {}", self.voice, self.pitch.name(), self.chord + 1)
+    }
+}
+
+// "3:soprano=E5" or "3:s=E5", counting chords from 1
+impl FromStr for Pin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid pin {:?}, expected CHORD:VOICE=PITCH such as 3:soprano=E5", s);
+        let (chord, rest) = s.trim().split_once(':').ok_or_else(invalid)?;
+        let (voice, pitch) = rest.split_once('=').ok_or_else(invalid)?;
+        let chord: usize = chord.trim().parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?;
+        Ok(Pin {
+            chord: chord - 1,
+            voice: voice.parse()?,
+            pitch: pitch.trim().parse().map_err(|e: ParseError| e.to_string())?,
+        })
+    }
+}
+
+// Why a pin cannot be met
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinProblem {
+    NoSuchChord,
+    BassGiven,      // the bass is the figured bass's own note
+    NotAChordTone,
+    OutOfRange,
+    NoVoicing,      // a chord tone in range, but no valid voicing has it there
+}
+
+impl fmt::Display for PinProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            PinProblem::NoSuchChord => "the progression has no such chord",
+            PinProblem::BassGiven => "the bass is already given",
+            PinProblem::NotAChordTone => "not a note of the chord",
+            PinProblem::OutOfRange => "outside the voice's range",
+            PinProblem::NoVoicing => "no valid voicing has it",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+// How the realizer picks among candidate voicings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Strategy {
+    // Best voicing for each chord given only the one before it, backing up
+    // to an earlier chord's next best voicing when a chord can only be
+    // reached by breaking a rule (such as parallel fifths or octaves) that
+    // reports a violation: fast, but an early choice can still force poor
+    // voice leading later on
+    Greedy,
+    // Highest total score over the whole progression (dynamic programming)
+    #[default]
+    Optimal,
+    // Like Optimal, but only the given number of best partial realizations
+    // survive each chord: cheaper on long progressions, exact once the
+    // width reaches the number of candidates per chord
+    Beam(usize),
+    // A realization drawn at random, favouring the best ones, from a
+    // random number generator seeded with the given seed: varied output
+    // that is the same again for the same seed
+    Sample(u64),
+}
+
+// One complete realization and its total score
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Realization {
+    pub voicings: Vec<Voicing>,
+    pub score: f32,
+}
+
+// A voicing suggested for the next chord, and how the rules scored it
+// against the chords before
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hint {
+    pub voicing: Voicing,
+    pub breakdown: ScoreBreakdown,
+}
+
+impl Hint {
+    pub fn score(&self) -> f32 {
+        self.breakdown.total()
+    }
+}
+
+// Everything the realizer can be configured with, set builder style:
+//
+//   Realizer::new().strategy(Strategy::Beam(8)).ranges(RangeProfile::ttbb())
+//
+// The free functions below use the defaults.
+#[derive(Debug, Clone)]
+pub struct Realizer {
+    pub strategy: Strategy,
+    pub ranges: RangeProfile,
+    pub rules: RuleSet,
+    pub completeness: Completeness,
+    pub pins: Vec<Pin>,
+    // How far from the best Strategy::Sample strays: a realization this
+    // many points worse is e (about 2.7) times less likely to be drawn. 0
+    // always draws the best.
+    pub temperature: f32,
+    // Treat the rules that report violations as constraints: a
+    // realization that breaks one is an error rather than a last resort
+    pub forbid_violations: bool,
+}
+
+// What a violation costs the search when violations are forbidden: more
+// than any realization can make up, so one is only chosen when every
+// realization has one, and then reported rather than returned
+const FORBIDDEN: f32 = 1.0e9;
+
+impl Default for Realizer {
+    fn default() -> Self {
+        Self {
+            strategy: Strategy::default(),
+            ranges: RangeProfile::default(),
+            rules: RuleSet::standard(&ScoringWeights::default()),
+            completeness: Completeness::default(),
+            pins: Vec::new(),
+            temperature: 3.0,
+            forbid_violations: false,
+        }
+    }
+}
+
+impl Realizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn ranges(mut self, ranges: RangeProfile) -> Self {
+        self.ranges = ranges;
+        self
+    }
+
+    // The standard rules with different weights
+    pub fn weights(mut self, weights: ScoringWeights) -> Self {
+        self.rules = RuleSet::standard(&weights);
+        self
+    }
+
+    pub fn rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.rules = profile.rules();
+        self
+    }
+
+    pub fn completeness(mut self, completeness: Completeness) -> Self {
+        self.completeness = completeness;
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    pub fn forbid_violations(mut self, forbid: bool) -> Self {
+        self.forbid_violations = forbid;
+        self
+    }
+
+    // Fix the note `voice` sings in chord `chord` (counting from 0)
+    pub fn pin(mut self, chord: usize, voice: Voice, pitch: Pitch) -> Self {
+        self.pins.push(Pin { chord, voice, pitch });
+        self
+    }
+
+    pub fn realize(&self, symbols: &[FiguredBassSymbol]) -> Result<Vec<Voicing>, RealizationError> {
+        let candidates = self.candidates(symbols)?;
+        self.allowed(symbols, self.search(symbols, &candidates))
+    }
+
+    // Realize again, keeping the voicings of `previous` wherever `locked`
+    // is true and searching the other chords afresh. The chords around a
+    // locked one are scored against it as usual, so the voice leading
+    // into and out of it still counts. Chords past the end of `locked` or
+    // `previous` are unlocked; pins apply to unlocked chords only.
+    pub fn reoptimize(
+        &self,
+        symbols: &[FiguredBassSymbol],
+        previous: &[Voicing],
+        locked: &[bool],
+    ) -> Result<Vec<Voicing>, RealizationError> {
+        let is_locked = |i: usize| locked.get(i) == Some(&true) && i < previous.len();
+        let pins = self.pins.iter().copied().filter(|pin| !is_locked(pin.chord)).collect();
+        let unlocked = Realizer { pins, ..self.clone() };
+        let mut candidates = unlocked.candidates(symbols)?;
+        for (i, chord) in candidates.iter_mut().enumerate() {
+            if is_locked(i) {
+                *chord = vec![previous[i].clone()];
+            }
+        }
+        self.allowed(symbols, self.search(symbols, &candidates))
+    }
+
+    fn search(&self, symbols: &[FiguredBassSymbol], candidates: &[Vec<Voicing>]) -> Vec<Voicing> {
+        match self.strategy {
+            Strategy::Greedy => realize_greedy(self, symbols, candidates),
+            Strategy::Optimal => realize_optimal(self, symbols, candidates),
+            Strategy::Sample(seed) => realize_sampled(self, symbols, candidates, seed),
+            Strategy::Beam(width) => {
+                // Pruning may have dropped every partial realization that avoids
+                // violations; the full search finds one if it exists
+                let voicings = realize_beam(self, symbols, candidates, width);
+                if self.has_violations(symbols, &voicings) {
+                    realize_optimal(self, symbols, candidates)
+                } else {
+                    voicings
+                }
+            }
+        }
+    }
+
+    // The `n` highest scoring realizations, best first (always a full search,
+    // whatever the strategy). With violations forbidden there may be
+    // fewer, but never none.
+    pub fn realize_n(&self, symbols: &[FiguredBassSymbol], n: usize) -> Result<Vec<Realization>, RealizationError> {
+        let candidates = self.candidates(symbols)?;
+        let mut realizations = realize_n_best(self, symbols, &candidates, n);
+        if self.forbid_violations && realizations.iter().any(|r| self.has_violations(symbols, &r.voicings)) {
+            let clean: Vec<Realization> =
+                realizations.iter().filter(|r| !self.has_violations(symbols, &r.voicings)).cloned().collect();
+            if clean.is_empty() {
+                self.allowed(symbols, realizations.swap_remove(0).voicings)?;
+            }
+            realizations = clean;
+        }
+        Ok(realizations)
+    }
+
+    // The best realization that differs from each of `previous` in at
+    // least `min_difference` chords, for stepping through alternatives:
+    // pass every realization shown so far to get the next one
+    pub fn realize_distinct(
+        &self,
+        symbols: &[FiguredBassSymbol],
+        previous: &[Vec<Voicing>],
+        min_difference: usize,
+    ) -> Result<Vec<Voicing>, RealizationError> {
+        let candidates = self.candidates(symbols)?;
+        let voicings = realize_excluding(self, symbols, &candidates, previous, min_difference)
+            .ok_or(RealizationError::NoDistinctRealization { min_difference })?;
+        self.allowed(symbols, voicings)
+    }
+
+    // The `k` best voicings for the chord after `voicings`, the realization
+    // so far of `symbols`, best first: suggestions for someone realizing
+    // the bass by hand. Each is scored against the chords before it only,
+    // with no look ahead to where it leads. With violations forbidden,
+    // voicings that break a rule are left out, and it is an error if they
+    // all do. An empty list once every chord has a voicing.
+    pub fn hints(
+        &self,
+        symbols: &[FiguredBassSymbol],
+        voicings: &[Voicing],
+        k: usize,
+    ) -> Result<Vec<Hint>, RealizationError> {
+        let i = voicings.len();
+        let Some(symbol) = symbols.get(i) else {
+            return Ok(Vec::new());
+        };
+        let mut hints: Vec<Hint> = self
+            .chord_candidates(i, symbol)?
+            .into_iter()
+            .map(|voicing| {
+                let context =
+                    RuleContext::new(&voicing, lookback(voicings, i, 1), lookback(voicings, i, 2), symbols, i, &self.ranges);
+                let breakdown = self.rules.breakdown(&context);
+                Hint { voicing, breakdown }
+            })
+            .collect();
+        hints.sort_by(|a, b| b.score().total_cmp(&a.score()));
+        if self.forbid_violations {
+            if hints.iter().all(|hint| hint.breakdown.has_violations()) {
+                return Err(RealizationError::Forbidden { chord: i, rules: hints[0].breakdown.violated() });
+            }
+            hints.retain(|hint| !hint.breakdown.has_violations());
+        }
+        hints.truncate(k);
+        Ok(hints)
+    }
+
+    // Sum of the scores of a realization, each chord scored against the
+    // one before it
+    pub fn total_score(&self, symbols: &[FiguredBassSymbol], voicings: &[Voicing]) -> f32 {
+        let mut total = 0.0;
+        for (i, voicing) in voicings.iter().enumerate().take(symbols.len()) {
+            let (prev, earlier) = (lookback(voicings, i, 1), lookback(voicings, i, 2));
+            let context = RuleContext::new(voicing, prev, earlier, symbols, i, &self.ranges);
+            total += self.rules.evaluate(&context).0;
+        }
+        total
+    }
+
+    // How each rule scored each chord of a realization, against the chord
+    // before it; the totals add up to total_score
+    pub fn breakdown(&self, symbols: &[FiguredBassSymbol], voicings: &[Voicing]) -> Vec<ScoreBreakdown> {
+        (0..voicings.len().min(symbols.len()))
+            .map(|i| {
+                let context =
+                    RuleContext::new(&voicings[i], lookback(voicings, i, 1), lookback(voicings, i, 2), symbols, i, &self.ranges);
+                self.rules.breakdown(&context)
+            })
+            .collect()
+    }
+
+    // Score of `voicing` for chord i following `earlier` and `prev` as the
+    // search sees it, and whether it breaks a rule the search should avoid
+    pub(crate) fn evaluate(
+        &self,
+        symbols: &[FiguredBassSymbol],
+        i: usize,
+        voicing: &Voicing,
+        prev: Option<&Voicing>,
+        earlier: Option<&Voicing>,
+    ) -> (f32, bool) {
+        let context = RuleContext::new(voicing, prev, earlier, symbols, i, &self.ranges);
+        let (score, violation) = self.rules.evaluate(&context);
+        match violation && self.forbid_violations {
+            true => (score - FORBIDDEN, violation),
+            false => (score, violation),
+        }
+    }
+
+    pub(crate) fn score(
+        &self,
+        symbols: &[FiguredBassSymbol],
+        i: usize,
+        voicing: &Voicing,
+        prev: Option<&Voicing>,
+        earlier: Option<&Voicing>,
+    ) -> f32 {
+        self.evaluate(symbols, i, voicing, prev, earlier).0
+    }
+
+    // `voicings`, unless violations are forbidden and it has one: then the
+    // first chord with one, and the rules it breaks
+    pub(crate) fn allowed(
+        &self,
+        symbols: &[FiguredBassSymbol],
+        voicings: Vec<Voicing>,
+    ) -> Result<Vec<Voicing>, RealizationError> {
+        if !self.forbid_violations {
+            return Ok(voicings);
+        }
+        let breakdowns = self.breakdown(symbols, &voicings);
+        match breakdowns.iter().position(ScoreBreakdown::has_violations) {
+            None => Ok(voicings),
+            Some(chord) => Err(RealizationError::Forbidden { chord, rules: breakdowns[chord].violated() }),
+        }
+    }
+
+    fn has_violations(&self, symbols: &[FiguredBassSymbol], voicings: &[Voicing]) -> bool {
+        (0..voicings.len())
+            .any(|i| self.evaluate(symbols, i, &voicings[i], lookback(voicings, i, 1), lookback(voicings, i, 2)).1)
+    }
+
+    fn candidates(&self, symbols: &[FiguredBassSymbol]) -> Result<Vec<Vec<Voicing>>, RealizationError> {
+        if let Some(&pin) = self.pins.iter().find(|pin| pin.chord >= symbols.len()) {
+            return Err(RealizationError::Unsatisfiable { pin, problem: PinProblem::NoSuchChord });
+        }
+        symbols.iter().enumerate().map(|(i, symbol)| self.chord_candidates(i, symbol)).collect()
+    }
+
+    // The voicings chord i may take, `symbol` being its figured bass
+    fn chord_candidates(&self, i: usize, symbol: &FiguredBassSymbol) -> Result<Vec<Voicing>, RealizationError> {
+        let candidates = generate_voicings(symbol, &self.ranges, self.completeness);
+        if candidates.is_empty() {
+            return Err(RealizationError::NoVoicings {
+                chord: i,
+                bass: symbol.bass,
+                eliminated_by: rejections(symbol, &self.ranges, self.completeness),
+            });
+        }
+        let pinned: Vec<Voicing> = candidates.into_iter().filter(|v| self.pinned(i, v)).collect();
+        if pinned.is_empty() {
+            let (pin, problem) = self.pin_problem(i, symbol);
+            return Err(RealizationError::Unsatisfiable { pin, problem });
+        }
+        Ok(pinned)
+    }
+
+    // Whether `voicing` sings every note pinned in chord i
+    pub(crate) fn pinned(&self, i: usize, voicing: &Voicing) -> bool {
+        self.pins.iter().filter(|pin| pin.chord == i).all(|pin| voicing.voice(pin.voice) == pin.pitch)
+    }
+
+    // The pin on chord i that leaves it no voicing, and why: the first one
+    // that cannot be sung at all, or else the first one, since the pins
+    // only conflict with the other constraints (or each other) together
+    fn pin_problem(&self, i: usize, symbol: &FiguredBassSymbol) -> (Pin, PinProblem) {
+        let pins: Vec<Pin> = self.pins.iter().copied().filter(|pin| pin.chord == i).collect();
+        for &pin in &pins {
+            if pin.voice == Voice::Bass && pin.pitch != symbol.bass {
+                return (pin, PinProblem::BassGiven);
+            }
+            if symbol.chord_tones.iter().all(|tone| tone.pitch_class() != pin.pitch.pitch_class()) {
+                return (pin, PinProblem::NotAChordTone);
+            }
+            if !self.ranges.range(pin.voice).contains(pin.pitch) {
+                return (pin, PinProblem::OutOfRange);
+            }
+        }
+        (pins[0], PinProblem::NoVoicing)
+    }
+}
+
+pub fn realize_figured_bass(symbols: &[FiguredBassSymbol]) -> Result<Vec<Voicing>, RealizationError> {
+    Realizer::new().realize(symbols)
+}
+
+pub fn realize_with(symbols: &[FiguredBassSymbol], strategy: Strategy) -> Result<Vec<Voicing>, RealizationError> {
+    Realizer::new().strategy(strategy).realize(symbols)
+}
+
+pub fn realize_figured_bass_n(symbols: &[FiguredBassSymbol], n: usize) -> Result<Vec<Realization>, RealizationError> {
+    Realizer::new().realize_n(symbols, n)
+}
+
+pub fn total_score(symbols: &[FiguredBassSymbol], voicings: &[Voicing]) -> f32 {
+    Realizer::new().total_score(symbols, voicings)
+}
+
+// The voicing `n` chords before chord i, if there is one
+fn lookback(voicings: &[Voicing], i: usize, n: usize) -> Option<&Voicing> {
+    i.checked_sub(n).map(|k| &voicings[k])
+}
+
+// Depth-first greedy search: each chord tries the voicings that follow the
+// one before it without a rule violation, best first, and backs up when
+// none of them can be continued to the end. dead[i][c] marks candidates
+// already known to lead nowhere, so no chord is explored from the same
+// voicing twice. That only holds while violations depend on the chord
+// before alone: a rule that reads `context.earlier` (the chord two back)
+// may add a penalty but must not report a violation, or dead would have
+// to be keyed on the previous voicing as well.
+fn realize_greedy(realizer: &Realizer, symbols: &[FiguredBassSymbol], candidates: &[Vec<Voicing>]) -> Vec<Voicing> {
+    let mut dead: Vec<Vec<bool>> = candidates.iter().map(|chord| vec![false; chord.len()]).collect();
+    let mut path = Vec::with_capacity(candidates.len());
+
+    if extend_greedy(realizer, symbols, candidates, &mut dead, &mut path) {
+        path.iter().enumerate().map(|(i, &c)| candidates[i][c].clone()).collect()
+    } else {
+        // Every realization breaks a rule somewhere: take the violations with their penalty
+        realize_greedy_unchecked(realizer, symbols, candidates)
+    }
+}
+
+fn extend_greedy(
+    realizer: &Realizer,
+    symbols: &[FiguredBassSymbol],
+    candidates: &[Vec<Voicing>],
+    dead: &mut [Vec<bool>],
+    path: &mut Vec<usize>,
+) -> bool {
+    let i = path.len();
+    if i == candidates.len() {
+        return true;
+    }
+    let prev = path.last().map(|&c| &candidates[i - 1][c]);
+    let earlier = (i >= 2).then(|| &candidates[i - 2][path[i - 2]]);
+
+    let mut order: Vec<(f32, usize)> = candidates[i]
+        .iter()
+        .enumerate()
+        .filter(|&(c, _)| !dead[i][c])
+        .filter_map(|(c, candidate)| match realizer.evaluate(symbols, i, candidate, prev, earlier) {
+            (_, true) => None,
+            (score, false) => Some((score, c)),
+        })
+        .collect();
+    // Stable, so equal scores keep candidate order like the unchecked loop
+    order.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    for (_, c) in order {
+        path.push(c);
+        if extend_greedy(realizer, symbols, candidates, dead, path) {
+            return true;
+        }
+        path.pop();
+        dead[i][c] = true;
+    }
+    false
+}
+
+fn realize_greedy_unchecked(
+    realizer: &Realizer,
+    symbols: &[FiguredBassSymbol],
+    candidates: &[Vec<Voicing>],
+) -> Vec<Voicing> {
+    let mut result = Vec::new();
+
+    for (i, candidates) in candidates.iter().enumerate() {
+        let prev = lookback(&result, i, 1);
+        let earlier = lookback(&result, i, 2);
+
+        // Find best voicing
+        let mut best_voicing = None;
+        let mut best_score = f32::MIN;
+
+        for candidate in candidates {
+            let score = realizer.score(symbols, i, candidate, prev, earlier);
+            if score > best_score {
+                best_score = score;
+                best_voicing = Some(candidate.clone());
+            }
+        }
+
+        result.push(best_voicing.unwrap());
+    }
+
+    result
+}
+
+// Viterbi over the candidate voicings: best[i][c] is the highest total score
+// of any realization of chords 0..=i ending in candidate c, and back[i][c]
+// the candidate of chord i - 1 it came from. Since violations (parallel
+// fifths and octaves by default) outweigh everything else, this revises
+// earlier chords as far back as needed to avoid them whenever that is
+// possible at all. Rules that look two chords back see the path through
+// back[i - 1][j], so those are optimized along the best paths only.
+fn realize_optimal(realizer: &Realizer, symbols: &[FiguredBassSymbol], candidates: &[Vec<Voicing>]) -> Vec<Voicing> {
+    let (best, back) = viterbi(realizer, symbols, candidates);
+
+    let Some(last) = best.last() else {
+        return Vec::new();
+    };
+    let mut j = (0..last.len()).fold(0, |a, b| if last[b] > last[a] { b } else { a });
+    let mut result = Vec::with_capacity(candidates.len());
+    for i in (0..candidates.len()).rev() {
+        result.push(candidates[i][j].clone());
+        j = back[i][j];
+    }
+    result.reverse();
+    result
+}
+
+// The forward pass of realize_optimal: best[i][c] and back[i][c]
+pub(crate) fn viterbi(
+    realizer: &Realizer,
+    symbols: &[FiguredBassSymbol],
+    candidates: &[Vec<Voicing>],
+) -> (Vec<Vec<f32>>, Vec<Vec<usize>>) {
+    let mut best: Vec<Vec<f32>> = Vec::with_capacity(candidates.len());
+    let mut back: Vec<Vec<usize>> = Vec::with_capacity(candidates.len());
+
+    for (i, chord) in candidates.iter().enumerate() {
+        let (scores, from): (Vec<f32>, Vec<usize>) = chord
+            .iter()
+            .map(|candidate| match i {
+                0 => (realizer.score(symbols, i, candidate, None, None), 0),
+                _ => candidates[i - 1]
+                    .iter()
+                    .enumerate()
+                    .map(|(j, prev)| {
+                        let earlier = (i >= 2).then(|| &candidates[i - 2][back[i - 1][j]]);
+                        (best[i - 1][j] + realizer.score(symbols, i, candidate, Some(prev), earlier), j)
+                    })
+                    .fold((f32::MIN, 0), |a, b| if b.0 > a.0 { b } else { a }),
+            })
+            .unzip();
+        best.push(scores);
+        back.push(from);
+    }
+    (best, back)
+}
+
+// Beam search: each step keeps at most `width` (score, candidate, parent)
+// entries, sorted best first, where parent indexes the previous step. A
+// candidate is only kept once, from its best parent, since the rest of the
+// search mostly depends on the last voicing (rules that look two chords
+// back see the best parent's path).
+fn realize_beam(
+    realizer: &Realizer,
+    symbols: &[FiguredBassSymbol],
+    candidates: &[Vec<Voicing>],
+    width: usize,
+) -> Vec<Voicing> {
+    let mut steps: Vec<Vec<(f32, usize, usize)>> = Vec::with_capacity(candidates.len());
+
+    for (i, chord) in candidates.iter().enumerate() {
+        let mut beam: Vec<(f32, usize, usize)> = chord
+            .iter()
+            .enumerate()
+            .map(|(c, candidate)| match steps.last() {
+                None => (realizer.score(symbols, i, candidate, None, None), c, 0),
+                Some(prev_beam) => prev_beam
+                    .iter()
+                    .enumerate()
+                    .map(|(k, &(score, prev, parent))| {
+                        let earlier = (i >= 2).then(|| &candidates[i - 2][steps[i - 2][parent].1]);
+                        let step = realizer.score(symbols, i, candidate, Some(&candidates[i - 1][prev]), earlier);
+                        (score + step, c, k)
+                    })
+                    .fold((f32::MIN, c, 0), |a, b| if b.0 > a.0 { b } else { a }),
+            })
+            .collect();
+        beam.sort_by(|a, b| b.0.total_cmp(&a.0));
+        beam.truncate(width.max(1));
+        steps.push(beam);
+    }
+
+    let mut result = Vec::with_capacity(steps.len());
+    let mut k = 0;
+    for (i, beam) in steps.iter().enumerate().rev() {
+        let (_, c, parent) = beam[k];
+        result.push(candidates[i][c].clone());
+        k = parent;
+    }
+    result.reverse();
+    result
+}
+
+// Same recurrence as realize_optimal, but every candidate keeps its n best
+// (score, previous candidate, rank of that candidate's path) entries
+// instead of one
+fn realize_n_best(
+    realizer: &Realizer,
+    symbols: &[FiguredBassSymbol],
+    candidates: &[Vec<Voicing>],
+    n: usize,
+) -> Vec<Realization> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut best: Vec<Vec<Vec<(f32, usize, usize)>>> = Vec::with_capacity(candidates.len());
+
+    for (i, chord) in candidates.iter().enumerate() {
+        let entries = chord
+            .iter()
+            .map(|candidate| {
+                if i == 0 {
+                    return vec![(realizer.score(symbols, i, candidate, None, None), 0, 0)];
+                }
+                let mut paths: Vec<(f32, usize, usize)> = Vec::new();
+                for (j, prev) in candidates[i - 1].iter().enumerate() {
+                    for (rank, &(score, before, _)) in best[i - 1][j].iter().enumerate() {
+                        let earlier = (i >= 2).then(|| &candidates[i - 2][before]);
+                        paths.push((score + realizer.score(symbols, i, candidate, Some(prev), earlier), j, rank));
+                    }
+                }
+                paths.sort_by(|a, b| b.0.total_cmp(&a.0));
+                paths.truncate(n);
+                paths
+            })
+            .collect();
+        best.push(entries);
+    }
+
+    let Some(last) = best.last() else {
+        return Vec::new();
+    };
+    let mut ends: Vec<(f32, usize, usize)> = last
+        .iter()
+        .enumerate()
+        .flat_map(|(c, paths)| (0..paths.len()).map(move |rank| (paths[rank].0, c, rank)))
+        .collect();
+    ends.sort_by(|a, b| b.0.total_cmp(&a.0));
+    ends.truncate(n);
+
+    ends.into_iter()
+        .map(|(score, mut c, mut rank)| {
+            let mut voicings = Vec::with_capacity(candidates.len());
+            for i in (0..candidates.len()).rev() {
+                voicings.push(candidates[i][c].clone());
+                let (_, prev, prev_rank) = best[i][c][rank];
+                c = prev;
+                rank = prev_rank;
+            }
+            voicings.reverse();
+            Realization { voicings, score }
+        })
+        .collect()
+}
+
+// realize_optimal with each candidate's paths told apart by how many
+// chords they have changed from each previous realization so far (counted
+// up to `min_difference`, past which more makes no difference): each
+// (candidate, counts) state keeps its best (score, previous candidate,
+// previous counts). A realization shorter than the progression differs
+// wherever it has no voicing.
+fn realize_excluding(
+    realizer: &Realizer,
+    symbols: &[FiguredBassSymbol],
+    candidates: &[Vec<Voicing>],
+    previous: &[Vec<Voicing>],
+    min_difference: usize,
+) -> Option<Vec<Voicing>> {
+    type States = BTreeMap<Vec<usize>, (f32, usize, Vec<usize>)>;
+    let counted = |counts: &[usize], i: usize, candidate: &Voicing| -> Vec<usize> {
+        previous
+            .iter()
+            .zip(counts)
+            .map(|(realization, &count)| {
+                let differs = realization.get(i) != Some(candidate);
+                (count + differs as usize).min(min_difference)
+            })
+            .collect()
+    };
+
+    let mut best: Vec<Vec<States>> = Vec::with_capacity(candidates.len());
+    for (i, chord) in candidates.iter().enumerate() {
+        let step: Vec<States> = chord
+            .iter()
+            .map(|candidate| {
+                let mut states = States::new();
+                if i == 0 {
+                    let score = realizer.score(symbols, i, candidate, None, None);
+                    states.insert(counted(&vec![0; previous.len()], i, candidate), (score, 0, Vec::new()));
+                    return states;
+                }
+                for (j, prev) in candidates[i - 1].iter().enumerate() {
+                    for (counts, &(score, before, _)) in &best[i - 1][j] {
+                        let earlier = (i >= 2).then(|| &candidates[i - 2][before]);
+                        let total = score + realizer.score(symbols, i, candidate, Some(prev), earlier);
+                        let entry = states.entry(counted(counts, i, candidate)).or_insert((f32::MIN, j, Vec::new()));
+                        if total > entry.0 {
+                            *entry = (total, j, counts.clone());
+                        }
+                    }
+                }
+                states
+            })
+            .collect();
+        best.push(step);
+    }
+
+    let last = best.last()?;
+    let (mut c, mut counts, _) = last
+        .iter()
+        .enumerate()
+        .flat_map(|(c, states)| states.iter().map(move |(counts, entry)| (c, counts.clone(), entry.0)))
+        .filter(|(_, counts, _)| counts.iter().all(|&count| count >= min_difference))
+        .fold(None, |a: Option<(usize, Vec<usize>, f32)>, b| match a {
+            Some(a) if a.2 >= b.2 => Some(a),
+            _ => Some(b),
+        })?;
+
+    let mut result = Vec::with_capacity(candidates.len());
+    for i in (0..candidates.len()).rev() {
+        result.push(candidates[i][c].clone());
+        let (_, prev, prev_counts) = best[i][c][&counts].clone();
+        c = prev;
+        counts = prev_counts;
+    }
+    result.reverse();
+    Some(result)
+}
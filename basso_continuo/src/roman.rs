@@ -0,0 +1,443 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::figure::{ChordMember, Figure, FiguredBassSymbol};
+use crate::key::{Key, Mode};
+use crate::meter;
+use crate::parse::ParseError;
+use crate::pitch::{Pitch, PitchClass};
+
+// Roman numeral input, as students write progressions:
+//
+//   numeral      I to VII, upper case for major and lower case for minor,
+//                "o" (or "°") for diminished, "ø" (or "%") for half
+//                diminished, "+" for augmented: "V", "ii", "viio", "III+"
+//   alteration   flats or sharps in front: "bVI", "#iv"
+//   figures      the inversion: "6", "64", "7", "65", "43", "42" (or "2"),
+//                with or without slashes; "M7" for a major seventh
+//   applied      "/" and the numeral tonicized: "V7/V", "viio7/ii"
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Quality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+}
+
+impl Quality {
+    // Semitones from the root to the third and the fifth
+    fn triad(&self) -> [u8; 2] {
+        match self {
+            Quality::Major => [4, 7],
+            Quality::Minor => [3, 7],
+            Quality::Diminished => [3, 6],
+            Quality::Augmented => [4, 8],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SeventhKind {
+    Diatonic, // whatever the key (or the tonicized key) gives, as in "V7"
+    Major,
+    Minor,
+    Diminished,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RomanNumeral {
+    pub degree: usize,    // 0 for I
+    pub alteration: i8,   // semitones: -1 for bVI
+    pub quality: Quality,
+    pub seventh: Option<SeventhKind>,
+    pub inversion: u8,    // 0 root position, up to 3 for a seventh chord's 4/2
+    pub applied: Option<Box<RomanNumeral>>, // the numeral after "/"
+}
+
+const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+
+impl RomanNumeral {
+    // The key the numeral is read in: `key` itself, or the key of the
+    // applied numeral (major or minor as that chord is)
+    fn local_key(&self, key: &Key) -> Key {
+        match &self.applied {
+            None => key.clone(),
+            Some(target) => {
+                let mode = if matches!(target.quality, Quality::Major | Quality::Augmented) {
+                    Mode::Major
+                } else {
+                    Mode::Minor
+                };
+                Key::new(target.root(key).0, mode)
+            }
+        }
+    }
+
+    pub fn root(&self, key: &Key) -> PitchClass {
+        let key = self.local_key(key);
+        // The diminished chord on the 7th in minor is built on the raised
+        // 7th, the leading tone
+        if key.mode.is_minor() && self.degree == 6 && self.alteration == 0 && self.quality == Quality::Diminished {
+            return PitchClass::new(key.leading_tone());
+        }
+        // Altered numerals count from the major scale, so bVI is F in both
+        // A major and A minor
+        if self.alteration != 0 {
+            let major = Mode::Major.steps()[self.degree];
+            return PitchClass::new(key.tonic + major).transpose(self.alteration as i16);
+        }
+        PitchClass::new(key.scale[self.degree])
+    }
+
+    // Chord tones from the root up: root, third, fifth and any seventh
+    pub fn pitch_classes(&self, key: &Key) -> Vec<PitchClass> {
+        let root = self.root(key);
+        let mut tones = vec![root];
+        tones.extend(self.quality.triad().map(|semitones| root.transpose(semitones as i16)));
+
+        if let Some(kind) = self.seventh {
+            let semitones = match kind {
+                SeventhKind::Major => 11,
+                SeventhKind::Minor => 10,
+                SeventhKind::Diminished => 9,
+                SeventhKind::Diatonic => {
+                    let local = self.local_key(key);
+                    root.interval_to(PitchClass::new(local.scale[self.degree + 6]))
+                }
+            };
+            tones.push(root.transpose(semitones as i16));
+        }
+        tones
+    }
+
+    pub fn bass(&self, key: &Key) -> PitchClass {
+        let tones = self.pitch_classes(key);
+        tones[self.inversion as usize % tones.len()]
+    }
+
+    // The figured bass symbol for this chord, with the bass in the octave
+    // nearest `near`. The figure gets whatever accidentals it takes to
+    // produce the chord in `key`.
+    pub fn symbol(&self, key: &Key, near: Pitch) -> Result<FiguredBassSymbol, ParseError> {
+        let tones = self.pitch_classes(key);
+        let bass = self.bass(key).nearest(near);
+
+        let numbers: &[u8] = match (tones.len(), self.inversion) {
+            (3, 0) => &[3, 5],
+            (3, 1) => &[3, 6],
+            (3, _) => &[4, 6],
+            (_, 0) => &[3, 5, 7],
+            (_, 1) => &[3, 5, 6],
+            (_, 2) => &[3, 4, 6],
+            _ => &[2, 4, 6],
+        };
+        let above: Vec<(u8, PitchClass)> = (1..tones.len())
+            .map(|i| tones[(self.inversion as usize + i) % tones.len()])
+            .zip(numbers)
+            .map(|(pc, &number)| (number, pc))
+            .collect();
+
+        let figure = Figure::for_tones(bass, &above, key)
+            .ok_or_else(|| ParseError::new(&self.to_string(), format!("cannot figure this chord in {}", key)))?;
+        Ok(FiguredBassSymbol::new(bass, figure, key))
+    }
+
+    // The numeral a realized chord is read as in `key`: diatonic where the
+    // chord is, then as an applied dominant or leading-tone chord, then as
+    // an altered (borrowed) degree. None for chords that are not a triad or
+    // seventh chord in thirds, such as a suspension before it resolves.
+    pub fn analyze(symbol: &FiguredBassSymbol, key: &Key) -> Option<Self> {
+        let root = symbol.root();
+        let third = root.interval_to(symbol.pitch_class_of(ChordMember::Third)?);
+        let fifth = symbol.pitch_class_of(ChordMember::Fifth).map_or(7, |pc| root.interval_to(pc));
+        let quality = match (third, fifth) {
+            (4, 7) => Quality::Major,
+            (3, 7) => Quality::Minor,
+            (3, 6) => Quality::Diminished,
+            (4, 8) => Quality::Augmented,
+            _ => return None,
+        };
+        let seventh = match symbol.seventh().map(|pc| root.interval_to(pc)) {
+            None => None,
+            Some(11) => Some(SeventhKind::Major),
+            Some(10) => Some(SeventhKind::Minor),
+            Some(9) => Some(SeventhKind::Diminished),
+            Some(_) => return None,
+        };
+        let inversion = match symbol.member(symbol.bass)? {
+            ChordMember::Root => 0,
+            ChordMember::Third => 1,
+            ChordMember::Fifth => 2,
+            ChordMember::Seventh => 3,
+            ChordMember::Other => return None,
+        };
+
+        let mut tones: Vec<PitchClass> = symbol.members.iter().map(|&(pc, _)| pc).collect();
+        tones.sort();
+        let spells = |numeral: &RomanNumeral| {
+            let mut spelled = numeral.pitch_classes(key);
+            spelled.sort();
+            spelled == tones
+        };
+        let numeral = |degree, alteration, applied| {
+            let mut numeral = RomanNumeral { degree, alteration, quality, seventh, inversion, applied };
+            numeral.seventh = seventh.map(|kind| numeral.seventh_kind(kind, key));
+            numeral
+        };
+
+        let diatonic = (0..7).map(|degree| numeral(degree, 0, None)).find(|n| n.root(key) == root);
+        let in_key = |n: &RomanNumeral| n.pitch_classes(key).iter().all(|pc| key.contains(pc.0) || pc.0 == key.leading_tone());
+        if let Some(n) = diatonic.as_ref().filter(|n| in_key(n)) {
+            return Some(n.clone());
+        }
+
+        // V/x and vii°/x: the chord leads to a major or minor triad of the
+        // key a fifth below or a semitone above its root
+        let applied = match (quality, seventh) {
+            (Quality::Major, None | Some(SeventhKind::Minor)) => Some((4, root.transpose(5))),
+            (Quality::Diminished, _) => Some((6, root.transpose(1))),
+            _ => None,
+        };
+        if let Some((function, target)) = applied
+            && let Some(degree) = key.scale.degree_of(target.0).filter(|&d| d != 0)
+        {
+            let chord = key.diatonic_chord(degree, 3);
+            let target_quality = match PitchClass::new(chord[0]).interval_to(PitchClass::new(chord[1])) {
+                4 => Quality::Major,
+                _ => Quality::Minor,
+            };
+            let fifth = PitchClass::new(chord[0]).interval_to(PitchClass::new(chord[2]));
+            let target = RomanNumeral {
+                degree,
+                alteration: 0,
+                quality: target_quality,
+                seventh: None,
+                inversion: 0,
+                applied: None,
+            };
+            let n = numeral(function, 0, Some(Box::new(target)));
+            if fifth == 7 && spells(&n) {
+                return Some(n);
+            }
+        }
+
+        // A degree of the key in another quality (mixture), or a flat or
+        // sharp degree counted from the major scale
+        let altered = [-1, 1].into_iter().flat_map(|alteration| (0..7).map(move |degree| (degree, alteration)));
+        diatonic
+            .into_iter()
+            .chain(altered.map(|(degree, alteration)| numeral(degree, alteration, None)))
+            .find(|n| n.root(key) == root && spells(n))
+    }
+
+    // How to write a seventh of `kind` on this numeral: plain "7" when it
+    // is the one the key gives, except on a diminished triad, where "o7"
+    // and "ø7" tell the two apart
+    fn seventh_kind(&self, kind: SeventhKind, key: &Key) -> SeventhKind {
+        if self.quality == Quality::Diminished {
+            return kind;
+        }
+        let diatonic = RomanNumeral { seventh: Some(SeventhKind::Diatonic), ..self.clone() };
+        let explicit = RomanNumeral { seventh: Some(kind), ..self.clone() };
+        if diatonic.pitch_classes(key) == explicit.pitch_classes(key) {
+            SeventhKind::Diatonic
+        } else {
+            kind
+        }
+    }
+}
+
+impl fmt::Display for RomanNumeral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.alteration > 0 { "#" } else { "b" };
+        write!(f, "{}", sign.repeat(self.alteration.unsigned_abs() as usize))?;
+
+        let numeral = NUMERALS[self.degree % 7];
+        match self.quality {
+            Quality::Major | Quality::Augmented => write!(f, "{}", numeral)?,
+            Quality::Minor | Quality::Diminished => write!(f, "{}", numeral.to_lowercase())?,
+        }
+        match (self.quality, self.seventh) {
+            (Quality::Diminished, Some(SeventhKind::Minor)) => write!(f, "ø")?,
+            (Quality::Diminished, _) => write!(f, "o")?,
+            (Quality::Augmented, _) => write!(f, "+")?,
+            _ => {}
+        }
+        if self.seventh == Some(SeventhKind::Major) {
+            write!(f, "M")?;
+        }
+
+        let figure = match (self.seventh.is_some(), self.inversion) {
+            (false, 0) => "",
+            (false, 1) => "6",
+            (false, _) => "64",
+            (true, 0) => "7",
+            (true, 1) => "65",
+            (true, 2) => "43",
+            (true, _) => "42",
+        };
+        write!(f, "{}", figure)?;
+
+        if let Some(target) = &self.applied {
+            write!(f, "/{}", target)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RomanNumeral {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_numeral(s.trim(), s)
+    }
+}
+
+fn parse_numeral(text: &str, input: &str) -> Result<RomanNumeral, ParseError> {
+    let invalid = |message: &str| ParseError::new(input, message);
+
+    let mut rest = text;
+    let mut alteration = 0;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            'b' | '♭' => alteration -= 1,
+            '#' | '♯' => alteration += 1,
+            _ => break,
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    // Longest numeral first, so "VII" is not read as "V" and "II"
+    let upper = rest.to_ascii_uppercase();
+    let degree = (0..7)
+        .rev()
+        .max_by_key(|&d| if upper.starts_with(NUMERALS[d]) { NUMERALS[d].len() } else { 0 })
+        .filter(|&d| upper.starts_with(NUMERALS[d]))
+        .ok_or_else(|| invalid("expected a Roman numeral such as I, ii or V7"))?;
+    let (numeral, after) = rest.split_at(NUMERALS[degree].len());
+    let major = if numeral.chars().all(|c| c.is_ascii_uppercase()) {
+        true
+    } else if numeral.chars().all(|c| c.is_ascii_lowercase()) {
+        false
+    } else {
+        return Err(invalid("mixed case numeral"));
+    };
+    rest = after;
+
+    let mut quality = if major { Quality::Major } else { Quality::Minor };
+    let mut half_diminished = false;
+    match rest.chars().next() {
+        Some('o' | '°') => quality = Quality::Diminished,
+        Some('ø' | '%') => {
+            quality = Quality::Diminished;
+            half_diminished = true;
+        }
+        Some('+') => quality = Quality::Augmented,
+        _ => {}
+    }
+    if quality != Quality::Major && quality != Quality::Minor {
+        rest = &rest[rest.chars().next().map_or(0, char::len_utf8)..];
+    }
+
+    let major_seventh = rest.starts_with('M');
+    if major_seventh {
+        rest = &rest[1..];
+    }
+
+    // Figures run up to a "/" that is not followed by a digit
+    let mut figure = String::new();
+    let mut applied = None;
+    let mut chars = rest.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '0'..='9' => figure.push(c),
+            '/' if chars.peek().is_some_and(|&(_, next)| next.is_ascii_digit()) => {}
+            '/' => {
+                applied = Some(Box::new(parse_numeral(&rest[i + 1..], input)?));
+                break;
+            }
+            _ => return Err(invalid(&format!("unexpected {:?} in Roman numeral", c))),
+        }
+    }
+
+    let (seventh, inversion) = match figure.as_str() {
+        "" if half_diminished || major_seventh => (true, 0),
+        "" | "53" => (false, 0),
+        "6" | "63" => (false, 1),
+        "64" => (false, 2),
+        "7" | "753" => (true, 0),
+        "65" | "653" => (true, 1),
+        "43" | "643" => (true, 2),
+        "42" | "2" | "642" => (true, 3),
+        _ => return Err(invalid(&format!("unknown inversion figure {:?}", figure))),
+    };
+
+    let seventh = seventh.then_some(if half_diminished {
+        SeventhKind::Minor
+    } else if major_seventh {
+        SeventhKind::Major
+    } else if quality == Quality::Diminished {
+        SeventhKind::Diminished
+    } else {
+        SeventhKind::Diatonic
+    });
+
+    Ok(RomanNumeral { degree, alteration, quality, seventh, inversion, applied })
+}
+
+// Roman numerals separated by whitespace, ";" after one for a fermata
+// ending a phrase ("V;"). Each bass is taken in the octave nearest a point
+// halfway between the bass before and D3, so the line moves smoothly
+// without drifting out of the bass range.
+pub fn parse_roman_progression(text: &str, key: &Key) -> Result<Vec<FiguredBassSymbol>, ParseError> {
+    const CENTER: u8 = 50;
+    let mut prev = CENTER;
+    let mut symbols = Vec::new();
+    for numeral in text.split_whitespace() {
+        let (numeral, fermata) = numeral.strip_suffix(';').map_or((numeral, false), |numeral| (numeral, true));
+        let mut symbol = numeral.parse::<RomanNumeral>()?.symbol(key, Pitch::new((prev + CENTER) / 2))?;
+        symbol.fermata = fermata;
+        prev = symbol.bass.midi_number;
+        symbols.push(symbol);
+    }
+    meter::place(&mut symbols);
+    Ok(symbols)
+}
+
+// Numerals for a whole progression. A chord that is not a stack of thirds
+// over a bass that is then held, as the 4 of a 4-3 suspension, takes the
+// numeral of the chord it resolves to; a new harmony over a pedal resolves
+// nothing.
+pub fn analyze(symbols: &[FiguredBassSymbol], key: &Key) -> Vec<Option<RomanNumeral>> {
+    let mut numerals: Vec<_> = symbols.iter().map(|symbol| RomanNumeral::analyze(symbol, key)).collect();
+    for i in (0..symbols.len().saturating_sub(1)).rev() {
+        if numerals[i].is_none() && symbols[i + 1].held && !symbols[i + 1].pedal {
+            numerals[i] = numerals[i + 1].clone();
+        }
+    }
+    numerals
+}
+
+// The major or minor key a progression is most likely in: the one with
+// the most chord tones in its scale (counting the raised leading tone in
+// minor), favouring a key whose tonic is the last bass and then the first
+pub fn detect_key(symbols: &[FiguredBassSymbol]) -> Key {
+    let keys = (0..12).flat_map(|tonic| [Key::new(tonic, Mode::Major), Key::new(tonic, Mode::Minor)]);
+    let fit = |key: &Key| {
+        let tones = symbols
+            .iter()
+            .flat_map(|symbol| &symbol.chord_tones)
+            .filter(|pitch| key.contains(pitch.pitch_class().0) || pitch.pitch_class().0 == key.leading_tone())
+            .count();
+        let ends_on_tonic = symbols.last().is_some_and(|s| s.bass.pitch_class().0 == key.tonic);
+        let starts_on_tonic = symbols.first().is_some_and(|s| s.bass.pitch_class().0 == key.tonic);
+        (tones, ends_on_tonic, starts_on_tonic)
+    };
+    // max_by_key keeps the last of equals; reversing keeps the first, so
+    // C major wins over A minor when nothing tells them apart
+    keys.rev().max_by_key(fit).unwrap_or_else(Key::c_major)
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+
+use crate::figure::FiguredBassSymbol;
+use crate::key::{Key, Mode};
+use crate::meter;
+use crate::parse::ParseError;
+use crate::pitch::{Letter, Pitch};
+use crate::roman::RomanNumeral;
+
+// RomanText, the Roman numeral format music21 reads and writes:
+//
+//   Title: Chorale
+//   Time Signature: 4/4
+//   m1 g: i b2 iv6 b3 V7 b4 i
+//   m2 Bb: V65/IV b3 IV ||
+//   m3-4 = m1-2
+//
+// Each measure line is the numerals in it, with beats ("b3") and key
+// changes ("g:", "Bb:", lower case for minor) before them. Numerals are
+// written as the crate reads them, with "/o" for half diminished, "Cad64"
+// for the cadential 6/4 and "N6" for the Neapolitan; augmented sixths
+// are not supported, and additions in brackets ("[add6]") are left out.
+// Only the order of the chords is kept, not their beats.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomanTextError {
+    pub line: usize, // counting from 1
+    pub message: String,
+}
+
+impl fmt::Display for RomanTextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RomanText line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RomanTextError {}
+
+// The chords of a RomanText analysis as figured bass symbols, each in the
+// key in force where it stands. `key` is the key before the first key
+// change, which RomanText otherwise has to start with.
+pub fn read_romantext(text: &str, key: Option<&Key>) -> Result<Vec<FiguredBassSymbol>, RomanTextError> {
+    // The tokens of each measure, and the line they are on
+    let mut measures: Vec<(u32, Vec<(String, usize)>)> = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line_number = n + 1;
+        let error = |message: String| RomanTextError { line: line_number, message };
+        let mut words = line.split_whitespace();
+        let Some(first) = words.next() else {
+            continue;
+        };
+        // Headers ("Title: ...", "Note: ...") and other lines are skipped
+        let Some(range) = first.strip_prefix('m').filter(|r| r.starts_with(|c: char| c.is_ascii_digit())) else {
+            continue;
+        };
+        // Variant readings ("m3var1") are not the piece itself
+        if range.contains("var") {
+            continue;
+        }
+        let (start, end) = measure_range(range).ok_or_else(|| error(format!("invalid measure number {:?}", first)))?;
+        let rest: Vec<&str> = words.collect();
+        if rest.first() == Some(&"=") {
+            // "m5-8 = m1-4" repeats measures written before
+            let source = rest.get(1).and_then(|s| s.strip_prefix('m')).and_then(measure_range);
+            let (from, to) = source.ok_or_else(|| error("expected the measures repeated after \"=\"".to_string()))?;
+            if to - from != end - start {
+                return Err(error(format!("m{}-{} repeats a different number of measures", start, end)));
+            }
+            for (offset, number) in (from..=to).enumerate() {
+                let tokens = measures.iter().find(|(m, _)| *m == number).map(|(_, tokens)| tokens.clone());
+                let tokens = tokens.ok_or_else(|| error(format!("m{} is repeated before it is written", number)))?;
+                measures.push((start + offset as u32, tokens));
+            }
+        } else {
+            measures.push((start, rest.iter().map(|word| (word.to_string(), line_number)).collect()));
+        }
+    }
+
+    const CENTER: u8 = 50;
+    let mut prev = CENTER;
+    let mut key = key.cloned();
+    let mut symbols = Vec::new();
+    for (_, tokens) in &measures {
+        for (token, line) in tokens {
+            let error = |message: String| RomanTextError { line: *line, message };
+            if let Some(name) = token.strip_suffix(':') {
+                key = Some(key_token(name).ok_or_else(|| error(format!("invalid key {:?}", token)))?);
+                continue;
+            }
+            // Beats, bar lines, phrase ends and repeat signs
+            let beat = token.strip_prefix('b').is_some_and(|b| b.starts_with(|c: char| c.is_ascii_digit()));
+            if beat || token.chars().all(|c| matches!(c, '|' | ':' | '.')) {
+                continue;
+            }
+            let key = key.as_ref().ok_or_else(|| error(format!("{:?} comes before any key", token)))?;
+            let numeral = numeral(token, key).map_err(error)?;
+            let symbol = numeral.symbol(key, Pitch::new((prev + CENTER) / 2)).map_err(|e| error(e.to_string()))?;
+            prev = symbol.bass.midi_number;
+            symbols.push(symbol);
+        }
+    }
+    if symbols.is_empty() {
+        return Err(RomanTextError { line: text.lines().count(), message: "no chords".to_string() });
+    }
+    meter::place(&mut symbols);
+    Ok(symbols)
+}
+
+// An analysis as RomanText: `numerals` in `key`, four chords to a measure
+// of 4/4 and a beat each. A chord with no numeral is left out, with a
+// note saying so.
+pub fn write_romantext(numerals: &[Option<RomanNumeral>], key: &Key, title: &str) -> String {
+    let mut text = format!("Title: {}\nAnalyst: basso_continuo\nTime Signature: 4/4\n\n", title);
+    for (m, measure) in numerals.chunks(4).enumerate() {
+        let mut line = format!("m{}", m + 1);
+        if m == 0 {
+            line.push_str(&format!(" {}:", key_name(key)));
+        }
+        for (beat, numeral) in measure.iter().enumerate() {
+            let Some(numeral) = numeral else {
+                continue;
+            };
+            if beat > 0 {
+                line.push_str(&format!(" b{}", beat + 1));
+            }
+            line.push_str(&format!(" {}", numeral.to_string().replace('ø', "/o")));
+        }
+        text.push_str(&line);
+        text.push('\n');
+    }
+    let missing: Vec<String> =
+        numerals.iter().enumerate().filter(|(_, n)| n.is_none()).map(|(i, _)| (i + 1).to_string()).collect();
+    if !missing.is_empty() {
+        text.push_str(&format!("Note: no Roman numeral for chords {}\n", missing.join(", ")));
+    }
+    text
+}
+
+// "3" or "3-4"
+fn measure_range(text: &str) -> Option<(u32, u32)> {
+    match text.split_once('-') {
+        Some((start, end)) => Some((start.parse().ok()?, end.parse().ok()?)).filter(|(s, e)| s <= e),
+        None => text.parse().ok().map(|m| (m, m)),
+    }
+}
+
+// "C", "f#", "Bb", "bb" or "b-" (B flat minor): upper case major, lower
+// case minor
+fn key_token(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    let first = chars.next()?;
+    let letter = Letter::from_char(first)?;
+    let mut alter = 0i16;
+    for c in chars {
+        match c {
+            '#' => alter += 1,
+            'b' | '-' => alter -= 1,
+            _ => return None,
+        }
+    }
+    let mode = if first.is_ascii_lowercase() { Mode::Minor } else { Mode::Major };
+    Some(Key::with_letter((letter.pitch_class() as i16 + alter).rem_euclid(12) as u8, letter, mode))
+}
+
+// The inverse: "Bb" for B flat major, "f#" for F sharp minor
+fn key_name(key: &Key) -> String {
+    let name = key.tonic_name();
+    match key.mode.is_minor() {
+        true => name.to_lowercase(),
+        false => name,
+    }
+}
+
+// A RomanText numeral in the crate's own spelling, read in `key`
+fn numeral(token: &str, key: &Key) -> Result<RomanNumeral, String> {
+    let token = match token.find('[') {
+        Some(at) => &token[..at],
+        None => token,
+    };
+    if ["It", "Fr", "Ger", "Sw"].iter().any(|name| token.starts_with(name)) {
+        return Err(format!("{:?}: augmented sixth chords are not supported", token));
+    }
+    let text = token.replace("/o", "ø");
+    let text = match text.as_str() {
+        "Cad64" if key.mode.is_minor() => "i64".to_string(),
+        "Cad64" => "I64".to_string(),
+        _ => match text.strip_prefix('N') {
+            Some(figures) => format!("bII{}", figures),
+            None => text,
+        },
+    };
+    text.parse().map_err(|e: ParseError| e.to_string())
+}
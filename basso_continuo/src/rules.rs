@@ -0,0 +1,690 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::figure::{ChordMember, FiguredBassSymbol};
+use crate::pitch::PitchClass;
+use crate::range::RangeProfile;
+use crate::scoring::{
+    contrary_motion_bonus, doubling_score, has_hidden_perfects, has_parallel_perfects, is_augmented_or_diminished,
+    range_comfort_score, spacing_score, voice_motion_score, ScoringWeights,
+};
+use crate::voicing::Voicing;
+
+// What a rule gets to look at: a candidate voicing for chord `index`, the
+// voicings chosen for the two chords before it (if any), and the whole
+// figured bass so rules can look around the chord
+pub struct RuleContext<'a> {
+    pub voicing: &'a Voicing,
+    pub prev: Option<&'a Voicing>,
+    // The voicing before `prev` on the path being extended. The full
+    // search keeps one path per voicing, so rules that look this far back
+    // are scored along the best path to `prev` rather than every path.
+    pub earlier: Option<&'a Voicing>,
+    pub symbols: &'a [FiguredBassSymbol],
+    pub index: usize,
+    pub ranges: &'a RangeProfile,
+}
+
+impl<'a> RuleContext<'a> {
+    // The context as the realizer builds it: the lines start afresh after
+    // a fermata, so the chords before the end of a phrase are not looked
+    // back to
+    pub fn new(
+        voicing: &'a Voicing,
+        prev: Option<&'a Voicing>,
+        earlier: Option<&'a Voicing>,
+        symbols: &'a [FiguredBassSymbol],
+        index: usize,
+        ranges: &'a RangeProfile,
+    ) -> Self {
+        let after_fermata = |n: usize| index >= n && symbols[index - n].fermata;
+        let prev = prev.filter(|_| !after_fermata(1));
+        let earlier = earlier.filter(|_| !after_fermata(1) && !after_fermata(2));
+        Self { voicing, prev, earlier, symbols, index, ranges }
+    }
+
+    pub fn symbol(&self) -> &FiguredBassSymbol {
+        &self.symbols[self.index]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RuleOutcome {
+    Pass,
+    Bonus(f32),
+    Penalty(f32),
+    // A penalty for breaking a rule that should only be broken when there
+    // is no way around it; the search backs up to avoid these
+    Violation(f32),
+}
+
+impl RuleOutcome {
+    // Contribution to the voicing's score
+    pub fn score(&self) -> f32 {
+        match *self {
+            RuleOutcome::Pass => 0.0,
+            RuleOutcome::Bonus(amount) => amount,
+            RuleOutcome::Penalty(amount) | RuleOutcome::Violation(amount) => -amount,
+        }
+    }
+
+    pub fn is_violation(&self) -> bool {
+        matches!(self, RuleOutcome::Violation(_))
+    }
+}
+
+// Each rule's part in one voicing's score, for seeing why it won: the
+// outcome of every rule in the set, in the set's order
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreBreakdown {
+    pub outcomes: Vec<(String, RuleOutcome)>,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> f32 {
+        self.outcomes.iter().map(|(_, outcome)| outcome.score()).sum()
+    }
+
+    pub fn has_violations(&self) -> bool {
+        self.outcomes.iter().any(|(_, outcome)| outcome.is_violation())
+    }
+
+    // The names of the rules that report violations
+    pub fn violated(&self) -> Vec<String> {
+        self.outcomes.iter().filter(|(_, outcome)| outcome.is_violation()).map(|(name, _)| name.clone()).collect()
+    }
+
+    // The outcome of the rule called `name`, if the set has one
+    pub fn outcome(&self, name: &str) -> Option<RuleOutcome> {
+        self.outcomes.iter().find(|(n, _)| n == name).map(|&(_, outcome)| outcome)
+    }
+}
+
+// "31.5: doubling +20, spacing -2, parallel-perfects -1000 (violation)",
+// leaving out the rules that passed
+impl fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Two decimals at most, so float noise such as 1.4000001 stays out
+        let points = |score: f32| {
+            let text = format!("{:.2}", score);
+            text.trim_end_matches('0').trim_end_matches('.').to_string()
+        };
+        write!(f, "{}", points(self.total()))?;
+        let mut sep = ": ";
+        for (name, outcome) in &self.outcomes {
+            if *outcome == RuleOutcome::Pass {
+                continue;
+            }
+            let sign = if outcome.score() > 0.0 { "+" } else { "" };
+            write!(f, "{}{} {}{}", sep, name, sign, points(outcome.score()))?;
+            if outcome.is_violation() {
+                write!(f, " (violation)")?;
+            }
+            sep = ", ";
+        }
+        Ok(())
+    }
+}
+
+pub trait Rule: Send + Sync {
+    // Identifies the rule in a RuleSet
+    fn name(&self) -> &str;
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome;
+}
+
+// Turn a score into an outcome, so the built-in rules can reuse the
+// scoring functions
+fn outcome(score: f32) -> RuleOutcome {
+    if score > 0.0 {
+        RuleOutcome::Bonus(score)
+    } else if score < 0.0 {
+        RuleOutcome::Penalty(-score)
+    } else {
+        RuleOutcome::Pass
+    }
+}
+
+// Doubling the right chord member for the inversion, rewarded per voice
+// singing it; doubling the leading tone costs `leading_tone`
+pub struct Doubling {
+    pub weight: f32,
+    pub leading_tone: f32,
+    pub weak_beat: f32, // share of `weight` that counts on weak beats and off the beat
+}
+
+impl Rule for Doubling {
+    fn name(&self) -> &str {
+        "doubling"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let symbol = context.symbol();
+        let weight = match symbol.meter.accent(symbol.offset) {
+            0 => self.weight * self.weak_beat,
+            _ => self.weight,
+        };
+        let weights = ScoringWeights::default().doubling(weight).leading_tone_doubling(self.leading_tone);
+        outcome(doubling_score(context.voicing, symbol, &weights))
+    }
+}
+
+// Gaps between upper voices wider than `limit` semitones
+pub struct Spacing {
+    pub weight: f32,
+    pub limit: u8,
+}
+
+impl Rule for Spacing {
+    fn name(&self) -> &str {
+        "spacing"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let weights = ScoringWeights::default().spacing(self.weight).spacing_limit(self.limit);
+        outcome(spacing_score(context.voicing, &weights))
+    }
+}
+
+// Upper voices away from the middle of their ranges
+pub struct RangeComfort {
+    pub weight: f32,
+}
+
+impl Rule for RangeComfort {
+    fn name(&self) -> &str {
+        "range-comfort"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let weights = ScoringWeights::default().range_comfort(self.weight);
+        outcome(range_comfort_score(context.voicing, context.ranges, &weights))
+    }
+}
+
+// Parallel fifths and octaves between any two voices that both move: a
+// pedal, or any voice holding its note, is in none
+pub struct ParallelPerfects {
+    pub weight: f32,
+}
+
+impl Rule for ParallelPerfects {
+    fn name(&self) -> &str {
+        "parallel-perfects"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        match context.prev {
+            Some(prev) if has_parallel_perfects(prev, context.voicing) => RuleOutcome::Violation(self.weight),
+            _ => RuleOutcome::Pass,
+        }
+    }
+}
+
+// Hidden fifths and octaves between the outer voices; a violation when
+// `forbid` is set, otherwise an ordinary penalty
+pub struct HiddenPerfects {
+    pub weight: f32,
+    pub forbid: bool,
+}
+
+impl Rule for HiddenPerfects {
+    fn name(&self) -> &str {
+        "hidden-perfects"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        match context.prev {
+            Some(prev) if has_hidden_perfects(prev, context.voicing) => {
+                if self.forbid {
+                    RuleOutcome::Violation(self.weight)
+                } else {
+                    RuleOutcome::Penalty(self.weight)
+                }
+            }
+            _ => RuleOutcome::Pass,
+        }
+    }
+}
+
+// Total distance moved by the upper voices
+pub struct VoiceMotion {
+    pub weight: f32,
+}
+
+impl Rule for VoiceMotion {
+    fn name(&self) -> &str {
+        "voice-motion"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let Some(prev) = context.prev else {
+            return RuleOutcome::Pass;
+        };
+        let weights = ScoringWeights::default().motion(self.weight);
+        outcome(voice_motion_score(prev, context.voicing, &weights))
+    }
+}
+
+// Soprano and bass moving in opposite directions
+pub struct ContraryMotion {
+    pub weight: f32,
+}
+
+impl Rule for ContraryMotion {
+    fn name(&self) -> &str {
+        "contrary-motion"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let Some(prev) = context.prev else {
+            return RuleOutcome::Pass;
+        };
+        let weights = ScoringWeights::default().contrary_motion(self.weight);
+        outcome(contrary_motion_bonus(prev, context.voicing, &weights))
+    }
+}
+
+// Chordal sevenths: the voice with the seventh should already have sung
+// that note in the chord before (preparation), and must then step down to
+// the next chord (resolution). A seventh may be held while the next chord
+// keeps it as its seventh. Only the upper voices count, the bass is given.
+pub struct ChordalSeventh {
+    pub resolution: f32,  // penalty per seventh that rises, leaps or stalls
+    pub preparation: f32, // penalty per seventh entered without preparation
+}
+
+impl Rule for ChordalSeventh {
+    fn name(&self) -> &str {
+        "chordal-seventh"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        // A context built with a voicing before the first chord has no
+        // chord before it to read
+        let (Some(prev), Some(before)) = (context.prev, context.index.checked_sub(1).map(|i| &context.symbols[i])) else {
+            return RuleOutcome::Pass;
+        };
+        let after = context.symbol();
+        let mut penalty = 0.0;
+
+        for (from, to) in prev.upper().into_iter().zip(context.voicing.upper()) {
+            let step = to.semitones() - from.semitones();
+            if let Some(seventh) = before.seventh()
+                && from.pitch_class() == seventh
+                && !(step == -1 || step == -2 || (step == 0 && after.seventh() == Some(seventh)))
+            {
+                penalty += self.resolution;
+            }
+            if let Some(seventh) = after.seventh()
+                && to.pitch_class() == seventh
+                && step != 0
+            {
+                penalty += self.preparation;
+            }
+        }
+        outcome(-penalty)
+    }
+}
+
+// The melodic line of each upper voice: augmented and diminished
+// intervals, leaps wider than an octave, and leaps of more than a fourth
+// that the next note does not recover from by stepping back
+pub struct MelodicIntervals {
+    pub augmented: f32,
+    pub wide_leap: f32,
+    pub recovery: f32,
+}
+
+impl Rule for MelodicIntervals {
+    fn name(&self) -> &str {
+        "melodic-intervals"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let Some(prev) = context.prev else {
+            return RuleOutcome::Pass;
+        };
+        let key = &context.symbol().key;
+        let mut penalty = 0.0;
+
+        for (voice, (from, to)) in prev.upper().into_iter().zip(context.voicing.upper()).enumerate() {
+            let motion = to.semitones() - from.semitones();
+            if is_augmented_or_diminished(from, to, key) {
+                penalty += self.augmented;
+            }
+            if motion.abs() > 12 {
+                penalty += self.wide_leap;
+            }
+            if let Some(earlier) = context.earlier {
+                let leap = from.semitones() - earlier.upper()[voice].semitones();
+                let recovered = motion != 0 && motion.signum() != leap.signum() && motion.abs() <= 2;
+                if leap.abs() > 5 && !recovered {
+                    penalty += self.recovery;
+                }
+            }
+        }
+        outcome(-penalty)
+    }
+}
+
+// Suspensions over a held bass: the suspended voice keeps its note from the
+// chord before (preparation), then steps down to the resolution
+pub struct Suspension {
+    pub weight: f32, // penalty per voice that fails to prepare or resolve
+}
+
+impl Rule for Suspension {
+    fn name(&self) -> &str {
+        "suspension"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let (Some(prev), Some(before)) = (context.prev, context.index.checked_sub(1).map(|i| &context.symbols[i])) else {
+            return RuleOutcome::Pass;
+        };
+        let moves = || prev.upper().into_iter().zip(context.voicing.upper());
+        let mut penalty = 0.0;
+
+        if let Some(next) = context.symbols.get(context.index + 1) {
+            for (suspension, _) in context.symbol().suspensions(next) {
+                penalty += self.weight
+                    * moves().filter(|(from, to)| to.pitch_class() == suspension && from != to).count() as f32;
+            }
+        }
+
+        for (suspension, resolution) in before.suspensions(context.symbol()) {
+            let unresolved = moves().filter(|(from, to)| {
+                let step = to.semitones() - from.semitones();
+                from.pitch_class() == suspension
+                    && !(to.pitch_class() == resolution && (step == -1 || step == -2))
+            });
+            penalty += self.weight * unresolved.count() as f32;
+        }
+
+        outcome(-penalty)
+    }
+}
+
+// A 6/4 chord whose bass stays put into a root position chord, the
+// dominant's 6/4 at a cadence
+pub fn is_cadential_six_four(symbols: &[FiguredBassSymbol], index: usize) -> bool {
+    let (Some(chord), Some(next)) = (symbols.get(index), symbols.get(index + 1)) else {
+        return false;
+    };
+    chord.member(chord.bass) == Some(ChordMember::Fifth)
+        && chord.seventh().is_none()
+        && next.bass.pitch_class() == chord.bass.pitch_class()
+        && next.member(next.bass) == Some(ChordMember::Root)
+}
+
+// Cadential 6/4s: the bass doubled, and the voices with the 6 and the 4
+// stepping down to the 5 and 3 over the held bass. Both are violations.
+pub struct CadentialSixFour {
+    pub weight: f32,
+}
+
+impl Rule for CadentialSixFour {
+    fn name(&self) -> &str {
+        "cadential-six-four"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let voicing = context.voicing;
+        let bass = voicing.bass.pitch_class();
+
+        if is_cadential_six_four(context.symbols, context.index)
+            && !voicing.upper().iter().any(|p| p.pitch_class() == bass)
+        {
+            return RuleOutcome::Violation(self.weight);
+        }
+
+        if let Some(prev) = context.prev
+            && let Some(before) = context.index.checked_sub(1)
+            && is_cadential_six_four(context.symbols, before)
+        {
+            let chord = &context.symbols[before];
+            for (from, to) in prev.upper().into_iter().zip(voicing.upper()) {
+                let step = to.semitones() - from.semitones();
+                let above_bass = chord.bass.pitch_class().interval_to(from.pitch_class());
+                // The 4th (5 semitones) and 6th (8 or 9) above the bass
+                if matches!(above_bass, 5 | 8 | 9) && !(step == -1 || step == -2) {
+                    return RuleOutcome::Violation(self.weight);
+                }
+            }
+        }
+
+        RuleOutcome::Pass
+    }
+}
+
+// A 6/4 sounding on a strong beat: the cadential 6/4 belongs there, but
+// passing, neighbour and arpeggiated 6/4s fall on weak beats, and one on
+// the downbeat sounds as a tonic out of place
+pub struct StrongBeatSixFour {
+    pub weight: f32,
+}
+
+impl Rule for StrongBeatSixFour {
+    fn name(&self) -> &str {
+        "strong-beat-six-four"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let symbol = context.symbol();
+        if symbol.meter.accent(symbol.offset) == 0 || is_cadential_six_four(context.symbols, context.index) {
+            return RuleOutcome::Pass;
+        }
+        let bass = context.voicing.bass.pitch_class();
+        let above: Vec<u8> = context.voicing.upper().iter().map(|p| bass.interval_to(p.pitch_class())).collect();
+        // A fourth and a sixth over the bass, with no third or fifth
+        let six_four = above.contains(&5)
+            && above.iter().any(|&i| matches!(i, 8 | 9))
+            && !above.iter().any(|&i| matches!(i, 3 | 4 | 7));
+        if six_four { RuleOutcome::Penalty(self.weight) } else { RuleOutcome::Pass }
+    }
+}
+
+// Whether two chords are the same harmony, whatever their bass
+fn same_harmony(a: &FiguredBassSymbol, b: &FiguredBassSymbol) -> bool {
+    a.root() == b.root()
+        && a.members.len() == b.members.len()
+        && a.members.iter().all(|(pc, _)| b.members.iter().any(|(other, _)| other == pc))
+}
+
+// Harmony changing with the strong beats: a bonus for a new chord on one,
+// a penalty for the chord before carried over it, each half as much on the
+// middle of the bar as on the downbeat
+pub struct HarmonicRhythm {
+    pub weight: f32,
+}
+
+impl Rule for HarmonicRhythm {
+    fn name(&self) -> &str {
+        "harmonic-rhythm"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let symbol = context.symbol();
+        let accent = symbol.meter.accent(symbol.offset);
+        if context.index == 0 || accent == 0 {
+            return RuleOutcome::Pass;
+        }
+        let weight = self.weight * accent as f32 / 2.0;
+        if same_harmony(&context.symbols[context.index - 1], symbol) {
+            RuleOutcome::Penalty(weight)
+        } else {
+            RuleOutcome::Bonus(weight)
+        }
+    }
+}
+
+// Phrase endings: the chord under a fermata closing a cadence. A bonus
+// for each of the harmony of one (V or vii° to I, IV to I, or any chord to
+// V, the bass on the root), the soprano on the root, and half as much for
+// the soprano stepping into it, as in 2-1 or 7-8.
+pub struct Cadence {
+    pub weight: f32,
+}
+
+impl Rule for Cadence {
+    fn name(&self) -> &str {
+        "cadence"
+    }
+
+    fn evaluate(&self, context: &RuleContext) -> RuleOutcome {
+        let symbol = context.symbol();
+        if !symbol.fermata {
+            return RuleOutcome::Pass;
+        }
+        let voicing = context.voicing;
+        let root = symbol.root();
+        let mut bonus = 0.0;
+        // The chord before is the one before the bass note, past a 4-3 or
+        // a 6/4 over it
+        let mut start = context.index;
+        while start > 0 && context.symbols[start].held {
+            start -= 1;
+        }
+        if start > 0 && symbol.member(symbol.bass) == Some(ChordMember::Root) {
+            let tonic = PitchClass::new(symbol.key.tonic);
+            let from = tonic.interval_to(context.symbols[start - 1].root());
+            match tonic.interval_to(root) {
+                0 if matches!(from, 5 | 7 | 11) => bonus += self.weight,
+                7 if from != 7 => bonus += self.weight,
+                _ => {}
+            }
+        }
+        if voicing.soprano.pitch_class() == root {
+            bonus += self.weight;
+        }
+        if let Some(prev) = context.prev
+            && matches!((voicing.soprano.semitones() - prev.soprano.semitones()).abs(), 1 | 2)
+        {
+            bonus += self.weight / 2.0;
+        }
+        outcome(bonus)
+    }
+}
+
+// The rules a realization is scored by, in evaluation order. Names are
+// unique: adding a rule with a name already in the set replaces it.
+#[derive(Clone, Default)]
+pub struct RuleSet {
+    rules: Vec<Arc<dyn Rule>>,
+}
+
+impl fmt::Debug for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl RuleSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The built-in rules, weighted by `weights`: the rules of
+    // score_voicing, plus the rules that need the figures (chordal sevenths,
+    // cadential 6/4s and suspensions) or a longer view of the lines
+    // (melodic intervals), the meter (strong-beat 6/4s, harmonic rhythm,
+    // and doubling, which counts for less on weak beats) or the phrasing
+    // (cadences at fermatas)
+    pub fn standard(weights: &ScoringWeights) -> Self {
+        Self::new()
+            .with(Doubling {
+                weight: weights.doubling,
+                leading_tone: weights.leading_tone_doubling,
+                weak_beat: weights.weak_beat_doubling,
+            })
+            .with(Spacing { weight: weights.spacing, limit: weights.spacing_limit })
+            .with(RangeComfort { weight: weights.range_comfort })
+            .with(ParallelPerfects { weight: weights.parallels })
+            .with(VoiceMotion { weight: weights.motion })
+            .with(ContraryMotion { weight: weights.contrary_motion })
+            .with(ChordalSeventh {
+                resolution: weights.seventh_resolution,
+                preparation: weights.seventh_preparation,
+            })
+            .with(CadentialSixFour { weight: weights.cadential_six_four })
+            .with(Suspension { weight: weights.suspension })
+            .with(MelodicIntervals {
+                augmented: weights.augmented_leap,
+                wide_leap: weights.wide_leap,
+                recovery: weights.leap_recovery,
+            })
+            .with(StrongBeatSixFour { weight: weights.strong_beat_six_four })
+            .with(HarmonicRhythm { weight: weights.harmonic_rhythm })
+            .with(Cadence { weight: weights.cadence })
+    }
+
+    pub fn with(mut self, rule: impl Rule + 'static) -> Self {
+        self.add(rule);
+        self
+    }
+
+    pub fn add(&mut self, rule: impl Rule + 'static) {
+        let rule: Arc<dyn Rule> = Arc::new(rule);
+        match self.rules.iter().position(|r| r.name() == rule.name()) {
+            Some(i) => self.rules[i] = rule,
+            None => self.rules.push(rule),
+        }
+    }
+
+    // Swap the rule called `name` for `rule`, keeping its place (and
+    // dropping any other rule with the new rule's name); false if there is
+    // no such rule
+    pub fn replace(&mut self, name: &str, rule: impl Rule + 'static) -> bool {
+        let Some(at) = self.rules.iter().position(|r| r.name() == name) else {
+            return false;
+        };
+        self.rules[at] = Arc::new(rule);
+        let new_name = self.rules[at].name().to_string();
+        let mut i = 0;
+        self.rules.retain(|r| {
+            let keep = i == at || r.name() != new_name;
+            i += 1;
+            keep
+        });
+        true
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.rules.len();
+        self.rules.retain(|r| r.name() != name);
+        self.rules.len() != before
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.rules.iter().any(|r| r.name() == name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.rules.iter().map(|r| r.name()).collect()
+    }
+
+    // Every rule's outcome, by name
+    pub fn outcomes(&self, context: &RuleContext) -> Vec<(&str, RuleOutcome)> {
+        self.rules.iter().map(|r| (r.name(), r.evaluate(context))).collect()
+    }
+
+    pub fn breakdown(&self, context: &RuleContext) -> ScoreBreakdown {
+        let outcomes = self.rules.iter().map(|r| (r.name().to_string(), r.evaluate(context))).collect();
+        ScoreBreakdown { outcomes }
+    }
+
+    // Total score, and whether any rule reported a violation
+    pub fn evaluate(&self, context: &RuleContext) -> (f32, bool) {
+        let mut score = 0.0;
+        let mut violation = false;
+        for rule in &self.rules {
+            let outcome = rule.evaluate(context);
+            score += outcome.score();
+            violation |= outcome.is_violation();
+        }
+        (score, violation)
+    }
+}
@@ -0,0 +1,93 @@
+use crate::figure::FiguredBassSymbol;
+use crate::realize::{viterbi, Realizer};
+use crate::voicing::Voicing;
+
+// SplitMix64: small, fast and the same on every platform, which is all
+// reproducible sampling needs
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Whether an event of probability `p` happened
+    pub(crate) fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p
+    }
+
+    // An index into `weights`, each picked with probability proportional
+    // to its weight
+    pub(crate) fn weighted(&mut self, weights: &[f64]) -> usize {
+        let mut r = self.next_f64() * weights.iter().sum::<f64>();
+        for (i, &w) in weights.iter().enumerate() {
+            if r < w {
+                return i;
+            }
+            r -= w;
+        }
+        weights.len() - 1
+    }
+
+    // An index into `scores`, each picked with probability proportional
+    // to exp(score / temperature); the first best one at temperature 0
+    fn choose(&mut self, scores: &[f32], temperature: f32) -> usize {
+        let max = scores.iter().copied().fold(f32::MIN, f32::max);
+        if temperature <= 0.0 {
+            return scores.iter().position(|&s| s == max).unwrap_or(0);
+        }
+        let weights: Vec<f64> = scores.iter().map(|&s| (((s - max) / temperature) as f64).exp()).collect();
+        self.weighted(&weights)
+    }
+}
+
+// Sampling backwards through the Viterbi table: the last chord is drawn by
+// the best total score of a realization ending in each candidate, and each
+// chord before it by the best total score through each candidate into the
+// chord already drawn. Every realization can come out, the best ones most
+// often; at temperature 0 this is realize_optimal.
+pub(crate) fn realize_sampled(
+    realizer: &Realizer,
+    symbols: &[FiguredBassSymbol],
+    candidates: &[Vec<Voicing>],
+    seed: u64,
+) -> Vec<Voicing> {
+    let (best, back) = viterbi(realizer, symbols, candidates);
+    let mut rng = Rng::new(seed);
+
+    let Some(last) = best.last() else {
+        return Vec::new();
+    };
+    let temperature = realizer.temperature;
+    let mut c = rng.choose(last, temperature);
+    let mut result = vec![candidates[candidates.len() - 1][c].clone()];
+    for i in (1..candidates.len()).rev() {
+        let scores: Vec<f32> = candidates[i - 1]
+            .iter()
+            .enumerate()
+            .map(|(j, prev)| {
+                let earlier = (i >= 2).then(|| &candidates[i - 2][back[i - 1][j]]);
+                best[i - 1][j] + realizer.score(symbols, i, &candidates[i][c], Some(prev), earlier)
+            })
+            .collect();
+        c = rng.choose(&scores, temperature);
+        result.push(candidates[i - 1][c].clone());
+    }
+    result.reverse();
+    result
+}
@@ -0,0 +1,336 @@
+use crate::figure::{ChordMember, FiguredBassSymbol};
+use crate::key::Key;
+use crate::pitch::{Pitch, PitchClass};
+use crate::range::RangeProfile;
+use crate::voicing::{Voice, Voicing};
+
+// How much each scoring rule counts; all positive, the functions below apply
+// the sign. Set builder style on top of the defaults:
+//
+//   ScoringWeights::default().doubling(6.0).motion(1.0)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoringWeights {
+    pub doubling: f32,              // bonus per voice on the member best doubled
+    pub leading_tone_doubling: f32, // penalty for doubling the leading tone
+    pub spacing: f32,               // penalty per semitone an upper gap exceeds spacing_limit
+    pub spacing_limit: u8,          // widest comfortable gap between upper voices
+    pub range_comfort: f32,         // penalty per semitone from the middle of a range
+    pub parallels: f32,             // penalty for parallel fifths or octaves
+    pub motion: f32,                // penalty per semitone moved by the upper voices
+    pub contrary_motion: f32,       // bonus for outer voices moving in opposite directions
+    pub seventh_resolution: f32,    // penalty for a chordal seventh not stepping down
+    pub seventh_preparation: f32,   // penalty for a chordal seventh not held over
+    pub cadential_six_four: f32,    // penalty for a cadential 6/4 not doubling the bass or resolving
+    pub suspension: f32,            // penalty for a suspension not prepared or not resolved
+    pub augmented_leap: f32,        // penalty for an augmented or diminished melodic interval
+    pub wide_leap: f32,             // penalty for a leap wider than an octave
+    pub leap_recovery: f32,         // penalty for a large leap not followed by a step back
+    pub strong_beat_six_four: f32,  // penalty for a 6/4 on a strong beat that is not cadential
+    pub harmonic_rhythm: f32,       // bonus for a new harmony on a strong beat, penalty for none
+    pub weak_beat_doubling: f32,    // share of the doubling bonus that counts on weak beats
+    pub cadence: f32,               // bonus for a cadence closing a phrase at a fermata
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            doubling: 10.0,
+            leading_tone_doubling: 30.0,
+            spacing: 2.0,
+            spacing_limit: 7,
+            range_comfort: 0.1,
+            parallels: 1000.0,
+            motion: 0.5,
+            contrary_motion: 5.0,
+            seventh_resolution: 50.0,
+            seventh_preparation: 5.0,
+            cadential_six_four: 500.0,
+            suspension: 100.0,
+            augmented_leap: 30.0,
+            wide_leap: 30.0,
+            leap_recovery: 10.0,
+            strong_beat_six_four: 20.0,
+            harmonic_rhythm: 5.0,
+            weak_beat_doubling: 0.5,
+            cadence: 10.0,
+        }
+    }
+}
+
+impl ScoringWeights {
+    pub fn doubling(mut self, weight: f32) -> Self {
+        self.doubling = weight;
+        self
+    }
+
+    pub fn leading_tone_doubling(mut self, weight: f32) -> Self {
+        self.leading_tone_doubling = weight;
+        self
+    }
+
+    pub fn spacing(mut self, weight: f32) -> Self {
+        self.spacing = weight;
+        self
+    }
+
+    pub fn spacing_limit(mut self, semitones: u8) -> Self {
+        self.spacing_limit = semitones;
+        self
+    }
+
+    pub fn range_comfort(mut self, weight: f32) -> Self {
+        self.range_comfort = weight;
+        self
+    }
+
+    pub fn parallels(mut self, weight: f32) -> Self {
+        self.parallels = weight;
+        self
+    }
+
+    pub fn motion(mut self, weight: f32) -> Self {
+        self.motion = weight;
+        self
+    }
+
+    pub fn contrary_motion(mut self, weight: f32) -> Self {
+        self.contrary_motion = weight;
+        self
+    }
+
+    pub fn seventh_resolution(mut self, weight: f32) -> Self {
+        self.seventh_resolution = weight;
+        self
+    }
+
+    pub fn seventh_preparation(mut self, weight: f32) -> Self {
+        self.seventh_preparation = weight;
+        self
+    }
+
+    pub fn cadential_six_four(mut self, weight: f32) -> Self {
+        self.cadential_six_four = weight;
+        self
+    }
+
+    pub fn suspension(mut self, weight: f32) -> Self {
+        self.suspension = weight;
+        self
+    }
+
+    pub fn augmented_leap(mut self, weight: f32) -> Self {
+        self.augmented_leap = weight;
+        self
+    }
+
+    pub fn wide_leap(mut self, weight: f32) -> Self {
+        self.wide_leap = weight;
+        self
+    }
+
+    pub fn leap_recovery(mut self, weight: f32) -> Self {
+        self.leap_recovery = weight;
+        self
+    }
+
+    pub fn strong_beat_six_four(mut self, weight: f32) -> Self {
+        self.strong_beat_six_four = weight;
+        self
+    }
+
+    pub fn harmonic_rhythm(mut self, weight: f32) -> Self {
+        self.harmonic_rhythm = weight;
+        self
+    }
+
+    pub fn weak_beat_doubling(mut self, share: f32) -> Self {
+        self.weak_beat_doubling = share;
+        self
+    }
+
+    pub fn cadence(mut self, weight: f32) -> Self {
+        self.cadence = weight;
+        self
+    }
+}
+
+pub fn score_voicing(
+    voicing: &Voicing,
+    prev: Option<&Voicing>,
+    symbol: &FiguredBassSymbol,
+    ranges: &RangeProfile,
+    weights: &ScoringWeights,
+) -> f32 {
+    let mut score = 0.0;
+
+    // Static scores
+    score += doubling_score(voicing, symbol, weights);
+    score += spacing_score(voicing, weights);
+    score += range_comfort_score(voicing, ranges, weights);
+
+    // Dynamic scores (if there's a previous chord)
+    if let Some(prev_voicing) = prev {
+        score += parallel_motion_penalty(prev_voicing, voicing, weights);
+        score += voice_motion_score(prev_voicing, voicing, weights);
+        score += contrary_motion_bonus(prev_voicing, voicing, weights);
+    }
+
+    score
+}
+
+// Which member to double depends on the inversion: the root in root
+// position and in seventh chords, the soprano's note or the root over a 6
+// chord, the bass of a 6/4. Never the leading tone.
+pub fn doubling_score(voicing: &Voicing, symbol: &FiguredBassSymbol, weights: &ScoringWeights) -> f32 {
+    let voices = voicing.pitch_classes();
+    let count = |pc: PitchClass| voices.iter().filter(|&&v| v == pc).count() as f32;
+
+    let root = symbol.root();
+    let voices_on_preferred = match symbol.member(symbol.bass) {
+        _ if symbol.seventh().is_some() => count(root),
+        Some(ChordMember::Third) => count(root).max(count(voices[0])),
+        Some(ChordMember::Fifth) => count(voices[3]),
+        _ => count(root),
+    };
+
+    // A tripled root is allowed in an incomplete chord, but no better
+    let mut score = voices_on_preferred.min(2.0) * weights.doubling;
+    if count(PitchClass::new(symbol.key.leading_tone())) > 1.0 {
+        score -= weights.leading_tone_doubling;
+    }
+
+    score
+}
+
+pub fn spacing_score(voicing: &Voicing, weights: &ScoringWeights) -> f32 {
+    let mut score = 0.0;
+    let limit = weights.spacing_limit;
+
+    // Penalize large gaps in upper voices
+    let sop_alto_gap = voicing.soprano.midi_number - voicing.alto.midi_number;
+    let alto_tenor_gap = voicing.alto.midi_number - voicing.tenor.midi_number;
+
+    if sop_alto_gap > limit {
+        score -= (sop_alto_gap - limit) as f32 * weights.spacing;
+    }
+    if alto_tenor_gap > limit {
+        score -= (alto_tenor_gap - limit) as f32 * weights.spacing;
+    }
+
+    score
+}
+
+pub fn range_comfort_score(voicing: &Voicing, ranges: &RangeProfile, weights: &ScoringWeights) -> f32 {
+    let mut score = 0.0;
+
+    // Prefer notes in the middle of each range
+    let soprano_mid = ranges.soprano.mid();
+    let alto_mid = ranges.alto.mid();
+    let tenor_mid = ranges.tenor.mid();
+
+    score -= ((voicing.soprano.midi_number as i16 - soprano_mid as i16).abs() as f32) * weights.range_comfort;
+    score -= ((voicing.alto.midi_number as i16 - alto_mid as i16).abs() as f32) * weights.range_comfort;
+    score -= ((voicing.tenor.midi_number as i16 - tenor_mid as i16).abs() as f32) * weights.range_comfort;
+
+    score
+}
+
+// Pairs of voices, upper then lower, moving the same way from one perfect
+// fifth or octave to another, compound intervals and unisons included,
+// with the interval class: 7 for fifths, 0 for octaves. The rule and the
+// diagnostics both go by this, so they cannot disagree.
+pub fn parallel_perfects(v1: &Voicing, v2: &Voicing) -> Vec<(Voice, Voice, i16)> {
+    const VOICES: [Voice; 4] = [Voice::Soprano, Voice::Alto, Voice::Tenor, Voice::Bass];
+    let motion = |voice: Voice| v2.voice(voice).semitones() - v1.voice(voice).semitones();
+    let mut parallels = Vec::new();
+
+    for (a, &upper) in VOICES.iter().enumerate() {
+        for &lower in &VOICES[a + 1..] {
+            let from = (v1.voice(upper).semitones() - v1.voice(lower).semitones()).rem_euclid(12);
+            let to = (v2.voice(upper).semitones() - v2.voice(lower).semitones()).rem_euclid(12);
+            let same_way = motion(upper) != 0 && motion(upper).signum() == motion(lower).signum();
+            if same_way && from == to && (to == 7 || to == 0) {
+                parallels.push((upper, lower, to));
+            }
+        }
+    }
+    parallels
+}
+
+// Whether any pair of voices moves in parallel perfect fifths or octaves
+pub fn has_parallel_perfects(v1: &Voicing, v2: &Voicing) -> bool {
+    !parallel_perfects(v1, v2).is_empty()
+}
+
+// Hidden (direct) fifths or octaves: soprano and bass move the same way into
+// a perfect fifth or octave, with the soprano leaping
+pub fn has_hidden_perfects(v1: &Voicing, v2: &Voicing) -> bool {
+    let sop_motion = v2.soprano.semitones() - v1.soprano.semitones();
+    let bass_motion = v2.bass.semitones() - v1.bass.semitones();
+    let arriving = (v2.soprano.semitones() - v2.bass.semitones()).rem_euclid(12);
+
+    sop_motion != 0
+        && bass_motion != 0
+        && sop_motion.signum() == bass_motion.signum()
+        && sop_motion.abs() > 2
+        && (arriving == 0 || arriving == 7)
+}
+
+// Position of a pitch on the key's scale, counting scale steps from the
+// tonic below middle C; chromatic notes share their letter's step
+fn scale_step(pitch: Pitch, key: &Key) -> i16 {
+    let octave = (pitch.semitones() - key.tonic as i16).div_euclid(12);
+    octave * 7 + key.pitch_class_to_scale_degree(pitch.pitch_class().0) as i16
+}
+
+// Whether a melodic move is augmented or diminished as spelled in `key`:
+// an augmented second (the 6th to the raised 7th in minor), a tritone,
+// a diminished fourth or seventh and so on. Chromatic inflections of one
+// letter (G to G#) are not counted.
+pub fn is_augmented_or_diminished(from: Pitch, to: Pitch, key: &Key) -> bool {
+    let steps = (scale_step(to, key) - scale_step(from, key)).abs() % 7;
+    let semitones = (to.semitones() - from.semitones()).abs() % 12;
+    let usual: &[i16] = match steps {
+        0 => return false,
+        1 => &[1, 2],
+        2 => &[3, 4],
+        3 => &[5],
+        4 => &[7],
+        5 => &[8, 9],
+        _ => &[10, 11],
+    };
+    !usual.contains(&semitones)
+}
+
+pub fn parallel_motion_penalty(v1: &Voicing, v2: &Voicing, weights: &ScoringWeights) -> f32 {
+    if has_parallel_perfects(v1, v2) {
+        -weights.parallels // Huge penalty by default!
+    } else {
+        0.0
+    }
+}
+
+pub fn voice_motion_score(v1: &Voicing, v2: &Voicing, weights: &ScoringWeights) -> f32 {
+    let total_motion =
+        (v2.soprano.semitones() - v1.soprano.semitones()).abs() +
+        (v2.alto.semitones() - v1.alto.semitones()).abs() +
+        (v2.tenor.semitones() - v1.tenor.semitones()).abs();
+
+    // Prefer less motion (common tone retention, stepwise motion)
+    -weights.motion * (total_motion as f32)
+}
+
+pub fn contrary_motion_bonus(v1: &Voicing, v2: &Voicing, weights: &ScoringWeights) -> f32 {
+    let mut score = 0.0;
+
+    let sop_motion = v2.soprano.semitones() - v1.soprano.semitones();
+    let bass_motion = v2.bass.semitones() - v1.bass.semitones();
+
+    // Bonus for contrary motion between outer voices
+    if sop_motion != 0 && bass_motion != 0 && sop_motion.signum() != bass_motion.signum() {
+        score += weights.contrary_motion;
+    }
+
+    score
+}
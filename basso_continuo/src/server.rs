@@ -0,0 +1,147 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+use crate::api::{self, to_json, ApiError, Request, Response};
+
+// The JSON API over HTTP, for web front-ends:
+//
+//   POST /realize   a request as in `api`, answered with its response
+//   POST /analyze
+//
+// Errors are {"error": "..."} with a 4xx status. Every response allows any
+// origin, so a page served from elsewhere can call it. This is plain
+// HTTP/1.1 on std::net, a connection a request and a thread a connection:
+// enough for a trainer on a LAN or behind a proxy, not a public service.
+
+const MAX_BODY: usize = 1 << 20; // bytes
+const MAX_HEADER_LINES: usize = 100;
+const MAX_LINE: u64 = 8192; // bytes in the request line or a header
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// Serve until the process is stopped; Err only if `addr` cannot be bound
+pub fn serve(addr: impl ToSocketAddrs) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        thread::spawn(move || {
+            // A client that goes away mid-request needs no answer
+            let _ = handle(stream);
+        });
+    }
+    Ok(())
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+fn handle(stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+    let (status, body) = match read_request(&mut reader) {
+        Ok(request) => route(&request),
+        Err((status, message)) => (status, error_body(message)),
+    };
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Unprocessable Content",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Content-Type\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        body.len()
+    );
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
+
+// The status and body to answer `request` with
+fn route(request: &HttpRequest) -> (u16, String) {
+    let answer: fn(&Request) -> Result<Response, ApiError> = match request.path.as_str() {
+        "/realize" => api::realize,
+        "/analyze" => api::analyze,
+        _ => return (404, error_body(format!("no such endpoint {}, expected /realize or /analyze", request.path))),
+    };
+    match request.method.as_str() {
+        "OPTIONS" => return (204, String::new()), // a browser asking first
+        "POST" => {}
+        _ => return (405, error_body(format!("{} takes POST", request.path))),
+    }
+    let request: Request = match serde_json::from_slice(&request.body) {
+        Ok(request) => request,
+        Err(e) => return (400, error_body(e.to_string())),
+    };
+    match answer(&request) {
+        Ok(response) => (200, to_json(&response)),
+        Err(e) => (422, error_body(e.message)),
+    }
+}
+
+fn error_body(message: impl Into<String>) -> String {
+    let error = serde_json::Value::String(message.into());
+    format!("{{\"error\":{}}}", error)
+}
+
+// The request line, the headers as far as Content-Length, and the body
+fn read_request(reader: &mut impl BufRead) -> Result<HttpRequest, (u16, String)> {
+    let bad = |message: &str| (400, message.to_string());
+    let mut line = String::new();
+    read_line(reader, &mut line).map_err(|_| bad("cannot read the request"))?;
+    let mut words = line.split_whitespace();
+    let (Some(method), Some(target)) = (words.next(), words.next()) else {
+        return Err(bad("expected a request line such as POST /realize HTTP/1.1"));
+    };
+    let method = method.to_string();
+    let path = target.split('?').next().unwrap_or(target).to_string();
+
+    let mut length = 0;
+    for _ in 0..MAX_HEADER_LINES {
+        line.clear();
+        read_line(reader, &mut line).map_err(|_| bad("cannot read the headers"))?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            let body = read_body(reader, length)?;
+            return Ok(HttpRequest { method, path, body });
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse().map_err(|_| bad("invalid Content-Length"))?;
+        }
+    }
+    Err(bad("too many headers"))
+}
+
+// A line of at most MAX_LINE bytes
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<()> {
+    let read = reader.take(MAX_LINE).read_line(line)?;
+    if read as u64 == MAX_LINE && !line.ends_with('\n') {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+    }
+    Ok(())
+}
+
+fn read_body(reader: &mut impl BufRead, length: usize) -> Result<Vec<u8>, (u16, String)> {
+    if length > MAX_BODY {
+        return Err((413, format!("the body is over {} bytes", MAX_BODY)));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|_| (400, "the body is shorter than its Content-Length".to_string()))?;
+    Ok(body)
+}
@@ -0,0 +1,325 @@
+use std::fmt;
+use std::sync::Arc;
+
+// SoundFont 2 instruments for the synth, so a realization can be heard
+// on a harpsichord or organ rather than sine waves, written to a file by
+// `render` or sent to the audio device by `play`. Enough of the format
+// is read to play a preset's samples at the right pitch: key and velocity
+// ranges, root key and tuning, loops, attenuation and release. Filters,
+// modulators and the rest of the envelopes are left out, so a preset that
+// leans on them (a swelling pad, a filtered pluck) sounds flatter than in
+// a full SoundFont synth.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundFontError {
+    pub message: String,
+}
+
+impl SoundFontError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl fmt::Display for SoundFontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SoundFontError {}
+
+#[derive(Debug, Clone)]
+pub struct SoundFont {
+    pub name: String,
+    pub presets: Vec<Preset>, // by bank, then program
+}
+
+// An instrument as the file's presets give it, ready to play
+#[derive(Clone)]
+pub struct Preset {
+    pub name: String,
+    pub bank: u16,
+    pub program: u16,
+    zones: Vec<Zone>,
+    samples: Arc<Vec<f32>>, // the whole file's, shared between presets
+}
+
+impl fmt::Debug for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Preset({:?}, {}:{}, {} zones)", self.name, self.bank, self.program, self.zones.len())
+    }
+}
+
+// A sample and the notes it plays
+#[derive(Debug, Clone)]
+struct Zone {
+    keys: (u8, u8),
+    velocities: (u8, u8),
+    start: usize,
+    end: usize,
+    loop_start: usize,
+    loop_end: usize,
+    looped: bool,
+    root: u8,
+    tune: f32, // semitones
+    sample_rate: u32,
+    gain: f32,
+    release: f32, // seconds
+    stereo: bool, // one side of a pair, sounding with the other
+}
+
+const ATTACK: f32 = 0.005; // seconds
+const LEVEL: f32 = 0.2; // a voice's peak, as the synth's own
+
+// Generators: the settings a zone can give
+const START_OFFSET: usize = 0;
+const END_OFFSET: usize = 1;
+const LOOP_START_OFFSET: usize = 2;
+const LOOP_END_OFFSET: usize = 3;
+const START_COARSE_OFFSET: usize = 4;
+const END_COARSE_OFFSET: usize = 12;
+const RELEASE: usize = 38;
+const INSTRUMENT: usize = 41;
+const KEY_RANGE: usize = 43;
+const VELOCITY_RANGE: usize = 44;
+const LOOP_START_COARSE_OFFSET: usize = 45;
+const ATTENUATION: usize = 48;
+const LOOP_END_COARSE_OFFSET: usize = 50;
+const COARSE_TUNE: usize = 51;
+const FINE_TUNE: usize = 52;
+const SAMPLE: usize = 53;
+const SAMPLE_MODES: usize = 54;
+const ROOT_KEY: usize = 58;
+const GENERATORS: usize = 61;
+
+type Generators = [Option<u16>; GENERATORS];
+
+// A RIFF chunk's id and body
+type Chunk<'a> = ([u8; 4], &'a [u8]);
+
+impl SoundFont {
+    // The .sf2 file in `bytes`
+    pub fn read(bytes: &[u8]) -> Result<Self, SoundFontError> {
+        if bytes.get(..4) != Some(b"RIFF") || bytes.get(8..12) != Some(b"sfbk") {
+            return Err(SoundFontError::new("not a SoundFont: expected a RIFF sfbk file"));
+        }
+        let (_, body) = chunk(bytes, 0)?;
+        let mut name = String::new();
+        let mut samples = Vec::new();
+        let mut hydra = Vec::new();
+        for (id, list) in chunks(&body[4..])? {
+            if id != *b"LIST" || list.len() < 4 {
+                continue;
+            }
+            for (id, data) in chunks(&list[4..])? {
+                match &list[..4] {
+                    b"INFO" if id == *b"INAM" => name = text(data),
+                    b"sdta" if id == *b"smpl" => {
+                        samples = data.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0).collect()
+                    }
+                    b"pdta" => hydra.push((id, data)),
+                    _ => {}
+                }
+            }
+        }
+        let part = |id: &[u8; 4], size: usize| -> Result<Vec<&[u8]>, SoundFontError> {
+            let data = hydra.iter().find(|(found, _)| found == id).map(|&(_, data)| data);
+            let data = data.ok_or_else(|| SoundFontError::new(format!("the SoundFont has no {} chunk", text(id))))?;
+            Ok(data.chunks_exact(size).collect())
+        };
+        let (phdr, pbag, pgen) = (part(b"phdr", 38)?, part(b"pbag", 4)?, part(b"pgen", 4)?);
+        let (inst, ibag, igen, shdr) = (part(b"inst", 22)?, part(b"ibag", 4)?, part(b"igen", 4)?, part(b"shdr", 46)?);
+        let samples = Arc::new(samples);
+
+        // Each list ends with a record marking the end of the one before
+        let zones = |bags: &[&[u8]], gens: &[&[u8]], first: usize, last: usize| -> Vec<Generators> {
+            (first..last.min(bags.len().saturating_sub(1)))
+                .map(|bag| {
+                    let mut generators = [None; GENERATORS];
+                    let (from, to) = (word(bags[bag], 0) as usize, word(bags[bag + 1], 0) as usize);
+                    for generator in gens.get(from..to.min(gens.len())).unwrap_or(&[]) {
+                        if let Some(slot) = generators.get_mut(word(generator, 0) as usize) {
+                            *slot = Some(word(generator, 2));
+                        }
+                    }
+                    generators
+                })
+                .collect()
+        };
+        let mut presets = Vec::new();
+        for pair in phdr.windows(2) {
+            let (header, next) = (pair[0], pair[1]);
+            let (global, local) = split_global(zones(&pbag, &pgen, word(header, 24) as usize, word(next, 24) as usize), INSTRUMENT);
+            let mut preset_zones = Vec::new();
+            for preset_zone in local {
+                let generators = merge(&global, &preset_zone);
+                let Some(instrument) = generators[INSTRUMENT].map(usize::from) else {
+                    continue;
+                };
+                let (Some(first), Some(next)) = (inst.get(instrument), inst.get(instrument + 1)) else {
+                    return Err(SoundFontError::new(format!("preset {:?} names a missing instrument", text(&header[..20]))));
+                };
+                let (inst_global, inst_local) =
+                    split_global(zones(&ibag, &igen, word(first, 20) as usize, word(next, 20) as usize), SAMPLE);
+                for instrument_zone in inst_local {
+                    let zone = Zone::new(&merge(&inst_global, &instrument_zone), &generators, &shdr, samples.len());
+                    preset_zones.extend(zone);
+                }
+            }
+            presets.push(Preset {
+                name: text(&header[..20]),
+                bank: word(header, 22),
+                program: word(header, 20),
+                zones: preset_zones,
+                samples: samples.clone(),
+            });
+        }
+        presets.sort_by_key(|preset| (preset.bank, preset.program));
+        if presets.is_empty() {
+            return Err(SoundFontError::new("the SoundFont has no presets"));
+        }
+        Ok(SoundFont { name, presets })
+    }
+
+    // The preset named `query` (any case), or at "bank:program"
+    pub fn preset(&self, query: &str) -> Result<&Preset, SoundFontError> {
+        let number = query.split_once(':').and_then(|(bank, program)| Some((bank.parse().ok()?, program.parse().ok()?)));
+        let found = self.presets.iter().find(|preset| match number {
+            Some(number) => (preset.bank, preset.program) == number,
+            None => preset.name.eq_ignore_ascii_case(query.trim()),
+        });
+        found.ok_or_else(|| {
+            let names: Vec<String> = self.presets.iter().map(|p| format!("{} ({}:{})", p.name, p.bank, p.program)).collect();
+            SoundFontError::new(format!("no preset {:?}, expected one of {}", query, names.join(", ")))
+        })
+    }
+}
+
+impl Preset {
+    // Mix `note`, `detune` semitones off its pitch, held for `length`
+    // samples into the start of `samples`, its release running on after
+    pub(crate) fn add_note(&self, samples: &mut [f32], sample_rate: u32, note: u8, detune: f32, velocity: u8, length: usize) {
+        let rate = sample_rate as f32;
+        let zones = self.zones.iter().filter(|zone| {
+            (zone.keys.0..=zone.keys.1).contains(&note) && (zone.velocities.0..=zone.velocities.1).contains(&velocity)
+        });
+        for zone in zones {
+            let semitones = note as f64 - zone.root as f64 + (zone.tune + detune) as f64;
+            let step = 2f64.powf(semitones / 12.0) * zone.sample_rate as f64 / rate as f64;
+            let (attack, release) = (rate * ATTACK, rate * zone.release);
+            let gain = LEVEL * zone.gain * if zone.stereo { 0.5 } else { 1.0 };
+            let mut position = zone.start as f64;
+            for (n, sample) in samples.iter_mut().take(length + release as usize).enumerate() {
+                let index = position as usize;
+                let next = if zone.looped && index + 1 >= zone.loop_end { zone.loop_start } else { index + 1 };
+                if next >= zone.end {
+                    break;
+                }
+                let fraction = (position - index as f64) as f32;
+                let value = self.samples[index] * (1.0 - fraction) + self.samples[next] * fraction;
+                let envelope = if n < length { (n as f32 / attack).min(1.0) } else { 1.0 - (n - length) as f32 / release };
+                *sample += gain * envelope * value;
+                position += step;
+                while zone.looped && position >= zone.loop_end as f64 {
+                    position -= (zone.loop_end - zone.loop_start) as f64;
+                }
+            }
+        }
+    }
+}
+
+impl Zone {
+    // The zone an instrument's `generators` give within a preset zone
+    // with `preset`'s, if it plays a sample there is
+    fn new(generators: &Generators, preset: &Generators, headers: &[&[u8]], sample_count: usize) -> Option<Self> {
+        let header = headers.get(generators[SAMPLE]? as usize)?;
+        if u16::from_le_bytes([header[44], header[45]]) & 0x8000 != 0 {
+            return None; // in ROM, not in the file
+        }
+        let signed = |generators: &Generators, n: usize| generators[n].unwrap_or(0) as i16 as i64;
+        let address = |at: usize, fine: usize, coarse: usize| {
+            let base = u32::from_le_bytes(header[at..at + 4].try_into().unwrap()) as i64;
+            (base + signed(generators, fine) + signed(generators, coarse) * 32768).clamp(0, sample_count as i64) as usize
+        };
+        let (start, end) = (address(20, START_OFFSET, START_COARSE_OFFSET), address(24, END_OFFSET, END_COARSE_OFFSET));
+        let loop_start = address(28, LOOP_START_OFFSET, LOOP_START_COARSE_OFFSET);
+        let loop_end = address(32, LOOP_END_OFFSET, LOOP_END_COARSE_OFFSET);
+        let range = |n: usize| {
+            let within = |generators: &Generators| generators[n].map_or((0, 127), |r| ((r & 0xff) as u8, (r >> 8) as u8));
+            let (a, b) = (within(generators), within(preset));
+            (a.0.max(b.0), a.1.min(b.1))
+        };
+        // The preset's tuning, attenuation and release add to the
+        // instrument's
+        let sum = |n: usize| signed(generators, n) + signed(preset, n);
+        let root = match generators[ROOT_KEY].map(|key| key as i16) {
+            Some(key @ 0..=127) => key as u8,
+            _ => Some(header[40]).filter(|&key| key <= 127).unwrap_or(60),
+        };
+        let correction = header[41] as i8 as f32 / 100.0;
+        let release = generators[RELEASE].map_or(-12000, |tc| tc as i16 as i64) + signed(preset, RELEASE);
+        Some(Zone {
+            keys: range(KEY_RANGE),
+            velocities: range(VELOCITY_RANGE),
+            start,
+            end,
+            loop_start,
+            loop_end,
+            looped: matches!(generators[SAMPLE_MODES], Some(1) | Some(3)) && start <= loop_start && loop_start < loop_end && loop_end <= end,
+            root,
+            tune: sum(COARSE_TUNE) as f32 + sum(FINE_TUNE) as f32 / 100.0 + correction,
+            sample_rate: u32::from_le_bytes(header[36..40].try_into().unwrap()).max(1),
+            gain: 10f32.powf(-(sum(ATTENUATION).max(0) as f32) / 200.0),
+            release: 2f32.powf(release as f32 / 1200.0).clamp(0.02, 2.0),
+            stereo: matches!(u16::from_le_bytes([header[44], header[45]]), 2 | 4),
+        })
+    }
+}
+
+// The global zone, whose settings the others start from, and the rest: the
+// global zone is the first, if it lacks the generator that ends the others
+fn split_global(mut zones: Vec<Generators>, last: usize) -> (Generators, Vec<Generators>) {
+    match zones.first() {
+        Some(first) if zones.len() > 1 && first[last].is_none() => {
+            let global = zones.remove(0);
+            (global, zones)
+        }
+        _ => ([None; GENERATORS], zones),
+    }
+}
+
+fn merge(global: &Generators, local: &Generators) -> Generators {
+    std::array::from_fn(|n| local[n].or(global[n]))
+}
+
+// The chunk at `at`
+fn chunk(bytes: &[u8], at: usize) -> Result<Chunk<'_>, SoundFontError> {
+    let truncated = || SoundFontError::new("the SoundFont is cut short");
+    let header = bytes.get(at..at + 8).ok_or_else(truncated)?;
+    let size = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+    let body = bytes.get(at + 8..at + 8 + size).ok_or_else(truncated)?;
+    Ok((header[..4].try_into().unwrap(), body))
+}
+
+// The chunks one after another in `bytes`, each padded to an even length
+fn chunks(bytes: &[u8]) -> Result<Vec<Chunk<'_>>, SoundFontError> {
+    let mut chunks = Vec::new();
+    let mut at = 0;
+    while at + 8 <= bytes.len() {
+        let (id, body) = chunk(bytes, at)?;
+        chunks.push((id, body));
+        at += 8 + body.len() + body.len() % 2;
+    }
+    Ok(chunks)
+}
+
+fn word(record: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([record[at], record[at + 1]])
+}
+
+// A name, up to its terminating zero
+fn text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
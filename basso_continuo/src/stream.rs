@@ -0,0 +1,102 @@
+use crate::figure::FiguredBassSymbol;
+use crate::realize::{Pin, RealizationError, Realizer};
+use crate::voicing::Voicing;
+
+// Realizing a bass as it arrives, a chord at a time, for playing along
+// live. Each chord is decided once `lookahead` more bass notes have come
+// in, by the full search over those chords and the two decided before
+// them, and is never changed after: 0 answers every note at once, more
+// trades delay for better voice leading.
+#[derive(Debug, Clone)]
+pub struct StreamingRealizer {
+    realizer: Realizer,
+    lookahead: usize,
+    symbols: Vec<FiguredBassSymbol>,
+    voicings: Vec<Voicing>, // one for each decided chord, from the first
+}
+
+impl StreamingRealizer {
+    pub fn new(realizer: Realizer, lookahead: usize) -> Self {
+        Self { realizer, lookahead, symbols: Vec::new(), voicings: Vec::new() }
+    }
+
+    // Take the next bass symbol; the voicing of the chord this decides, if
+    // it decides one. A chord that leaves no realization (this one, or one
+    // still waiting) is dropped with the error, so the stream can go on
+    // without it.
+    // The symbol follows on in the bar from the one before
+    pub fn push(&mut self, mut symbol: FiguredBassSymbol) -> Result<Option<Voicing>, RealizationError> {
+        if let Some(last) = self.symbols.last() {
+            symbol.offset = (last.offset + last.duration).within(symbol.meter.bar());
+        }
+        self.symbols.push(symbol);
+        if self.symbols.len() - self.voicings.len() <= self.lookahead {
+            return Ok(None);
+        }
+        let voicing = match self.search() {
+            Ok(mut voicings) => voicings.swap_remove(0),
+            Err(e) => {
+                let chord = match &e {
+                    RealizationError::NoVoicings { chord, .. } | RealizationError::Forbidden { chord, .. } => *chord,
+                    RealizationError::Unsatisfiable { pin, .. } => pin.chord,
+                    _ => self.symbols.len() - 1,
+                };
+                self.symbols.remove(chord.max(self.voicings.len()));
+                return Err(e);
+            }
+        };
+        self.voicings.push(voicing.clone());
+        Ok(Some(voicing))
+    }
+
+    // The bass has ended: decide the chords still waiting
+    pub fn finish(&mut self) -> Result<Vec<Voicing>, RealizationError> {
+        if self.voicings.len() == self.symbols.len() {
+            return Ok(Vec::new());
+        }
+        let rest = self.search()?;
+        self.voicings.extend(rest.iter().cloned());
+        Ok(rest)
+    }
+
+    pub fn symbols(&self) -> &[FiguredBassSymbol] {
+        &self.symbols
+    }
+
+    // The voicings decided so far
+    pub fn voicings(&self) -> &[Voicing] {
+        &self.voicings
+    }
+
+    // The best voicings of the undecided chords, following the decided
+    // ones. Pins count chords from the start of the stream, so they are
+    // moved into the window searched, and so are chords named in errors.
+    fn search(&self) -> Result<Vec<Voicing>, RealizationError> {
+        let start = self.voicings.len().saturating_sub(2);
+        let pins = self
+            .realizer
+            .pins
+            .iter()
+            .filter(|pin| pin.chord >= start && pin.chord < self.symbols.len())
+            .map(|&pin| Pin { chord: pin.chord - start, ..pin })
+            .collect();
+        let window = Realizer { pins, ..self.realizer.clone() };
+        let previous = &self.voicings[start..];
+        let locked = vec![true; previous.len()];
+        let mut voicings = window.reoptimize(&self.symbols[start..], previous, &locked).map_err(|e| shifted(e, start))?;
+        Ok(voicings.split_off(previous.len()))
+    }
+}
+
+fn shifted(error: RealizationError, by: usize) -> RealizationError {
+    match error {
+        RealizationError::NoVoicings { chord, bass, eliminated_by } => {
+            RealizationError::NoVoicings { chord: chord + by, bass, eliminated_by }
+        }
+        RealizationError::Unsatisfiable { pin, problem } => {
+            RealizationError::Unsatisfiable { pin: Pin { chord: pin.chord + by, ..pin }, problem }
+        }
+        RealizationError::Forbidden { chord, rules } => RealizationError::Forbidden { chord: chord + by, rules },
+        error => error,
+    }
+}
@@ -0,0 +1,225 @@
+use std::f32::consts::TAU;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::figure::FiguredBassSymbol;
+use crate::pitch::Pitch;
+#[cfg(feature = "soundfont")]
+use crate::soundfont::Preset;
+use crate::temperament::Temperament;
+use crate::voicing::{Voice, Voicing};
+
+// A small additive synthesizer, to hear a realization without any MIDI
+// set up: each chord sounds for its length at the tempo, a voice keeping its
+// note into a held chord holds it rather than striking it again, and the
+// whole is written as a WAV file (mono, 16-bit). With the soundfont
+// feature a SoundFont preset can sound in place of the synth's own tones.
+// Notes are tuned in the temperament, a just one over each chord's key,
+// with A4 at the reference pitch: 415 Hz for much baroque music, 440 for
+// modern, and 430 or 442 heard on period and modern instruments too.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Timbre {
+    // Pure tones, where the voice leading is easiest to follow
+    Sine,
+    // Flue pipes at 8', 4', 2 2/3' and 2': the fundamental and its first
+    // partials, each softer than the last
+    #[default]
+    Organ,
+}
+
+impl Timbre {
+    // The partials, as multiples of the fundamental, and their levels
+    fn partials(&self) -> &'static [(f32, f32)] {
+        match self {
+            Timbre::Sine => &[(1.0, 1.0)],
+            Timbre::Organ => &[(1.0, 1.0), (2.0, 0.5), (3.0, 0.25), (4.0, 0.2)],
+        }
+    }
+}
+
+impl fmt::Display for Timbre {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Timbre::Sine => "sine",
+            Timbre::Organ => "organ",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Timbre {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sine" => Ok(Timbre::Sine),
+            "organ" => Ok(Timbre::Organ),
+            _ => Err(format!("unknown timbre {:?}, expected sine or organ", s)),
+        }
+    }
+}
+
+const VOICES: [Voice; 4] = [Voice::Soprano, Voice::Alto, Voice::Tenor, Voice::Bass];
+
+const ATTACK: f32 = 0.01; // seconds
+const RELEASE: f32 = 0.08;
+const LEVEL: f32 = 0.2; // each voice's peak, leaving room for four
+#[cfg(feature = "soundfont")]
+const VELOCITY: u8 = 80; // for choosing a preset's samples
+
+#[derive(Debug, Clone)]
+pub struct Synth {
+    pub timbre: Timbre,
+    pub tempo: f32, // quarter-note beats a minute
+    pub sample_rate: u32,
+    pub temperament: Temperament,
+    pub reference: f32, // A4, in hertz
+    #[cfg(feature = "soundfont")]
+    pub preset: Option<Preset>, // played in place of the timbre
+}
+
+impl Default for Synth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Synth {
+    pub fn new() -> Self {
+        Self {
+            timbre: Timbre::default(),
+            tempo: 72.0,
+            sample_rate: 44100,
+            temperament: Temperament::Equal,
+            reference: 440.0,
+            #[cfg(feature = "soundfont")]
+            preset: None,
+        }
+    }
+
+    pub fn timbre(mut self, timbre: Timbre) -> Self {
+        self.timbre = timbre;
+        self
+    }
+
+    pub fn tempo(mut self, tempo: f32) -> Self {
+        self.tempo = tempo;
+        self
+    }
+
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    pub fn temperament(mut self, temperament: Temperament) -> Self {
+        self.temperament = temperament;
+        self
+    }
+
+    pub fn reference(mut self, reference: f32) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    #[cfg(feature = "soundfont")]
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.preset = Some(preset);
+        self
+    }
+
+    // When each chord starts, in seconds, and then when the last ends
+    pub fn times(&self, symbols: &[FiguredBassSymbol]) -> Vec<f32> {
+        let mut times = vec![0.0];
+        for symbol in symbols {
+            times.push(times[times.len() - 1] + symbol.duration.beats() * 60.0 / self.tempo);
+        }
+        times
+    }
+
+    // The realization as samples between -1 and 1, the last chord dying
+    // away after it ends
+    pub fn render(&self, symbols: &[FiguredBassSymbol], realization: &[Voicing]) -> Vec<f32> {
+        let rate = self.sample_rate as f32;
+        let chords = realization.len().min(symbols.len());
+        let starts: Vec<usize> = self.times(&symbols[..chords]).iter().map(|time| (time * rate).round() as usize).collect();
+        let mut samples = vec![0.0; starts[chords] + (rate * RELEASE) as usize];
+        for voice in VOICES {
+            let mut start = 0;
+            while start < chords {
+                let pitch = realization[start].voice(voice);
+                let mut end = start + 1;
+                while end < chords && symbols[end].held && realization[end].voice(voice) == pitch {
+                    end += 1;
+                }
+                let tonic = symbols[start].key.tonic;
+                self.add_note(&mut samples[starts[start]..], pitch.midi_number, tonic, starts[end] - starts[start]);
+                start = end;
+            }
+        }
+        samples
+    }
+
+    // The same as a WAV file
+    pub fn render_wav(&self, symbols: &[FiguredBassSymbol], realization: &[Voicing]) -> Vec<u8> {
+        encode_wav(&self.render(symbols, realization), self.sample_rate)
+    }
+
+    // Mix a note of `length` samples into the start of `samples`, its
+    // release running on after
+    fn add_note(&self, samples: &mut [f32], note: u8, tonic: u8, length: usize) {
+        let detune = self.temperament.deviation(note, tonic);
+        #[cfg(feature = "soundfont")]
+        if let Some(preset) = &self.preset {
+            // A preset's samples are tuned to A4 at 440
+            let detune = detune + 12.0 * (self.reference / 440.0).log2();
+            return preset.add_note(samples, self.sample_rate, note, detune, VELOCITY, length);
+        }
+        self.add_tone(samples, Pitch::new(note).frequency(self.reference) * 2f32.powf(detune / 12.0), length)
+    }
+
+    fn add_tone(&self, samples: &mut [f32], frequency: f32, length: usize) {
+        let rate = self.sample_rate as f32;
+        let (attack, release) = (rate * ATTACK, rate * RELEASE);
+        // Partials above half the sample rate would fold back as noise
+        let partials: Vec<(f32, f32)> =
+            self.timbre.partials().iter().filter(|&&(multiple, _)| frequency * multiple < rate / 2.0).copied().collect();
+        let total: f32 = self.timbre.partials().iter().map(|&(_, level)| level).sum();
+        let sounding = length + release as usize;
+        for (n, sample) in samples.iter_mut().take(sounding).enumerate() {
+            let envelope = if n < length {
+                (n as f32 / attack).min(1.0)
+            } else {
+                1.0 - (n - length) as f32 / release
+            };
+            let t = n as f32 / rate;
+            let tone: f32 = partials.iter().map(|&(multiple, level)| level * (TAU * frequency * multiple * t).sin()).sum();
+            *sample += LEVEL * envelope * tone / total;
+        }
+    }
+}
+
+// Samples between -1 and 1 as a mono 16-bit PCM WAV file
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_length = samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_length as usize);
+    bytes.extend(b"RIFF");
+    bytes.extend((36 + data_length).to_le_bytes());
+    bytes.extend(b"WAVE");
+    bytes.extend(b"fmt ");
+    bytes.extend(16u32.to_le_bytes());
+    bytes.extend(1u16.to_le_bytes()); // PCM
+    bytes.extend(1u16.to_le_bytes()); // one channel
+    bytes.extend(sample_rate.to_le_bytes());
+    bytes.extend((sample_rate * 2).to_le_bytes()); // bytes a second
+    bytes.extend(2u16.to_le_bytes()); // bytes a frame
+    bytes.extend(16u16.to_le_bytes()); // bits a sample
+    bytes.extend(b"data");
+    bytes.extend(data_length.to_le_bytes());
+    for sample in samples {
+        bytes.extend(((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes());
+    }
+    bytes
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Tunings for the synth. Continuo was played on keyboards tuned long
+// before equal temperament: meantone, with its pure thirds and its wolf
+// between G# and Eb, and the well temperaments that let every key be
+// played, each sounding its own. Each is given as cents above C; A4 keeps
+// its pitch in all of them.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Temperament {
+    #[default]
+    Equal,
+    // Fifths a quarter of a syntonic comma narrow, so four of them make a
+    // pure major third; Eb to G#, on C
+    QuarterCommaMeantone,
+    // Werckmeister's "correct temperament No. 1" (III): C-G-D-A and B-F#
+    // a quarter of a Pythagorean comma narrow, the rest pure
+    WerckmeisterIII,
+    // Five-limit ratios over the tonic of the music
+    Just,
+}
+
+// Cents above C
+const WERCKMEISTER_III: [f32; 12] =
+    [0.0, 90.225, 192.18, 294.135, 390.225, 498.045, 588.27, 696.09, 792.18, 888.27, 996.09, 1092.18];
+
+// Ratios over the tonic, a semitone apart
+const JUST: [(u32, u32); 12] =
+    [(1, 1), (16, 15), (9, 8), (6, 5), (5, 4), (4, 3), (45, 32), (3, 2), (8, 5), (5, 3), (9, 5), (15, 8)];
+
+// The meantone fifth, in cents: a pure fifth less a quarter of the
+// syntonic comma (81/80)
+fn meantone_fifth() -> f32 {
+    1200.0 * (1.5f32.log2() - (81.0f32 / 80.0).log2() / 4.0)
+}
+
+impl Temperament {
+    // Cents above C of each pitch class, `tonic` being the key's
+    pub fn cents(&self, tonic: u8) -> [f32; 12] {
+        match self {
+            Temperament::Equal => std::array::from_fn(|pc| pc as f32 * 100.0),
+            Temperament::QuarterCommaMeantone => {
+                let mut cents = [0.0; 12];
+                // Three fifths down from C to Eb, eight up to G#
+                for fifths in -3i32..=8 {
+                    let pc = (fifths * 7).rem_euclid(12) as usize;
+                    cents[pc] = (fifths as f32 * meantone_fifth()).rem_euclid(1200.0);
+                }
+                cents
+            }
+            Temperament::WerckmeisterIII => WERCKMEISTER_III,
+            Temperament::Just => std::array::from_fn(|pc| {
+                let above = (pc as i32 - tonic as i32).rem_euclid(12) as usize;
+                let (numerator, denominator) = JUST[above];
+                (tonic as f32 * 100.0 + 1200.0 * (numerator as f32 / denominator as f32).log2()).rem_euclid(1200.0)
+            }),
+        }
+    }
+
+    // How far `note` lies from its equal-tempered pitch, in semitones,
+    // with A4 where equal temperament has it
+    pub fn deviation(&self, note: u8, tonic: u8) -> f32 {
+        let cents = self.cents(tonic);
+        let pc = note as usize % 12;
+        let from_a = |pc: usize| cents[pc] - pc as f32 * 100.0;
+        // A pitch class tuned across C (a just B over C#, say) has wrapped
+        // to the octave's other end
+        let deviation = (from_a(pc) - from_a(9)) / 100.0;
+        deviation - 12.0 * (deviation / 12.0).round()
+    }
+}
+
+impl fmt::Display for Temperament {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Temperament::Equal => "equal",
+            Temperament::QuarterCommaMeantone => "meantone",
+            Temperament::WerckmeisterIII => "werckmeister",
+            Temperament::Just => "just",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Temperament {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "equal" | "12-tet" => Ok(Temperament::Equal),
+            "meantone" | "quarter-comma-meantone" => Ok(Temperament::QuarterCommaMeantone),
+            "werckmeister" | "werckmeister-iii" => Ok(Temperament::WerckmeisterIII),
+            "just" => Ok(Temperament::Just),
+            _ => Err(format!("unknown temperament {:?}, expected equal, meantone, werckmeister or just", s)),
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::figure::{ChordMember, FiguredBassSymbol};
+use crate::pitch::{Pitch, PitchClass};
+use crate::range::RangeProfile;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Voicing {
+    pub soprano: Pitch,
+    pub alto: Pitch,
+    pub tenor: Pitch,
+    pub bass: Pitch,
+}
+
+impl fmt::Display for Voicing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "S:{} A:{} T:{} B:{}",
+               self.soprano.name(), self.alto.name(),
+               self.tenor.name(), self.bass.name())
+    }
+}
+
+// One of the four parts, top to bottom
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Voice {
+    Soprano,
+    Alto,
+    Tenor,
+    Bass,
+}
+
+impl fmt::Display for Voice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Voice::Soprano => "soprano",
+            Voice::Alto => "alto",
+            Voice::Tenor => "tenor",
+            Voice::Bass => "bass",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// The voice's name or its initial
+impl FromStr for Voice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "soprano" | "s" => Ok(Voice::Soprano),
+            "alto" | "a" => Ok(Voice::Alto),
+            "tenor" | "t" => Ok(Voice::Tenor),
+            "bass" | "b" => Ok(Voice::Bass),
+            _ => Err(format!("unknown voice {:?}, expected soprano, alto, tenor or bass", s)),
+        }
+    }
+}
+
+impl Voicing {
+    pub fn voice(&self, voice: Voice) -> Pitch {
+        match voice {
+            Voice::Soprano => self.soprano,
+            Voice::Alto => self.alto,
+            Voice::Tenor => self.tenor,
+            Voice::Bass => self.bass,
+        }
+    }
+
+    // Soprano, alto and tenor, the voices the realization chooses
+    pub fn upper(&self) -> [Pitch; 3] {
+        [self.soprano, self.alto, self.tenor]
+    }
+
+    pub fn pitch_classes(&self) -> [PitchClass; 4] {
+        [self.soprano, self.alto, self.tenor, self.bass].map(|p| p.pitch_class())
+    }
+
+    // Like Display, with the notes spelled as members of `symbol`
+    pub fn spelled(&self, symbol: &FiguredBassSymbol) -> String {
+        format!("S:{} A:{} T:{} B:{}",
+                symbol.spell(self.soprano), symbol.spell(self.alto),
+                symbol.spell(self.tenor), symbol.spell(self.bass))
+    }
+}
+
+// Which chord tones a voicing may leave out; the root takes the place of a
+// missing tone. Four voices hold a seventh chord's four tones only if none
+// is doubled, so by default its fifth may go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Completeness {
+    // Every chord tone present
+    Complete,
+    #[default]
+    OmitFifthInSevenths,
+    // Any chord may drop its fifth: a seventh chord doubles the root, a
+    // triad triples it, as in a final tonic
+    OmitFifth,
+}
+
+impl Completeness {
+    // Whether a voicing of `symbol` may leave out pitch class `pc`
+    pub fn may_omit(&self, symbol: &FiguredBassSymbol, pc: PitchClass) -> bool {
+        let fifth = symbol.pitch_class_of(ChordMember::Fifth) == Some(pc);
+        match self {
+            Completeness::Complete => false,
+            Completeness::OmitFifthInSevenths => fifth && symbol.seventh().is_some(),
+            Completeness::OmitFifth => fifth,
+        }
+    }
+}
+
+impl fmt::Display for Completeness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Completeness::Complete => "complete",
+            Completeness::OmitFifthInSevenths => "sevenths",
+            Completeness::OmitFifth => "omit-fifth",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Completeness {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "complete" => Ok(Completeness::Complete),
+            "sevenths" | "omit-fifth-in-sevenths" => Ok(Completeness::OmitFifthInSevenths),
+            "omit-fifth" | "incomplete" => Ok(Completeness::OmitFifth),
+            _ => Err(format!("unknown completeness {:?}, expected complete, sevenths or omit-fifth", s)),
+        }
+    }
+}
+
+pub fn generate_voicings(symbol: &FiguredBassSymbol, ranges: &RangeProfile, completeness: Completeness) -> Vec<Voicing> {
+    let mut voicings = Vec::new();
+    let bass = symbol.bass;
+
+    // Get chord tones in various octaves for upper voices
+    let soprano_notes = get_notes_in_range(&symbol.chord_tones, ranges.soprano.min, ranges.soprano.max);
+    let alto_notes = get_notes_in_range(&symbol.chord_tones, ranges.alto.min, ranges.alto.max);
+    let tenor_notes = get_notes_in_range(&symbol.chord_tones, ranges.tenor.min, ranges.tenor.max);
+
+    // Generate all combinations
+    for &soprano in &soprano_notes {
+        for &alto in &alto_notes {
+            for &tenor in &tenor_notes {
+                let voicing = Voicing { soprano, alto, tenor, bass };
+
+                // Basic validity checks
+                if is_valid_voicing(&voicing, symbol, ranges, completeness) {
+                    voicings.push(voicing);
+                }
+            }
+        }
+    }
+
+    voicings
+}
+
+pub fn get_notes_in_range(chord_tones: &[Pitch], min: u8, max: u8) -> Vec<Pitch> {
+    let mut notes = Vec::new();
+
+    for &tone in chord_tones {
+        // Generate this pitch class in all octaves within range
+        let mut midi = tone.pitch_class().0;
+        while midi < min {
+            midi += 12;
+        }
+        while midi <= max {
+            notes.push(Pitch::new(midi));
+            midi += 12;
+        }
+    }
+
+    notes.sort();
+    notes.dedup();
+    notes
+}
+
+// The checks a candidate voicing has to pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Constraint {
+    VoiceRange,       // every upper voice inside its range; the bass is given, wherever it lies
+    VoiceCrossing,    // each voice at or below the one above it
+    Spacing,          // at most an octave between neighbouring upper voices
+    MissingChordTone, // every chord tone present, save those the completeness policy lets go
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Constraint::VoiceRange => "voice range",
+            Constraint::VoiceCrossing => "voice crossing",
+            Constraint::Spacing => "spacing",
+            Constraint::MissingChordTone => "missing chord tone",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub fn is_valid_voicing(
+    voicing: &Voicing,
+    symbol: &FiguredBassSymbol,
+    ranges: &RangeProfile,
+    completeness: Completeness,
+) -> bool {
+    check_voicing(voicing, symbol, ranges, completeness).is_ok()
+}
+
+// The first constraint the voicing breaks, if any
+pub fn check_voicing(
+    voicing: &Voicing,
+    symbol: &FiguredBassSymbol,
+    ranges: &RangeProfile,
+    completeness: Completeness,
+) -> Result<(), Constraint> {
+    if !(ranges.soprano.contains(voicing.soprano)
+        && ranges.alto.contains(voicing.alto)
+        && ranges.tenor.contains(voicing.tenor))
+    {
+        return Err(Constraint::VoiceRange);
+    }
+
+    // Check voices don't cross
+    if voicing.soprano.midi_number < voicing.alto.midi_number {
+        return Err(Constraint::VoiceCrossing);
+    }
+    if voicing.alto.midi_number < voicing.tenor.midi_number {
+        return Err(Constraint::VoiceCrossing);
+    }
+    if voicing.tenor.midi_number < voicing.bass.midi_number {
+        return Err(Constraint::VoiceCrossing);
+    }
+
+    // Check spacing between upper voices (not more than an octave)
+    if voicing.soprano.midi_number - voicing.alto.midi_number > 12 {
+        return Err(Constraint::Spacing);
+    }
+    if voicing.alto.midi_number - voicing.tenor.midi_number > 12 {
+        return Err(Constraint::Spacing);
+    }
+
+    // Check all chord tones are represented
+    if !missing_chord_tones(voicing, symbol, completeness).is_empty() {
+        return Err(Constraint::MissingChordTone);
+    }
+
+    Ok(())
+}
+
+// The chord tones `voicing` leaves out that `completeness` does not let it.
+// A tone may only be left out if the root takes its place, with no other
+// tone doubled. The chord's own policy overrides the global one.
+pub fn missing_chord_tones(
+    voicing: &Voicing,
+    symbol: &FiguredBassSymbol,
+    completeness: Completeness,
+) -> Vec<PitchClass> {
+    let voicing_pcs = voicing.pitch_classes();
+    let completeness = symbol.completeness.unwrap_or(completeness);
+    let root_fills_in = voicing_pcs
+        .iter()
+        .all(|&pc| pc == symbol.root() || voicing_pcs.iter().filter(|&&p| p == pc).count() == 1);
+    let mut missing = Vec::new();
+    for tone in &symbol.chord_tones {
+        let pc = tone.pitch_class();
+        let omitted = root_fills_in && completeness.may_omit(symbol, pc);
+        if !voicing_pcs.contains(&pc) && !omitted && !missing.contains(&pc) {
+            missing.push(pc);
+        }
+    }
+    missing
+}
+
+// How many of the combinations generate_voicings tries each constraint
+// rules out, counting every combination under the first check it fails.
+// Used to explain a chord with no valid voicing.
+pub fn rejections(
+    symbol: &FiguredBassSymbol,
+    ranges: &RangeProfile,
+    completeness: Completeness,
+) -> Vec<(Constraint, usize)> {
+    let soprano_notes = get_notes_in_range(&symbol.chord_tones, ranges.soprano.min, ranges.soprano.max);
+    let alto_notes = get_notes_in_range(&symbol.chord_tones, ranges.alto.min, ranges.alto.max);
+    let tenor_notes = get_notes_in_range(&symbol.chord_tones, ranges.tenor.min, ranges.tenor.max);
+    let mut counts: Vec<(Constraint, usize)> = Vec::new();
+
+    for &soprano in &soprano_notes {
+        for &alto in &alto_notes {
+            for &tenor in &tenor_notes {
+                let voicing = Voicing { soprano, alto, tenor, bass: symbol.bass };
+                if let Err(constraint) = check_voicing(&voicing, symbol, ranges, completeness) {
+                    match counts.iter_mut().find(|(c, _)| *c == constraint) {
+                        Some((_, count)) => *count += 1,
+                        None => counts.push((constraint, 1)),
+                    }
+                }
+            }
+        }
+    }
+
+    counts
+}
@@ -0,0 +1,21 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::api;
+
+// The JSON API as a browser sees it, through wasm-bindgen: each function
+// takes a request as text and gives back the response as text, as in
+// `api`, so a page does no more than JSON.stringify and JSON.parse.
+//
+//   import init, { realize } from "./basso_continuo.js";
+//   await init();
+//   const response = JSON.parse(realize(JSON.stringify({progression: "C3 G2 C3"})));
+
+#[wasm_bindgen]
+pub fn realize(request: &str) -> String {
+    api::realize_json(request)
+}
+
+#[wasm_bindgen]
+pub fn analyze(request: &str) -> String {
+    api::analyze_json(request)
+}
@@ -0,0 +1,158 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::styled::StyledText;
+
+#[derive(Debug, Serialize)]
+pub struct Count {
+    pub item: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LineStats {
+    pub line: usize, // 1-based
+    pub chars: usize,
+    pub words: usize,
+    pub highlights: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HighlightPosition {
+    pub text: String,
+    pub line: usize,   // 1-based
+    pub column: usize, // 1-based, in characters
+    pub offset: usize, // byte offset into the plain text
+    pub relative_column: f32, // 0.0 = line start, 1.0 = line end
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub lines: usize,
+    pub stanzas: usize,
+    pub words: usize,
+    pub letters: usize,
+    pub letter_frequencies: Vec<Count>,
+    pub word_frequencies: Vec<Count>,
+    pub line_stats: Vec<LineStats>,
+    pub highlights: Vec<HighlightPosition>,
+    pub hidden_message: String,
+}
+
+fn words(line: &str) -> impl Iterator<Item = String> + '_ {
+    line.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+}
+
+// Most frequent first, ties in alphabetical order
+fn sorted(counts: BTreeMap<String, usize>) -> Vec<Count> {
+    let mut counts: Vec<Count> = counts.into_iter().map(|(item, count)| Count { item, count }).collect();
+    counts.sort_by_key(|c| Reverse(c.count));
+    counts
+}
+
+pub fn analyze(doc: &StyledText) -> Report {
+    let mut letters = BTreeMap::new();
+    let mut word_counts = BTreeMap::new();
+    let mut line_stats = Vec::new();
+    let mut highlights = Vec::new();
+    let mut stanzas = 0;
+    let mut in_stanza = false;
+
+    let mut line_start = 0;
+    for (i, line) in doc.text.split_inclusive('\n').enumerate() {
+        let content = line.trim_end_matches('\n');
+        let line_end = line_start + content.len();
+        let chars = content.chars().count();
+
+        if content.trim().is_empty() {
+            in_stanza = false;
+        } else if !in_stanza {
+            stanzas += 1;
+            in_stanza = true;
+        }
+
+        for c in content.chars().filter(|c| c.is_alphabetic()) {
+            *letters.entry(c.to_lowercase().to_string()).or_insert(0) += 1;
+        }
+        let mut word_total = 0;
+        for word in words(content) {
+            *word_counts.entry(word).or_insert(0) += 1;
+            word_total += 1;
+        }
+
+        let mut line_highlights = 0;
+        let spans = doc.spans.iter().map(|s| &s.range);
+        for range in spans.filter(|r| r.start >= line_start && r.start < line_end) {
+            let column = content[..range.start - line_start].chars().count();
+            highlights.push(HighlightPosition {
+                text: doc.text[range.clone()].to_string(),
+                line: i + 1,
+                column: column + 1,
+                offset: range.start,
+                relative_column: if chars > 1 { column as f32 / (chars - 1) as f32 } else { 0.0 },
+            });
+            line_highlights += 1;
+        }
+
+        line_stats.push(LineStats { line: i + 1, chars, words: word_total, highlights: line_highlights });
+        line_start += line.len();
+    }
+
+    Report {
+        lines: line_stats.len(),
+        stanzas,
+        words: word_counts.values().sum(),
+        letters: letters.values().sum(),
+        letter_frequencies: sorted(letters),
+        word_frequencies: sorted(word_counts),
+        line_stats,
+        highlights,
+        hidden_message: doc.hidden_message(),
+    }
+}
+
+// Plain-text tables; `top` limits the frequency lists
+pub fn to_table(report: &Report, top: usize) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{} lines in {} stanzas, {} words, {} letters\n",
+        report.lines, report.stanzas, report.words, report.letters
+    );
+
+    let _ = writeln!(out, "Letter frequencies:");
+    for c in report.letter_frequencies.iter().take(top) {
+        let share = 100.0 * c.count as f32 / report.letters.max(1) as f32;
+        let _ = writeln!(out, "  {:<3} {:>4}  {:>5.1}%", c.item, c.count, share);
+    }
+
+    let _ = writeln!(out, "\nWord frequencies:");
+    for c in report.word_frequencies.iter().take(top) {
+        let _ = writeln!(out, "  {:<14} {:>4}", c.item, c.count);
+    }
+
+    let _ = writeln!(out, "\nLines:\n  line  chars  words  highlights");
+    for l in &report.line_stats {
+        let _ = writeln!(out, "  {:>4}  {:>5}  {:>5}  {:>10}", l.line, l.chars, l.words, l.highlights);
+    }
+
+    let _ = writeln!(out, "\nHighlights:\n  text  line  column  offset  position");
+    for h in &report.highlights {
+        let _ = writeln!(
+            out,
+            "  {:<4}  {:>4}  {:>6}  {:>6}  {:>7.0}%",
+            h.text,
+            h.line,
+            h.column,
+            h.offset,
+            100.0 * h.relative_column
+        );
+    }
+    let _ = writeln!(out, "\nHidden message: {}", report.hidden_message);
+    out
+}
@@ -0,0 +1,115 @@
+use crate::render::TextRenderer;
+use crate::styled::{Rgb, Style, StyledText};
+
+// Renders with 24-bit ANSI true-colour escape sequences
+pub struct Ansi;
+
+impl TextRenderer for Ansi {
+    fn render_text(&self, doc: &StyledText) -> String {
+        to_ansi(doc)
+    }
+}
+
+pub fn to_ansi(doc: &StyledText) -> String {
+    let mut out = String::new();
+
+    for (piece, style) in doc.segments() {
+        match style.map(|id| doc.style(id)) {
+            Some(Style { color, link: Some(url) }) => out.push_str(&hyperlink(&paint(piece, *color), url)),
+            Some(style) => out.push_str(&paint(piece, style.color)),
+            None => out.push_str(piece),
+        }
+    }
+    out
+}
+
+// OSC 8 hyperlink around already painted text; terminals without support
+// ignore the sequences and just show the text
+pub fn hyperlink(text: &str, url: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+// Wrap text in a 24-bit ANSI foreground colour and reset afterwards
+pub fn paint(text: &str, color: Rgb) -> String {
+    let Rgb(r, g, b) = color;
+    format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, text)
+}
+
+// Recover styled text from ANSI output: letters drawn with a 24-bit
+// foreground colour become spans (one style per colour and OSC 8 link),
+// other escapes are dropped.
+pub fn parse(ansi: &str) -> StyledText {
+    let mut chars = Vec::with_capacity(ansi.len());
+    let mut styles: Vec<Style> = Vec::new();
+    let mut current: Option<Rgb> = None;
+    let mut link: Option<String> = None;
+    let mut input = ansi.chars().peekable();
+
+    while let Some(c) = input.next() {
+        // OSC ... ended by BEL or ESC \; only OSC 8 (hyperlinks) matters
+        if c == '\x1b' && input.peek() == Some(&']') {
+            input.next();
+            let mut body = String::new();
+            while let Some(c) = input.next() {
+                if c == '\x07' || (c == '\x1b' && input.next_if_eq(&'\\').is_some()) {
+                    break;
+                }
+                body.push(c);
+            }
+            if let Some(rest) = body.strip_prefix("8;") {
+                let url = rest.split_once(';').map_or("", |(_, url)| url);
+                link = (!url.is_empty()).then(|| url.to_string());
+            }
+            continue;
+        }
+
+        if c == '\x1b' && input.peek() == Some(&'[') {
+            input.next();
+            let mut params = String::new();
+            let mut final_byte = None;
+            for c in input.by_ref() {
+                if ('\x40'..='\x7e').contains(&c) {
+                    final_byte = Some(c);
+                    break;
+                }
+                params.push(c);
+            }
+            if final_byte == Some('m') {
+                current = sgr_foreground(&params, current);
+            }
+            continue;
+        }
+
+        let style = current.map(|color| {
+            let style = Style { color, link: link.clone() };
+            styles.iter().position(|s| *s == style).unwrap_or_else(|| {
+                styles.push(style);
+                styles.len() - 1
+            })
+        });
+        chars.push((c, style));
+    }
+
+    let mut doc = StyledText::from_styled_chars(chars, styles);
+    doc.snap_to_graphemes();
+    doc
+}
+
+// Apply one SGR parameter list to the current foreground colour
+pub fn sgr_foreground(params: &str, current: Option<Rgb>) -> Option<Rgb> {
+    let codes: Vec<u8> = params.split(';').map(|p| p.parse().unwrap_or(0)).collect();
+    let mut fg = current;
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 | 39 => fg = None,
+            38 if codes.get(i + 1) == Some(&2) && i + 4 < codes.len() => {
+                fg = Some(Rgb(codes[i + 2], codes[i + 3], codes[i + 4]));
+                i += 4;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    fg
+}
@@ -0,0 +1,79 @@
+use crate::ansi::paint;
+use crate::styled::Rgb;
+
+const HEIGHT: usize = 5;
+
+// Embedded 5-row block font: uppercase letters, digits and a little
+// punctuation. Lowercase input is drawn with the uppercase glyphs.
+fn glyph(c: char) -> Option<[&'static str; HEIGHT]> {
+    Some(match c.to_ascii_uppercase() {
+        'A' => [" ### ", "#   #", "#####", "#   #", "#   #"],
+        'B' => ["#### ", "#   #", "#### ", "#   #", "#### "],
+        'C' => [" ####", "#    ", "#    ", "#    ", " ####"],
+        'D' => ["#### ", "#   #", "#   #", "#   #", "#### "],
+        'E' => ["#####", "#    ", "#### ", "#    ", "#####"],
+        'F' => ["#####", "#    ", "#### ", "#    ", "#    "],
+        'G' => [" ####", "#    ", "#  ##", "#   #", " ### "],
+        'H' => ["#   #", "#   #", "#####", "#   #", "#   #"],
+        'I' => ["###", " # ", " # ", " # ", "###"],
+        'J' => ["  ###", "   # ", "   # ", "#  # ", " ##  "],
+        'K' => ["#   #", "#  # ", "###  ", "#  # ", "#   #"],
+        'L' => ["#    ", "#    ", "#    ", "#    ", "#####"],
+        'M' => ["#   #", "## ##", "# # #", "#   #", "#   #"],
+        'N' => ["#   #", "##  #", "# # #", "#  ##", "#   #"],
+        'O' => [" ### ", "#   #", "#   #", "#   #", " ### "],
+        'P' => ["#### ", "#   #", "#### ", "#    ", "#    "],
+        'Q' => [" ### ", "#   #", "# # #", "#  # ", " ## #"],
+        'R' => ["#### ", "#   #", "#### ", "#  # ", "#   #"],
+        'S' => [" ####", "#    ", " ### ", "    #", "#### "],
+        'T' => ["#####", "  #  ", "  #  ", "  #  ", "  #  "],
+        'U' => ["#   #", "#   #", "#   #", "#   #", " ### "],
+        'V' => ["#   #", "#   #", "#   #", " # # ", "  #  "],
+        'W' => ["#   #", "#   #", "# # #", "## ##", "#   #"],
+        'X' => ["#   #", " # # ", "  #  ", " # # ", "#   #"],
+        'Y' => ["#   #", " # # ", "  #  ", "  #  ", "  #  "],
+        'Z' => ["#####", "   # ", "  #  ", " #   ", "#####"],
+        '0' => [" ### ", "#  ##", "# # #", "##  #", " ### "],
+        '1' => [" # ", "## ", " # ", " # ", "###"],
+        '2' => [" ### ", "#   #", "  ## ", " #   ", "#####"],
+        '3' => ["#### ", "    #", " ### ", "    #", "#### "],
+        '4' => ["#   #", "#   #", "#####", "    #", "    #"],
+        '5' => ["#####", "#    ", "#### ", "    #", "#### "],
+        '6' => [" ### ", "#    ", "#### ", "#   #", " ### "],
+        '7' => ["#####", "    #", "   # ", "  #  ", "  #  "],
+        '8' => [" ### ", "#   #", " ### ", "#   #", " ### "],
+        '9' => [" ### ", "#   #", " ####", "    #", " ### "],
+        ' ' => ["   ", "   ", "   ", "   ", "   "],
+        '.' => ["  ", "  ", "  ", "  ", "# "],
+        ',' => ["  ", "  ", "  ", " #", "# "],
+        '!' => ["#", "#", "#", " ", "#"],
+        '?' => [" ### ", "#   #", "  ## ", "     ", "  #  "],
+        '-' => ["    ", "    ", "####", "    ", "    "],
+        '\'' => ["#", "#", " ", " ", " "],
+        _ => return None,
+    })
+}
+
+// Large banner of `text`, one glyph column gap, unknown characters skipped
+pub fn render(text: &str) -> Vec<String> {
+    let mut rows = vec![String::new(); HEIGHT];
+    for glyph in text.chars().filter_map(glyph) {
+        for (row, part) in rows.iter_mut().zip(glyph) {
+            if !row.is_empty() {
+                row.push(' ');
+            }
+            row.push_str(part);
+        }
+    }
+    rows.iter().map(|row| row.trim_end().to_string()).collect()
+}
+
+// The banner wrapped in the 24-bit ANSI colour of the highlights
+pub fn render_ansi(text: &str, color: Rgb) -> String {
+    let mut out = String::new();
+    for row in render(text) {
+        out.push_str(&paint(&row, color));
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,95 @@
+use clap::ValueEnum;
+
+use crate::render::TextRenderer;
+use crate::styled::StyledText;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BrailleSource {
+    /// The whole poem, line by line
+    Poem,
+    /// Only the hidden message
+    Message,
+}
+
+// The poem in uncontracted (grade 1) English braille, one braille line per verse line
+pub struct Braille;
+
+impl TextRenderer for Braille {
+    fn render_text(&self, doc: &StyledText) -> String {
+        to_braille(&doc.text)
+    }
+}
+
+const CAPITAL: u8 = 0b100000; // dot 6
+const NUMBER: u8 = 0b111100; // dots 3-4-5-6
+const LETTER: u8 = 0b110000; // dots 5-6, after a number: "a-j are letters again"
+
+// Dots 1-6 as bits 0-5, the layout of the Unicode braille block (U+2800)
+fn cell(dots: u8) -> char {
+    char::from_u32(0x2800 + dots as u32).unwrap()
+}
+
+// a-j use only the top two rows; k-t add dot 3, u-z add dots 3 and 6 (w is
+// the exception, French braille had no w)
+fn letter_dots(c: char) -> Option<u8> {
+    const A_TO_J: [u8; 10] = [0x01, 0x03, 0x09, 0x19, 0x11, 0x0b, 0x1b, 0x13, 0x0a, 0x1a];
+    let c = c.to_ascii_lowercase();
+    match c {
+        'a'..='j' => Some(A_TO_J[(c as u8 - b'a') as usize]),
+        'k'..='t' => Some(A_TO_J[(c as u8 - b'k') as usize] | 0x04),
+        'w' => Some(0x3a),
+        'u' | 'v' | 'x' | 'y' | 'z' => Some(A_TO_J["uvxyz".find(c)?] | 0x24),
+        _ => None,
+    }
+}
+
+fn punctuation_dots(c: char) -> Option<u8> {
+    Some(match c {
+        ',' => 0x02,
+        ';' => 0x06,
+        ':' => 0x12,
+        '.' => 0x32,
+        '!' => 0x16,
+        '?' | '“' => 0x26,
+        '”' => 0x34,
+        '\'' | '’' => 0x04,
+        '-' => 0x24,
+        _ => return None,
+    })
+}
+
+// Anything without a braille equivalent is kept as it is, so line breaks
+// and spacing survive
+pub fn to_braille(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 3);
+    let mut in_number = false;
+
+    for c in text.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            if !in_number {
+                out.push(cell(NUMBER));
+                in_number = true;
+            }
+            // 1-9 are a-i, 0 is j
+            let letter = (b'a' + (digit + 9) as u8 % 10) as char;
+            out.push(cell(letter_dots(letter).unwrap()));
+            continue;
+        }
+
+        if let Some(dots) = letter_dots(c) {
+            if in_number && c.is_ascii_lowercase() && c <= 'j' {
+                out.push(cell(LETTER));
+            }
+            if c.is_ascii_uppercase() {
+                out.push(cell(CAPITAL));
+            }
+            out.push(cell(dots));
+        } else if let Some(dots) = punctuation_dots(c) {
+            out.push(cell(dots));
+        } else {
+            out.push(c);
+        }
+        in_number = false;
+    }
+    out
+}
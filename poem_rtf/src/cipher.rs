@@ -0,0 +1,47 @@
+use clap::ValueEnum;
+
+use crate::styled::StyledText;
+
+// What the letter shift is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CipherScope {
+    /// Every letter of the poem
+    Text,
+    /// Only the hidden letters
+    Message,
+}
+
+// Shift an ASCII letter `shift` places along the alphabet, keeping its case;
+// everything else is left alone
+pub fn rotate_char(c: char, shift: u8) -> char {
+    let base = match c {
+        'a'..='z' => b'a',
+        'A'..='Z' => b'A',
+        _ => return c,
+    };
+    ((c as u8 - base + shift % 26) % 26 + base) as char
+}
+
+pub fn rotate(text: &str, shift: u8) -> String {
+    text.chars().map(|c| rotate_char(c, shift)).collect()
+}
+
+// Undo `rotate(_, shift)`
+pub fn unrotate(text: &str, shift: u8) -> String {
+    rotate(text, 26 - shift % 26)
+}
+
+// Only ASCII letters change and they stay one byte each, so the spans
+// still cover the same characters afterwards
+pub fn apply(doc: &StyledText, shift: u8, scope: CipherScope) -> StyledText {
+    let mut out = doc.clone();
+    out.text = match scope {
+        CipherScope::Text => rotate(&doc.text, shift),
+        CipherScope::Message => doc
+            .text
+            .char_indices()
+            .map(|(i, c)| if doc.style_at(i).is_some() { rotate_char(c, shift) } else { c })
+            .collect(),
+    };
+    out
+}
@@ -0,0 +1,77 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+
+use crate::ansi;
+use crate::styled::StyledText;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CopySource {
+    /// The poem with its ANSI colours
+    Ansi,
+    /// The poem as plain text
+    Text,
+    /// Only the hidden message
+    Message,
+}
+
+impl CopySource {
+    pub fn contents(self, doc: &StyledText, message: &str) -> String {
+        match self {
+            CopySource::Ansi => ansi::to_ansi(doc),
+            CopySource::Text => doc.text.clone(),
+            CopySource::Message => message.to_string(),
+        }
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Standard base64 with padding, as OSC 52 expects
+pub fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// ESC ] 52 ; c ; <base64> BEL sets the clipboard ("c") in terminals that
+// allow it, including over SSH since the terminal does the copying.
+pub fn osc52(data: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(data.as_bytes()))
+}
+
+// tmux and screen swallow unknown sequences unless they are passed through
+// in a DCS wrapper (tmux also needs `set -g allow-passthrough on`)
+fn wrap_for_multiplexer(seq: &str) -> String {
+    if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"))
+    } else if std::env::var("TERM").is_ok_and(|t| t.starts_with("screen")) {
+        format!("\x1bP{}\x1b\\", seq)
+    } else {
+        seq.to_string()
+    }
+}
+
+// Written to the controlling terminal so it still works when stdout is
+// redirected; falls back to stdout when there is no terminal to open.
+pub fn copy(data: &str) -> io::Result<()> {
+    let seq = wrap_for_multiplexer(&osc52(data));
+    match OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(mut tty) => tty.write_all(seq.as_bytes()),
+        Err(_) => {
+            let mut stdout = io::stdout();
+            stdout.write_all(seq.as_bytes())?;
+            stdout.flush()
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::ops::Range;
+
+// Byte ranges where the two inputs differ. Bytes past the end of the
+// shorter input count as differing, as one final range.
+pub fn mismatches(expected: &[u8], actual: &[u8]) -> Vec<Range<usize>> {
+    let common = expected.len().min(actual.len());
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for i in (0..common).filter(|&i| expected[i] != actual[i]) {
+        match ranges.last_mut() {
+            Some(last) if last.end == i => last.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    if expected.len() != actual.len() {
+        ranges.push(common..expected.len().max(actual.len()));
+    }
+    ranges
+}
+
+fn hex_row(bytes: &[u8], window: &Range<usize>) -> String {
+    window
+        .clone()
+        .map(|i| bytes.get(i).map_or("--".to_string(), |b| format!("{:02X}", b)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// cmp-style report for one mismatch with `context` bytes of hex either
+// side: the expected and actual rows, and carets under the differing bytes
+pub fn report(expected: &[u8], actual: &[u8], range: &Range<usize>, context: usize) -> String {
+    let window = range.start.saturating_sub(context)..range.end + context;
+    let window = window.start..window.end.min(expected.len().max(actual.len()));
+    let markers: Vec<&str> = window
+        .clone()
+        .map(|i| if range.contains(&i) { "^^" } else { "  " })
+        .collect();
+
+    format!(
+        "offset 0x{:08x} ({}): {} {}\n  0x{:08x}  expected  {}\n              actual    {}\n                        {}\n",
+        range.start,
+        range.start,
+        range.len(),
+        if range.len() == 1 { "byte differs" } else { "bytes differ" },
+        window.start,
+        hex_row(expected, &window),
+        hex_row(actual, &window),
+        markers.join(" ").trim_end()
+    )
+}
@@ -0,0 +1,97 @@
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use serde::Deserialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::output::Format;
+use crate::styled::{Rgb, Style, StyledText};
+
+// Settings read from a TOML file, e.g.
+//
+//   formats = ["ansi", "html"]
+//
+//   [[highlight]]
+//   color = "#cd6600"
+//   at = ["1:18", "2:15"]   # line:column, both counted from 1
+//   url = "https://..."     # optional: the letters become links
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub formats: Vec<Format>,
+    #[serde(default)]
+    pub highlight: Vec<HighlightGroup>,
+}
+
+// Letters that share one colour (the --color one if none is given) and
+// optionally a link target
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HighlightGroup {
+    pub color: Option<Rgb>,
+    pub at: Vec<String>,
+    pub url: Option<String>,
+}
+
+pub fn load(path: &Path) -> io::Result<Config> {
+    let contents = std::fs::read_to_string(path)?;
+    toml::from_str(&contents)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
+// Byte range of the character at a "line:column" spec. Columns count
+// grapheme clusters, so an accented letter or an emoji is one column however
+// many code points it takes. Blank lines count, so the numbers match what
+// an editor shows. Ok(None) if the text has no such position.
+pub fn locate(text: &str, spec: &str) -> Result<Option<Range<usize>>, String> {
+    let invalid = || format!("invalid highlight position {:?}, expected line:column", spec);
+    let (line, column) = spec.split_once(':').ok_or_else(invalid)?;
+    let line: usize = line.trim().parse().map_err(|_| invalid())?;
+    let column: usize = column.trim().parse().map_err(|_| invalid())?;
+    if line == 0 || column == 0 {
+        return Err(invalid());
+    }
+
+    let mut start = 0;
+    for (i, l) in text.split('\n').enumerate() {
+        if i + 1 == line {
+            return Ok(l
+                .grapheme_indices(true)
+                .nth(column - 1)
+                .map(|(at, g)| start + at..start + at + g.len()));
+        }
+        start += l.len() + 1;
+    }
+    Ok(None)
+}
+
+impl Config {
+    // Add every highlight group to `doc` on top of its existing spans.
+    // Returns the positions that fall outside the text.
+    pub fn apply(&self, doc: &mut StyledText, default_color: Rgb) -> Result<Vec<String>, String> {
+        let mut unplaced = Vec::new();
+        for group in &self.highlight {
+            // The URL ends up inside an OSC 8 sequence, where a control
+            // character would end it early
+            if let Some(url) = &group.url
+                && url.chars().any(char::is_control)
+            {
+                return Err(format!("highlight url {:?} contains control characters", url));
+            }
+            let id = doc.add_style(Style {
+                color: group.color.unwrap_or(default_color),
+                link: group.url.clone(),
+            });
+            for spec in &group.at {
+                match locate(&doc.text, spec)? {
+                    Some(range) => doc.add_span(range, id),
+                    None => unplaced.push(spec.clone()),
+                }
+            }
+        }
+        doc.merge_spans();
+        Ok(unplaced)
+    }
+}
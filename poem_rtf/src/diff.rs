@@ -0,0 +1,164 @@
+use crate::styled::{Rgb, Style, StyledText};
+
+pub const INSERTED: Rgb = Rgb(0, 170, 0);
+pub const DELETED: Rgb = Rgb(205, 0, 0);
+pub const CHANGED: Rgb = Rgb(205, 170, 0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    Inserted,
+    Deleted,
+    Changed, // replaced letter-for-letter; holds the new letters
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+// Longest-common-subsequence edit script between two sequences
+fn edit_script<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Op> {
+    let (n, m) = (a.len(), b.len());
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            ops.push(Op::Equal(i, j));
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            ops.push(Op::Insert(j));
+            j += 1;
+        } else {
+            ops.push(Op::Delete(i));
+            i += 1;
+        }
+    }
+    ops
+}
+
+fn push(out: &mut Vec<(String, Change)>, text: &str, change: Change) {
+    match out.last_mut() {
+        Some((last, kind)) if *kind == change => last.push_str(text),
+        _ => out.push((text.to_string(), change)),
+    }
+}
+
+// Letter-level diff of one changed block. A deletion directly followed by an
+// insertion of the same length is reported as changed letters.
+fn diff_chars(old: &str, new: &str, out: &mut Vec<(String, Change)>) {
+    let a: Vec<char> = old.chars().collect();
+    let b: Vec<char> = new.chars().collect();
+    let ops = edit_script(&a, &b);
+
+    let mut k = 0;
+    while k < ops.len() {
+        match ops[k] {
+            Op::Equal(i, _) => {
+                push(out, &a[i].to_string(), Change::Same);
+                k += 1;
+            }
+            _ => {
+                let mut deleted = String::new();
+                let mut inserted = String::new();
+                while let Some(&op) = ops.get(k) {
+                    match op {
+                        Op::Delete(i) => deleted.push(a[i]),
+                        Op::Insert(j) => inserted.push(b[j]),
+                        Op::Equal(..) => break,
+                    }
+                    k += 1;
+                }
+                if deleted.chars().count() == inserted.chars().count() {
+                    push(out, &inserted, Change::Changed);
+                } else {
+                    if !deleted.is_empty() {
+                        push(out, &deleted, Change::Deleted);
+                    }
+                    if !inserted.is_empty() {
+                        push(out, &inserted, Change::Inserted);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Diff whole lines first, then letters inside each block of changed lines,
+// so long poems do not need a quadratic table over every character.
+pub fn diff(old: &str, new: &str) -> Vec<(String, Change)> {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = edit_script(&a, &b);
+    let mut out = Vec::new();
+
+    let mut k = 0;
+    while k < ops.len() {
+        if let Op::Equal(i, _) = ops[k] {
+            push(&mut out, a[i], Change::Same);
+            k += 1;
+            continue;
+        }
+
+        let mut old_block = String::new();
+        let mut new_block = String::new();
+        while let Some(&op) = ops.get(k) {
+            match op {
+                Op::Delete(i) => old_block.push_str(a[i]),
+                Op::Insert(j) => new_block.push_str(b[j]),
+                Op::Equal(..) => break,
+            }
+            k += 1;
+        }
+        diff_chars(&old_block, &new_block, &mut out);
+    }
+    out
+}
+
+// The diff as a document any format can render: inserted letters green,
+// deleted letters (kept in the text) red, changed letters yellow
+pub fn to_styled(changes: &[(String, Change)]) -> StyledText {
+    let mut doc = StyledText::new(changes.iter().map(|(text, _)| text.as_str()).collect::<String>());
+    let mut start = 0;
+    for (text, change) in changes {
+        let color = match change {
+            Change::Same => None,
+            Change::Inserted => Some(INSERTED),
+            Change::Deleted => Some(DELETED),
+            Change::Changed => Some(CHANGED),
+        };
+        if let Some(color) = color {
+            let id = doc.add_style(Style::new(color));
+            doc.add_span(start..start + text.len(), id);
+        }
+        start += text.len();
+    }
+    doc
+}
+
+// Number of letters per kind of change: (inserted, deleted, changed)
+pub fn summary(changes: &[(String, Change)]) -> (usize, usize, usize) {
+    let count = |kind: Change| -> usize {
+        changes
+            .iter()
+            .filter(|(_, c)| *c == kind)
+            .map(|(t, _)| t.chars().filter(|c| !c.is_whitespace()).count())
+            .sum()
+    };
+    (count(Change::Inserted), count(Change::Deleted), count(Change::Changed))
+}
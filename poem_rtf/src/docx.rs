@@ -0,0 +1,93 @@
+use std::io::{self, Cursor, Write};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::render::Renderer;
+use crate::styled::{Rgb, StyledText};
+
+pub struct Docx;
+
+impl Renderer for Docx {
+    fn render(&self, doc: &StyledText) -> io::Result<Vec<u8>> {
+        to_docx(doc)
+    }
+}
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+</Types>
+"#;
+
+const RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+</Relationships>
+"#;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn run(text: &str, color: Option<Rgb>) -> String {
+    let props = match color {
+        Some(Rgb(r, g, b)) => format!("<w:rPr><w:color w:val=\"{:02X}{:02X}{:02X}\"/></w:rPr>", r, g, b),
+        None => String::new(),
+    };
+    format!("<w:r>{}<w:t xml:space=\"preserve\">{}</w:t></w:r>", props, escape(text))
+}
+
+// word/document.xml: one paragraph per stanza, <w:br/> between its lines,
+// and a coloured run for every highlighted letter.
+fn document_xml(doc: &StyledText) -> String {
+    let mut body = String::new();
+    let mut in_paragraph = false;
+
+    for line in doc.lines() {
+        if line.is_empty() {
+            if in_paragraph {
+                body.push_str("</w:p>\n");
+                in_paragraph = false;
+            }
+            continue;
+        }
+
+        if in_paragraph {
+            body.push_str("<w:r><w:br/></w:r>");
+        } else {
+            body.push_str("<w:p>");
+            in_paragraph = true;
+        }
+        for (piece, id) in line {
+            body.push_str(&run(piece, id.map(|id| doc.style(id).color)));
+        }
+    }
+    if in_paragraph {
+        body.push_str("</w:p>\n");
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\n\
+         <w:body>\n{}</w:body>\n</w:document>\n",
+        body
+    )
+}
+
+// A .docx is a zip of XML parts; three are enough for Word and LibreOffice
+pub fn to_docx(doc: &StyledText) -> io::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    zip.start_file("[Content_Types].xml", options)?;
+    zip.write_all(CONTENT_TYPES.as_bytes())?;
+    zip.start_file("_rels/.rels", options)?;
+    zip.write_all(RELS.as_bytes())?;
+    zip.start_file("word/document.xml", options)?;
+    zip.write_all(document_xml(doc).as_bytes())?;
+
+    Ok(zip.finish()?.into_inner())
+}
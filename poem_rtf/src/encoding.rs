@@ -0,0 +1,138 @@
+use std::fmt;
+
+use clap::ValueEnum;
+
+// Byte encodings the text can be dumped in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    #[value(name = "utf8")]
+    Utf8,
+    #[value(name = "utf16le")]
+    Utf16Le,
+    #[value(name = "utf16be")]
+    Utf16Be,
+    #[value(name = "utf32le")]
+    Utf32Le,
+    #[value(name = "utf32be")]
+    Utf32Be,
+    /// ISO 8859-1: U+0000..U+00FF, one byte each
+    #[value(name = "latin1")]
+    Latin1,
+    /// IBM code page 037 (US/Canada EBCDIC), the same repertoire as Latin-1
+    Ebcdic,
+}
+
+// What to do with a character the encoding has no byte for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Unmappable {
+    /// Write the encoding's "?" instead
+    Replace,
+    /// Leave the character out
+    Skip,
+    /// Stop with an error naming the character
+    Error,
+}
+
+// Latin-1 byte -> code page 037 byte
+const EBCDIC_037: [u8; 256] = [
+    0x00, 0x01, 0x02, 0x03, 0x37, 0x2D, 0x2E, 0x2F, 0x16, 0x05, 0x25, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F,
+    0x10, 0x11, 0x12, 0x13, 0x3C, 0x3D, 0x32, 0x26, 0x18, 0x19, 0x3F, 0x27, 0x1C, 0x1D, 0x1E, 0x1F,
+    0x40, 0x5A, 0x7F, 0x7B, 0x5B, 0x6C, 0x50, 0x7D, 0x4D, 0x5D, 0x5C, 0x4E, 0x6B, 0x60, 0x4B, 0x61,
+    0xF0, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0x7A, 0x5E, 0x4C, 0x7E, 0x6E, 0x6F,
+    0x7C, 0xC1, 0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xD1, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6,
+    0xD7, 0xD8, 0xD9, 0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xBA, 0xE0, 0xBB, 0xB0, 0x6D,
+    0x79, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x91, 0x92, 0x93, 0x94, 0x95, 0x96,
+    0x97, 0x98, 0x99, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7, 0xA8, 0xA9, 0xC0, 0x4F, 0xD0, 0xA1, 0x07,
+    0x20, 0x21, 0x22, 0x23, 0x24, 0x15, 0x06, 0x17, 0x28, 0x29, 0x2A, 0x2B, 0x2C, 0x09, 0x0A, 0x1B,
+    0x30, 0x31, 0x1A, 0x33, 0x34, 0x35, 0x36, 0x08, 0x38, 0x39, 0x3A, 0x3B, 0x04, 0x14, 0x3E, 0xFF,
+    0x41, 0xAA, 0x4A, 0xB1, 0x9F, 0xB2, 0x6A, 0xB5, 0xBD, 0xB4, 0x9A, 0x8A, 0x5F, 0xCA, 0xAF, 0xBC,
+    0x90, 0x8F, 0xEA, 0xFA, 0xBE, 0xA0, 0xB6, 0xB3, 0x9D, 0xDA, 0x9B, 0x8B, 0xB7, 0xB8, 0xB9, 0xAB,
+    0x64, 0x65, 0x62, 0x66, 0x63, 0x67, 0x9E, 0x68, 0x74, 0x71, 0x72, 0x73, 0x78, 0x75, 0x76, 0x77,
+    0xAC, 0x69, 0xED, 0xEE, 0xEB, 0xEF, 0xEC, 0xBF, 0x80, 0xFD, 0xFE, 0xFB, 0xFC, 0xAD, 0xAE, 0x59,
+    0x44, 0x45, 0x42, 0x46, 0x43, 0x47, 0x9C, 0x48, 0x54, 0x51, 0x52, 0x53, 0x58, 0x55, 0x56, 0x57,
+    0x8C, 0x49, 0xCD, 0xCE, 0xCB, 0xCF, 0xCC, 0xE1, 0x70, 0xDD, 0xDE, 0xDB, 0xDC, 0x8D, 0x8E, 0xDF,
+];
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Utf32Le => "UTF-32LE",
+            Encoding::Utf32Be => "UTF-32BE",
+            Encoding::Latin1 => "Latin-1",
+            Encoding::Ebcdic => "EBCDIC 037",
+        })
+    }
+}
+
+impl Encoding {
+    // Byte order mark: U+FEFF in this encoding (none for the single-byte ones)
+    pub fn bom(self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if matches!(self, Encoding::Latin1 | Encoding::Ebcdic) {
+            return out;
+        }
+        self.encode_char('\u{feff}', &mut out);
+        out
+    }
+
+    // "?" in this encoding, the substitute for unmappable characters
+    pub fn substitute(self) -> u8 {
+        match self {
+            Encoding::Ebcdic => EBCDIC_037[b'?' as usize],
+            _ => b'?',
+        }
+    }
+
+    // Append the bytes of `c`; false (and nothing written) if the encoding
+    // cannot represent it. UTF-16 needs a surrogate pair beyond the BMP.
+    pub fn encode_char(self, c: char, out: &mut Vec<u8>) -> bool {
+        match self {
+            Encoding::Utf8 => {
+                let mut buf = [0; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let mut buf = [0; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    match self {
+                        Encoding::Utf16Le => out.extend_from_slice(&unit.to_le_bytes()),
+                        _ => out.extend_from_slice(&unit.to_be_bytes()),
+                    }
+                }
+            }
+            Encoding::Utf32Le => out.extend_from_slice(&(c as u32).to_le_bytes()),
+            Encoding::Utf32Be => out.extend_from_slice(&(c as u32).to_be_bytes()),
+            Encoding::Latin1 | Encoding::Ebcdic => {
+                let Ok(byte) = u8::try_from(c) else {
+                    return false;
+                };
+                out.push(if self == Encoding::Ebcdic { EBCDIC_037[byte as usize] } else { byte });
+            }
+        }
+        true
+    }
+
+    // `encode_char`, with `policy` deciding about unmappable characters
+    pub fn encode_char_or(self, c: char, policy: Unmappable, out: &mut Vec<u8>) -> Result<(), String> {
+        if self.encode_char(c, out) {
+            return Ok(());
+        }
+        match policy {
+            Unmappable::Replace => out.push(self.substitute()),
+            Unmappable::Skip => {}
+            Unmappable::Error => return Err(format!("{:?} (U+{:04X}) cannot be encoded in {}", c, c as u32, self)),
+        }
+        Ok(())
+    }
+
+    pub fn encode(self, text: &str, bom: bool, policy: Unmappable) -> Result<Vec<u8>, String> {
+        let mut out = if bom { self.bom() } else { Vec::new() };
+        for c in text.chars() {
+            self.encode_char_or(c, policy, &mut out)?;
+        }
+        Ok(out)
+    }
+}
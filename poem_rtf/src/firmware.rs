@@ -0,0 +1,103 @@
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RecordFormat {
+    /// Intel HEX (":" records)
+    Ihex,
+    /// Motorola S-record ("S" records)
+    Srec,
+}
+
+// Data bytes per record, what most tools write
+const RECORD_LEN: usize = 16;
+
+// "0x8000", "8000h" or plain decimal
+pub fn parse_address(s: &str) -> Result<u32, String> {
+    let parsed = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u32::from_str_radix(hex, 16)
+    } else if let Some(hex) = s.strip_suffix('h').or_else(|| s.strip_suffix('H')) {
+        u32::from_str_radix(hex, 16)
+    } else {
+        s.parse()
+    };
+    parsed.map_err(|_| format!("invalid address {:?}, expected e.g. 0x8000", s))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+// One Intel HEX record; the checksum makes all its bytes sum to zero
+fn ihex_record(kind: u8, address: u16, data: &[u8]) -> String {
+    let mut bytes = vec![data.len() as u8];
+    bytes.extend_from_slice(&address.to_be_bytes());
+    bytes.push(kind);
+    bytes.extend_from_slice(data);
+    let sum = bytes.iter().fold(0u8, |s, &b| s.wrapping_add(b));
+    bytes.push(sum.wrapping_neg());
+    format!(":{}\n", hex(&bytes))
+}
+
+// Data records at `base` onwards. Records carry 16-bit addresses, so an
+// extended linear address record (type 04) sets the upper 16 bits whenever
+// they change; a record never crosses a 64 KiB boundary.
+pub fn to_ihex(bytes: &[u8], base: u32) -> Result<String, String> {
+    if base as u64 + bytes.len() as u64 > 1 << 32 {
+        return Err("data does not fit below 4 GiB at this base address".to_string());
+    }
+    let mut out = String::new();
+    let mut upper = None;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let address = base + pos as u32;
+        let to_boundary = 0x1_0000 - (address & 0xffff) as usize;
+        let len = RECORD_LEN.min(bytes.len() - pos).min(to_boundary);
+        if upper != Some(address >> 16) {
+            upper = Some(address >> 16);
+            out.push_str(&ihex_record(0x04, 0, &((address >> 16) as u16).to_be_bytes()));
+        }
+        out.push_str(&ihex_record(0x00, address as u16, &bytes[pos..pos + len]));
+        pos += len;
+    }
+    out.push_str(&ihex_record(0x01, 0, &[]));
+    Ok(out)
+}
+
+// One S-record; the count covers address, data and checksum, and the
+// checksum is the ones' complement of the low byte of their sum
+fn srec_record(kind: u8, address: u32, address_len: usize, data: &[u8]) -> String {
+    let mut bytes = vec![(address_len + data.len() + 1) as u8];
+    bytes.extend_from_slice(&address.to_be_bytes()[4 - address_len..]);
+    bytes.extend_from_slice(data);
+    let sum = bytes.iter().fold(0u8, |s, &b| s.wrapping_add(b));
+    bytes.push(!sum);
+    format!("S{}{}\n", kind, hex(&bytes))
+}
+
+// S0 header with `name`, then S1/S2/S3 data records (16-, 24- or 32-bit
+// addresses, the smallest that fits), an S5 record count when it fits in
+// 16 bits, and the matching S9/S8/S7 terminator pointing at `base`
+pub fn to_srec(bytes: &[u8], base: u32, name: &str) -> Result<String, String> {
+    let end = base as u64 + bytes.len() as u64;
+    if end > 1 << 32 {
+        return Err("data does not fit below 4 GiB at this base address".to_string());
+    }
+    let (data_kind, address_len, end_kind) = match end.saturating_sub(1) {
+        0..=0xffff => (1, 2, 9),
+        0x1_0000..=0xff_ffff => (2, 3, 8),
+        _ => (3, 4, 7),
+    };
+
+    let header: Vec<u8> = name.bytes().take(64).collect();
+    let mut out = srec_record(0, 0, 2, &header);
+    let mut count = 0u32;
+    for (i, chunk) in bytes.chunks(RECORD_LEN).enumerate() {
+        out.push_str(&srec_record(data_kind, base + (i * RECORD_LEN) as u32, address_len, chunk));
+        count += 1;
+    }
+    if count <= 0xffff {
+        out.push_str(&srec_record(5, count, 2, &[]));
+    }
+    out.push_str(&srec_record(end_kind, base, address_len, &[]));
+    Ok(out)
+}
@@ -0,0 +1,323 @@
+use std::io::{self, Read, Write};
+
+use clap::ValueEnum;
+
+use crate::ansi::{self, paint, sgr_foreground};
+use crate::encoding::{Encoding, Unmappable};
+use crate::render::TextRenderer;
+use crate::styled::{Rgb, StyledText};
+
+// Colour for the bytes of ANSI escape sequences in a coloured dump
+const ESCAPE: Rgb = Rgb(110, 110, 110);
+
+// Hex dump of the ANSI rendering, as written to poem_ansi_hex.txt
+pub struct Hex;
+
+impl TextRenderer for Hex {
+    fn render_text(&self, doc: &StyledText) -> String {
+        let mut out = dump(ansi::to_ansi(doc).as_bytes());
+        out.push_str("\n# Each pair above is one UTF-8 byte of the ANSI-colored poem.\n");
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteKind {
+    Text,
+    Escape,    // part of an ANSI escape sequence
+    Highlight, // part of a letter drawn in a highlight colour
+    Bom,       // byte order mark in front of re-encoded text
+}
+
+// Two-digit hex per byte, 16 bytes per line
+pub fn dump(bytes: &[u8]) -> String {
+    let mut writer = HexWriter::new(Vec::with_capacity(bytes.len() * 3 + bytes.len() / 16), 0, false, None);
+    writer.write_chunk(bytes).expect("writing to a Vec cannot fail");
+    String::from_utf8(writer.finish().expect("writing to a Vec cannot fail")).unwrap()
+}
+
+fn colored_hex(b: u8, kind: ByteKind, highlight: Rgb) -> String {
+    let hex = format!("{:02X}", b);
+    match kind {
+        ByteKind::Text => hex,
+        ByteKind::Escape | ByteKind::Bom => paint(&hex, ESCAPE),
+        ByteKind::Highlight => paint(&hex, highlight),
+    }
+}
+
+// Byte-at-a-time version of the ANSI parser, so a stream can be classified
+// in chunks of any size. An escape sequence split across chunks is still
+// recognised, since all the state lives here. Both CSI (ESC [) and OSC
+// (ESC ], e.g. hyperlinks) sequences count as escapes.
+#[derive(Debug, Default)]
+pub struct Classifier {
+    foreground: Option<Rgb>,
+    escape: Option<Vec<u8>>, // bytes after ESC of an unfinished sequence
+}
+
+impl Classifier {
+    pub fn push(&mut self, b: u8) -> ByteKind {
+        if let Some(seq) = &mut self.escape {
+            if seq.is_empty() && b != b'[' && b != b']' {
+                // Only ESC [ and ESC ] start a sequence
+                self.escape = None;
+            } else {
+                seq.push(b);
+                let done = match seq[0] {
+                    b'[' => seq.len() > 1 && (0x40..=0x7e).contains(&b),
+                    _ => b == 0x07 || seq.ends_with(b"\x1b\\"),
+                };
+                if done {
+                    if seq[0] == b'[' && b == b'm' {
+                        let params = String::from_utf8_lossy(&seq[1..seq.len() - 1]);
+                        self.foreground = sgr_foreground(&params, self.foreground);
+                    }
+                    self.escape = None;
+                }
+                return ByteKind::Escape;
+            }
+        }
+        if b == 0x1b {
+            self.escape = Some(Vec::new());
+            return ByteKind::Escape;
+        }
+
+        if self.foreground.is_some() && !b.is_ascii_whitespace() {
+            ByteKind::Highlight
+        } else {
+            ByteKind::Text
+        }
+    }
+}
+
+// Work out which bytes of an ANSI stream are escapes, highlighted letters
+// (including every UTF-8 byte of a multi-byte letter) or plain text.
+pub fn classify(bytes: &[u8]) -> Vec<ByteKind> {
+    let mut classifier = Classifier::default();
+    bytes.iter().map(|&b| classifier.push(b)).collect()
+}
+
+// Writes the `dump` layout incrementally, so input of any size can be dumped
+// chunk by chunk in constant memory; the output does not depend on how the
+// input was split. Rows optionally start with the offset of their first byte
+// (counted from `start`), and with a highlight colour the bytes are coloured
+// as in `dump_colored`.
+pub struct HexWriter<W: Write> {
+    out: W,
+    start: u64,
+    written: u64,
+    offsets: bool,
+    colors: Option<(Classifier, Rgb)>,
+}
+
+impl<W: Write> HexWriter<W> {
+    pub fn new(out: W, start: u64, offsets: bool, highlight: Option<Rgb>) -> Self {
+        let colors = highlight.map(|h| (Classifier::default(), h));
+        Self { out, start, written: 0, offsets, colors }
+    }
+
+    pub fn write_chunk(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let mut row = String::with_capacity(bytes.len() * 3 + 16);
+        for &b in bytes {
+            if self.offsets && self.written.is_multiple_of(16) {
+                row.push_str(&format!("{:08x}: ", self.start + self.written));
+            }
+            match &mut self.colors {
+                Some((classifier, highlight)) => row.push_str(&colored_hex(b, classifier.push(b), *highlight)),
+                None => row.push_str(&format!("{:02X}", b)),
+            }
+            row.push(' ');
+            self.written += 1;
+            if self.written.is_multiple_of(16) {
+                row.push('\n');
+            }
+        }
+        self.out.write_all(row.as_bytes())
+    }
+
+    // Dump everything `input` yields, 64 KiB at a time
+    pub fn copy_from(&mut self, input: &mut impl Read) -> io::Result<u64> {
+        let mut buf = vec![0; 64 * 1024];
+        let before = self.written;
+        loop {
+            match input.read(&mut buf) {
+                Ok(0) => return Ok(self.written - before),
+                Ok(n) => self.write_chunk(&buf[..n])?,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+// Same layout as `dump`, but escape bytes are grey and the hidden letters'
+// bytes are drawn in the highlight colour
+pub fn dump_colored(bytes: &[u8], highlight: Rgb) -> String {
+    let mut writer = HexWriter::new(Vec::new(), 0, false, Some(highlight));
+    writer.write_chunk(bytes).expect("writing to a Vec cannot fail");
+    String::from_utf8(writer.finish().expect("writing to a Vec cannot fail")).unwrap()
+}
+
+// The text re-encoded (UTF-16, UTF-32, ...) with each byte classified by the
+// character it came from
+pub fn encode_classified(
+    text: &str,
+    encoding: Encoding,
+    bom: bool,
+    policy: Unmappable,
+) -> Result<(Vec<u8>, Vec<ByteKind>), String> {
+    let utf8_kinds = classify(text.as_bytes());
+    let mut bytes = if bom { encoding.bom() } else { Vec::new() };
+    let mut kinds = vec![ByteKind::Bom; bytes.len()];
+    for (i, c) in text.char_indices() {
+        encoding.encode_char_or(c, policy, &mut bytes)?;
+        kinds.resize(bytes.len(), utf8_kinds[i]);
+    }
+    Ok((bytes, kinds))
+}
+
+// `dump_colored` for the text in another encoding
+pub fn dump_colored_encoded(
+    text: &str,
+    encoding: Encoding,
+    bom: bool,
+    policy: Unmappable,
+    highlight: Rgb,
+) -> Result<String, String> {
+    let (bytes, kinds) = encode_classified(text, encoding, bom, policy)?;
+    Ok(dump_kinds(&bytes, &kinds, highlight))
+}
+
+fn dump_kinds(bytes: &[u8], kinds: &[ByteKind], highlight: Rgb) -> String {
+    let mut out = String::new();
+    for (i, (&b, &kind)) in bytes.iter().zip(kinds).enumerate() {
+        out.push_str(&colored_hex(b, kind, highlight));
+        out.push(' ');
+        if (i + 1) % 16 == 0 {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+// Printable stand-in for characters that would disturb the layout
+fn visible(c: char) -> char {
+    match c {
+        '\x1b' => '␛',
+        '\u{feff}' => '∅',
+        '\n' => '↵',
+        '\t' => '→',
+        '\r' => '␍',
+        c if c.is_control() => '·',
+        c => c,
+    }
+}
+
+fn flush(chars_row: &mut String, hex_row: &mut String, out: &mut String) {
+    out.push_str(chars_row.trim_end());
+    out.push('\n');
+    out.push_str(hex_row.trim_end());
+    out.push_str("\n\n");
+    chars_row.clear();
+    hex_row.clear();
+}
+
+// Teaching layout: a row of source characters, then the hex of exactly those
+// bytes underneath, each character centred over its own bytes. Rows hold at
+// most `width` bytes and never split a multi-byte character. A byte order
+// mark shows as ∅; skipped unmappable characters do not show at all.
+pub fn dump_interleaved(
+    text: &str,
+    width: usize,
+    encoding: Encoding,
+    bom: bool,
+    policy: Unmappable,
+) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars_row = String::new();
+    let mut hex_row = String::new();
+    let mut row_bytes = 0;
+
+    let bom = (bom && !encoding.bom().is_empty()).then_some('\u{feff}');
+    for c in bom.into_iter().chain(text.chars()) {
+        let mut bytes = Vec::with_capacity(4);
+        encoding.encode_char_or(c, policy, &mut bytes)?;
+        if bytes.is_empty() {
+            continue;
+        }
+        if row_bytes > 0 && row_bytes + bytes.len() > width {
+            flush(&mut chars_row, &mut hex_row, &mut out);
+            row_bytes = 0;
+        }
+
+        // Each byte takes "XX " so a character owns 3 columns per byte
+        let span = bytes.len() * 3;
+        chars_row.push_str(&format!("{:^w$}", visible(c), w = span - 1));
+        chars_row.push(' ');
+        for b in &bytes {
+            hex_row.push_str(&format!("{:02X} ", b));
+        }
+        row_bytes += bytes.len();
+
+        if c == '\n' {
+            flush(&mut chars_row, &mut hex_row, &mut out);
+            row_bytes = 0;
+        }
+    }
+    if row_bytes > 0 {
+        flush(&mut chars_row, &mut hex_row, &mut out);
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ArrayStyle {
+    /// unsigned char array plus an unsigned int length, like xxd -i
+    C,
+    /// &[u8; N] constant plus a usize length constant
+    Rust,
+}
+
+// Turn a file name into an identifier: anything else becomes '_'
+pub fn identifier(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if id.is_empty() || id.starts_with(|c: char| c.is_ascii_digit()) {
+        id.insert(0, '_');
+    }
+    id
+}
+
+// The bytes as source code to embed, twelve per row as xxd -i does
+pub fn array_literal(bytes: &[u8], style: ArrayStyle, name: &str) -> String {
+    let rows: Vec<String> = bytes
+        .chunks(12)
+        .map(|row| row.iter().map(|b| format!("0x{:02x}", b)).collect::<Vec<_>>().join(", "))
+        .collect();
+
+    match style {
+        ArrayStyle::C => {
+            let name = identifier(name);
+            format!(
+                "unsigned char {name}[] = {{\n{}}};\nunsigned int {name}_len = {};\n",
+                rows.iter().map(|r| format!("  {},\n", r)).collect::<String>(),
+                bytes.len()
+            )
+        }
+        ArrayStyle::Rust => {
+            let name = identifier(name).to_uppercase();
+            format!(
+                "pub const {name}_LEN: usize = {};\npub const {name}: &[u8; {name}_LEN] = &[\n{}];\n",
+                bytes.len(),
+                rows.iter().map(|r| format!("    {},\n", r)).collect::<String>()
+            )
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use crate::render::TextRenderer;
+use crate::styled::{StyleId, StyledText};
+
+pub struct Html;
+
+impl TextRenderer for Html {
+    fn render_text(&self, doc: &StyledText) -> String {
+        to_html(doc)
+    }
+}
+
+// CSS class of a style: poemhl1, poemhl2, ...
+fn class_name(id: StyleId) -> String {
+    format!("poemhl{}", id + 1)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Standalone page; the verse sits in a <pre> so line breaks and any
+// alignment padding survive, and each style becomes a CSS class.
+pub fn to_html(doc: &StyledText) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n");
+    for (id, style) in doc.styles.iter().enumerate() {
+        out.push_str(&format!(".{} {{ color: {}; }}\n", class_name(id), style.color));
+    }
+    out.push_str("</style>\n</head>\n<body>\n<pre class=\"poem\">");
+
+    for (piece, id) in doc.segments() {
+        let Some(id) = id else {
+            out.push_str(&escape(piece));
+            continue;
+        };
+        let span = format!("<span class=\"{}\">{}</span>", class_name(id), escape(piece));
+        match &doc.style(id).link {
+            Some(url) => out.push_str(&format!("<a href=\"{}\">{}</a>", escape(url), span)),
+            None => out.push_str(&span),
+        }
+    }
+
+    out.push_str("</pre>\n</body>\n</html>\n");
+    out
+}
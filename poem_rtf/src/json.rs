@@ -0,0 +1,48 @@
+use crate::render::TextRenderer;
+use crate::styled::StyledText;
+
+// The span model itself, so other tools can edit it and hand it back:
+// {"text": "...", "spans": [{"range": {"start": 0, "end": 1}, "style": 0}],
+//  "styles": [{"color": "#cd6600"}]}
+pub struct Json;
+
+impl TextRenderer for Json {
+    fn render_text(&self, doc: &StyledText) -> String {
+        to_json(doc)
+    }
+}
+
+pub fn to_json(doc: &StyledText) -> String {
+    let mut out = serde_json::to_string_pretty(doc).expect("StyledText always serializes");
+    out.push('\n');
+    out
+}
+
+// Parse a document and check its spans, since the file may have been edited
+// by hand: every span must use a known style, sit on character boundaries
+// inside the text, and not overlap the previous one.
+pub fn from_json(json: &str) -> Result<StyledText, String> {
+    let mut doc: StyledText = serde_json::from_str(json).map_err(|e| format!("invalid JSON document: {}", e))?;
+
+    doc.spans.sort_by_key(|s| s.range.start);
+    let mut end = 0;
+    for span in &doc.spans {
+        let range = &span.range;
+        if span.style >= doc.styles.len() {
+            return Err(format!("span {:?} uses unknown style {}", range, span.style));
+        }
+        if range.start > range.end || range.end > doc.text.len() {
+            return Err(format!("span {:?} is outside the text ({} bytes)", range, doc.text.len()));
+        }
+        if !doc.text.is_char_boundary(range.start) || !doc.text.is_char_boundary(range.end) {
+            return Err(format!("span {:?} is not on character boundaries", range));
+        }
+        if range.start < end {
+            return Err(format!("span {:?} overlaps the span before it", range));
+        }
+        end = range.end;
+    }
+    doc.spans.retain(|s| !s.range.is_empty());
+    doc.snap_to_graphemes();
+    Ok(doc)
+}
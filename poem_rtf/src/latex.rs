@@ -0,0 +1,110 @@
+use clap::ValueEnum;
+
+use crate::render::TextRenderer;
+use crate::styled::{Rgb, StyleId, StyledText};
+
+// How the hidden letters are marked in LaTeX
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LatexStyle {
+    #[value(name = "textcolor")]
+    TextColor, // xcolor: coloured glyphs
+    Highlight, // soul: \hl background marker
+}
+
+// Renders a complete document, or just the fragment for pasting
+pub struct Latex {
+    pub style: LatexStyle,
+    pub standalone: bool,
+}
+
+impl TextRenderer for Latex {
+    fn render_text(&self, doc: &StyledText) -> String {
+        if self.standalone {
+            to_latex_document(doc, self.style)
+        } else {
+            to_latex_fragment(doc, self.style)
+        }
+    }
+}
+
+// xcolor name of a style: poemhl1, poemhl2, ...
+fn color_name(id: StyleId) -> String {
+    format!("poemhl{}", id + 1)
+}
+
+// Escape the characters LaTeX treats specially
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str(r"\textbackslash{}"),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '^' => out.push_str(r"\textasciicircum{}"),
+            '~' => out.push_str(r"\textasciitilde{}"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// The verse environment body: "\\" ends a line, a blank line ends a stanza
+// (the last line of each stanza must not carry "\\" or LaTeX complains).
+fn verse_body(doc: &StyledText, style: LatexStyle) -> String {
+    let lines = doc.lines();
+    let mut out = String::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            out.push('\n');
+            continue;
+        }
+
+        for &(piece, id) in line {
+            match (id, style) {
+                (None, _) => out.push_str(&escape(piece)),
+                (Some(id), LatexStyle::TextColor) => {
+                    out.push_str(&format!(r"\textcolor{{{}}}{{{}}}", color_name(id), escape(piece)))
+                }
+                (Some(id), LatexStyle::Highlight) => {
+                    out.push_str(&format!(r"{{\sethlcolor{{{}}}\hl{{{}}}}}", color_name(id), escape(piece)))
+                }
+            }
+        }
+
+        let stanza_ends = lines.get(i + 1).is_none_or(|next| next.is_empty());
+        if !stanza_ends {
+            out.push_str(r" \\");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// Fragment that can be pasted into an existing document.
+// Needs \usepackage{xcolor} (and \usepackage{soul} for LatexStyle::Highlight).
+pub fn to_latex_fragment(doc: &StyledText, style: LatexStyle) -> String {
+    let mut out = String::new();
+    for (id, s) in doc.styles.iter().enumerate() {
+        let Rgb(r, g, b) = s.color;
+        out.push_str(&format!("\\definecolor{{{}}}{{RGB}}{{{},{},{}}}\n", color_name(id), r, g, b));
+    }
+    out.push_str("\\begin{verse}\n");
+    out.push_str(&verse_body(doc, style));
+    out.push_str("\\end{verse}\n");
+    out
+}
+
+// Complete document that compiles on its own with pdflatex/xelatex
+pub fn to_latex_document(doc: &StyledText, style: LatexStyle) -> String {
+    let mut out = String::from("\\documentclass{article}\n\\usepackage[utf8]{inputenc}\n\\usepackage{xcolor}\n");
+    if style == LatexStyle::Highlight {
+        out.push_str("\\usepackage{soul}\n");
+    }
+    out.push_str("\n\\begin{document}\n\n");
+    out.push_str(&to_latex_fragment(doc, style));
+    out.push_str("\n\\end{document}\n");
+    out
+}
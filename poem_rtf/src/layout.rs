@@ -0,0 +1,149 @@
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::styled::{StyleId, StyledText};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// --width: a column count, or "term" for the width of the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Width {
+    Columns(usize),
+    Terminal,
+}
+
+impl FromStr for Width {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "term" | "auto" => Ok(Width::Terminal),
+            _ => s
+                .parse()
+                .map(Width::Columns)
+                .map_err(|_| format!("invalid width {:?}, expected a number or \"term\"", s)),
+        }
+    }
+}
+
+impl Width {
+    // Columns to lay out for; a terminal that cannot be measured counts as 80
+    pub fn columns(self) -> usize {
+        match self {
+            Width::Columns(n) => n,
+            Width::Terminal => terminal_width().unwrap_or(80),
+        }
+    }
+}
+
+// Width of the terminal on stdout, or $COLUMNS when stdout is not one
+pub fn terminal_width() -> Option<usize> {
+    if let Some((terminal_size::Width(w), _)) = terminal_size::terminal_size() {
+        return Some(w as usize);
+    }
+    std::env::var("COLUMNS").ok()?.parse().ok()
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutOptions {
+    pub width: Option<usize>, // wrap lines wider than this many terminal cells
+    pub align: Align,
+    pub hanging_indent: usize, // extra indent for the continuation of a wrapped line
+}
+
+// A line as grapheme clusters ("e" plus a combining accent, an emoji with
+// its modifiers, ...) with their styles, so nothing is ever cut in half
+type Line<'a> = Vec<(&'a str, Option<StyleId>)>;
+
+// Terminal cells a line takes up: East Asian wide characters take two,
+// combining marks none
+fn cells(line: &[(&str, Option<StyleId>)]) -> usize {
+    line.iter().map(|(g, _)| g.width()).sum()
+}
+
+// Greedy word wrap of one verse line. Continuation lines get the hanging
+// indent; a word wider than the available width is broken by force.
+fn wrap<'a>(line: &[(&'a str, Option<StyleId>)], width: usize, indent: usize) -> Vec<Line<'a>> {
+    let continuation_width = width.saturating_sub(indent).max(1);
+    let limit = |lines: &Vec<Line>| if lines.len() == 1 { width } else { continuation_width };
+    let mut out: Vec<Line> = vec![Vec::new()];
+
+    for (i, word) in line.split(|&(g, _)| g == " ").enumerate() {
+        let current = out.last().unwrap();
+        if i > 0 && !current.is_empty() {
+            if cells(current) + 1 + cells(word) <= limit(&out) {
+                out.last_mut().unwrap().push((" ", None));
+            } else {
+                out.push(Vec::new());
+            }
+        }
+        for &(g, style) in word {
+            let current = out.last().unwrap();
+            if !current.is_empty() && cells(current) + g.width() > limit(&out) {
+                out.push(Vec::new());
+            }
+            out.last_mut().unwrap().push((g, style));
+        }
+    }
+
+    for continuation in out.iter_mut().skip(1) {
+        continuation.splice(0..0, std::iter::repeat_n((" ", None), indent));
+    }
+    out
+}
+
+// Re-flow the text before any styling is rendered. Highlights travel with
+// their characters, so every backend sees the same wrapped layout. A cluster
+// takes the style of its first character.
+pub fn apply(doc: &StyledText, options: &LayoutOptions) -> StyledText {
+    let clusters: Vec<(&str, Option<StyleId>)> = doc
+        .text
+        .grapheme_indices(true)
+        .map(|(i, g)| (g, doc.style_at(i)))
+        .collect();
+    let trailing_newline = doc.text.ends_with('\n');
+    let body = if trailing_newline { &clusters[..clusters.len() - 1] } else { &clusters[..] };
+
+    let mut lines: Vec<Line> = Vec::new();
+    for line in body.split(|&(g, _)| g == "\n" || g == "\r\n") {
+        match options.width {
+            Some(width) if cells(line) > width => lines.extend(wrap(line, width, options.hanging_indent)),
+            _ => lines.push(line.to_vec()),
+        }
+    }
+
+    // Without a fixed width, align against the longest line
+    let width = options
+        .width
+        .unwrap_or_else(|| lines.iter().map(|l| cells(l)).max().unwrap_or(0));
+
+    let mut out = Vec::with_capacity(doc.text.len());
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            out.push(('\n', None));
+        }
+        let padding = match options.align {
+            _ if line.is_empty() => 0,
+            Align::Left => 0,
+            Align::Center => width.saturating_sub(cells(line)) / 2,
+            Align::Right => width.saturating_sub(cells(line)),
+        };
+        out.extend(std::iter::repeat_n((' ', None), padding));
+        for &(g, style) in line {
+            out.extend(g.chars().map(|c| (c, style)));
+        }
+    }
+    if trailing_newline {
+        out.push(('\n', None));
+    }
+
+    StyledText::from_styled_chars(out, doc.styles.clone())
+}
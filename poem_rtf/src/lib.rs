@@ -0,0 +1,43 @@
+// Styled-text toolkit behind the poem_rtf binary: a span model
+// (`styled::StyledText`), renderers for every output format, and the
+// analysis helpers used by the subcommands.
+
+pub mod analyze;
+pub mod ansi;
+pub mod banner;
+pub mod braille;
+pub mod cipher;
+pub mod clipboard;
+pub mod compare;
+pub mod config;
+pub mod diff;
+pub mod docx;
+pub mod encoding;
+pub mod firmware;
+pub mod hexdump;
+pub mod html;
+pub mod json;
+pub mod latex;
+pub mod layout;
+pub mod lyrics;
+pub mod markdown;
+pub mod meter;
+pub mod morse;
+pub mod normalize;
+pub mod output;
+pub mod pdf;
+pub mod poem;
+pub mod qr;
+pub mod raster;
+pub mod render;
+pub mod reveal;
+pub mod rhyme;
+pub mod rtf;
+pub mod styled;
+pub mod svg;
+pub mod typst;
+pub mod verify;
+pub mod watch;
+
+pub use render::{Renderer, TextRenderer};
+pub use styled::{Rgb, Span, Style, StyleId, StyledText};
@@ -0,0 +1,86 @@
+use crate::meter;
+use crate::render::TextRenderer;
+use crate::styled::StyledText;
+
+#[derive(Debug, Clone)]
+pub struct TimingOptions {
+    pub seconds_per_line: f32,
+    // When set, a line lasts this long per estimated syllable instead
+    pub seconds_per_syllable: Option<f32>,
+}
+
+// A verse line with its start and end in milliseconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue<'a> {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: &'a str,
+}
+
+// Lines follow each other without gaps; blank lines (stanza breaks) take
+// no time and get no cue
+pub fn cues<'a>(text: &'a str, options: &TimingOptions) -> Vec<Cue<'a>> {
+    let mut cues = Vec::new();
+    let mut at = 0;
+    for line in text.lines().map(str::trim_end).filter(|l| !l.trim().is_empty()) {
+        let seconds = match options.seconds_per_syllable {
+            Some(per) => per * meter::line_syllables(line).max(1) as f32,
+            None => options.seconds_per_line,
+        };
+        let end = at + (seconds * 1000.0).round() as u64;
+        cues.push(Cue { start_ms: at, end_ms: end, text: line });
+        at = end;
+    }
+    cues
+}
+
+// LRC: "[mm:ss.xx]line", one per line, plus an empty cue when the last line ends
+pub struct Lrc(pub TimingOptions);
+
+impl TextRenderer for Lrc {
+    fn render_text(&self, doc: &StyledText) -> String {
+        to_lrc(&cues(&doc.text, &self.0))
+    }
+}
+
+// SubRip: numbered blocks with "hh:mm:ss,mmm --> hh:mm:ss,mmm"
+pub struct Srt(pub TimingOptions);
+
+impl TextRenderer for Srt {
+    fn render_text(&self, doc: &StyledText) -> String {
+        to_srt(&cues(&doc.text, &self.0))
+    }
+}
+
+fn lrc_time(ms: u64) -> String {
+    format!("[{:02}:{:02}.{:02}]", ms / 60_000, ms / 1000 % 60, ms % 1000 / 10)
+}
+
+fn srt_time(ms: u64) -> String {
+    format!("{:02}:{:02}:{:02},{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+pub fn to_lrc(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for cue in cues {
+        out.push_str(&format!("{}{}\n", lrc_time(cue.start_ms), cue.text));
+    }
+    if let Some(last) = cues.last() {
+        out.push_str(&format!("{}\n", lrc_time(last.end_ms)));
+    }
+    out
+}
+
+pub fn to_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            srt_time(cue.start_ms),
+            srt_time(cue.end_ms),
+            cue.text
+        ));
+    }
+    out
+}
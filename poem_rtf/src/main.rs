@@ -1,45 +1,939 @@
-use std::fs::File;
-use std::io::Write;
+use std::io::{BufWriter, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use poem_rtf::ansi;
+use poem_rtf::braille::{self, BrailleSource};
+use poem_rtf::cipher::{self, CipherScope};
+use poem_rtf::clipboard::{self, CopySource};
+use poem_rtf::compare;
+use poem_rtf::config::{self, Config};
+use poem_rtf::encoding::{Encoding, Unmappable};
+use poem_rtf::firmware::{self, RecordFormat};
+use poem_rtf::hexdump::{ArrayStyle, HexWriter};
+use poem_rtf::layout::{self, Align, LayoutOptions, Width};
+use poem_rtf::latex::LatexStyle;
+use poem_rtf::lyrics::TimingOptions;
+use poem_rtf::normalize::{self, Normalization};
+use poem_rtf::output::{self, Format, RenderOptions};
+use poem_rtf::pdf::{PageSize, PdfOptions};
+use poem_rtf::qr::{self, QrSource};
+use poem_rtf::raster::PngOptions;
+use poem_rtf::reveal::{self, RevealMode};
+use poem_rtf::svg::SvgOptions;
+use poem_rtf::{analyze, banner, diff, hexdump, json, meter, morse, poem, rhyme, verify, watch};
+use poem_rtf::{Rgb, Style, StyledText};
+
+#[derive(Parser, Debug)]
+#[command(about = "Write the RUSTRUST poem with its hidden letters highlighted")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Formats to write (default: all; png only with --png-font)
+    #[arg(long, global = true, value_enum, value_delimiter = ',')]
+    formats: Vec<Format>,
+
+    /// Output file, directory, or file stem when several formats are written
+    #[arg(long, global = true)]
+    out: Option<PathBuf>,
+
+    /// Write the single requested format to stdout instead of a file
+    #[arg(long, global = true)]
+    stdout: bool,
+
+    /// Font family for the SVG rendering
+    #[arg(long, default_value = "Georgia, serif")]
+    font_family: String,
+
+    /// Font size in pixels for the SVG rendering
+    #[arg(long, default_value_t = 20.0)]
+    font_size: f32,
+
+    /// Line spacing for the SVG and PNG renderings, as a multiple of the font size
+    #[arg(long, default_value_t = 1.4)]
+    line_spacing: f32,
+
+    /// TrueType/OpenType font used to rasterize the PNG output
+    #[arg(long)]
+    png_font: Option<PathBuf>,
+
+    /// Font size in pixels for the PNG rendering (sets the image resolution)
+    #[arg(long, default_value_t = 32.0)]
+    png_font_size: f32,
+
+    /// Background colour of the PNG rendering
+    #[arg(long, default_value = "#ffffff")]
+    background: Rgb,
+
+    /// PDF page size: a4, a5, letter, legal, or WIDTHxHEIGHT in millimetres
+    #[arg(long, default_value = "a4")]
+    page_size: PageSize,
+
+    /// PDF page margin in millimetres
+    #[arg(long, default_value_t = 25.0)]
+    margin_mm: f32,
+
+    /// Font size in points for the PDF rendering
+    #[arg(long, default_value_t = 12.0)]
+    pdf_font_size: f32,
+
+    /// Seconds each line is shown in the LRC and SRT exports
+    #[arg(long, default_value_t = 3.0)]
+    seconds_per_line: f32,
+
+    /// Time lines by their estimated syllables instead, this many seconds each
+    #[arg(long)]
+    seconds_per_syllable: Option<f32>,
+
+    /// How the LaTeX output marks the hidden letters: coloured glyphs, or a
+    /// soul \hl background
+    #[arg(long, value_enum, default_value_t = LatexStyle::TextColor)]
+    latex_style: LatexStyle,
+
+    /// Colour of the hidden letters
+    #[arg(long, global = true, default_value = "#cd6600")]
+    color: Rgb,
+
+    /// TOML file with highlight positions and default formats
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Unicode normalization applied to the text before highlighting and dumping
+    #[arg(long, global = true, value_enum)]
+    normalize: Option<Normalization>,
+
+    /// Shift letters N places along the alphabet (13 for ROT13); decode shifts them back
+    #[arg(long, global = true)]
+    rot: Option<u8>,
+
+    /// Whether --rot obfuscates the whole poem or only the hidden letters
+    #[arg(long, global = true, value_enum, default_value_t = CipherScope::Text)]
+    rot_scope: CipherScope,
+
+    /// Re-render whenever the input or config file changes
+    #[arg(long, global = true)]
+    watch: bool,
+
+    /// Copy the poem (ANSI by default), its plain text or the hidden message
+    /// to the clipboard with an OSC 52 escape sequence
+    #[arg(long, global = true, value_enum, num_args = 0..=1, default_missing_value = "ansi")]
+    copy: Option<CopySource>,
+
+    /// Append a large banner of the hidden message to the terminal output
+    #[arg(long, global = true)]
+    banner: bool,
+
+    /// Use this hidden message instead of the highlighted letters
+    #[arg(long, global = true)]
+    message: Option<String>,
+
+    /// Wrap verse lines wider than this many terminal cells ("term": the terminal width)
+    #[arg(long, global = true)]
+    width: Option<Width>,
+
+    /// Alignment of each line within the width (or the longest line)
+    #[arg(long, global = true, value_enum, default_value_t = Align::Left)]
+    align: Align,
+
+    /// Extra indent for the continuation of a wrapped line
+    #[arg(long, global = true, default_value_t = 4)]
+    hanging_indent: usize,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Reveal the hidden letters one by one
+    Reveal {
+        #[arg(long, value_enum, default_value_t = RevealMode::Terminal)]
+        mode: RevealMode,
+
+        /// Pause between steps in milliseconds
+        #[arg(long, default_value_t = 400)]
+        delay_ms: u64,
+    },
+    /// Encode the poem or its hidden message as a QR code
+    Qr {
+        #[arg(long, value_enum, default_value_t = QrSource::Ansi)]
+        source: QrSource,
+
+        /// Write a PNG instead of drawing the code in the terminal
+        #[arg(long)]
+        png: Option<PathBuf>,
+
+        /// Pixels per module in the PNG
+        #[arg(long, default_value_t = 8)]
+        scale: usize,
+
+        /// Draw dark modules instead of light ones (for light terminals)
+        #[arg(long)]
+        invert: bool,
+    },
+    /// Recover the hidden message from an ANSI-coloured file
+    Decode {
+        /// File written by the default mode
+        #[arg(default_value = "poem_ansi_utf8.txt")]
+        file: PathBuf,
+    },
+    /// Report letter/word frequencies, line lengths and highlight positions
+    Analyze {
+        /// Poem to analyze (brace markup, ANSI or JSON); defaults to the built-in poem
+        file: Option<PathBuf>,
+
+        /// Print JSON instead of tables
+        #[arg(long)]
+        json: bool,
+
+        /// How many entries of each frequency table to show
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Label the rhyme scheme from the line endings
+    Rhyme {
+        /// Poem to examine (brace markup, ANSI or JSON); defaults to the built-in poem
+        file: Option<PathBuf>,
+
+        /// Colour each line by its rhyme group
+        #[arg(long)]
+        colorize: bool,
+    },
+    /// Estimate syllables per line and flag lines that miss the target
+    Meter {
+        /// Poem to scan (brace markup, ANSI or JSON); defaults to the built-in poem
+        file: Option<PathBuf>,
+
+        /// Expected syllables per line (10 for pentameter)
+        #[arg(long, default_value_t = 10)]
+        target: usize,
+
+        /// Allowed difference before a line is flagged
+        #[arg(long, default_value_t = 0)]
+        tolerance: usize,
+
+        /// Print the coloured poem with a count after every line
+        #[arg(long)]
+        annotate: bool,
+    },
+    /// Hex dump of the ANSI bytes, escapes and hidden letters coloured apart
+    Hex {
+        /// ANSI file to dump ("-" for stdin); defaults to the built-in poem
+        file: Option<PathBuf>,
+
+        /// Leave out this many bytes at the start
+        #[arg(long, default_value_t = 0)]
+        skip: u64,
+
+        /// Dump at most this many bytes
+        #[arg(long)]
+        length: Option<u64>,
+
+        /// Start each row with its byte offset (plain byte dumps only)
+        #[arg(long)]
+        offsets: bool,
+
+        /// Print the bytes as a C or Rust array literal instead
+        #[arg(long, value_enum)]
+        array: Option<ArrayStyle>,
+
+        /// Print the bytes as Intel HEX or Motorola S-records instead
+        #[arg(long, value_enum, conflicts_with = "array")]
+        records: Option<RecordFormat>,
+
+        /// Load address of the first byte in --records output
+        #[arg(long, value_parser = firmware::parse_address, default_value = "0")]
+        base_address: u32,
+
+        /// Name of the array or S-record header (default: the file name, or "poem")
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Show each row of source characters above the hex of its bytes
+        #[arg(long)]
+        interleave: bool,
+
+        /// Re-encode the text before dumping it
+        #[arg(long, value_enum, default_value_t = Encoding::Utf8)]
+        encoding: Encoding,
+
+        /// Start with the encoding's byte order mark
+        #[arg(long)]
+        bom: bool,
+
+        /// What to do with characters the encoding cannot represent
+        #[arg(long, value_enum, default_value_t = Unmappable::Replace)]
+        unmappable: Unmappable,
+
+        /// Dump the plain text without the ANSI escapes (e.g. for Latin-1 or EBCDIC)
+        #[arg(long)]
+        plain: bool,
+    },
+    /// Compare bytes with a golden file and show hex context around each difference
+    Compare {
+        /// Reference: a hex dump like poem_ansi_hex.txt, or raw bytes
+        reference: PathBuf,
+
+        /// Bytes to check (dump or raw); defaults to a fresh ANSI render of the poem
+        actual: Option<PathBuf>,
+
+        /// Bytes of context on either side of a difference
+        #[arg(long, default_value_t = 8)]
+        context: usize,
+
+        /// Stop reporting after this many differences
+        #[arg(long, default_value_t = 10)]
+        max_diffs: usize,
+    },
+    /// Compare two versions of a poem letter by letter, in the terminal or
+    /// written out in --formats
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+    },
+    /// Spell the hidden message in Morse code
+    Morse {
+        /// Also write the tone/gap durations ("on 60", "off 180", ...) to this file
+        #[arg(long)]
+        timings: Option<PathBuf>,
+
+        /// Length of a dot in milliseconds (60 is about 20 words per minute)
+        #[arg(long, default_value_t = 60)]
+        unit_ms: u64,
+    },
+    /// Print the poem or its hidden message in Unicode braille
+    Braille {
+        #[arg(long, value_enum, default_value_t = BrailleSource::Poem)]
+        source: BrailleSource,
+    },
+    /// Write every requested format from one annotated document
+    Render {
+        /// Brace markup, ANSI or a JSON span model
+        input: PathBuf,
+    },
+    /// Check written outputs against each other and against a fresh render;
+    /// looks for them where the same --formats/--out would write them
+    Verify {
+        /// Source the outputs were rendered from; defaults to the built-in poem
+        input: Option<PathBuf>,
+    },
+    /// Render every .txt, .md and .json poem under a directory with the shared
+    /// --config, mirroring the directory tree under --out (default "out")
+    Batch {
+        dir: PathBuf,
+    },
+}
 
 fn main() -> std::io::Result<()> {
-    // 24-bit ANSI true-color escape sequences
-    const ORANGE: &str = "\x1b[38;2;205;102;0m";
-    const RESET:  &str = "\x1b[0m";
-
-    // Poem with only selected letters wrapped in ANSI color
-    let poem = format!(
-"Registers under s{orange}h{reset}adowed threads,
-Units signal t{orange}i{reset}mes return,
-Structures trace res{orange}p{reset}lendent unknowns,
-Tokens recode un{orange}i{reset}versal speech.
-
-Realms unfold s{orange}e{reset}amless trains,
-Universe spins, th{orange}r{reset}eads recur,
-Signals transform, {orange}r{reset}unes unbound,
-Truth reiterates unifi{orange}e{reset}d syntax.
-",
-        orange = ORANGE,
-        reset = RESET
-    );
+    let cli = Cli::parse();
+
+    // Default mode and `render` prepare their own document, once per run
+    let command = match cli.command {
+        None => return run(&cli, None),
+        Some(Command::Render { ref input }) => return run(&cli, Some(input)),
+        Some(Command::Batch { ref dir }) => return batch(&cli, dir),
+        Some(Command::Verify { ref input }) => return verify(&cli, input.as_deref()),
+        Some(ref command) => command,
+    };
 
-    // Write the normal ANSI version (optional)
-    std::fs::write("poem_ansi_utf8.txt", &poem)?;
+    // Poem with only selected letters highlighted
+    let (poem, unplaced) = prepare(&cli, None, &load_config(&cli)?)?;
+    warn_unplaced(&unplaced);
+    let layout_options = layout_options(&cli);
+    let message = cli.message.clone().unwrap_or_else(|| poem.hidden_message());
 
-    // Write the hex-encoded version
-    let mut hex_file = File::create("poem_ansi_hex.txt")?;
-    for (i, b) in poem.as_bytes().iter().enumerate() {
-        write!(hex_file, "{:02X} ", b)?;          // two-digit hex per byte
-        if (i + 1) % 16 == 0 {
-            writeln!(hex_file)?;                  // break line every 16 bytes
+    match *command {
+        Command::Reveal { mode, delay_ms } => {
+            let delay = Duration::from_millis(delay_ms);
+            match mode {
+                RevealMode::Terminal => {
+                    reveal::play(&poem, delay)?;
+                    print_banner(&cli, &message, cli.color);
+                    Ok(())
+                }
+                RevealMode::Script => {
+                    print!("{}", reveal::script(&poem, delay));
+                    Ok(())
+                }
+            }
+        }
+        Command::Qr { source, ref png, scale, invert } => {
+            let data = match source {
+                QrSource::Ansi => ansi::to_ansi(&poem),
+                QrSource::Message => message,
+            };
+            let code = qr::encode(data.as_bytes())?;
+            match png {
+                Some(path) => {
+                    qr::write_png(&code, scale, path)?;
+                    println!("Wrote {}", path.display());
+                }
+                None => print!("{}", qr::to_unicode(&code, invert)),
+            }
+            Ok(())
+        }
+        Command::Decode { ref file } => {
+            let decoded = ansi::parse(&std::fs::read_to_string(file)?);
+            let mut message = cli.message.clone().unwrap_or_else(|| decoded.hidden_message());
+            if let Some(shift) = cli.rot {
+                message = cipher::unrotate(&message, shift);
+            }
+            println!("{}", message);
+            print_banner(&cli, &message, decoded.primary_color().unwrap_or(cli.color));
+            Ok(())
+        }
+        Command::Analyze { ref file, json, top } => {
+            let poem = match file {
+                Some(path) => layout::apply(&load(&cli, path)?, &layout_options),
+                None => poem,
+            };
+            let report = analyze::analyze(&poem);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", analyze::to_table(&report, top));
+            }
+            Ok(())
+        }
+        Command::Rhyme { ref file, colorize } => {
+            let poem = match file {
+                Some(path) => load(&cli, path)?,
+                None => poem,
+            };
+            let labels = rhyme::scheme(&poem.text);
+            print!("{}", rhyme::annotate(&poem.text, &labels, colorize));
+            println!("\nRhyme scheme: {}", rhyme::summary(&labels));
+            Ok(())
         }
+        Command::Meter { ref file, target, tolerance, annotate } => {
+            let poem = match file {
+                Some(path) => load(&cli, path)?,
+                None => poem,
+            };
+            let counts = meter::scan(&poem.text);
+            if annotate {
+                print!("{}", meter::annotate(&ansi::to_ansi(&poem), &counts, target, tolerance));
+            } else {
+                for (i, count) in counts.iter().enumerate() {
+                    if let Some(n) = count {
+                        println!("{:>3}: {:>2} syllables", i + 1, n);
+                    }
+                }
+            }
+
+            let off = meter::deviations(&counts, target, tolerance);
+            if off.is_empty() {
+                println!("\nAll lines within {} of {} syllables", tolerance, target);
+            } else {
+                let lines: Vec<String> = off.iter().map(|l| l.to_string()).collect();
+                println!("\nLines off the {}-syllable target: {}", target, lines.join(", "));
+            }
+            Ok(())
+        }
+        Command::Hex {
+            ref file,
+            skip,
+            length,
+            offsets,
+            array,
+            records,
+            base_address,
+            ref name,
+            interleave,
+            encoding,
+            bom,
+            unmappable,
+            plain,
+        } => {
+            let mut input: Box<dyn Read> = match file {
+                Some(path) if path == Path::new("-") => Box::new(std::io::stdin().lock()),
+                Some(path) => Box::new(std::fs::File::open(path)?),
+                None => Box::new(Cursor::new(ansi::to_ansi(&poem).into_bytes())),
+            };
+            std::io::copy(&mut input.by_ref().take(skip), &mut std::io::sink())?;
+            let mut input = input.take(length.unwrap_or(u64::MAX));
+
+            // A plain byte dump streams in constant memory; the other modes
+            // work on the whole text
+            let streaming = array.is_none() && records.is_none() && !interleave && encoding == Encoding::Utf8 && !bom && !plain;
+            if streaming && (file.is_none() || cli.normalize.is_none()) {
+                let mut writer = HexWriter::new(BufWriter::new(std::io::stdout().lock()), skip, offsets, Some(cli.color));
+                writer.copy_from(&mut input)?;
+                writeln!(writer.finish()?)?;
+                return Ok(());
+            }
+            if offsets {
+                return Err(std::io::Error::other("--offsets only applies to the plain byte dump"));
+            }
+
+            let mut bytes = Vec::new();
+            input.read_to_end(&mut bytes)?;
+            if file.is_some()
+                && let Some(form) = cli.normalize
+                && let Ok(text) = std::str::from_utf8(&bytes)
+            {
+                bytes = normalize::normalize_text(text, form).into_bytes();
+            }
+            let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+            if array.is_some() || records.is_some() {
+                // These hold the bytes as they are, so the encoding options
+                // still apply
+                if encoding != Encoding::Utf8 || bom || plain {
+                    let mut text = String::from_utf8(bytes)
+                        .map_err(|_| invalid("--encoding, --bom and --plain need UTF-8 input".to_string()))?;
+                    if plain {
+                        text = ansi::parse(&text).text;
+                    }
+                    bytes = encoding.encode(&text, bom, unmappable).map_err(invalid)?;
+                }
+                let default_name = match file {
+                    Some(path) if path != Path::new("-") => path.file_name().unwrap_or_default().to_string_lossy(),
+                    _ => "poem".into(),
+                };
+                let name = name.as_deref().unwrap_or(&default_name);
+                let out = match (array, records) {
+                    (Some(style), _) => hexdump::array_literal(&bytes, style, name),
+                    (_, Some(RecordFormat::Ihex)) => firmware::to_ihex(&bytes, base_address).map_err(invalid)?,
+                    (_, Some(RecordFormat::Srec)) => firmware::to_srec(&bytes, base_address, name).map_err(invalid)?,
+                    (None, None) => unreachable!(),
+                };
+                print!("{}", out);
+            } else if interleave {
+                let mut text = String::from_utf8_lossy(&bytes).into_owned();
+                if plain {
+                    text = ansi::parse(&text).text;
+                }
+                let dump = hexdump::dump_interleaved(&text, 16, encoding, bom, unmappable).map_err(invalid)?;
+                print!("{}", dump);
+            } else if streaming {
+                println!("{}", hexdump::dump_colored(&bytes, cli.color));
+            } else {
+                // Re-encoding goes through the text, so the input must be UTF-8
+                let mut text = String::from_utf8(bytes)
+                    .map_err(|_| invalid("--encoding, --bom and --plain need UTF-8 input".to_string()))?;
+                if plain {
+                    text = ansi::parse(&text).text;
+                }
+                let dump = hexdump::dump_colored_encoded(&text, encoding, bom, unmappable, cli.color).map_err(invalid)?;
+                println!("{}", dump);
+            }
+            Ok(())
+        }
+        Command::Compare { ref reference, ref actual, context, max_diffs } => {
+            let expected = read_bytes_or_dump(reference)?;
+            let actual = match actual {
+                Some(path) => read_bytes_or_dump(path)?,
+                None => ansi::to_ansi(&poem).into_bytes(),
+            };
+
+            let ranges = compare::mismatches(&expected, &actual);
+            for range in ranges.iter().take(max_diffs) {
+                println!("{}", compare::report(&expected, &actual, range, context));
+            }
+            if ranges.len() > max_diffs {
+                println!("... and {} more", ranges.len() - max_diffs);
+            }
+            if expected.len() != actual.len() {
+                println!("Lengths differ: expected {} bytes, got {}", expected.len(), actual.len());
+            }
+            if ranges.is_empty() {
+                println!("{} bytes identical", expected.len());
+                Ok(())
+            } else {
+                let differing: usize = ranges.iter().map(|r| r.len()).sum();
+                Err(std::io::Error::other(format!("{} bytes differ in {} places", differing, ranges.len())))
+            }
+        }
+        Command::Diff { ref old, ref new } => {
+            // Highlights are dropped so only the visible text is compared
+            let old = load(&cli, old)?;
+            let new = load(&cli, new)?;
+            let changes = diff::diff(&old.text, &new.text);
+            let doc = diff::to_styled(&changes);
+            if cli.formats.is_empty() {
+                let options = render_options(&cli)?;
+                std::io::stdout().write_all(&Format::Ansi.renderer(&options)?.render(&doc)?)?;
+            } else {
+                write_files(&cli, &doc, &[])?;
+            }
+
+            let (inserted, deleted, changed) = diff::summary(&changes);
+            println!(
+                "\n{} inserted, {} deleted, {} changed letters",
+                inserted, deleted, changed
+            );
+            Ok(())
+        }
+        Command::Morse { ref timings, unit_ms } => {
+            println!("{}", morse::to_text(&message));
+            if let Some(path) = timings {
+                let steps = morse::timings(&message, unit_ms);
+                std::fs::write(path, morse::to_timing_file(&steps))?;
+                let total: u64 = steps.iter().map(|&(_, ms)| ms).sum();
+                println!("Wrote {} ({} steps, {} ms)", path.display(), steps.len(), total);
+            }
+            Ok(())
+        }
+        Command::Braille { source } => {
+            let text = match source {
+                BrailleSource::Poem => poem.text,
+                BrailleSource::Message => message + "\n",
+            };
+            print!("{}", braille::to_braille(&text));
+            Ok(())
+        }
+        Command::Render { .. } | Command::Batch { .. } | Command::Verify { .. } => unreachable!(),
+    }
+}
+
+fn layout_options(cli: &Cli) -> LayoutOptions {
+    LayoutOptions {
+        width: cli.width.map(Width::columns),
+        align: cli.align,
+        hanging_indent: cli.hanging_indent,
+    }
+}
+
+fn load_config(cli: &Cli) -> std::io::Result<Config> {
+    match &cli.config {
+        Some(path) => config::load(path),
+        None => Ok(Config::default()),
+    }
+}
+
+// The document to write: the input file (or the built-in poem in --color)
+// with the config highlights added, then laid out. Layout happens before
+// styling so every output shares it. Also returns the highlight positions
+// that did not fit the text.
+fn prepare(cli: &Cli, input: Option<&Path>, config: &Config) -> std::io::Result<(StyledText, Vec<String>)> {
+    let mut doc = match input {
+        Some(path) => load(cli, path)?,
+        None => {
+            let mut poem = poem::poem();
+            for style in &mut poem.styles {
+                style.color = cli.color;
+            }
+            normalized(cli, poem)
+        }
+    };
+
+    let unplaced = config
+        .apply(&mut doc, cli.color)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    if let Some(shift) = cli.rot {
+        doc = cipher::apply(&doc, shift, cli.rot_scope);
+    }
+
+    Ok((layout::apply(&doc, &layout_options(cli)), unplaced))
+}
+
+fn warn_unplaced(unplaced: &[String]) {
+    for spec in unplaced {
+        eprintln!("warning: highlight {} is outside the text", spec);
+    }
+}
+
+// Default mode and `render`: write the files once, or on every change with --watch
+fn run(cli: &Cli, input: Option<&Path>) -> std::io::Result<()> {
+    if !cli.watch {
+        return emit(cli, input);
+    }
+    let paths: Vec<PathBuf> = input.map(Path::to_path_buf).into_iter().chain(cli.config.clone()).collect();
+    if paths.is_empty() {
+        return Err(std::io::Error::other("--watch needs an input file or --config"));
+    }
+    watch::watch(&paths, || emit(cli, input))
+}
+
+// Parsed and laid out once, so all formats show the same spans
+fn emit(cli: &Cli, input: Option<&Path>) -> std::io::Result<()> {
+    let config = load_config(cli)?;
+    let (doc, unplaced) = prepare(cli, input, &config)?;
+    warn_unplaced(&unplaced);
+    let message = cli.message.clone().unwrap_or_else(|| doc.hidden_message());
+    write_files(cli, &doc, &config.formats)?;
+    if cli.watch {
+        print!("\n{}", ansi::to_ansi(&doc));
+    }
+    copy_to_clipboard(cli, &doc, &message)?;
+    print_banner(cli, &message, doc.primary_color().unwrap_or(cli.color));
+    Ok(())
+}
+
+// Read a poem file: a .json span model, ANSI output, or brace markup in the --color style
+fn load(cli: &Cli, path: &Path) -> std::io::Result<StyledText> {
+    let contents = std::fs::read_to_string(path)?;
+    if path.extension().is_some_and(|ext| ext == "json") {
+        return json::from_json(&contents)
+            .map(|doc| normalized(cli, doc))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)));
+    }
+    Ok(normalized(cli, StyledText::load(&contents, Style::new(cli.color))))
+}
+
+// Apply --normalize. Highlight positions count characters of the normalized
+// text, so a change in length is worth a warning.
+fn normalized(cli: &Cli, doc: StyledText) -> StyledText {
+    let Some(form) = cli.normalize else {
+        return doc;
+    };
+    let normal = normalize::normalize(&doc, form);
+    if normal.text.len() != doc.text.len() {
+        eprintln!(
+            "warning: {} normalization changes the text from {} to {} bytes; highlight positions refer to the normalized text",
+            form,
+            doc.text.len(),
+            normal.text.len()
+        );
+    }
+    normal
+}
+
+// A hex dump (as written by the hex format) is decoded, anything else is
+// taken as the bytes themselves
+fn read_bytes_or_dump(path: &Path) -> std::io::Result<Vec<u8>> {
+    let bytes = std::fs::read(path)?;
+    match std::str::from_utf8(&bytes).ok().map(verify::hex_decode) {
+        Some(Ok(decoded)) if !decoded.is_empty() => Ok(decoded),
+        _ => Ok(bytes),
+    }
+}
+
+fn copy_to_clipboard(cli: &Cli, doc: &StyledText, message: &str) -> std::io::Result<()> {
+    if let Some(source) = cli.copy {
+        clipboard::copy(&source.contents(doc, message))?;
+    }
+    Ok(())
+}
+
+fn print_banner(cli: &Cli, message: &str, color: Rgb) {
+    if cli.banner {
+        print!("\n{}", banner::render_ansi(message, color));
+    }
+}
+
+// Default mode: write the requested formats to files (or stdout)
+// Without --formats the config decides; without either every format is
+// written, PNG only when a font is given
+fn formats(cli: &Cli, config_formats: &[Format]) -> Vec<Format> {
+    if !cli.formats.is_empty() {
+        cli.formats.clone()
+    } else if !config_formats.is_empty() {
+        config_formats.to_vec()
+    } else {
+        Format::value_variants()
+            .iter()
+            .copied()
+            .filter(|&f| f != Format::Png || cli.png_font.is_some())
+            .collect()
+    }
+}
+
+fn render_options(cli: &Cli) -> std::io::Result<RenderOptions> {
+    Ok(RenderOptions {
+        svg: SvgOptions {
+            font_family: cli.font_family.clone(),
+            font_size: cli.font_size,
+            line_spacing: cli.line_spacing,
+        },
+        png: PngOptions {
+            font_size: cli.png_font_size,
+            line_spacing: cli.line_spacing,
+            background: cli.background,
+            foreground: Rgb(0, 0, 0),
+        },
+        pdf: PdfOptions {
+            page: cli.page_size,
+            margin: cli.margin_mm * 72.0 / 25.4,
+            font_size: cli.pdf_font_size,
+            line_spacing: cli.line_spacing,
+        },
+        png_font: cli.png_font.as_ref().map(std::fs::read).transpose()?,
+        timing: TimingOptions {
+            seconds_per_line: cli.seconds_per_line,
+            seconds_per_syllable: cli.seconds_per_syllable,
+        },
+        latex_style: cli.latex_style,
+    })
+}
+
+fn write_files(cli: &Cli, poem: &StyledText, config_formats: &[Format]) -> std::io::Result<()> {
+    let formats = formats(cli, config_formats);
+    let options = render_options(cli)?;
+
+    if cli.stdout {
+        let [format] = formats[..] else {
+            return Err(std::io::Error::other("--stdout needs exactly one --formats entry"));
+        };
+        return std::io::stdout().write_all(&output::render(format, poem, &options)?);
+    }
+
+    let paths = output::output_paths(&formats, cli.out.as_deref());
+    for (&format, path) in formats.iter().zip(&paths) {
+        std::fs::write(path, output::render(format, poem, &options)?)?;
     }
-    writeln!(
-        hex_file,
-        "\n# Each pair above is one UTF-8 byte of the ANSI-colored poem."
-    )?;
 
     println!("Generated:");
-    println!("  poem_ansi_utf8.txt  → visible text with ANSI colors");
-    println!("  poem_ansi_hex.txt   → hex dump of the same data");
+    let width = paths.iter().map(|p| p.display().to_string().chars().count()).max().unwrap_or(0);
+    for (format, path) in formats.iter().zip(&paths) {
+        println!("  {:<width$} → {}", path.display().to_string(), format.description());
+    }
+    Ok(())
+}
+
+// Poem files under `dir`, skipping hidden entries and the output tree
+fn collect_poems(dir: &Path, skip: &Path, found: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if hidden || path.canonicalize().is_ok_and(|p| p == skip) {
+            continue;
+        }
+        if path.is_dir() {
+            collect_poems(&path, skip, found)?;
+        } else if path.extension().is_some_and(|ext| ext == "txt" || ext == "md" || ext == "json") {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+// `batch`: dir/a/b.txt becomes out/a/b_ansi.txt, out/a/b.html, ... One bad
+// file does not stop the run; the summary lists it and the exit status says so.
+fn batch(cli: &Cli, dir: &Path) -> std::io::Result<()> {
+    let out_root = cli.out.clone().unwrap_or_else(|| PathBuf::from("out"));
+    let config = load_config(cli)?;
+    let formats = formats(cli, &config.formats);
+    let options = render_options(cli)?;
+
+    std::fs::create_dir_all(&out_root)?;
+    let mut files = Vec::new();
+    collect_poems(dir, &out_root.canonicalize()?, &mut files)?;
+    files.sort();
+
+    let mut incomplete = Vec::new();
+    let mut failed = 0;
+    for file in &files {
+        let relative = file.strip_prefix(dir).unwrap_or(file);
+        let stem = out_root.join(relative).with_extension("");
+        let written = prepare(cli, Some(file), &config).and_then(|(doc, unplaced)| {
+            if let Some(parent) = stem.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let name = stem.file_name().unwrap_or_default().to_string_lossy();
+            for &format in &formats {
+                let path = stem.with_file_name(format.derived_filename(&name));
+                std::fs::write(path, output::render(format, &doc, &options)?)?;
+            }
+            Ok(unplaced)
+        });
+        match written {
+            Ok(unplaced) if unplaced.is_empty() => println!("  ok      {}", relative.display()),
+            Ok(unplaced) => {
+                println!("  partial {} ({} highlights not placed)", relative.display(), unplaced.len());
+                incomplete.push((relative, unplaced));
+            }
+            Err(e) => {
+                println!("  failed  {}: {}", relative.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "\n{} files, {} formats each, written under {}",
+        files.len(),
+        formats.len(),
+        out_root.display()
+    );
+    if !incomplete.is_empty() {
+        println!("Highlights that did not fit the text:");
+        for (file, unplaced) in &incomplete {
+            println!("  {}: {}", file.display(), unplaced.join(", "));
+        }
+    }
+    if failed > 0 {
+        return Err(std::io::Error::other(format!("{} of {} files failed", failed, files.len())));
+    }
+    Ok(())
+}
+
+// `verify`: every output found is decoded back where possible and compared
+// with the source, the hex dump with the ANSI file, and each file's CRC-32
+// with a fresh render. Any mismatch makes the exit status nonzero.
+fn verify(cli: &Cli, input: Option<&Path>) -> std::io::Result<()> {
+    let config = load_config(cli)?;
+    let (doc, _) = prepare(cli, input, &config)?;
+    let formats = formats(cli, &config.formats);
+    let options = render_options(cli)?;
+    let paths = output::output_paths(&formats, cli.out.as_deref());
+
+    let mut failures = 0;
+    let mut report = |ok: bool, path: &Path, what: &str| {
+        println!("  {}  {}: {}", if ok { "ok  " } else { "FAIL" }, path.display(), what);
+        if !ok {
+            failures += 1;
+        }
+    };
+
+    let ansi_bytes = formats
+        .iter()
+        .position(|&f| f == Format::Ansi)
+        .and_then(|i| std::fs::read(&paths[i]).ok());
+
+    for (&format, path) in formats.iter().zip(&paths) {
+        let Ok(bytes) = std::fs::read(path) else {
+            println!("  skip  {}: not found", path.display());
+            continue;
+        };
+        let contents = String::from_utf8_lossy(&bytes);
+
+        match format {
+            Format::Ansi => {
+                let decoded = ansi::parse(&contents);
+                report(decoded.text == doc.text, path, "plain text matches the source");
+                report(
+                    decoded.hidden_message() == doc.hidden_message(),
+                    path,
+                    "hidden letters match the source",
+                );
+            }
+            Format::Hex => match (verify::hex_decode(&contents), &ansi_bytes) {
+                (Ok(decoded), Some(ansi)) => report(decoded == *ansi, path, "decodes to the ANSI file"),
+                (Ok(_), None) => println!("  skip  {}: no ANSI file to compare with", path.display()),
+                (Err(e), _) => report(false, path, &e),
+            },
+            Format::Html => {
+                let text = verify::html_text(&contents);
+                report(text.as_deref() == Some(&doc.text), path, "plain text matches the source");
+            }
+            Format::Rtf => report(verify::rtf_text(&contents) == doc.text, path, "plain text matches the source"),
+            Format::Json => match json::from_json(&contents) {
+                Ok(decoded) => report(
+                    decoded.text == doc.text && decoded.spans == doc.spans,
+                    path,
+                    "text and spans match the source",
+                ),
+                Err(e) => report(false, path, &e),
+            },
+            _ => {}
+        }
+
+        if format != Format::Png || options.png_font.is_some() {
+            let fresh = output::render(format, &doc, &options)?;
+            let (expected, found) = (verify::crc32(&fresh), verify::crc32(&bytes));
+            report(
+                expected == found,
+                path,
+                &format!("CRC-32 {:08x}, fresh render {:08x}", found, expected),
+            );
+        }
+    }
+
+    if failures > 0 {
+        return Err(std::io::Error::other(format!("{} checks failed", failures)));
+    }
+    println!("All checks passed");
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,72 @@
+use crate::render::TextRenderer;
+use crate::styled::StyledText;
+
+pub struct Markdown;
+
+impl TextRenderer for Markdown {
+    fn render_text(&self, doc: &StyledText) -> String {
+        to_markdown(doc)
+    }
+}
+
+// Backslash-escape what Markdown would read as formatting, and make the
+// HTML-significant characters entities since the spans are raw HTML
+fn escape(text: &str, line_start: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '|' | '~' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            // Headings, lists and quotes only matter at the start of a line
+            '#' | '-' | '+' | '=' if line_start && i == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            // Leading spaces would be dropped or start an indented code block
+            ' ' if line_start && text[..i].bytes().all(|b| b == b' ') => out.push_str("&nbsp;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// Each verse line ends in "\" (a hard line break in CommonMark and GFM) except
+// the last of a stanza; stanzas stay separate paragraphs. Highlights are
+// inline <span style="color: ...">, which GitHub-style renderers accept.
+pub fn to_markdown(doc: &StyledText) -> String {
+    let lines = doc.lines();
+    let mut out = String::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            out.push('\n');
+            continue;
+        }
+
+        for (j, &(piece, id)) in line.iter().enumerate() {
+            let text = escape(piece, j == 0);
+            let Some(id) = id else {
+                out.push_str(&text);
+                continue;
+            };
+            let style = doc.style(id);
+            let span = format!("<span style=\"color: {}\">{}</span>", style.color, text);
+            match &style.link {
+                Some(url) => out.push_str(&format!("<a href=\"{}\">{}</a>", url.replace('"', "%22"), span)),
+                None => out.push_str(&span),
+            }
+        }
+
+        let stanza_ends = lines.get(i + 1).is_none_or(|next| next.is_empty());
+        if !stanza_ends {
+            out.push('\\');
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,85 @@
+use crate::ansi::paint;
+use crate::styled::Rgb;
+
+const OFF_TARGET: Rgb = Rgb(205, 0, 0);
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+// English spelling heuristic: count vowel groups, then correct for the
+// usual silent endings. Good to about one syllable per line of verse.
+pub fn word_syllables(word: &str) -> usize {
+    let w: Vec<char> = word
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if w.is_empty() {
+        return 0;
+    }
+
+    let mut count = 0;
+    let mut prev_vowel = false;
+    for &c in &w {
+        let vowel = is_vowel(c);
+        if vowel && !prev_vowel {
+            count += 1;
+        }
+        prev_vowel = vowel;
+    }
+
+    let n = w.len();
+    let ends_with = |s: &str| w.iter().rev().take(s.len()).rev().copied().eq(s.chars());
+    if n > 2 && w[n - 1] == 'e' && !is_vowel(w[n - 2]) {
+        // "time", "rhyme" drop the e, but "table", "syllable" keep the le
+        if !ends_with("le") || is_vowel(w[n - 3]) {
+            count -= 1;
+        }
+    } else if n > 3 && (ends_with("es") || ends_with("ed")) && !ends_with("ted") && !ends_with("ded") {
+        // "traces", "threaded" keep the extra syllable after t/d/s/c/g
+        if !matches!(w[n - 3], 's' | 'c' | 'g' | 'z' | 'x') || ends_with("ed") {
+            count -= 1;
+        }
+    }
+
+    count.max(1)
+}
+
+pub fn line_syllables(line: &str) -> usize {
+    line.split_whitespace().map(word_syllables).sum()
+}
+
+// Syllables of every line (None for blank lines)
+pub fn scan(text: &str) -> Vec<Option<usize>> {
+    text.lines()
+        .map(|line| (!line.trim().is_empty()).then(|| line_syllables(line)))
+        .collect()
+}
+
+// Lines whose estimate differs from the target by more than the tolerance
+pub fn deviations(counts: &[Option<usize>], target: usize, tolerance: usize) -> Vec<usize> {
+    counts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| c.filter(|c| c.abs_diff(target) > tolerance).map(|_| i + 1))
+        .collect()
+}
+
+// Append "[n]" to each line of already rendered output, red when off target
+pub fn annotate(rendered: &str, counts: &[Option<usize>], target: usize, tolerance: usize) -> String {
+    let mut out = String::new();
+    for (line, count) in rendered.lines().zip(counts) {
+        out.push_str(line);
+        if let Some(n) = count {
+            let tag = format!("  [{}]", n);
+            if n.abs_diff(target) > tolerance {
+                out.push_str(&paint(&tag, OFF_TARGET));
+            } else {
+                out.push_str(&tag);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,108 @@
+// International Morse code for letters, digits and common punctuation
+fn code(c: char) -> Option<&'static str> {
+    Some(match c.to_ascii_lowercase() {
+        'a' => ".-",
+        'b' => "-...",
+        'c' => "-.-.",
+        'd' => "-..",
+        'e' => ".",
+        'f' => "..-.",
+        'g' => "--.",
+        'h' => "....",
+        'i' => "..",
+        'j' => ".---",
+        'k' => "-.-",
+        'l' => ".-..",
+        'm' => "--",
+        'n' => "-.",
+        'o' => "---",
+        'p' => ".--.",
+        'q' => "--.-",
+        'r' => ".-.",
+        's' => "...",
+        't' => "-",
+        'u' => "..-",
+        'v' => "...-",
+        'w' => ".--",
+        'x' => "-..-",
+        'y' => "-.--",
+        'z' => "--..",
+        '0' => "-----",
+        '1' => ".----",
+        '2' => "..---",
+        '3' => "...--",
+        '4' => "....-",
+        '5' => ".....",
+        '6' => "-....",
+        '7' => "--...",
+        '8' => "---..",
+        '9' => "----.",
+        '.' => ".-.-.-",
+        ',' => "--..--",
+        '?' => "..--..",
+        '\'' => ".----.",
+        '!' => "-.-.--",
+        '/' => "-..-.",
+        '(' => "-.--.",
+        ')' => "-.--.-",
+        '&' => ".-...",
+        ':' => "---...",
+        ';' => "-.-.-.",
+        '=' => "-...-",
+        '+' => ".-.-.",
+        '-' => "-....-",
+        '"' => ".-..-.",
+        '@' => ".--.-.",
+        _ => return None,
+    })
+}
+
+// Codes for each word, skipping characters Morse has no code for
+fn words(message: &str) -> Vec<Vec<&'static str>> {
+    message
+        .split_whitespace()
+        .map(|w| w.chars().filter_map(code).collect::<Vec<_>>())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+// Letters separated by spaces, words by " / "
+pub fn to_text(message: &str) -> String {
+    words(message)
+        .iter()
+        .map(|w| w.join(" "))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+// Key-down/key-up durations in standard timing: dot 1 unit, dash 3,
+// 1 between the elements of a letter, 3 between letters, 7 between words.
+// Returns (on, milliseconds) pairs, starting and ending with a tone.
+pub fn timings(message: &str, unit_ms: u64) -> Vec<(bool, u64)> {
+    let mut out = Vec::new();
+    for (w, word) in words(message).iter().enumerate() {
+        if w > 0 {
+            out.push((false, 7 * unit_ms));
+        }
+        for (l, letter) in word.iter().enumerate() {
+            if l > 0 {
+                out.push((false, 3 * unit_ms));
+            }
+            for (e, element) in letter.chars().enumerate() {
+                if e > 0 {
+                    out.push((false, unit_ms));
+                }
+                out.push((true, if element == '-' { 3 * unit_ms } else { unit_ms }));
+            }
+        }
+    }
+    out
+}
+
+// One "on <ms>" / "off <ms>" line per step, for players to read
+pub fn to_timing_file(timings: &[(bool, u64)]) -> String {
+    timings
+        .iter()
+        .map(|&(on, ms)| format!("{} {}\n", if on { "on" } else { "off" }, ms))
+        .collect()
+}
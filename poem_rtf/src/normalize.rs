@@ -0,0 +1,50 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::canonical_combining_class;
+
+use crate::styled::{StyleId, StyledText};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Normalization {
+    /// Composed: "é" is one code point
+    Nfc,
+    /// Decomposed: "é" is "e" plus a combining acute accent
+    Nfd,
+}
+
+impl fmt::Display for Normalization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Normalization::Nfc => "NFC",
+            Normalization::Nfd => "NFD",
+        })
+    }
+}
+
+pub fn normalize_text(text: &str, form: Normalization) -> String {
+    match form {
+        Normalization::Nfc => text.nfc().collect(),
+        Normalization::Nfd => text.nfd().collect(),
+    }
+}
+
+// Normalize while keeping the styling. The text is cut into runs that only
+// ever start at a base character (combining class 0), so a combining mark
+// always stays with its base letter and takes that letter's style; each run
+// is normalized on its own.
+pub fn normalize(doc: &StyledText, form: Normalization) -> StyledText {
+    let mut runs: Vec<(String, Option<StyleId>)> = Vec::new();
+    for (c, style) in doc.styled_chars() {
+        match runs.last_mut() {
+            Some((run, last)) if *last == style || canonical_combining_class(c) != 0 => run.push(c),
+            _ => runs.push((c.to_string(), style)),
+        }
+    }
+
+    let chars = runs
+        .iter()
+        .flat_map(|(run, style)| normalize_text(run, form).chars().map(|c| (c, *style)).collect::<Vec<_>>());
+    StyledText::from_styled_chars(chars, doc.styles.clone())
+}
@@ -0,0 +1,166 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::ansi::Ansi;
+use crate::braille::Braille;
+use crate::docx::Docx;
+use crate::hexdump::Hex;
+use crate::html::Html;
+use crate::json::Json;
+use crate::latex::{Latex, LatexStyle};
+use crate::lyrics::{Lrc, Srt, TimingOptions};
+use crate::markdown::Markdown;
+use crate::pdf::{Pdf, PdfOptions};
+use crate::raster::{Png, PngOptions};
+use crate::render::Renderer;
+use crate::rtf::Rtf;
+use crate::styled::StyledText;
+use crate::svg::{Svg, SvgOptions};
+use crate::typst::Typst;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Ansi,
+    Hex,
+    Latex,
+    Typst,
+    Svg,
+    Docx,
+    Pdf,
+    Png,
+    Html,
+    Markdown,
+    Rtf,
+    Braille,
+    Lrc,
+    Srt,
+    Json,
+}
+
+impl Format {
+    // File name used when no --out is given (or --out names a directory)
+    pub fn default_filename(self) -> &'static str {
+        match self {
+            Format::Ansi => "poem_ansi_utf8.txt",
+            Format::Hex => "poem_ansi_hex.txt",
+            Format::Latex => "poem.tex",
+            Format::Typst => "poem.typ",
+            Format::Svg => "poem.svg",
+            Format::Docx => "poem.docx",
+            Format::Pdf => "poem.pdf",
+            Format::Png => "poem.png",
+            Format::Html => "poem.html",
+            Format::Markdown => "poem.md",
+            Format::Rtf => "poem_colored_utf8.rtf",
+            Format::Braille => "poem_braille.txt",
+            Format::Lrc => "poem.lrc",
+            Format::Srt => "poem.srt",
+            Format::Json => "poem.json",
+        }
+    }
+
+    // Name derived from an --out stem when several formats share it;
+    // ANSI and hex are both .txt, so they get a suffix.
+    pub fn derived_filename(self, stem: &str) -> String {
+        match self {
+            Format::Ansi => format!("{}_ansi.txt", stem),
+            Format::Hex => format!("{}_hex.txt", stem),
+            Format::Latex => format!("{}.tex", stem),
+            Format::Typst => format!("{}.typ", stem),
+            Format::Svg => format!("{}.svg", stem),
+            Format::Docx => format!("{}.docx", stem),
+            Format::Pdf => format!("{}.pdf", stem),
+            Format::Png => format!("{}.png", stem),
+            Format::Html => format!("{}.html", stem),
+            Format::Markdown => format!("{}.md", stem),
+            Format::Rtf => format!("{}.rtf", stem),
+            Format::Braille => format!("{}_braille.txt", stem),
+            Format::Lrc => format!("{}.lrc", stem),
+            Format::Srt => format!("{}.srt", stem),
+            Format::Json => format!("{}.json", stem),
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Format::Ansi => "visible text with ANSI colors",
+            Format::Hex => "hex dump of the same data",
+            Format::Latex => "LaTeX verse with xcolor highlights",
+            Format::Typst => "Typst markup with coloured text spans",
+            Format::Svg => "SVG image with tspan highlights",
+            Format::Docx => "Word document with coloured runs",
+            Format::Pdf => "printable PDF with coloured glyphs",
+            Format::Png => "raster image of the coloured poem",
+            Format::Html => "HTML page with coloured spans",
+            Format::Markdown => "Markdown verse with inline HTML spans",
+            Format::Rtf => "rich text with a colour table",
+            Format::Braille => "grade 1 Unicode braille, line by line",
+            Format::Lrc => "LRC lyrics with a timestamp per line",
+            Format::Srt => "SubRip subtitles, one cue per line",
+            Format::Json => "span model for editing and re-import",
+        }
+    }
+}
+
+// Where each format goes:
+//   no --out               default names in the current directory
+//   --out DIR (existing)   default names inside DIR
+//   --out FILE, 1 format   exactly FILE
+//   --out STEM, n formats  STEM_ansi.txt, STEM.tex, ... (any extension dropped)
+pub fn output_paths(formats: &[Format], out: Option<&Path>) -> Vec<PathBuf> {
+    match out {
+        None => formats.iter().map(|f| PathBuf::from(f.default_filename())).collect(),
+        Some(dir) if dir.is_dir() => formats.iter().map(|f| dir.join(f.default_filename())).collect(),
+        Some(file) if formats.len() == 1 => vec![file.to_path_buf()],
+        Some(stem) => {
+            let name = stem.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            formats.iter().map(|f| stem.with_file_name(f.derived_filename(&name))).collect()
+        }
+    }
+}
+
+pub struct RenderOptions {
+    pub svg: SvgOptions,
+    pub png: PngOptions,
+    pub pdf: PdfOptions,
+    pub png_font: Option<Vec<u8>>,
+    pub timing: TimingOptions,
+    pub latex_style: LatexStyle,
+}
+
+impl Format {
+    // The backend that produces this format
+    pub fn renderer(self, options: &RenderOptions) -> io::Result<Box<dyn Renderer>> {
+        Ok(match self {
+            Format::Ansi => Box::new(Ansi),
+            Format::Hex => Box::new(Hex),
+            Format::Latex => Box::new(Latex { style: options.latex_style, standalone: true }),
+            Format::Typst => Box::new(Typst),
+            Format::Svg => Box::new(Svg(options.svg.clone())),
+            Format::Docx => Box::new(Docx),
+            Format::Pdf => Box::new(Pdf(options.pdf.clone())),
+            Format::Png => {
+                let font = options
+                    .png_font
+                    .clone()
+                    .ok_or_else(|| io::Error::other("PNG output needs a font, pass --png-font"))?;
+                Box::new(Png { font, options: options.png.clone() })
+            }
+            Format::Html => Box::new(Html),
+            Format::Markdown => Box::new(Markdown),
+            Format::Rtf => Box::new(Rtf),
+            Format::Braille => Box::new(Braille),
+            Format::Lrc => Box::new(Lrc(options.timing.clone())),
+            Format::Srt => Box::new(Srt(options.timing.clone())),
+            Format::Json => Box::new(Json),
+        })
+    }
+}
+
+pub fn render(format: Format, doc: &StyledText, options: &RenderOptions) -> io::Result<Vec<u8>> {
+    format.renderer(options)?.render(doc)
+}
@@ -0,0 +1,173 @@
+use std::str::FromStr;
+
+use crate::render::Renderer;
+use crate::styled::{Rgb, StyledText};
+
+// Millimetres to PDF points (1/72 inch)
+const PT_PER_MM: f32 = 72.0 / 25.4;
+
+// Page size in points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+// Accepts a4, a5, letter, legal, or WIDTHxHEIGHT in millimetres ("148x210")
+impl FromStr for PageSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (w, h) = match s.to_ascii_lowercase().as_str() {
+            "a4" => (210.0, 297.0),
+            "a5" => (148.0, 210.0),
+            "letter" => (215.9, 279.4),
+            "legal" => (215.9, 355.6),
+            custom => custom
+                .split_once('x')
+                .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+                .filter(|&(w, h): &(f32, f32)| w > 0.0 && h > 0.0)
+                .ok_or_else(|| format!("invalid page size {:?}, expected a4, a5, letter, legal or WxH in mm", s))?,
+        };
+        Ok(PageSize { width: w * PT_PER_MM, height: h * PT_PER_MM })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PdfOptions {
+    pub page: PageSize,
+    pub margin: f32,       // pt, on every side
+    pub font_size: f32,    // pt
+    pub line_spacing: f32, // multiple of the font size
+}
+
+pub struct Pdf(pub PdfOptions);
+
+impl Renderer for Pdf {
+    fn render(&self, doc: &StyledText) -> std::io::Result<Vec<u8>> {
+        Ok(to_pdf(doc, &self.0))
+    }
+}
+
+// The standard Times-Roman font needs no embedding but only covers
+// WinAnsi (close to Latin-1 plus typographic quotes and dashes);
+// anything else prints as "?"
+fn win_ansi(c: char) -> u8 {
+    match c {
+        '€' => 0x80,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        c if (' '..='~').contains(&c) || ('\u{a0}'..='\u{ff}').contains(&c) => c as u8,
+        _ => b'?',
+    }
+}
+
+// A PDF literal string, kept 7-bit with octal escapes
+fn literal(text: &str) -> String {
+    let mut out = String::from("(");
+    for c in text.chars() {
+        match win_ansi(c) {
+            b @ (b'\\' | b'(' | b')') => {
+                out.push('\\');
+                out.push(b as char);
+            }
+            b if b.is_ascii() => out.push(b as char),
+            b => out.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    out.push(')');
+    out
+}
+
+fn fill(color: Rgb) -> String {
+    let Rgb(r, g, b) = color;
+    format!("{:.3} {:.3} {:.3} rg", r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+}
+
+// Text operators for each page: lines flow top to bottom inside the margins
+// and continue on a new page when one is full
+fn page_contents(doc: &StyledText, options: &PdfOptions) -> Vec<String> {
+    let leading = options.font_size * options.line_spacing;
+    let usable = options.page.height - 2.0 * options.margin;
+    let per_page = ((usable / leading).floor() as usize).max(1);
+
+    doc.lines()
+        .chunks(per_page)
+        .map(|lines| {
+            let mut ops = format!(
+                "BT\n/F1 {} Tf\n{} TL\n{} {} Td\n",
+                options.font_size,
+                leading,
+                options.margin,
+                options.page.height - options.margin - options.font_size
+            );
+            for line in lines {
+                for &(piece, id) in line {
+                    match id {
+                        Some(id) => ops.push_str(&format!(
+                            "{} {} Tj {}\n",
+                            fill(doc.style(id).color),
+                            literal(piece),
+                            fill(Rgb(0, 0, 0))
+                        )),
+                        None => ops.push_str(&format!("{} Tj\n", literal(piece))),
+                    }
+                }
+                ops.push_str("T*\n");
+            }
+            ops.push_str("ET\n");
+            ops
+        })
+        .collect()
+}
+
+// A minimal PDF 1.4 file: catalog, page tree, one font, and a page plus
+// content stream per page, followed by the cross-reference table
+pub fn to_pdf(doc: &StyledText, options: &PdfOptions) -> Vec<u8> {
+    let mut pages = page_contents(doc, options);
+    if pages.is_empty() {
+        pages.push(String::new());
+    }
+
+    // Objects 1-3 are fixed; page n is object 4 + 2n, its content 5 + 2n
+    let kids: Vec<String> = (0..pages.len()).map(|n| format!("{} 0 R", 4 + 2 * n)).collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Times-Roman /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+    for (n, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            options.page.width,
+            options.page.height,
+            5 + 2 * n
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+    }
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    let xref = out.len();
+    out.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+    for offset in offsets {
+        out.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    out.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+    out.into_bytes()
+}
@@ -0,0 +1,26 @@
+use crate::styled::{Rgb, Style, StyledText};
+
+// Colour of the hidden letters
+pub const ORANGE: Rgb = Rgb(205, 102, 0);
+
+// The poem with the hidden letters marked in braces, e.g. "s{h}adowed"
+pub const POEM: &str = "\
+Registers under s{h}adowed threads,
+Units signal t{i}mes return,
+Structures trace res{p}lendent unknowns,
+Tokens recode un{i}versal speech.
+
+Realms unfold s{e}amless trains,
+Universe spins, th{r}eads recur,
+Signals transform, {r}unes unbound,
+Truth reiterates unifi{e}d syntax.
+";
+
+pub fn default_style() -> Style {
+    Style::new(ORANGE)
+}
+
+// The built-in poem with its hidden letters in orange
+pub fn poem() -> StyledText {
+    StyledText::parse_markup(POEM, default_style())
+}
@@ -0,0 +1,71 @@
+use std::io;
+use std::path::Path;
+
+use clap::ValueEnum;
+use qrcode::{Color, QrCode};
+
+use crate::raster;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QrSource {
+    /// The ANSI-coloured UTF-8 bytes of the poem
+    Ansi,
+    /// Only the hidden message
+    Message,
+}
+
+// Light border around the symbol that scanners need
+const QUIET_ZONE: usize = 4;
+
+pub fn encode(data: &[u8]) -> io::Result<QrCode> {
+    QrCode::new(data).map_err(io::Error::other)
+}
+
+fn is_dark(code: &QrCode, x: isize, y: isize) -> bool {
+    let width = code.width() as isize;
+    x >= 0 && y >= 0 && x < width && y < width && code[(x as usize, y as usize)] == Color::Dark
+}
+
+// Two modules per character cell using half blocks. Light modules are drawn,
+// which suits dark terminals; `invert` draws the dark modules instead.
+pub fn to_unicode(code: &QrCode, invert: bool) -> String {
+    let quiet = QUIET_ZONE as isize;
+    let width = code.width() as isize;
+    let mut out = String::new();
+
+    let mut y = -quiet;
+    while y < width + quiet {
+        for x in -quiet..width + quiet {
+            let top = is_dark(code, x, y) == invert;
+            let bottom = is_dark(code, x, y + 1) == invert;
+            out.push(match (top, bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        out.push('\n');
+        y += 2;
+    }
+    out
+}
+
+// Black on white PNG, `scale` pixels per module
+pub fn write_png(code: &QrCode, scale: usize, path: &Path) -> io::Result<()> {
+    let size = (code.width() + 2 * QUIET_ZONE) * scale;
+    let mut pixels = vec![255u8; size * size * 3];
+
+    for py in 0..size {
+        for px in 0..size {
+            let x = (px / scale) as isize - QUIET_ZONE as isize;
+            let y = (py / scale) as isize - QUIET_ZONE as isize;
+            if is_dark(code, x, y) {
+                let idx = (py * size + px) * 3;
+                pixels[idx..idx + 3].fill(0);
+            }
+        }
+    }
+
+    std::fs::write(path, raster::encode_png(&pixels, size, size)?)
+}
@@ -0,0 +1,102 @@
+use std::io;
+
+use fontdue::{Font, FontSettings};
+
+use crate::render::Renderer;
+use crate::styled::{Rgb, StyleId, StyledText};
+
+#[derive(Debug, Clone)]
+pub struct PngOptions {
+    pub font_size: f32, // px, this sets the resolution of the image
+    pub line_spacing: f32,
+    pub background: Rgb,
+    pub foreground: Rgb,
+}
+
+// Renders with the font data it carries
+pub struct Png {
+    pub font: Vec<u8>,
+    pub options: PngOptions,
+}
+
+impl Renderer for Png {
+    fn render(&self, doc: &StyledText) -> io::Result<Vec<u8>> {
+        to_png(doc, &self.font, &self.options)
+    }
+}
+
+// Blend one channel of the glyph colour over the background by coverage
+fn blend(bg: u8, fg: u8, coverage: u8) -> u8 {
+    let a = coverage as u32;
+    ((fg as u32 * a + bg as u32 * (255 - a)) / 255) as u8
+}
+
+// Rasterize the poem with the given TrueType/OpenType font into a PNG
+pub fn to_png(doc: &StyledText, font_data: &[u8], options: &PngOptions) -> io::Result<Vec<u8>> {
+    let font = Font::from_bytes(font_data, FontSettings::default()).map_err(io::Error::other)?;
+    let px = options.font_size;
+    let metrics = font
+        .horizontal_line_metrics(px)
+        .ok_or_else(|| io::Error::other("font has no horizontal metrics"))?;
+    let line_height = px * options.line_spacing;
+    let margin = px;
+
+    let lines = doc.lines();
+    let line_width = |line: &[(&str, Option<StyleId>)]| -> f32 {
+        line.iter()
+            .flat_map(|(piece, _)| piece.chars())
+            .map(|c| font.metrics(c, px).advance_width)
+            .sum()
+    };
+    let widest = lines.iter().map(|l| line_width(l)).fold(0.0, f32::max);
+
+    let width = (widest + 2.0 * margin).ceil() as usize;
+    let height = (lines.len() as f32 * line_height + 2.0 * margin).ceil() as usize;
+
+    let Rgb(br, bg, bb) = options.background;
+    let mut pixels = [br, bg, bb].repeat(width * height);
+
+    for (i, line) in lines.iter().enumerate() {
+        let baseline = margin + metrics.ascent + i as f32 * line_height;
+        let mut pen_x = margin;
+
+        for &(piece, id) in line {
+            let Rgb(r, g, b) = id.map_or(options.foreground, |id| doc.style(id).color);
+
+            for c in piece.chars() {
+                let (glyph, coverage) = font.rasterize(c, px);
+                let left = (pen_x + glyph.xmin as f32).round() as i64;
+                let top = (baseline - glyph.height as f32 - glyph.ymin as f32).round() as i64;
+
+                for gy in 0..glyph.height {
+                    for gx in 0..glyph.width {
+                        let (x, y) = (left + gx as i64, top + gy as i64);
+                        if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+                            continue;
+                        }
+                        let a = coverage[gy * glyph.width + gx];
+                        let idx = (y as usize * width + x as usize) * 3;
+                        pixels[idx] = blend(pixels[idx], r, a);
+                        pixels[idx + 1] = blend(pixels[idx + 1], g, a);
+                        pixels[idx + 2] = blend(pixels[idx + 2], b, a);
+                    }
+                }
+                pen_x += glyph.advance_width;
+            }
+        }
+    }
+
+    encode_png(&pixels, width, height)
+}
+
+// Encode 8-bit RGB pixels (row-major, 3 bytes per pixel) as a PNG
+pub fn encode_png(pixels: &[u8], width: usize, height: usize) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer.write_image_data(pixels).map_err(io::Error::other)?;
+    writer.finish().map_err(io::Error::other)?;
+    Ok(data)
+}
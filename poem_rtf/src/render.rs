@@ -0,0 +1,19 @@
+use std::io;
+
+use crate::styled::StyledText;
+
+// A backend that turns styled text into the bytes of an output file
+pub trait Renderer {
+    fn render(&self, doc: &StyledText) -> io::Result<Vec<u8>>;
+}
+
+// A backend whose output is text; every one is also a `Renderer`
+pub trait TextRenderer {
+    fn render_text(&self, doc: &StyledText) -> String;
+}
+
+impl<T: TextRenderer> Renderer for T {
+    fn render(&self, doc: &StyledText) -> io::Result<Vec<u8>> {
+        Ok(self.render_text(doc).into_bytes())
+    }
+}
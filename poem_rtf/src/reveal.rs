@@ -0,0 +1,86 @@
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::ansi;
+use crate::styled::StyledText;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RevealMode {
+    /// Play the animation in this terminal
+    Terminal,
+    /// Print a shell script that plays the animation
+    Script,
+}
+
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+// Frame i shows the poem with the first i hidden letters coloured
+pub fn frames(doc: &StyledText) -> Vec<String> {
+    (0..=doc.spans.len())
+        .map(|i| {
+            let mut partial = doc.clone();
+            partial.spans.truncate(i);
+            ansi::to_ansi(&partial)
+        })
+        .collect()
+}
+
+// Escape sequence that moves the cursor back to the first line of a frame
+fn rewind(doc: &StyledText) -> String {
+    format!("\x1b[{}F", doc.text.lines().count())
+}
+
+pub fn play(doc: &StyledText, delay: Duration) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    let rewind = rewind(doc);
+
+    write!(stdout, "{}", HIDE_CURSOR)?;
+    for (i, frame) in frames(doc).iter().enumerate() {
+        if i > 0 {
+            thread::sleep(delay);
+            write!(stdout, "{}", rewind)?;
+        }
+        write!(stdout, "{}", frame)?;
+        stdout.flush()?;
+    }
+    write!(stdout, "{}", SHOW_CURSOR)?;
+    stdout.flush()
+}
+
+// Turn arbitrary text into a single-quoted printf format string
+fn printf_literal(text: &str) -> String {
+    let mut out = String::from("'");
+    for c in text.chars() {
+        match c {
+            '\x1b' => out.push_str("\\033"),
+            '\\' => out.push_str("\\\\"),
+            '%' => out.push_str("%%"),
+            '\n' => out.push_str("\\n"),
+            '\'' => out.push_str("'\\''"),
+            _ => out.push(c),
+        }
+    }
+    out.push('\'');
+    out
+}
+
+// POSIX sh script that replays the same frames with printf and sleep
+pub fn script(doc: &StyledText, delay: Duration) -> String {
+    let seconds = format!("{:.3}", delay.as_secs_f64());
+    let mut out = String::from("#!/bin/sh\n# Reveals the hidden letters of the poem one by one\n");
+    out.push_str(&format!("printf {}\n", printf_literal(HIDE_CURSOR)));
+    out.push_str("trap 'printf \"\\033[?25h\"' EXIT\n");
+
+    for (i, frame) in frames(doc).iter().enumerate() {
+        if i > 0 {
+            out.push_str(&format!("sleep {}\n", seconds));
+            out.push_str(&format!("printf {}\n", printf_literal(&rewind(doc))));
+        }
+        out.push_str(&format!("printf {}\n", printf_literal(frame)));
+    }
+    out
+}
@@ -0,0 +1,120 @@
+use crate::ansi::paint;
+use crate::styled::Rgb;
+
+// Colours cycled through for the rhyme groups
+const PALETTE: [Rgb; 6] = [
+    Rgb(205, 102, 0),
+    Rgb(0, 128, 205),
+    Rgb(0, 160, 80),
+    Rgb(170, 60, 170),
+    Rgb(200, 40, 40),
+    Rgb(120, 120, 0),
+];
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+// Lowercase letters of the last word of the line
+fn last_word(line: &str) -> String {
+    line.split_whitespace()
+        .rev()
+        .map(|w| w.chars().filter(|c| c.is_alphabetic()).collect::<String>().to_lowercase())
+        .find(|w| !w.is_empty())
+        .unwrap_or_default()
+}
+
+// Spelling stand-in for the final stressed sound: from the last vowel group
+// to the end, ignoring a silent final "e" ("time" -> "im", "threads" -> "eads").
+fn rime(word: &str) -> String {
+    let mut chars: Vec<char> = word.chars().collect();
+    if chars.len() > 2
+        && chars.last() == Some(&'e')
+        && !is_vowel(chars[chars.len() - 2])
+        && chars[..chars.len() - 1].iter().any(|&c| is_vowel(c))
+    {
+        chars.pop();
+    }
+
+    let Some(last_vowel) = chars.iter().rposition(|&c| is_vowel(c)) else {
+        return chars.into_iter().collect();
+    };
+    let mut start = last_vowel;
+    while start > 0 && is_vowel(chars[start - 1]) {
+        start -= 1;
+    }
+    chars[start..].iter().collect()
+}
+
+fn common_suffix(a: &str, b: &str) -> usize {
+    a.chars().rev().zip(b.chars().rev()).take_while(|(x, y)| x == y).count()
+}
+
+// Same rime, or a shared ending of three letters or more that includes a vowel
+fn rhymes(a: &str, b: &str) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    if a == b || rime(a) == rime(b) {
+        return true;
+    }
+    let n = common_suffix(a, b);
+    n >= 3 && a.chars().rev().take(n).any(is_vowel)
+}
+
+// One label per line (None for blank lines); letters continue across stanzas
+pub fn scheme(text: &str) -> Vec<Option<char>> {
+    let mut groups: Vec<String> = Vec::new();
+
+    text.lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                return None;
+            }
+            let word = last_word(line);
+            let group = match groups.iter().position(|g| rhymes(g, &word)) {
+                Some(i) => i,
+                None => {
+                    groups.push(word);
+                    groups.len() - 1
+                }
+            };
+            Some(label(group))
+        })
+        .collect()
+}
+
+fn label(group: usize) -> char {
+    // A..Z, then a..z for very long poems
+    match group {
+        0..26 => (b'A' + group as u8) as char,
+        26..52 => (b'a' + (group - 26) as u8) as char,
+        _ => '?',
+    }
+}
+
+// "ABAB CDCD": stanzas separated by spaces
+pub fn summary(labels: &[Option<char>]) -> String {
+    labels
+        .split(Option::is_none)
+        .filter(|stanza| !stanza.is_empty())
+        .map(|stanza| stanza.iter().flatten().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Each line prefixed with its label, optionally coloured by rhyme group
+pub fn annotate(text: &str, labels: &[Option<char>], color: bool) -> String {
+    let mut out = String::new();
+    for (line, label) in text.lines().zip(labels) {
+        match label {
+            Some(l) if color => {
+                let group = if l.is_ascii_uppercase() { *l as usize - 'A' as usize } else { 0 };
+                out.push_str(&format!("{}  {}\n", l, paint(line, PALETTE[group % PALETTE.len()])));
+            }
+            Some(l) => out.push_str(&format!("{}  {}\n", l, line)),
+            None => out.push('\n'),
+        }
+    }
+    out
+}
@@ -0,0 +1,56 @@
+use crate::render::TextRenderer;
+use crate::styled::{Rgb, StyledText};
+
+pub struct Rtf;
+
+impl TextRenderer for Rtf {
+    fn render_text(&self, doc: &StyledText) -> String {
+        to_rtf(doc)
+    }
+}
+
+// RTF is 7-bit: control characters get a backslash, anything beyond ASCII
+// becomes \uN? (N a signed 16-bit UTF-16 unit, '?' the fallback for old readers)
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_ascii() => out.push(c),
+            c => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
+    }
+    out
+}
+
+// Colour table entry N+1 belongs to style N (entry 0 is the default colour);
+// each highlighted run is a {\cfN ...} group followed by \cf0 to reset.
+pub fn to_rtf(doc: &StyledText) -> String {
+    let mut out = String::from("{\\rtf1\\ansi\n{\\colortbl ;");
+    for style in &doc.styles {
+        let Rgb(r, g, b) = style.color;
+        out.push_str(&format!("\\red{}\\green{}\\blue{};", r, g, b));
+    }
+    out.push_str("}\n");
+
+    for line in doc.lines() {
+        for (piece, id) in line {
+            match id {
+                Some(id) => out.push_str(&format!("{{\\cf{} {}}}\\cf0 ", id + 1, escape(piece))),
+                None => out.push_str(&escape(piece)),
+            }
+        }
+        out.push_str("\\line\n");
+    }
+
+    out.push('}');
+    out
+}
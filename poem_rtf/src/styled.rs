@@ -0,0 +1,306 @@
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::ansi;
+
+// 24-bit colour; serialized as "#rrggbb"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+impl From<Rgb> for String {
+    fn from(rgb: Rgb) -> Self {
+        rgb.to_string()
+    }
+}
+
+impl TryFrom<String> for Rgb {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+// Accepts "#rrggbb" or "rrggbb"
+impl FromStr for Rgb {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| format!("invalid colour {:?}, expected #rrggbb", s))
+        };
+        if hex.len() != 6 {
+            return Err(format!("invalid colour {:?}, expected #rrggbb", s));
+        }
+        Ok(Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+// How a span is drawn, and optionally where it links to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Style {
+    pub color: Rgb,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+}
+
+impl Style {
+    pub fn new(color: Rgb) -> Self {
+        Self { color, link: None }
+    }
+}
+
+// Index into `StyledText::styles`
+pub type StyleId = usize;
+
+// A styled byte range of the text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub style: StyleId,
+}
+
+// Plain text, the styles in use and the spans that apply them. Spans are
+// kept sorted and never overlap, so every byte has at most one style.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StyledText {
+    pub text: String,
+    pub spans: Vec<Span>,
+    pub styles: Vec<Style>,
+}
+
+impl StyledText {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), spans: Vec::new(), styles: Vec::new() }
+    }
+
+    // Register a style, reusing the id of an identical one
+    pub fn add_style(&mut self, style: Style) -> StyleId {
+        match self.styles.iter().position(|s| *s == style) {
+            Some(id) => id,
+            None => {
+                self.styles.push(style);
+                self.styles.len() - 1
+            }
+        }
+    }
+
+    pub fn style(&self, id: StyleId) -> &Style {
+        &self.styles[id]
+    }
+
+    // Style `range`; whatever styling it overlaps is replaced.
+    // Panics like string slicing if the range is not on char boundaries.
+    pub fn add_span(&mut self, range: Range<usize>, style: StyleId) {
+        assert!(style < self.styles.len(), "unknown style id {}", style);
+        assert!(
+            self.text.is_char_boundary(range.start) && self.text.is_char_boundary(range.end),
+            "span {:?} is not on character boundaries",
+            range
+        );
+        if range.is_empty() {
+            return;
+        }
+        self.clear_range(range.clone());
+        let at = self.spans.partition_point(|s| s.range.start < range.start);
+        self.spans.insert(at, Span { range, style });
+    }
+
+    // Remove styling from `range`, trimming or splitting the spans it touches
+    pub fn clear_range(&mut self, range: Range<usize>) {
+        let mut kept = Vec::with_capacity(self.spans.len() + 1);
+        for span in self.spans.drain(..) {
+            if span.range.end <= range.start || span.range.start >= range.end {
+                kept.push(span);
+                continue;
+            }
+            if span.range.start < range.start {
+                kept.push(Span { range: span.range.start..range.start, style: span.style });
+            }
+            if span.range.end > range.end {
+                kept.push(Span { range: range.end..span.range.end, style: span.style });
+            }
+        }
+        self.spans = kept;
+    }
+
+    // Split the span containing byte `at` into two; false if there is none
+    pub fn split_span(&mut self, at: usize) -> bool {
+        let Some(i) = self.spans.iter().position(|s| s.range.start < at && at < s.range.end) else {
+            return false;
+        };
+        let end = self.spans[i].range.end;
+        self.spans[i].range.end = at;
+        let style = self.spans[i].style;
+        self.spans.insert(i + 1, Span { range: at..end, style });
+        true
+    }
+
+    // Join spans that touch and share a style
+    pub fn merge_spans(&mut self) {
+        let mut merged: Vec<Span> = Vec::with_capacity(self.spans.len());
+        for span in self.spans.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.range.end == span.range.start && last.style == span.style => {
+                    last.range.end = span.range.end;
+                }
+                _ => merged.push(span),
+            }
+        }
+        self.spans = merged;
+    }
+
+    // Widen spans that cut through a grapheme cluster (say a letter styled
+    // without its combining accent, or half of an emoji sequence) so that
+    // no renderer puts a colour change inside one. Where two spans now
+    // share a cluster, the earlier one keeps it.
+    pub fn snap_to_graphemes(&mut self) {
+        let bounds: Vec<usize> =
+            self.text.grapheme_indices(true).map(|(i, _)| i).chain([self.text.len()]).collect();
+        let mut end = 0;
+        for span in &mut self.spans {
+            let start = bounds[bounds.partition_point(|&b| b <= span.range.start) - 1];
+            span.range.end = bounds[bounds.partition_point(|&b| b < span.range.end)];
+            span.range.start = start.max(end).min(span.range.end);
+            end = span.range.end.max(end);
+        }
+        self.spans.retain(|s| !s.range.is_empty());
+        self.merge_spans();
+    }
+
+    pub fn style_at(&self, offset: usize) -> Option<StyleId> {
+        self.spans.iter().find(|s| s.range.contains(&offset)).map(|s| s.style)
+    }
+
+    // Colour of the first style, used where a single highlight colour is needed
+    pub fn primary_color(&self) -> Option<Rgb> {
+        self.styles.first().map(|s| s.color)
+    }
+
+    // Split the text into (piece, style) runs, in order
+    pub fn segments(&self) -> Vec<(&str, Option<StyleId>)> {
+        let mut segments = Vec::new();
+        let mut pos = 0;
+
+        for span in &self.spans {
+            if span.range.start > pos {
+                segments.push((&self.text[pos..span.range.start], None));
+            }
+            segments.push((&self.text[span.range.clone()], Some(span.style)));
+            pos = span.range.end;
+        }
+        if pos < self.text.len() {
+            segments.push((&self.text[pos..], None));
+        }
+
+        segments
+    }
+
+    // Same runs as `segments`, but cut at line breaks (newlines are dropped)
+    pub fn lines(&self) -> Vec<Vec<(&str, Option<StyleId>)>> {
+        let mut lines = vec![Vec::new()];
+
+        for (piece, style) in self.segments() {
+            for (i, part) in piece.split('\n').enumerate() {
+                if i > 0 {
+                    lines.push(Vec::new());
+                }
+                if !part.is_empty() {
+                    lines.last_mut().unwrap().push((part, style));
+                }
+            }
+        }
+
+        // A trailing newline does not start another line
+        if self.text.ends_with('\n') {
+            lines.pop();
+        }
+        lines
+    }
+
+    // Every character with its style, for transformations that need to
+    // move text around without losing the spans
+    pub fn styled_chars(&self) -> Vec<(char, Option<StyleId>)> {
+        let mut spans = self.spans.iter().peekable();
+        self.text
+            .char_indices()
+            .map(|(i, c)| {
+                while spans.next_if(|s| s.range.end <= i).is_some() {}
+                (c, spans.peek().filter(|s| s.range.contains(&i)).map(|s| s.style))
+            })
+            .collect()
+    }
+
+    // Inverse of `styled_chars`; neighbouring characters of one style form one span
+    pub fn from_styled_chars(
+        chars: impl IntoIterator<Item = (char, Option<StyleId>)>,
+        styles: Vec<Style>,
+    ) -> Self {
+        let mut doc = Self { text: String::new(), spans: Vec::new(), styles };
+
+        for (c, style) in chars {
+            let start = doc.text.len();
+            doc.text.push(c);
+            if let Some(style) = style {
+                match doc.spans.last_mut() {
+                    Some(last) if last.range.end == start && last.style == style => {
+                        last.range.end = doc.text.len()
+                    }
+                    _ => doc.spans.push(Span { range: start..doc.text.len(), style }),
+                }
+            }
+        }
+        doc
+    }
+
+    // Brace markup: everything inside {...} gets `style`
+    pub fn parse_markup(markup: &str, style: Style) -> Self {
+        let mut doc = Self::new(String::with_capacity(markup.len()));
+        let id = doc.add_style(style);
+        let mut start = None;
+
+        for c in markup.chars() {
+            match c {
+                '{' => start = Some(doc.text.len()),
+                '}' => {
+                    if let Some(s) = start.take()
+                        && s < doc.text.len()
+                    {
+                        doc.spans.push(Span { range: s..doc.text.len(), style: id });
+                    }
+                }
+                _ => doc.text.push(c),
+            }
+        }
+        doc.snap_to_graphemes();
+        doc
+    }
+
+    // Read either ANSI output or brace markup, whichever the text looks like
+    pub fn load(contents: &str, markup_style: Style) -> Self {
+        if contents.contains('\x1b') {
+            ansi::parse(contents)
+        } else {
+            Self::parse_markup(contents, markup_style)
+        }
+    }
+
+    // The styled letters read in order
+    pub fn hidden_message(&self) -> String {
+        self.spans.iter().map(|s| &self.text[s.range.clone()]).collect()
+    }
+}
@@ -0,0 +1,74 @@
+use crate::render::TextRenderer;
+use crate::styled::{Rgb, StyledText};
+
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    pub font_family: String,
+    pub font_size: f32,    // px
+    pub line_spacing: f32, // multiple of the font size
+}
+
+pub struct Svg(pub SvgOptions);
+
+impl TextRenderer for Svg {
+    fn render_text(&self, doc: &StyledText) -> String {
+        to_svg(doc, &self.0)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// One <text> element per line, with a <tspan> fill for each highlighted run.
+// SVG has no text layout, so the width is estimated from the longest line.
+pub fn to_svg(doc: &StyledText, options: &SvgOptions) -> String {
+    let lines = doc.lines();
+    let margin = options.font_size;
+    let line_height = options.font_size * options.line_spacing;
+
+    let longest = doc.text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+    let width = (longest as f32 * options.font_size * 0.6 + 2.0 * margin).ceil();
+    let height = (lines.len() as f32 * line_height + 2.0 * margin).ceil();
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = width,
+        h = height
+    );
+    out.push_str(&format!(
+        "<g font-family=\"{}\" font-size=\"{}\" xml:space=\"preserve\">\n",
+        escape(&options.font_family),
+        options.font_size
+    ));
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        // Baseline of the first line sits one font size below the top margin
+        let y = margin + options.font_size + i as f32 * line_height;
+        out.push_str(&format!("<text x=\"{}\" y=\"{}\">", margin, y));
+        for &(piece, id) in line {
+            if let Some(id) = id {
+                let Rgb(r, g, b) = doc.style(id).color;
+                out.push_str(&format!(
+                    "<tspan fill=\"#{:02x}{:02x}{:02x}\">{}</tspan>",
+                    r,
+                    g,
+                    b,
+                    escape(piece)
+                ));
+            } else {
+                out.push_str(&escape(piece));
+            }
+        }
+        out.push_str("</text>\n");
+    }
+
+    out.push_str("</g>\n</svg>\n");
+    out
+}
@@ -0,0 +1,72 @@
+use crate::render::TextRenderer;
+use crate::styled::{Rgb, StyledText};
+
+pub struct Typst;
+
+impl TextRenderer for Typst {
+    fn render_text(&self, doc: &StyledText) -> String {
+        to_typst(doc)
+    }
+}
+
+// Escape the characters Typst markup mode gives meaning to
+fn escape(text: &str, line_start: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut prev = None;
+    for (i, c) in text.char_indices() {
+        match c {
+            '\\' | '#' | '[' | ']' | '*' | '_' | '`' | '$' | '<' | '>' | '@' | '~' => {
+                out.push('\\');
+                out.push(c);
+            }
+            // "//" starts a comment, "--" an en dash
+            '/' | '-' if prev == Some(c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            // Headings and list markers only matter at the start of a line
+            '=' | '+' if line_start && i == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+        prev = Some(c);
+    }
+    out
+}
+
+// Typst markup: each highlighted run becomes #text(fill: ...)[...],
+// "\" forces a line break and a blank line separates stanzas.
+pub fn to_typst(doc: &StyledText) -> String {
+    let lines = doc.lines();
+    let mut out = String::new();
+    for (id, style) in doc.styles.iter().enumerate() {
+        let Rgb(r, g, b) = style.color;
+        out.push_str(&format!("#let poemhl{} = rgb({}, {}, {})\n", id + 1, r, g, b));
+    }
+    out.push('\n');
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            out.push('\n');
+            continue;
+        }
+
+        for (j, &(piece, id)) in line.iter().enumerate() {
+            let text = escape(piece, j == 0);
+            if let Some(id) = id {
+                out.push_str(&format!("#text(fill: poemhl{})[{}]", id + 1, text));
+            } else {
+                out.push_str(&text);
+            }
+        }
+
+        let stanza_ends = lines.get(i + 1).is_none_or(|next| next.is_empty());
+        if !stanza_ends {
+            out.push_str(" \\");
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,121 @@
+// Decoders that turn generated files back into what they were made from,
+// so the outputs can be checked against each other
+
+// Bytes of a hex dump; "#" comment lines (the trailer) are ignored
+pub fn hex_decode(dump: &str) -> Result<Vec<u8>, String> {
+    dump.lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .flat_map(str::split_whitespace)
+        .map(|pair| u8::from_str_radix(pair, 16).map_err(|_| format!("{:?} is not a hex byte", pair)))
+        .collect()
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+// Visible text of the <pre> written by the HTML backend
+pub fn html_text(html: &str) -> Option<String> {
+    let start = html.find("<pre")?;
+    let body = &html[start + html[start..].find('>')? + 1..];
+    let body = &body[..body.find("</pre>")?];
+
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in body.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    Some(unescape_html(&text))
+}
+
+// Plain text of the RTF subset the RTF backend writes: the header groups are
+// skipped, \line is a newline, \uN? a UTF-16 unit, and other control words
+// are formatting
+pub fn rtf_text(rtf: &str) -> String {
+    let mut text = String::new();
+    let mut units: Vec<u16> = Vec::new();
+    let mut depth = 0;
+    let mut skip_depth = None;
+    let mut chars = rtf.chars().peekable();
+
+    let flush = |units: &mut Vec<u16>, text: &mut String| {
+        text.extend(char::decode_utf16(units.drain(..)).map(|c| c.unwrap_or('\u{fffd}')));
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                if skip_depth == Some(depth) {
+                    skip_depth = None;
+                }
+                depth -= 1;
+            }
+            '\\' => {
+                let Some(&next) = chars.peek() else { break };
+                if !next.is_ascii_alphabetic() {
+                    chars.next();
+                    if skip_depth.is_none() {
+                        flush(&mut units, &mut text);
+                        text.push(next);
+                    }
+                    continue;
+                }
+                let mut word = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                    word.push(c);
+                    chars.next();
+                }
+                let mut number = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '-') {
+                    number.push(c);
+                    chars.next();
+                }
+                // A space ends a control word and is part of it
+                chars.next_if_eq(&' ');
+
+                match word.as_str() {
+                    "colortbl" | "fonttbl" | "stylesheet" | "info" => skip_depth = skip_depth.or(Some(depth)),
+                    _ if skip_depth.is_some() => {}
+                    "line" | "par" => {
+                        flush(&mut units, &mut text);
+                        text.push('\n');
+                    }
+                    "u" => {
+                        units.push(number.parse::<i16>().unwrap_or(0x3f) as u16);
+                        chars.next_if_eq(&'?');
+                    }
+                    _ => {}
+                }
+            }
+            '\n' | '\r' => {}
+            c if skip_depth.is_none() => {
+                flush(&mut units, &mut text);
+                text.push(c);
+            }
+            _ => {}
+        }
+    }
+    flush(&mut units, &mut text);
+    text
+}
+
+// CRC-32 (IEEE), as used by zip and PNG
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,41 @@
+use std::io;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// Clear the screen and move the cursor home
+pub const CLEAR: &str = "\x1b[2J\x1b[H";
+
+const POLL: Duration = Duration::from_millis(250);
+
+fn modified(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+// Run `rebuild` now and again whenever one of `paths` changes, until the
+// process is interrupted. Polling keeps this dependency-free; a failed
+// rebuild is reported and the watch goes on, since the author is probably
+// halfway through an edit.
+pub fn watch(paths: &[PathBuf], mut rebuild: impl FnMut() -> io::Result<()>) -> io::Result<()> {
+    let mut last = modified(paths);
+    loop {
+        print!("{}", CLEAR);
+        if let Err(e) = rebuild() {
+            eprintln!("Error: {}", e);
+        }
+        let names: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+        println!("\nWatching {} (Ctrl-C to stop)", names.join(", "));
+
+        loop {
+            thread::sleep(POLL);
+            let now = modified(paths);
+            if now != last {
+                last = now;
+                break;
+            }
+        }
+    }
+}